@@ -182,7 +182,7 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let p: PersistedBeaconChain<T> = PersistedBeaconChain {
             canonical_head,
             finalized_checkpoint,
-            op_pool: PersistedOperationPool::from_operation_pool(&self.op_pool),
+            op_pool: PersistedOperationPool::from_operation_pool(&self.op_pool).as_bytes(),
             genesis_block_root: self.genesis_block_root,
             ssz_head_tracker: self.head_tracker.to_ssz_container(),
             fork_choice: self.fork_choice.as_ssz_container(),
@@ -1390,8 +1390,10 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
         let mut graffiti: [u8; 32] = [0; 32];
         graffiti.copy_from_slice(GRAFFITI.as_bytes());
 
-        let (proposer_slashings, attester_slashings) =
-            self.op_pool.get_slashings(&state, &self.spec);
+        let operations = self
+            .op_pool
+            .get_block_operations(&state, &self.spec)
+            .map_err(BlockProductionError::OpPoolError)?;
 
         let eth1_data = eth1_chain.eth1_data_for_block_production(&state, &self.spec)?;
         let deposits = eth1_chain
@@ -1407,15 +1409,11 @@ impl<T: BeaconChainTypes> BeaconChain<T> {
                     randao_reveal,
                     eth1_data,
                     graffiti,
-                    proposer_slashings: proposer_slashings.into(),
-                    attester_slashings: attester_slashings.into(),
-                    attestations: self
-                        .op_pool
-                        .get_attestations(&state, &self.spec)
-                        .map_err(BlockProductionError::OpPoolError)?
-                        .into(),
+                    proposer_slashings: operations.proposer_slashings.into(),
+                    attester_slashings: operations.attester_slashings.into(),
+                    attestations: operations.attestations.into(),
                     deposits,
-                    voluntary_exits: self.op_pool.get_voluntary_exits(&state, &self.spec).into(),
+                    voluntary_exits: operations.voluntary_exits.into(),
                 },
             },
             // The block is not signed here, that is the task of a validator client.
@@ -8,9 +8,9 @@ use crate::{
     ForkChoice,
 };
 use eth1::Config as Eth1Config;
-use operation_pool::OperationPool;
+use operation_pool::{OperationPool, PersistedOperationPool};
 use proto_array_fork_choice::ProtoArrayForkChoice;
-use slog::{info, Logger};
+use slog::{info, warn, Logger};
 use slot_clock::{SlotClock, TestingSlotClock};
 use std::marker::PhantomData;
 use std::sync::Arc;
@@ -176,11 +176,22 @@ where
             Ok(Some(p)) => p,
         };
 
-        self.op_pool = Some(
-            p.op_pool
-                .clone()
-                .into_operation_pool(&p.canonical_head.beacon_state, &self.spec),
-        );
+        let op_pool = match PersistedOperationPool::from_bytes(&p.op_pool) {
+            Ok(op_pool) => op_pool.into_operation_pool(
+                OperationPool::new(),
+                &p.canonical_head.beacon_state,
+                &self.spec,
+            ),
+            Err(e) => {
+                warn!(
+                    log,
+                    "Discarding persisted operation pool";
+                    "error" => format!("{:?}", e)
+                );
+                OperationPool::new()
+            }
+        };
+        self.op_pool = Some(op_pool);
 
         self.finalized_checkpoint = Some(p.finalized_checkpoint.clone());
         self.genesis_block_root = Some(p.genesis_block_root);
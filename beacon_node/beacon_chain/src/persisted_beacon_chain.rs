@@ -2,7 +2,6 @@ use crate::eth1_chain::SszEth1;
 use crate::fork_choice::SszForkChoice;
 use crate::head_tracker::SszHeadTracker;
 use crate::{BeaconChainTypes, CheckPoint};
-use operation_pool::PersistedOperationPool;
 use ssz::{Decode, Encode};
 use ssz_derive::{Decode, Encode};
 use store::{DBColumn, Error as StoreError, SimpleStoreItem};
@@ -15,7 +14,9 @@ pub const BEACON_CHAIN_DB_KEY: &str = "PERSISTEDBEACONCHAINPERSISTEDBEA";
 pub struct PersistedBeaconChain<T: BeaconChainTypes> {
     pub canonical_head: CheckPoint<T::EthSpec>,
     pub finalized_checkpoint: CheckPoint<T::EthSpec>,
-    pub op_pool: PersistedOperationPool<T::EthSpec>,
+    /// The bytes of a `PersistedOperationPool`, which are decoded separately so that a pool
+    /// persisted with an old layout does not prevent the rest of the chain from loading.
+    pub op_pool: Vec<u8>,
     pub genesis_block_root: Hash256,
     pub ssz_head_tracker: SszHeadTracker,
     pub fork_choice: SszForkChoice,
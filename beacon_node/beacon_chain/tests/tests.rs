@@ -11,6 +11,7 @@ use beacon_chain::{
     },
     BlockProcessingOutcome,
 };
+use operation_pool::{OperationPool, PersistedOperationPool};
 use state_processing::{
     per_slot_processing, per_slot_processing::Error as SlotProcessingError, EpochProcessingError,
 };
@@ -350,9 +351,13 @@ fn roundtrip_operation_pool() {
     let p: PersistedBeaconChain<HarnessType<MinimalEthSpec>> =
         harness.chain.store.get(&key).unwrap().unwrap();
 
-    let restored_op_pool = p
-        .op_pool
-        .into_operation_pool(&p.canonical_head.beacon_state, &harness.spec);
+    let restored_op_pool = PersistedOperationPool::from_bytes(&p.op_pool)
+        .unwrap()
+        .into_operation_pool(
+            OperationPool::new(),
+            &p.canonical_head.beacon_state,
+            &harness.spec,
+        );
 
     assert_eq!(harness.chain.op_pool, restored_op_pool);
 }
@@ -0,0 +1,297 @@
+use criterion::Criterion;
+use criterion::{black_box, criterion_group, criterion_main, Benchmark};
+use operation_pool::OperationPool;
+use parking_lot::Mutex;
+use std::sync::Arc;
+use std::thread;
+use types::test_utils::{
+    AttestationTestTask, TestingAttestationBuilder, TestingBeaconStateBuilder,
+};
+use types::{
+    Attestation, BeaconCommittee, BeaconState, ChainSpec, EthSpec, Keypair, MainnetEthSpec, Slot,
+};
+
+/// Committee counts (per slot) to benchmark with. The size of each committee grows with the
+/// committee count, up to `target_committee_size`.
+const NUM_COMMITTEES: &[usize] = &[1, 4];
+
+/// Number of slots (prior to the state's slot) with attestations in the pool.
+const POOL_DEPTHS: &[u64] = &[1, 8, 32];
+
+/// Thread counts to benchmark concurrent inserts with.
+const NUM_THREADS: &[usize] = &[1, 8];
+
+/// Number of slots (prior to the state's slot) with attestations inserted concurrently.
+const CONCURRENT_INSERT_DEPTH: u64 = 16;
+
+/// Build a state with caches, half-way through an epoch.
+fn build_state<T: EthSpec>(num_committees: usize) -> (BeaconState<T>, Vec<Keypair>, ChainSpec) {
+    let spec = T::default_spec();
+
+    let num_validators =
+        num_committees * T::slots_per_epoch() as usize * spec.target_committee_size;
+    let mut state_builder =
+        TestingBeaconStateBuilder::from_default_keypairs_file_if_exists(num_validators, &spec);
+    let slot_offset = 1000 * T::slots_per_epoch() + T::slots_per_epoch() / 2;
+    state_builder.teleport_to_slot(spec.genesis_slot + slot_offset);
+    state_builder.build_caches(&spec).unwrap();
+    let (state, keypairs) = state_builder.build();
+
+    (state, keypairs, spec)
+}
+
+/// Create an attestation signed by `committee[i]` for each `i` in `signers`.
+fn signed_attestation<T: EthSpec>(
+    bc: &BeaconCommittee,
+    signers: &[usize],
+    keypairs: &[Keypair],
+    state: &BeaconState<T>,
+    spec: &ChainSpec,
+) -> Attestation<T> {
+    let mut builder = TestingAttestationBuilder::new(
+        AttestationTestTask::Valid,
+        state,
+        bc.committee,
+        bc.slot,
+        bc.index,
+        spec,
+    );
+    let validators = signers.iter().map(|&i| bc.committee[i]).collect::<Vec<_>>();
+    let secret_keys = validators
+        .iter()
+        .map(|&i| &keypairs[i].sk)
+        .collect::<Vec<_>>();
+    builder.sign(
+        AttestationTestTask::Valid,
+        &validators,
+        &secret_keys,
+        &state.fork,
+        spec,
+    );
+    builder.build()
+}
+
+/// Create pairwise-overlapping attestations (which cannot all be aggregated) for every committee
+/// in the `depth` slots prior to `state.slot`.
+fn pool_attestations<T: EthSpec>(
+    depth: u64,
+    keypairs: &[Keypair],
+    state: &BeaconState<T>,
+    spec: &ChainSpec,
+) -> Vec<Attestation<T>> {
+    let mut attestations = vec![];
+
+    for slot in (1..=depth).map(|i| state.slot - i) {
+        for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+            let step_size = std::cmp::max(1, bc.committee.len() / 4);
+            for i in (0..bc.committee.len()).step_by(step_size) {
+                let end = std::cmp::min(bc.committee.len(), i + 2 * step_size);
+                let signers = (i..end).collect::<Vec<_>>();
+                attestations.push(signed_attestation(&bc, &signers, keypairs, state, spec));
+            }
+        }
+    }
+
+    attestations
+}
+
+fn insert_attestation_bench<T: EthSpec>(c: &mut Criterion, spec_desc: &str, num_committees: usize) {
+    let (state, keypairs, spec) = build_state::<T>(num_committees);
+
+    let slot: Slot = state.slot - 1;
+    let bc = state
+        .get_beacon_committees_at_slot(slot)
+        .unwrap()
+        .into_iter()
+        .next()
+        .unwrap();
+    let last = bc.committee.len() - 1;
+
+    // Every attestation in the bucket shares the first validator, so none of them can be
+    // aggregated with each other. The new attestation is disjoint from all of them.
+    let bucket = (1..last)
+        .map(|i| signed_attestation(&bc, &[0, i], &keypairs, &state, &spec))
+        .collect::<Vec<_>>();
+    let new_attestation = signed_attestation(&bc, &[last], &keypairs, &state, &spec);
+
+    c.bench(
+        &format!("{}/{}_committees", spec_desc, num_committees),
+        Benchmark::new(
+            format!("insert_attestation/{}_bucket", bucket.len()),
+            move |b| {
+                b.iter_batched_ref(
+                    || {
+                        let op_pool = OperationPool::new();
+                        for attestation in &bucket {
+                            op_pool
+                                .insert_attestation(attestation.clone(), &state, &spec)
+                                .unwrap();
+                        }
+                        (op_pool, new_attestation.clone())
+                    },
+                    |(op_pool, attestation)| {
+                        black_box(
+                            op_pool
+                                .insert_attestation(attestation.clone(), &state, &spec)
+                                .unwrap(),
+                        )
+                    },
+                    criterion::BatchSize::SmallInput,
+                )
+            },
+        )
+        .sample_size(10),
+    );
+}
+
+/// Insert attestations into a pool from `num_threads` threads at once.
+///
+/// With `single_lock`, every insert holds a lock shared by all the threads, as a baseline for
+/// the contention avoided by sharding the attestations.
+fn concurrent_insert_bench<T: EthSpec>(
+    c: &mut Criterion,
+    spec_desc: &str,
+    num_committees: usize,
+    num_threads: usize,
+    single_lock: bool,
+) {
+    let (state, keypairs, spec) = build_state::<T>(num_committees);
+    let attestations = pool_attestations(CONCURRENT_INSERT_DEPTH, &keypairs, &state, &spec);
+
+    let state = Arc::new(state);
+    let spec = Arc::new(spec);
+    let lock_desc = if single_lock {
+        "single_lock"
+    } else {
+        "sharded"
+    };
+
+    c.bench(
+        &format!("{}/{}_committees", spec_desc, num_committees),
+        Benchmark::new(
+            format!("concurrent_insert/{}_threads/{}", num_threads, lock_desc),
+            move |b| {
+                b.iter_batched(
+                    || (Arc::new(OperationPool::new()), Arc::new(Mutex::new(()))),
+                    |(op_pool, pool_lock)| {
+                        let chunk_size = (attestations.len() + num_threads - 1) / num_threads;
+                        let handles = attestations
+                            .chunks(chunk_size)
+                            .map(|chunk| {
+                                let chunk = chunk.to_vec();
+                                let op_pool = op_pool.clone();
+                                let pool_lock = pool_lock.clone();
+                                let state = state.clone();
+                                let spec = spec.clone();
+                                thread::spawn(move || {
+                                    for attestation in chunk {
+                                        let _guard = if single_lock {
+                                            Some(pool_lock.lock())
+                                        } else {
+                                            None
+                                        };
+                                        op_pool
+                                            .insert_attestation(attestation, &state, &spec)
+                                            .unwrap();
+                                    }
+                                })
+                            })
+                            .collect::<Vec<_>>();
+
+                        for handle in handles {
+                            handle.join().unwrap();
+                        }
+
+                        op_pool
+                    },
+                    criterion::BatchSize::SmallInput,
+                )
+            },
+        )
+        .sample_size(10),
+    );
+}
+
+fn get_attestations_bench<T: EthSpec>(
+    c: &mut Criterion,
+    spec_desc: &str,
+    num_committees: usize,
+    depth: u64,
+) {
+    let (state, keypairs, spec) = build_state::<T>(num_committees);
+
+    let op_pool = OperationPool::new();
+    for attestation in pool_attestations(depth, &keypairs, &state, &spec) {
+        op_pool
+            .insert_attestation(attestation, &state, &spec)
+            .unwrap();
+    }
+
+    c.bench(
+        &format!("{}/{}_committees", spec_desc, num_committees),
+        Benchmark::new(format!("get_attestations/{}_slots", depth), move |b| {
+            b.iter(|| black_box(op_pool.get_attestations(&state, &spec).unwrap()))
+        })
+        .sample_size(10),
+    );
+}
+
+fn prune_all_bench<T: EthSpec>(
+    c: &mut Criterion,
+    spec_desc: &str,
+    num_committees: usize,
+    depth: u64,
+) {
+    let (state, keypairs, spec) = build_state::<T>(num_committees);
+    let attestations = pool_attestations(depth, &keypairs, &state, &spec);
+
+    // Advance far enough that every attestation is pruned.
+    let mut finalized_state = state.clone();
+    finalized_state.slot += 2 * T::slots_per_epoch();
+
+    c.bench(
+        &format!("{}/{}_committees", spec_desc, num_committees),
+        Benchmark::new(format!("prune_all/{}_slots", depth), move |b| {
+            b.iter_batched_ref(
+                || {
+                    let op_pool = OperationPool::new();
+                    for attestation in &attestations {
+                        op_pool
+                            .insert_attestation(attestation.clone(), &state, &spec)
+                            .unwrap();
+                    }
+                    op_pool
+                },
+                |op_pool| black_box(op_pool.prune_all(&finalized_state, &spec)),
+                criterion::BatchSize::SmallInput,
+            )
+        })
+        .sample_size(10),
+    );
+}
+
+fn all_benches(c: &mut Criterion) {
+    for &num_committees in NUM_COMMITTEES {
+        insert_attestation_bench::<MainnetEthSpec>(c, "mainnet", num_committees);
+
+        for &num_threads in NUM_THREADS {
+            for &single_lock in &[false, true] {
+                concurrent_insert_bench::<MainnetEthSpec>(
+                    c,
+                    "mainnet",
+                    num_committees,
+                    num_threads,
+                    single_lock,
+                );
+            }
+        }
+
+        for &depth in POOL_DEPTHS {
+            get_attestations_bench::<MainnetEthSpec>(c, "mainnet", num_committees, depth);
+            prune_all_bench::<MainnetEthSpec>(c, "mainnet", num_committees, depth);
+        }
+    }
+}
+
+criterion_group!(benches, all_benches,);
+criterion_main!(benches);
@@ -0,0 +1,32 @@
+use types::{Attestation, EthSpec};
+
+/// Determines how attestations inserted into the `OperationPool` are aggregated with the
+/// attestations (for the same data) that are already present.
+pub trait AggregationStrategy<T: EthSpec> {
+    /// Returns `true` if `incoming` may be aggregated into `existing`.
+    fn should_aggregate(&self, existing: &Attestation<T>, incoming: &Attestation<T>) -> bool;
+
+    /// Returns the indices of the `existing` attestations that `incoming` should be aggregated
+    /// into.
+    ///
+    /// If no indices are returned, `incoming` is stored as a separate attestation.
+    fn select_targets(&self, existing: &[Attestation<T>], incoming: &Attestation<T>) -> Vec<usize> {
+        existing
+            .iter()
+            .enumerate()
+            .filter(|(_, attestation)| self.should_aggregate(attestation, incoming))
+            .map(|(i, _)| i)
+            .collect()
+    }
+}
+
+/// Aggregates an incoming attestation into every existing attestation whose signers are disjoint
+/// from its own.
+#[derive(Default, Debug, Clone, Copy, PartialEq)]
+pub struct DisjointAggregationStrategy;
+
+impl<T: EthSpec> AggregationStrategy<T> for DisjointAggregationStrategy {
+    fn should_aggregate(&self, existing: &Attestation<T>, incoming: &Attestation<T>) -> bool {
+        existing.signers_disjoint_from(incoming)
+    }
+}
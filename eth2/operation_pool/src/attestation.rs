@@ -1,7 +1,7 @@
 use crate::max_cover::MaxCover;
 use state_processing::common::{get_attesting_indices, get_base_reward};
 use std::collections::HashMap;
-use types::{Attestation, BeaconState, BitList, ChainSpec, EthSpec};
+use types::{Attestation, BeaconState, BitList, ChainSpec, EthSpec, Hash256, Slot};
 
 pub struct AttMaxCover<'a, T: EthSpec> {
     /// Underlying attestation.
@@ -40,6 +40,7 @@ impl<'a, T: EthSpec> AttMaxCover<'a, T> {
 impl<'a, T: EthSpec> MaxCover for AttMaxCover<'a, T> {
     type Object = Attestation<T>;
     type Set = HashMap<u64, u64>;
+    type Key = (Slot, u64, Vec<u8>, Hash256);
 
     fn object(&self) -> Attestation<T> {
         self.att.clone()
@@ -68,15 +69,30 @@ impl<'a, T: EthSpec> MaxCover for AttMaxCover<'a, T> {
     fn score(&self) -> usize {
         self.fresh_validators_rewards.values().sum::<u64>() as usize
     }
+
+    /// Attestations of equal value are ordered by committee, then aggregation bits, then head
+    /// block, which is independent of the order in which they are stored in the pool and much
+    /// cheaper than hashing them.
+    fn key(&self) -> Self::Key {
+        (
+            self.att.data.slot,
+            self.att.data.index,
+            self.att.aggregation_bits.as_slice().to_vec(),
+            self.att.data.beacon_block_root,
+        )
+    }
 }
 
 /// Extract the validators for which `attestation` would be their earliest in the epoch.
 ///
 /// The reward paid to a proposer for including an attestation is proportional to the number
 /// of validators for which the included attestation is their first in the epoch. The attestation
-/// is judged against the state's `current_epoch_attestations` or `previous_epoch_attestations`
-/// depending on when it was created, and all those validators who have already attested are
-/// removed from the `aggregation_bits` before returning it.
+/// is judged against the state's `current_epoch_attestations` or `previous_epoch_attestations`,
+/// according to its target epoch, and all those validators who have already attested are removed
+/// from the `aggregation_bits` before returning it.
+///
+/// If the target epoch is neither the current nor the previous epoch of `state`, the attestation
+/// cannot be included and an empty `BitList` is returned.
 // TODO: This could be optimised with a map from validator index to whether that validator has
 // attested in each of the current and previous epochs. Currently quadratic in number of validators.
 pub fn earliest_attestation_validators<T: EthSpec>(
@@ -2,9 +2,12 @@ use int_to_bytes::int_to_bytes8;
 use serde_derive::{Deserialize, Serialize};
 use ssz::ssz_encode;
 use ssz_derive::{Decode, Encode};
-use types::{AttestationData, BeaconState, ChainSpec, Domain, Epoch, EthSpec};
+use types::{AttestationData, BeaconState, ChainSpec, Domain, Epoch, EthSpec, Fork};
 
 /// Serialized `AttestationData` augmented with a domain to encode the fork info.
+///
+/// IDs are ordered lexicographically by their bytes, e.g. to key attester slashings by a sorted
+/// pair of IDs.
 #[derive(
     PartialEq, Eq, Clone, Hash, Debug, PartialOrd, Ord, Encode, Decode, Serialize, Deserialize,
 )]
@@ -20,10 +23,23 @@ impl AttestationId {
         attestation: &AttestationData,
         state: &BeaconState<T>,
         spec: &ChainSpec,
+    ) -> Self {
+        Self::from_data_and_fork(attestation, &state.fork, spec)
+    }
+
+    /// Compute the ID of `attestation` using `fork` directly, rather than the fork of a state.
+    pub fn from_data_and_fork(
+        attestation: &AttestationData,
+        fork: &Fork,
+        spec: &ChainSpec,
     ) -> Self {
         let mut bytes = ssz_encode(attestation);
         let epoch = attestation.target.epoch;
-        bytes.extend_from_slice(&AttestationId::compute_domain_bytes(epoch, state, spec));
+        bytes.extend_from_slice(&int_to_bytes8(spec.get_domain(
+            epoch,
+            Domain::BeaconAttester,
+            fork,
+        )));
         AttestationId { v: bytes }
     }
 
@@ -39,3 +55,68 @@ impl AttestationId {
         &self.v[self.v.len() - DOMAIN_BYTES_LEN..] == domain_bytes
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use types::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use types::{Eth1Data, MinimalEthSpec};
+
+    #[test]
+    fn from_data_and_fork_matches_from_data() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = MinimalEthSpec::default_spec();
+
+        let mut state = BeaconState::<MinimalEthSpec>::new(0, Eth1Data::default(), &spec);
+        state.fork = Fork {
+            previous_version: [1, 0, 0, 0],
+            current_version: [2, 0, 0, 0],
+            epoch: Epoch::new(4),
+        };
+
+        for _ in 0..8 {
+            let data = AttestationData::random_for_test(&mut rng);
+
+            assert_eq!(
+                AttestationId::from_data_and_fork(&data, &state.fork, &spec),
+                AttestationId::from_data(&data, &state, &spec)
+            );
+        }
+    }
+
+    #[test]
+    fn ordering_is_total_and_matches_bytes() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let spec = MinimalEthSpec::default_spec();
+        let fork = Fork::default();
+
+        let ids = (0..16)
+            .map(|_| {
+                AttestationId::from_data_and_fork(
+                    &AttestationData::random_for_test(&mut rng),
+                    &fork,
+                    &spec,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        for a in &ids {
+            for b in &ids {
+                assert_eq!(a.cmp(b), a.v.cmp(&b.v));
+                assert_eq!(a.cmp(b), b.cmp(a).reverse());
+                assert_eq!(a.partial_cmp(b), Some(a.cmp(b)));
+                assert_eq!(a.cmp(b) == std::cmp::Ordering::Equal, a == b);
+            }
+        }
+
+        let mut sorted = ids.clone();
+        sorted.sort();
+        assert!(sorted.windows(2).all(|pair| pair[0] <= pair[1]));
+
+        // Sorting is independent of the initial order.
+        let mut reversed = ids;
+        reversed.reverse();
+        reversed.sort();
+        assert_eq!(reversed, sorted);
+    }
+}
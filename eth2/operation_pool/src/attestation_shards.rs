@@ -0,0 +1,383 @@
+use crate::attestation_id::AttestationId;
+use parking_lot::{RwLock, RwLockReadGuard, RwLockWriteGuard};
+use ssz::Encode;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::iter::FromIterator;
+use std::ops::Deref;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use types::{Attestation, EthSpec, Slot};
+
+/// The number of independently-locked shards the attestations are split across.
+pub const NUM_ATTESTATION_SHARDS: usize = 16;
+
+/// Once the attestations exceed their byte budget, they are evicted down to this percentage of
+/// it, so the next few inserts don't each lock every shard to evict again.
+pub const EVICTION_LOW_WATER_PERCENT: usize = 90;
+
+pub type AttestationMap<T> = HashMap<AttestationId, Vec<Attestation<T>>>;
+
+/// Orders the IDs in a shard for eviction: those whose attestations cover the fewest validators
+/// first, with ties broken in favour of evicting older IDs.
+type EvictionKey = (usize, Slot, AttestationId);
+
+/// One shard of an `AttestationShards`, which derefs to its map from ID to attestations.
+#[derive(Debug, Default, PartialEq)]
+pub struct AttestationShard<T: EthSpec> {
+    attestations: AttestationMap<T>,
+    /// The eviction key of every ID in `attestations`.
+    eviction_order: BTreeSet<EvictionKey>,
+}
+
+impl<T: EthSpec> AttestationShard<T> {
+    /// Inserts the non-empty `attestations` for `id`, which must not already be present.
+    fn insert(&mut self, id: AttestationId, attestations: Vec<Attestation<T>>) {
+        if let Some(key) = eviction_key(&id, &attestations) {
+            self.eviction_order.insert(key);
+        }
+        self.attestations.insert(id, attestations);
+    }
+
+    /// Removes and returns the attestations for `id`, if any.
+    fn remove(&mut self, id: &AttestationId) -> Option<Vec<Attestation<T>>> {
+        let attestations = self.attestations.remove(id)?;
+        if let Some(key) = eviction_key(id, &attestations) {
+            self.eviction_order.remove(&key);
+        }
+        Some(attestations)
+    }
+}
+
+impl<T: EthSpec> Deref for AttestationShard<T> {
+    type Target = AttestationMap<T>;
+
+    fn deref(&self) -> &AttestationMap<T> {
+        &self.attestations
+    }
+}
+
+/// A map from attestation ID to attestations, split across several shards so that operations on
+/// IDs in different shards do not contend for the same lock.
+///
+/// The shard for an ID is determined by its hash, so a given ID always resides in the same shard.
+///
+/// Every change to the attestations is made through this type, so that it can keep a running
+/// total of their size and number of IDs, and the order in which to evict IDs, without locking
+/// every shard to recompute them.
+#[derive(Debug)]
+pub struct AttestationShards<T: EthSpec> {
+    shards: Vec<RwLock<AttestationShard<T>>>,
+    /// Total SSZ size of the attestations in every shard, in bytes.
+    ///
+    /// Only updated whilst holding a write lock on the shard that changed.
+    num_bytes: AtomicUsize,
+    /// Total number of IDs in every shard, updated in the same way as `num_bytes`.
+    num_ids: AtomicUsize,
+    /// Incremented after every change to the attestations, whilst the changed shard is locked.
+    generation: AtomicUsize,
+}
+
+impl<T: EthSpec> AttestationShards<T> {
+    /// Returns the shard which contains (or would contain) `id`.
+    fn shard(&self, id: &AttestationId) -> &RwLock<AttestationShard<T>> {
+        let mut hasher = DefaultHasher::new();
+        id.hash(&mut hasher);
+        &self.shards[hasher.finish() as usize % self.shards.len()]
+    }
+
+    /// Takes a read lock on the shard which contains (or would contain) `id`.
+    pub fn read_shard(&self, id: &AttestationId) -> RwLockReadGuard<AttestationShard<T>> {
+        self.shard(id).read()
+    }
+
+    /// Takes a read lock on every shard, in order.
+    pub fn read_all(&self) -> Vec<RwLockReadGuard<AttestationShard<T>>> {
+        self.shards.iter().map(RwLock::read).collect()
+    }
+
+    /// Takes a write lock on every shard, in order.
+    fn write_all(&self) -> Vec<RwLockWriteGuard<AttestationShard<T>>> {
+        self.shards.iter().map(RwLock::write).collect()
+    }
+
+    /// Total SSZ size of every attestation, in bytes.
+    pub fn num_bytes(&self) -> usize {
+        self.num_bytes.load(Ordering::Relaxed)
+    }
+
+    /// Total number of IDs with at least one attestation.
+    pub fn num_ids(&self) -> usize {
+        self.num_ids.load(Ordering::Relaxed)
+    }
+
+    /// A number which changes whenever the attestations do.
+    ///
+    /// Reading this before reading the attestations guarantees that, if they change before they
+    /// are read, a later call will return a different number.
+    pub fn generation(&self) -> usize {
+        self.generation.load(Ordering::Acquire)
+    }
+
+    /// Calls `f` with the attestations for `id` (empty if there are none) whilst holding a write
+    /// lock on their shard, and returns its result.
+    ///
+    /// The ID is removed if `f` leaves it without any attestations.
+    pub fn update<F, R>(&self, id: AttestationId, f: F) -> R
+    where
+        F: FnOnce(&mut Vec<Attestation<T>>) -> R,
+    {
+        let mut shard = self.shard(&id).write();
+
+        let mut attestations = shard.remove(&id).unwrap_or_default();
+        let existed = !attestations.is_empty();
+        let bytes_before = ssz_bytes_len(&attestations);
+
+        let result = f(&mut attestations);
+
+        let exists = !attestations.is_empty();
+        let bytes_after = ssz_bytes_len(&attestations);
+        if exists {
+            shard.insert(id, attestations);
+        }
+
+        if bytes_after >= bytes_before {
+            self.num_bytes
+                .fetch_add(bytes_after - bytes_before, Ordering::Relaxed);
+        } else {
+            self.num_bytes
+                .fetch_sub(bytes_before - bytes_after, Ordering::Relaxed);
+        }
+        if exists && !existed {
+            self.num_ids.fetch_add(1, Ordering::Relaxed);
+        } else if existed && !exists {
+            self.num_ids.fetch_sub(1, Ordering::Relaxed);
+        }
+        self.generation.fetch_add(1, Ordering::Release);
+
+        result
+    }
+
+    /// Removes the attestations for `id` from `shard`, which must be locked, and updates the
+    /// running totals.
+    fn remove_locked(
+        &self,
+        shard: &mut AttestationShard<T>,
+        id: &AttestationId,
+    ) -> Option<Vec<Attestation<T>>> {
+        let attestations = shard.remove(id)?;
+        self.num_bytes
+            .fetch_sub(ssz_bytes_len(&attestations), Ordering::Relaxed);
+        self.num_ids.fetch_sub(1, Ordering::Relaxed);
+        self.generation.fetch_add(1, Ordering::Release);
+        Some(attestations)
+    }
+
+    /// Retains only the IDs for which `keep` returns `true`, locking one shard at a time.
+    pub fn retain<F>(&self, mut keep: F)
+    where
+        F: FnMut(&AttestationId, &[Attestation<T>]) -> bool,
+    {
+        for shard in &self.shards {
+            let mut shard = shard.write();
+            let removed = shard
+                .iter()
+                .filter(|(id, attestations)| !keep(id, attestations))
+                .map(|(id, _)| id.clone())
+                .collect::<Vec<_>>();
+            for id in removed {
+                self.remove_locked(&mut shard, &id);
+            }
+        }
+    }
+
+    /// If the total size of the attestations exceeds `max_bytes`, evicts attestations until it is
+    /// no more than `EVICTION_LOW_WATER_PERCENT` of `max_bytes`, returning the number evicted.
+    ///
+    /// Attestations with fewer attesters are evicted first, with ties broken in favour of
+    /// evicting older attestations. Every shard is locked, but only if the attestations are
+    /// larger than `max_bytes`.
+    pub fn evict_to_size(&self, max_bytes: usize) -> usize {
+        if self.num_bytes() <= max_bytes {
+            return 0;
+        }
+
+        let mut shards = self.write_all();
+
+        // Nothing else can change the attestations whilst every shard is locked, so the running
+        // total is exact.
+        let mut num_bytes = self.num_bytes();
+        if num_bytes <= max_bytes {
+            return 0;
+        }
+        let target_bytes = max_bytes - max_bytes / 100 * (100 - EVICTION_LOW_WATER_PERCENT);
+
+        let mut candidates = shards
+            .iter()
+            .enumerate()
+            .flat_map(|(shard_index, shard)| {
+                shard.iter().flat_map(move |(id, attestations)| {
+                    attestations
+                        .iter()
+                        .enumerate()
+                        .map(move |(i, attestation)| (shard_index, id, i, attestation))
+                })
+            })
+            .collect::<Vec<_>>();
+        candidates.sort_by_key(|(_, _, _, attestation)| {
+            (
+                attestation.aggregation_bits.num_set_bits(),
+                attestation.data.slot,
+            )
+        });
+
+        let mut evicted = HashSet::new();
+        for (shard_index, id, i, attestation) in candidates {
+            if num_bytes <= target_bytes {
+                break;
+            }
+            num_bytes -= attestation.ssz_bytes_len();
+            evicted.insert((shard_index, id.clone(), i));
+        }
+
+        let evicted_ids = evicted
+            .iter()
+            .map(|(shard_index, id, _)| (*shard_index, id.clone()))
+            .collect::<HashSet<_>>();
+        for (shard_index, id) in evicted_ids {
+            let shard = &mut shards[shard_index];
+            if let Some(mut attestations) = self.remove_locked(shard, &id) {
+                let mut i = 0;
+                attestations.retain(|_| {
+                    let keep = !evicted.contains(&(shard_index, id.clone(), i));
+                    i += 1;
+                    keep
+                });
+                if !attestations.is_empty() {
+                    self.num_bytes
+                        .fetch_add(ssz_bytes_len(&attestations), Ordering::Relaxed);
+                    self.num_ids.fetch_add(1, Ordering::Relaxed);
+                    shard.insert(id, attestations);
+                }
+            }
+        }
+
+        evicted.len()
+    }
+
+    /// Evicts IDs until there are no more than `max_ids`, returning the number of attestations
+    /// evicted.
+    ///
+    /// IDs whose attestations cover fewer validators are evicted first, with ties broken in
+    /// favour of evicting older IDs. Each shard keeps its IDs in eviction order, so only the
+    /// first ID of each shard is considered, and only the shard it is evicted from is written.
+    pub fn evict_to_len(&self, max_ids: usize) -> usize {
+        let mut num_evicted = 0;
+
+        while self.num_ids() > max_ids {
+            let next = self
+                .shards
+                .iter()
+                .enumerate()
+                .filter_map(|(shard_index, shard)| {
+                    let first = shard.read().eviction_order.iter().next().cloned();
+                    first.map(|key| (key, shard_index))
+                })
+                .min();
+            let (key, shard_index) = match next {
+                Some(next) => next,
+                None => break,
+            };
+
+            // The shard may have changed since it was read, in which case look again.
+            let mut shard = self.shards[shard_index].write();
+            if self.num_ids() > max_ids && shard.eviction_order.contains(&key) {
+                let (_, _, id) = key;
+                if let Some(attestations) = self.remove_locked(&mut shard, &id) {
+                    num_evicted += attestations.len();
+                }
+            }
+        }
+
+        num_evicted
+    }
+}
+
+impl<T: EthSpec> Default for AttestationShards<T> {
+    fn default() -> Self {
+        Self {
+            shards: (0..NUM_ATTESTATION_SHARDS)
+                .map(|_| RwLock::default())
+                .collect(),
+            num_bytes: AtomicUsize::default(),
+            num_ids: AtomicUsize::default(),
+            generation: AtomicUsize::default(),
+        }
+    }
+}
+
+impl<T: EthSpec> FromIterator<(AttestationId, Vec<Attestation<T>>)> for AttestationShards<T> {
+    fn from_iter<I>(iter: I) -> Self
+    where
+        I: IntoIterator<Item = (AttestationId, Vec<Attestation<T>>)>,
+    {
+        let shards = Self::default();
+        for (id, attestations) in iter {
+            shards.update(id, |existing| *existing = attestations);
+        }
+        shards
+    }
+}
+
+impl<T: EthSpec> IntoIterator for AttestationShards<T> {
+    type Item = (AttestationId, Vec<Attestation<T>>);
+    type IntoIter = std::iter::Flatten<std::vec::IntoIter<AttestationMap<T>>>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.shards
+            .into_iter()
+            .map(|shard| shard.into_inner().attestations)
+            .collect::<Vec<_>>()
+            .into_iter()
+            .flatten()
+    }
+}
+
+impl<T: EthSpec> PartialEq for AttestationShards<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.shards.len() == other.shards.len()
+            && self
+                .shards
+                .iter()
+                .zip(other.shards.iter())
+                .all(|(a, b)| a.read().attestations == b.read().attestations)
+    }
+}
+
+/// Total SSZ size of `attestations`, in bytes.
+fn ssz_bytes_len<T: EthSpec>(attestations: &[Attestation<T>]) -> usize {
+    attestations.iter().map(Encode::ssz_bytes_len).sum()
+}
+
+/// The key under which the non-empty `attestations` for `id` are ordered for eviction.
+fn eviction_key<T: EthSpec>(
+    id: &AttestationId,
+    attestations: &[Attestation<T>],
+) -> Option<EvictionKey> {
+    // All the attestations for an ID have the same data, so the first one has the slot of all.
+    let slot = attestations.first()?.data.slot;
+    Some((num_covered_validators(attestations), slot, id.clone()))
+}
+
+/// The number of distinct validators which signed any of `attestations`.
+///
+/// Assumes the attestations are all from the same committee.
+fn num_covered_validators<T: EthSpec>(attestations: &[Attestation<T>]) -> usize {
+    let mut iter = attestations.iter();
+    iter.next().map_or(0, |first| {
+        iter.fold(first.aggregation_bits.clone(), |bits, attestation| {
+            bits.union(&attestation.aggregation_bits)
+        })
+        .num_set_bits()
+    })
+}
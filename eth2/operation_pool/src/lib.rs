@@ -1,43 +1,123 @@
+mod aggregation_strategy;
 mod attestation;
 mod attestation_id;
+mod attestation_shards;
 mod max_cover;
 mod persistence;
 
-pub use persistence::PersistedOperationPool;
+pub use aggregation_strategy::{AggregationStrategy, DisjointAggregationStrategy};
+pub use attestation::earliest_attestation_validators;
+pub use attestation_id::AttestationId;
+pub use persistence::{Error as PersistenceError, PersistedOperationPool};
 
 use attestation::AttMaxCover;
-use attestation_id::AttestationId;
+use attestation_shards::{AttestationShard, AttestationShards};
 use max_cover::maximum_cover;
-use parking_lot::RwLock;
+use parking_lot::{RwLock, RwLockReadGuard};
+use ssz::Encode;
 use state_processing::per_block_processing::errors::{
     AttestationValidationError, AttesterSlashingValidationError, ExitValidationError,
     ProposerSlashingValidationError,
 };
 use state_processing::per_block_processing::{
-    get_slashable_indices_modular, verify_attestation_for_block_inclusion,
-    verify_attester_slashing, verify_exit, verify_exit_time_independent_only,
-    verify_proposer_slashing, VerifySignatures,
+    get_slashable_indices_modular, verify_attestation_for_state, verify_attester_slashing,
+    verify_exit_time_independent_only, verify_proposer_slashing, VerifySignatures,
 };
-use std::collections::{hash_map, HashMap, HashSet};
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
 use std::marker::PhantomData;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use types::{
-    typenum::Unsigned, Attestation, AttesterSlashing, BeaconState, BeaconStateError, ChainSpec,
-    EthSpec, ProposerSlashing, RelativeEpoch, SignedVoluntaryExit, Validator,
+    typenum::Unsigned, Attestation, AttesterSlashing, BeaconBlock, BeaconState, BeaconStateError,
+    ChainSpec, Epoch, EthSpec, Hash256, ProposerSlashing, RelativeEpoch, SignedVoluntaryExit, Slot,
+    Validator,
 };
 
 #[derive(Default, Debug)]
-pub struct OperationPool<T: EthSpec + Default> {
-    /// Map from attestation ID (see below) to vectors of attestations.
-    attestations: RwLock<HashMap<AttestationId, Vec<Attestation<T>>>>,
+pub struct OperationPool<T: EthSpec + Default, S = DisjointAggregationStrategy> {
+    /// Map from attestation ID (see below) to vectors of attestations, sharded to reduce lock
+    /// contention between concurrent inserts.
+    attestations: AttestationShards<T>,
+    /// The last result of `get_attestations_cached`.
+    attestations_cache: RwLock<Option<CachedAttestations<T>>>,
     /// Map from two attestation IDs to a slashing for those IDs.
     attester_slashings: RwLock<HashMap<(AttestationId, AttestationId), AttesterSlashing<T>>>,
     /// Map from proposer index to slashing.
     proposer_slashings: RwLock<HashMap<u64, ProposerSlashing>>,
     /// Map from exiting validator to their exit data.
     voluntary_exits: RwLock<HashMap<u64, SignedVoluntaryExit>>,
+    /// Determines how inserted attestations are aggregated with existing ones.
+    aggregation_strategy: S,
+    /// Upper bound on the total SSZ size of all operations in the pool, if any.
+    max_pool_bytes: Option<usize>,
+    /// Upper bound on the number of distinct attestation IDs in the pool, if any.
+    max_attestation_data_buckets: Option<usize>,
+    /// Upper bound on the number of proposer slashings in the pool, if any.
+    max_proposer_slashings: Option<usize>,
+    /// Number of attestations evicted to keep the pool within `max_pool_bytes`.
+    num_evicted_attestations: AtomicUsize,
     _phantom: PhantomData<T>,
 }
 
+/// A result of `OperationPool::get_attestations_cached`, along with what it was computed from.
+#[derive(Debug)]
+struct CachedAttestations<T: EthSpec> {
+    /// The generation of the pool's attestations when they were selected.
+    generation: usize,
+    /// The slot and latest block header root of the state they were selected for.
+    key: (Slot, Hash256),
+    attestations: Vec<Attestation<T>>,
+}
+
+/// The number of operations of each kind in an `OperationPool`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PoolStats {
+    /// Total number of attestations, including attestations for the same data.
+    pub num_attestations: usize,
+    /// Number of distinct attestation IDs, i.e., distinct attestation data and fork.
+    pub num_unique_attestation_data: usize,
+    pub num_attester_slashings: usize,
+    pub num_proposer_slashings: usize,
+    pub num_voluntary_exits: usize,
+}
+
+/// The number of operations of each kind removed by `OperationPool::prune_all`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PruneReport {
+    /// Total number of attestations removed, including attestations for the same data.
+    pub attestations_removed: usize,
+    pub proposer_slashings_removed: usize,
+    pub attester_slashings_removed: usize,
+    pub voluntary_exits_removed: usize,
+}
+
+/// The operations from an `OperationPool` to be included in a block.
+///
+/// Returned by `OperationPool::get_block_operations`.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct BlockOperations<T: EthSpec> {
+    pub attestations: Vec<Attestation<T>>,
+    pub proposer_slashings: Vec<ProposerSlashing>,
+    pub attester_slashings: Vec<AttesterSlashing<T>>,
+    pub voluntary_exits: Vec<SignedVoluntaryExit>,
+}
+
+/// A copy of every operation in an `OperationPool`, taken at a single instant.
+///
+/// Returned by `OperationPool::snapshot`. It holds no locks, so it can be inspected at leisure
+/// without blocking the pool.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct OperationPoolSnapshot<T: EthSpec> {
+    /// Map from attestation ID to attestations, merged across all shards.
+    pub attestations: HashMap<AttestationId, Vec<Attestation<T>>>,
+    /// Map from two attestation IDs to a slashing for those IDs.
+    pub attester_slashings: HashMap<(AttestationId, AttestationId), AttesterSlashing<T>>,
+    /// Map from proposer index to slashing.
+    pub proposer_slashings: HashMap<u64, ProposerSlashing>,
+    /// Map from exiting validator to their exit data.
+    pub voluntary_exits: HashMap<u64, SignedVoluntaryExit>,
+}
+
 #[derive(Debug, PartialEq)]
 pub enum OpPoolError {
     GetAttestationsTotalBalanceError(BeaconStateError),
@@ -49,7 +129,80 @@ impl<T: EthSpec> OperationPool<T> {
         Self::default()
     }
 
-    /// Insert an attestation into the pool, aggregating it with existing attestations if possible.
+    /// Compute the tuple ID that is used to identify an attester slashing.
+    ///
+    /// The IDs of the two attestations are sorted, so that slashings which differ only in the
+    /// order of their attestations share an ID.
+    ///
+    /// Depends on the fork field of the state, but not on the state's epoch.
+    fn attester_slashing_id(
+        slashing: &AttesterSlashing<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> (AttestationId, AttestationId) {
+        let id_1 = AttestationId::from_data(&slashing.attestation_1.data, state, spec);
+        let id_2 = AttestationId::from_data(&slashing.attestation_2.data, state, spec);
+
+        if id_1 <= id_2 {
+            (id_1, id_2)
+        } else {
+            (id_2, id_1)
+        }
+    }
+}
+
+impl<T: EthSpec, S: AggregationStrategy<T>> OperationPool<T, S> {
+    /// Create a new operation pool which aggregates attestations using `aggregation_strategy`.
+    pub fn with_aggregation_strategy(aggregation_strategy: S) -> Self {
+        Self {
+            attestations: AttestationShards::default(),
+            attestations_cache: RwLock::default(),
+            attester_slashings: RwLock::default(),
+            proposer_slashings: RwLock::default(),
+            voluntary_exits: RwLock::default(),
+            aggregation_strategy,
+            max_pool_bytes: None,
+            max_attestation_data_buckets: None,
+            max_proposer_slashings: None,
+            num_evicted_attestations: AtomicUsize::default(),
+            _phantom: PhantomData,
+        }
+    }
+
+    /// Limit the total SSZ size of all operations in the pool to `max_pool_bytes`.
+    ///
+    /// Whenever an insert takes the pool over this limit, the attestations with the fewest
+    /// attesters are evicted until it is back within the limit.
+    pub fn with_max_pool_bytes(mut self, max_pool_bytes: usize) -> Self {
+        self.max_pool_bytes = Some(max_pool_bytes);
+        self
+    }
+
+    /// Limit the number of distinct attestation IDs (i.e., buckets of attestations with the same
+    /// data) in the pool to `max_attestation_data_buckets`.
+    ///
+    /// Whenever an insert takes the pool over this limit, the buckets which cover the fewest
+    /// validators are evicted until it is back within the limit.
+    pub fn with_max_attestation_data_buckets(
+        mut self,
+        max_attestation_data_buckets: usize,
+    ) -> Self {
+        self.max_attestation_data_buckets = Some(max_attestation_data_buckets);
+        self
+    }
+
+    /// Limit the number of proposer slashings in the pool to `max_proposer_slashings`.
+    ///
+    /// Whenever an insert takes the pool over this limit, the slashings of the proposers with the
+    /// lowest effective balances (i.e., the smallest whistleblower rewards) are evicted until it
+    /// is back within the limit.
+    pub fn with_max_proposer_slashings(mut self, max_proposer_slashings: usize) -> Self {
+        self.max_proposer_slashings = Some(max_proposer_slashings);
+        self
+    }
+
+    /// Insert an attestation into the pool, aggregating it with existing attestations as directed
+    /// by the pool's `AggregationStrategy`.
     ///
     /// ## Note
     ///
@@ -62,37 +215,227 @@ impl<T: EthSpec> OperationPool<T> {
     ) -> Result<(), AttestationValidationError> {
         let id = AttestationId::from_data(&attestation.data, state, spec);
 
-        // Take a write lock on the attestations map.
-        let mut attestations = self.attestations.write();
+        // Take a write lock on the shard containing `id`, releasing it before eviction, which
+        // locks every shard. Any change to the attestations bumps their generation, outdating
+        // the result of `get_attestations_cached`, so no other lock is needed.
+        self.attestations.update(id, |existing_attestations| {
+            self.aggregate_into(existing_attestations, attestation)
+        });
 
-        let existing_attestations = match attestations.entry(id) {
-            hash_map::Entry::Vacant(entry) => {
-                entry.insert(vec![attestation]);
-                return Ok(());
-            }
-            hash_map::Entry::Occupied(entry) => entry.into_mut(),
-        };
+        self.evict_attestation_buckets_over_limit();
+        self.evict_attestations_over_budget();
+
+        Ok(())
+    }
+
+    /// Aggregate `attestation` into `existing_attestations`, which all have the same ID, as
+    /// directed by the pool's `AggregationStrategy`.
+    fn aggregate_into(
+        &self,
+        existing_attestations: &mut Vec<Attestation<T>>,
+        attestation: Attestation<T>,
+    ) {
+        // All the attestations with this ID are from the same committee, so an attestation signed
+        // by the entire committee covers any we could insert and there is no need to scan the
+        // others.
+        if existing_attestations.iter().any(is_fully_aggregated) {
+            return;
+        }
+
+        let is_duplicate = existing_attestations.contains(&attestation);
 
-        let mut aggregated = false;
-        for existing_attestation in existing_attestations.iter_mut() {
-            if existing_attestation.signers_disjoint_from(&attestation) {
+        let targets = self
+            .aggregation_strategy
+            .select_targets(existing_attestations, &attestation);
+
+        for &i in &targets {
+            if let Some(existing_attestation) = existing_attestations.get_mut(i) {
                 existing_attestation.aggregate(&attestation);
-                aggregated = true;
-            } else if *existing_attestation == attestation {
-                aggregated = true;
             }
         }
 
-        if !aggregated {
+        if targets.is_empty() && !is_duplicate {
             existing_attestations.push(attestation);
         }
+    }
 
-        Ok(())
+    /// Verify an attestation from an untrusted source (e.g., gossip) against `state`, including
+    /// its signature, and insert it into the pool only if it is valid.
+    ///
+    /// The attestation need not be includable in a block at `state.slot` yet, so the inclusion
+    /// delay and inclusion window are not checked.
+    pub fn insert_attestation_verified(
+        &self,
+        attestation: Attestation<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> Result<(), AttestationValidationError> {
+        verify_attestation_for_state(state, &attestation, VerifySignatures::True, spec)?;
+        self.insert_attestation(attestation, state, spec)
+    }
+
+    /// Returns `true` if the pool already holds `attestation`, or an aggregate whose attesters
+    /// include all of those of `attestation`.
+    ///
+    /// Only takes a read lock on the shard containing the attestation's ID.
+    pub fn contains_attestation(
+        &self,
+        attestation: &Attestation<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> bool {
+        let id = AttestationId::from_data(&attestation.data, state, spec);
+
+        self.attestations
+            .read_shard(&id)
+            .get(&id)
+            .map_or(false, |existing_attestations| {
+                existing_attestations.iter().any(|existing| {
+                    attestation
+                        .aggregation_bits
+                        .difference(&existing.aggregation_bits)
+                        .is_zero()
+                })
+            })
+    }
+
+    /// Remove the attestations in the pool made redundant by `attestation`, such as when it has
+    /// been included in a block.
+    ///
+    /// An aggregate cannot have attesters removed without invalidating its signature, so only the
+    /// attestations whose attesters are all covered by `attestation` are removed. Those with any
+    /// other attesters are retained, and the bucket is only dropped once it is empty.
+    pub fn remove_attestation(
+        &self,
+        attestation: &Attestation<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) {
+        let id = AttestationId::from_data(&attestation.data, state, spec);
+
+        self.attestations.update(id, |existing_attestations| {
+            existing_attestations.retain(|existing| {
+                !existing
+                    .aggregation_bits
+                    .difference(&attestation.aggregation_bits)
+                    .is_zero()
+            });
+        });
     }
 
     /// Total number of attestations in the pool, including attestations for the same data.
     pub fn num_attestations(&self) -> usize {
-        self.attestations.read().values().map(Vec::len).sum()
+        self.attestations
+            .read_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .map(Vec::len)
+            .sum()
+    }
+
+    /// Returns a clone of every attestation in the pool, regardless of whether it could be
+    /// included in a block.
+    ///
+    /// Clones every attestation whilst holding a read lock on every shard, so it should not be
+    /// called on a hot path.
+    pub fn all_attestations(&self) -> Vec<Attestation<T>> {
+        self.attestations_filtered(|_| true)
+    }
+
+    /// Returns a clone of every attestation in the pool for `slot`.
+    ///
+    /// See `all_attestations`.
+    pub fn attestations_for_slot(&self, slot: Slot) -> Vec<Attestation<T>> {
+        self.attestations_filtered(|attestation| attestation.data.slot == slot)
+    }
+
+    /// Returns a clone of every attestation in the pool that matches `filter`.
+    fn attestations_filtered<F>(&self, filter: F) -> Vec<Attestation<T>>
+    where
+        F: Fn(&Attestation<T>) -> bool,
+    {
+        self.attestations
+            .read_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flatten()
+            .filter(|attestation| filter(attestation))
+            .cloned()
+            .collect()
+    }
+
+    /// Total SSZ size of all operations in the pool, in bytes.
+    ///
+    /// The size of the attestations is tracked as they are inserted and removed, so this does not
+    /// lock any of the attestation shards.
+    pub fn current_pool_bytes(&self) -> usize {
+        self.attestations.num_bytes() + self.non_attestation_bytes()
+    }
+
+    /// Total SSZ size of all slashings and exits in the pool, in bytes.
+    fn non_attestation_bytes(&self) -> usize {
+        let attester_slashing_bytes = self
+            .attester_slashings
+            .read()
+            .values()
+            .map(Encode::ssz_bytes_len)
+            .sum::<usize>();
+        let proposer_slashing_bytes = self
+            .proposer_slashings
+            .read()
+            .values()
+            .map(Encode::ssz_bytes_len)
+            .sum::<usize>();
+        let voluntary_exit_bytes = self
+            .voluntary_exits
+            .read()
+            .values()
+            .map(Encode::ssz_bytes_len)
+            .sum::<usize>();
+
+        attester_slashing_bytes + proposer_slashing_bytes + voluntary_exit_bytes
+    }
+
+    /// Number of attestations evicted so far to keep the pool within its `max_pool_bytes`.
+    pub fn num_evicted_attestations(&self) -> usize {
+        self.num_evicted_attestations.load(Ordering::Relaxed)
+    }
+
+    /// Evict attestations until the pool is within `max_pool_bytes`, if it is set.
+    ///
+    /// Enough attestations are evicted to leave some headroom below the budget (see
+    /// `EVICTION_LOW_WATER_PERCENT`), so that the pool isn't locked for eviction on every insert
+    /// once it is full.
+    ///
+    /// Attestations with fewer attesters are evicted first, with ties broken in favour of
+    /// evicting older attestations. Slashings and exits are never evicted. The attestation shards
+    /// are only locked if the pool is actually over budget.
+    fn evict_attestations_over_budget(&self) {
+        let max_pool_bytes = match self.max_pool_bytes {
+            Some(max_pool_bytes) => max_pool_bytes,
+            None => return,
+        };
+
+        let max_attestation_bytes = max_pool_bytes.saturating_sub(self.non_attestation_bytes());
+        let num_evicted = self.attestations.evict_to_size(max_attestation_bytes);
+        self.num_evicted_attestations
+            .fetch_add(num_evicted, Ordering::Relaxed);
+    }
+
+    /// Evict buckets of attestations until the pool is within `max_attestation_data_buckets`, if
+    /// it is set.
+    ///
+    /// Buckets covering fewer validators are evicted first, with ties broken in favour of evicting
+    /// older buckets.
+    fn evict_attestation_buckets_over_limit(&self) {
+        let max_buckets = match self.max_attestation_data_buckets {
+            Some(max_buckets) => max_buckets,
+            None => return,
+        };
+
+        let num_evicted = self.attestations.evict_to_len(max_buckets);
+        self.num_evicted_attestations
+            .fetch_add(num_evicted, Ordering::Relaxed);
     }
 
     /// Get a list of attestations for inclusion in a block.
@@ -103,56 +446,194 @@ impl<T: EthSpec> OperationPool<T> {
         state: &BeaconState<T>,
         spec: &ChainSpec,
     ) -> Result<Vec<Attestation<T>>, OpPoolError> {
-        // Attestations for the current fork, which may be from the current or previous epoch.
-        let prev_epoch = state.previous_epoch();
-        let current_epoch = state.current_epoch();
-        let prev_domain_bytes = AttestationId::compute_domain_bytes(prev_epoch, state, spec);
-        let curr_domain_bytes = AttestationId::compute_domain_bytes(current_epoch, state, spec);
-        let reader = self.attestations.read();
-        let active_indices = state
-            .get_cached_active_validator_indices(RelativeEpoch::Current)
-            .map_err(OpPoolError::GetAttestationsTotalBalanceError)?;
-        let total_active_balance = state
-            .get_total_balance(&active_indices, spec)
-            .map_err(OpPoolError::GetAttestationsTotalBalanceError)?;
-        let valid_attestations = reader
-            .iter()
-            .filter(|(key, _)| {
-                key.domain_bytes_match(&prev_domain_bytes)
-                    || key.domain_bytes_match(&curr_domain_bytes)
-            })
-            .flat_map(|(_, attestations)| attestations)
-            // That are valid...
-            .filter(|attestation| {
-                verify_attestation_for_block_inclusion(
-                    state,
-                    attestation,
-                    VerifySignatures::False,
-                    spec,
-                )
-                .is_ok()
-            })
-            .flat_map(|att| AttMaxCover::new(att, state, total_active_balance, spec));
+        self.get_attestations_limited(state, spec, T::MaxAttestations::to_usize())
+    }
+
+    /// Identical to `get_attestations`, except at most `limit` attestations are returned.
+    ///
+    /// The `limit` may exceed `T::MaxAttestations` (e.g., to fetch the top attestations for an
+    /// API), in which case the result may not fit in a block.
+    pub fn get_attestations_limited(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+        limit: usize,
+    ) -> Result<Vec<Attestation<T>>, OpPoolError> {
+        select_attestations(
+            &self.attestations.read_all(),
+            state,
+            state.slot,
+            limit,
+            spec,
+        )
+    }
 
-        Ok(maximum_cover(
-            valid_attestations,
+    /// Get a list of attestations for inclusion in a block at `target_slot`.
+    ///
+    /// The inclusion delay and inclusion window are checked relative to `target_slot` rather
+    /// than `state.slot`, so that attestations may be selected ahead of time for a future block.
+    /// All other checks are made against `state`, so `target_slot` should lie in the same epoch
+    /// as `state.slot`.
+    ///
+    /// NOTE: Assumes that all attestations in the operation_pool are valid.
+    pub fn get_attestations_for_slot(
+        &self,
+        state: &BeaconState<T>,
+        target_slot: Slot,
+        spec: &ChainSpec,
+    ) -> Result<Vec<Attestation<T>>, OpPoolError> {
+        select_attestations(
+            &self.attestations.read_all(),
+            state,
+            target_slot,
             T::MaxAttestations::to_usize(),
-        ))
+            spec,
+        )
+    }
+
+    /// Identical to `get_attestations`, except the result is memoized against the slot and
+    /// latest block header of `state`, which together identify any state produced by the state
+    /// transition, without the cost of hashing the whole state.
+    ///
+    /// Repeated calls for the same state will return the cached result, until the attestations in
+    /// the pool change (e.g., by being inserted, removed, pruned or evicted).
+    pub fn get_attestations_cached(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> Result<Vec<Attestation<T>>, OpPoolError> {
+        let key = (state.slot, state.latest_block_header.canonical_root());
+
+        // Read the generation before selecting any attestations, so that if they change during
+        // selection, the result is stored under an outdated generation and never served.
+        let generation = self.attestations.generation();
+
+        if let Some(cached) = &*self.attestations_cache.read() {
+            if cached.generation == generation && cached.key == key {
+                return Ok(cached.attestations.clone());
+            }
+        }
+
+        let attestations = self.get_attestations(state, spec)?;
+        *self.attestations_cache.write() = Some(CachedAttestations {
+            generation,
+            key,
+            attestations: attestations.clone(),
+        });
+
+        Ok(attestations)
+    }
+
+    /// Returns the attestation with the most attesters from each bucket of attestations for the
+    /// current fork of `state`, keyed by attestation ID.
+    ///
+    /// Unlike `get_attestations`, the attestations are not checked for validity against `state`
+    /// and no maximum cover is computed.
+    pub fn best_attestation_per_id(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> HashMap<AttestationId, Attestation<T>> {
+        current_fork_buckets(&self.attestations.read_all(), state, spec)
+            .filter_map(|(key, attestations)| {
+                attestations
+                    .iter()
+                    .max_by_key(|attestation| attestation.aggregation_bits.num_set_bits())
+                    .map(|attestation| (key.clone(), attestation.clone()))
+            })
+            .collect()
+    }
+
+    /// Returns the attestation with the most attesters from each bucket of attestations for the
+    /// current fork of `state`, sorted by attestation ID.
+    ///
+    /// Intended for selecting aggregates to rebroadcast. As with `best_attestation_per_id`, no
+    /// inclusion delay or other block inclusion checks are applied, so attestations which are too
+    /// new (or some which are too old) to be included in a block at `state.slot` are returned.
+    pub fn best_aggregates(&self, state: &BeaconState<T>, spec: &ChainSpec) -> Vec<Attestation<T>> {
+        let mut best = self
+            .best_attestation_per_id(state, spec)
+            .into_iter()
+            .collect::<Vec<_>>();
+        best.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        best.into_iter()
+            .map(|(_, attestation)| attestation)
+            .collect()
+    }
+
+    /// Returns a summary of each bucket of attestations for the current fork of `state`: its
+    /// attestation ID, the number of attestations stored, and the greatest number of attesters
+    /// of any one of them.
+    ///
+    /// Buckets are sorted by attestation ID. Like `best_attestation_per_id`, the attestations are
+    /// not checked for validity against `state`.
+    pub fn attestation_coverage(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> Vec<(AttestationId, usize, usize)> {
+        let mut coverage = current_fork_buckets(&self.attestations.read_all(), state, spec)
+            .map(|(key, attestations)| {
+                let max_attesters = attestations
+                    .iter()
+                    .map(|attestation| attestation.aggregation_bits.num_set_bits())
+                    .max()
+                    .unwrap_or(0);
+                (key.clone(), attestations.len(), max_attesters)
+            })
+            .collect::<Vec<_>>();
+        coverage.sort_unstable_by(|a, b| a.0.cmp(&b.0));
+        coverage
     }
 
-    /// Remove attestations which are too old to be included in a block.
-    pub fn prune_attestations(&self, finalized_state: &BeaconState<T>) {
+    /// Remove attestations which are too old to be included in a block at or after the slot of
+    /// `finalized_state`.
+    ///
+    /// Returns the number of attestations removed.
+    pub fn prune_attestations(&self, finalized_state: &BeaconState<T>) -> usize {
         // We know we can include an attestation if:
         // state.slot <= attestation_slot + SLOTS_PER_EPOCH
-        // We approximate this check using the attestation's epoch, to avoid computing
-        // the slot or relying on the committee cache of the finalized state.
-        self.attestations.write().retain(|_, attestations| {
+        self.retain_attestation_buckets(|att| {
+            finalized_state.slot <= att.data.slot + T::slots_per_epoch()
+        })
+    }
+
+    /// Remove attestations with a target epoch more than `retain_epochs` prior to the current
+    /// epoch of `finalized_state`.
+    ///
+    /// With `retain_epochs == 1` this approximates `prune_attestations` using epochs, retaining
+    /// some attestations for up to an epoch after they can no longer be included in a block.
+    /// Attestations retained for more than one epoch may still be useful for other purposes
+    /// (e.g., serving them via an API).
+    ///
+    /// Returns the number of attestations removed.
+    pub fn prune_attestations_retaining(
+        &self,
+        finalized_state: &BeaconState<T>,
+        retain_epochs: u64,
+    ) -> usize {
+        self.retain_attestation_buckets(|att| {
+            finalized_state.current_epoch() <= att.data.target.epoch + retain_epochs
+        })
+    }
+
+    /// Retain the buckets of attestations for which `keep` returns `true` for the first
+    /// attestation, returning the number of attestations removed.
+    fn retain_attestation_buckets<F>(&self, keep: F) -> usize
+    where
+        F: Fn(&Attestation<T>) -> bool,
+    {
+        let mut num_removed = 0;
+        self.attestations.retain(|_, attestations| {
             // All the attestations in this bucket have the same data, so we only need to
             // check the first one.
-            attestations.first().map_or(false, |att| {
-                finalized_state.current_epoch() <= att.data.target.epoch + 1
-            })
+            let retain = attestations.first().map_or(false, &keep);
+            if !retain {
+                num_removed += attestations.len();
+            }
+            retain
         });
+        num_removed
     }
 
     /// Insert a proposer slashing into the pool.
@@ -165,24 +646,41 @@ impl<T: EthSpec> OperationPool<T> {
         // TODO: should maybe insert anyway if the proposer is unknown in the validator index,
         // because they could *become* known later
         verify_proposer_slashing(&slashing, state, VerifySignatures::True, spec)?;
-        self.proposer_slashings
-            .write()
-            .insert(slashing.proposer_index, slashing);
+
+        let mut proposer_slashings = self.proposer_slashings.write();
+        proposer_slashings.insert(slashing.proposer_index, slashing);
+        self.evict_proposer_slashings_over_limit(&mut proposer_slashings, state);
+
+        drop(proposer_slashings);
+        self.evict_attestations_over_budget();
         Ok(())
     }
 
-    /// Compute the tuple ID that is used to identify an attester slashing.
-    ///
-    /// Depends on the fork field of the state, but not on the state's epoch.
-    fn attester_slashing_id(
-        slashing: &AttesterSlashing<T>,
+    /// Evict the proposer slashings of the validators with the lowest effective balance in
+    /// `state` until there are no more than `self.max_proposer_slashings`.
+    fn evict_proposer_slashings_over_limit(
+        &self,
+        proposer_slashings: &mut HashMap<u64, ProposerSlashing>,
         state: &BeaconState<T>,
-        spec: &ChainSpec,
-    ) -> (AttestationId, AttestationId) {
-        (
-            AttestationId::from_data(&slashing.attestation_1.data, state, spec),
-            AttestationId::from_data(&slashing.attestation_2.data, state, spec),
-        )
+    ) {
+        if let Some(max_proposer_slashings) = self.max_proposer_slashings {
+            if proposer_slashings.len() > max_proposer_slashings {
+                let effective_balance = |index: u64| {
+                    state
+                        .validators
+                        .get(index as usize)
+                        .map_or(0, |validator| validator.effective_balance)
+                };
+
+                let mut indices = proposer_slashings.keys().copied().collect::<Vec<_>>();
+                indices.sort_by_key(|&index| (effective_balance(index), index));
+
+                let num_evicted = proposer_slashings.len() - max_proposer_slashings;
+                for index in indices.into_iter().take(num_evicted) {
+                    proposer_slashings.remove(&index);
+                }
+            }
+        }
     }
 
     /// Insert an attester slashing into the pool.
@@ -193,11 +691,45 @@ impl<T: EthSpec> OperationPool<T> {
         spec: &ChainSpec,
     ) -> Result<(), AttesterSlashingValidationError> {
         verify_attester_slashing(state, &slashing, true, VerifySignatures::True, spec)?;
-        let id = Self::attester_slashing_id(&slashing, state, spec);
+        let id = OperationPool::attester_slashing_id(&slashing, state, spec);
         self.attester_slashings.write().insert(id, slashing);
+        self.evict_attestations_over_budget();
         Ok(())
     }
 
+    /// Returns `true` if the pool holds a proposer slashing for `proposer_index`.
+    pub fn contains_proposer_slashing(&self, proposer_index: u64) -> bool {
+        self.proposer_slashings.read().contains_key(&proposer_index)
+    }
+
+    /// Remove the proposer slashing for `proposer_index`, returning it if it was in the pool.
+    pub fn remove_proposer_slashing(&self, proposer_index: u64) -> Option<ProposerSlashing> {
+        self.proposer_slashings.write().remove(&proposer_index)
+    }
+
+    /// Returns `true` if the pool holds `slashing`, under the ID derived from `state`.
+    pub fn contains_attester_slashing(
+        &self,
+        slashing: &AttesterSlashing<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> bool {
+        let id = OperationPool::attester_slashing_id(slashing, state, spec);
+        self.attester_slashings.read().get(&id) == Some(slashing)
+    }
+
+    /// Remove the attester slashing with the same ID as `slashing` (derived from `state`),
+    /// returning it if it was in the pool.
+    pub fn remove_attester_slashing(
+        &self,
+        slashing: &AttesterSlashing<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> Option<AttesterSlashing<T>> {
+        let id = OperationPool::attester_slashing_id(slashing, state, spec);
+        self.attester_slashings.write().remove(&id)
+    }
+
     /// Get proposer and attester slashings for inclusion in a block.
     ///
     /// This function computes both types of slashings together, because
@@ -208,72 +740,68 @@ impl<T: EthSpec> OperationPool<T> {
         state: &BeaconState<T>,
         spec: &ChainSpec,
     ) -> (Vec<ProposerSlashing>, Vec<AttesterSlashing<T>>) {
-        let proposer_slashings = filter_limit_operations(
-            self.proposer_slashings.read().values(),
-            |slashing| {
-                state
-                    .validators
-                    .get(slashing.proposer_index as usize)
-                    .map_or(false, |validator| !validator.slashed)
-            },
+        self.get_slashings_limited(
+            state,
+            spec,
             T::MaxProposerSlashings::to_usize(),
-        );
-
-        // Set of validators to be slashed, so we don't attempt to construct invalid attester
-        // slashings.
-        let mut to_be_slashed = proposer_slashings
-            .iter()
-            .map(|s| s.proposer_index)
-            .collect::<HashSet<_>>();
-
-        let epoch = state.current_epoch();
-        let attester_slashings = self
-            .attester_slashings
-            .read()
-            .iter()
-            .filter(|(id, slashing)| {
-                // Check the fork.
-                Self::attester_slashing_id(slashing, state, spec) == **id
-            })
-            .filter(|(_, slashing)| {
-                // Take all slashings that will slash 1 or more validators.
-                let slashed_validators =
-                    get_slashable_indices_modular(state, slashing, |index, validator| {
-                        validator.is_slashable_at(epoch) && !to_be_slashed.contains(&index)
-                    });
-
-                // Extend the `to_be_slashed` set so subsequent iterations don't try to include
-                // useless slashings.
-                if let Ok(validators) = slashed_validators {
-                    to_be_slashed.extend(validators);
-                    true
-                } else {
-                    false
-                }
-            })
-            .take(T::MaxAttesterSlashings::to_usize())
-            .map(|(_, slashing)| slashing.clone())
-            .collect();
+            T::MaxAttesterSlashings::to_usize(),
+        )
+    }
 
-        (proposer_slashings, attester_slashings)
+    /// Identical to `get_slashings`, except at most `max_proposer_slashings` proposer slashings
+    /// and `max_attester_slashings` attester slashings are returned.
+    ///
+    /// This allows a block producer to trade slashings off against other operations. Attester
+    /// slashings are still only chosen for the validators which the chosen proposer slashings do
+    /// not already slash.
+    pub fn get_slashings_limited(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+        max_proposer_slashings: usize,
+        max_attester_slashings: usize,
+    ) -> (Vec<ProposerSlashing>, Vec<AttesterSlashing<T>>) {
+        // Take the locks in the same order as `snapshot`.
+        let attester_slashings = self.attester_slashings.read();
+        let proposer_slashings = self.proposer_slashings.read();
+        select_slashings(
+            &proposer_slashings,
+            &attester_slashings,
+            state,
+            state.current_epoch(),
+            max_proposer_slashings,
+            max_attester_slashings,
+            spec,
+        )
     }
 
     /// Prune proposer slashings for all slashed or withdrawn validators.
-    pub fn prune_proposer_slashings(&self, finalized_state: &BeaconState<T>) {
+    ///
+    /// Returns the number of proposer slashings removed.
+    pub fn prune_proposer_slashings(&self, finalized_state: &BeaconState<T>) -> usize {
         prune_validator_hash_map(
             &mut self.proposer_slashings.write(),
             |validator| {
                 validator.slashed || validator.is_withdrawable_at(finalized_state.current_epoch())
             },
             finalized_state,
-        );
+        )
     }
 
     /// Prune attester slashings for all slashed or withdrawn validators, or attestations on another
     /// fork.
-    pub fn prune_attester_slashings(&self, finalized_state: &BeaconState<T>, spec: &ChainSpec) {
-        self.attester_slashings.write().retain(|id, slashing| {
-            let fork_ok = &Self::attester_slashing_id(slashing, finalized_state, spec) == id;
+    ///
+    /// Returns the number of attester slashings removed.
+    pub fn prune_attester_slashings(
+        &self,
+        finalized_state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> usize {
+        let mut attester_slashings = self.attester_slashings.write();
+        let num_before = attester_slashings.len();
+        attester_slashings.retain(|id, slashing| {
+            let fork_ok =
+                &OperationPool::attester_slashing_id(slashing, finalized_state, spec) == id;
             let curr_epoch = finalized_state.current_epoch();
             let slashing_ok =
                 get_slashable_indices_modular(finalized_state, slashing, |_, validator| {
@@ -282,6 +810,7 @@ impl<T: EthSpec> OperationPool<T> {
                 .is_ok();
             fork_ok && slashing_ok
         });
+        num_before - attester_slashings.len()
     }
 
     /// Total number of attester slashings in the pool.
@@ -305,43 +834,453 @@ impl<T: EthSpec> OperationPool<T> {
         self.voluntary_exits
             .write()
             .insert(exit.message.validator_index, exit);
+        self.evict_attestations_over_budget();
         Ok(())
     }
 
+    /// Returns `true` if the pool holds a voluntary exit for `validator_index`.
+    pub fn contains_voluntary_exit(&self, validator_index: u64) -> bool {
+        self.voluntary_exits.read().contains_key(&validator_index)
+    }
+
+    /// Remove the voluntary exit for `validator_index`, returning it if it was in the pool.
+    pub fn remove_voluntary_exit(&self, validator_index: u64) -> Option<SignedVoluntaryExit> {
+        self.voluntary_exits.write().remove(&validator_index)
+    }
+
     /// Get a list of voluntary exits for inclusion in a block.
     pub fn get_voluntary_exits(
         &self,
         state: &BeaconState<T>,
         spec: &ChainSpec,
     ) -> Vec<SignedVoluntaryExit> {
-        filter_limit_operations(
-            self.voluntary_exits.read().values(),
-            |exit| verify_exit(state, exit, VerifySignatures::False, spec).is_ok(),
-            T::MaxVoluntaryExits::to_usize(),
-        )
+        self.get_voluntary_exits_for_epoch(state, spec, state.current_epoch())
     }
 
-    /// Prune if validator has already exited at the last finalized state.
-    pub fn prune_voluntary_exits(&self, finalized_state: &BeaconState<T>) {
-        prune_validator_hash_map(
+    /// Get a list of voluntary exits for inclusion in a block at `epoch`, which may be later than
+    /// the current epoch of `state`.
+    ///
+    /// Exits are included if their epoch is at or before `epoch`. All other checks are made
+    /// against `state`, so with `epoch == state.current_epoch()` this is `get_voluntary_exits`.
+    pub fn get_voluntary_exits_for_epoch(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+        epoch: Epoch,
+    ) -> Vec<SignedVoluntaryExit> {
+        select_voluntary_exits(&self.voluntary_exits.read(), state, epoch, spec)
+    }
+
+    /// Returns the voluntary exits in the pool which cannot be included in a block until an
+    /// epoch later than the current epoch of `state`, ordered by epoch then validator index.
+    pub fn pending_voluntary_exits(&self, state: &BeaconState<T>) -> Vec<SignedVoluntaryExit> {
+        let mut pending = self
+            .voluntary_exits
+            .read()
+            .values()
+            .filter(|exit| exit.message.epoch > state.current_epoch())
+            .cloned()
+            .collect::<Vec<_>>();
+        pending.sort_unstable_by_key(|exit| (exit.message.epoch, exit.message.validator_index));
+        pending
+    }
+
+    /// Get all of the operations for inclusion in a block on top of `state`.
+    ///
+    /// Equivalent to calling `get_attestations`, `get_slashings` and `get_voluntary_exits` in
+    /// turn, except that every lock is held for the whole selection (in the same order as
+    /// `snapshot`), so the operations are selected from a single consistent view of the pool.
+    /// Proposer slashings are still given precedence over attester slashings for the same
+    /// validators.
+    pub fn get_block_operations(
+        &self,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> Result<BlockOperations<T>, OpPoolError> {
+        let current_epoch = state.current_epoch();
+        let attestation_shards = self.attestations.read_all();
+        let attester_slashings_map = self.attester_slashings.read();
+        let proposer_slashings_map = self.proposer_slashings.read();
+        let voluntary_exits_map = self.voluntary_exits.read();
+
+        let attestations = select_attestations(
+            &attestation_shards,
+            state,
+            state.slot,
+            T::MaxAttestations::to_usize(),
+            spec,
+        )?;
+        let (proposer_slashings, attester_slashings) = select_slashings(
+            &proposer_slashings_map,
+            &attester_slashings_map,
+            state,
+            current_epoch,
+            T::MaxProposerSlashings::to_usize(),
+            T::MaxAttesterSlashings::to_usize(),
+            spec,
+        );
+        let voluntary_exits =
+            select_voluntary_exits(&voluntary_exits_map, state, current_epoch, spec);
+
+        Ok(BlockOperations {
+            attestations,
+            proposer_slashings,
+            attester_slashings,
+            voluntary_exits,
+        })
+    }
+
+    /// Remove the operations included in `block` from the pool, without waiting for them to be
+    /// pruned once `block` is finalized.
+    ///
+    /// See `remove_attestation` for which attestations are removed.
+    pub fn remove_block_operations(
+        &self,
+        block: &BeaconBlock<T>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) {
+        for attestation in block.body.attestations.iter() {
+            self.remove_attestation(attestation, state, spec);
+        }
+        for slashing in block.body.proposer_slashings.iter() {
+            self.remove_proposer_slashing(slashing.proposer_index);
+        }
+        for slashing in block.body.attester_slashings.iter() {
+            self.remove_attester_slashing(slashing, state, spec);
+        }
+        for exit in block.body.voluntary_exits.iter() {
+            self.remove_voluntary_exit(exit.message.validator_index);
+        }
+    }
+
+    /// Move the operations of `other` into `self`, as though each had been inserted into `self`.
+    ///
+    /// Attestations are aggregated with those already in `self` as by `insert_attestation` (and
+    /// are not checked against `state`), one bucket at a time under the lock of its shard. The
+    /// pool's limits are only enforced once everything has been merged, so attestations from
+    /// `other` are never evicted by an intermediate state of the merge.
+    /// Slashings and exits are re-validated against `state` with the checks used on insertion,
+    /// except that signatures are not re-verified, and are dropped if invalid. Where `self`
+    /// already holds a slashing or exit for the same validator (or attester slashing ID), that of
+    /// `self` is kept.
+    pub fn merge<O>(&self, other: OperationPool<T, O>, state: &BeaconState<T>, spec: &ChainSpec) {
+        for (_, attestations) in other.attestations {
+            // Every attestation in a bucket has the same data, so they share an ID in `self`
+            // (which may differ from their ID in `other` if the pools are for different forks).
+            let id = match attestations.first() {
+                Some(first) => AttestationId::from_data(&first.data, state, spec),
+                None => continue,
+            };
+            self.attestations.update(id, |existing_attestations| {
+                for attestation in attestations {
+                    self.aggregate_into(existing_attestations, attestation);
+                }
+            });
+        }
+
+        let mut attester_slashings = self.attester_slashings.write();
+        for (_, slashing) in other.attester_slashings.into_inner() {
+            if verify_attester_slashing(state, &slashing, true, VerifySignatures::False, spec)
+                .is_ok()
+            {
+                let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+                attester_slashings.entry(id).or_insert(slashing);
+            }
+        }
+        drop(attester_slashings);
+
+        let mut proposer_slashings = self.proposer_slashings.write();
+        for (_, slashing) in other.proposer_slashings.into_inner() {
+            if verify_proposer_slashing(&slashing, state, VerifySignatures::False, spec).is_ok() {
+                proposer_slashings
+                    .entry(slashing.proposer_index)
+                    .or_insert(slashing);
+            }
+        }
+        self.evict_proposer_slashings_over_limit(&mut proposer_slashings, state);
+        drop(proposer_slashings);
+
+        let mut voluntary_exits = self.voluntary_exits.write();
+        for (_, exit) in other.voluntary_exits.into_inner() {
+            if verify_exit_time_independent_only(state, &exit, VerifySignatures::False, spec)
+                .is_ok()
+            {
+                voluntary_exits
+                    .entry(exit.message.validator_index)
+                    .or_insert(exit);
+            }
+        }
+        drop(voluntary_exits);
+
+        self.evict_attestation_buckets_over_limit();
+        self.evict_attestations_over_budget();
+    }
+
+    /// Prune if validator has already exited at the last finalized state.
+    ///
+    /// Returns the number of voluntary exits removed.
+    pub fn prune_voluntary_exits(&self, finalized_state: &BeaconState<T>) -> usize {
+        prune_validator_hash_map(
             &mut self.voluntary_exits.write(),
             |validator| validator.is_exited_at(finalized_state.current_epoch()),
             finalized_state,
-        );
+        )
     }
 
-    /// Prune all types of transactions given the latest finalized state.
-    pub fn prune_all(&self, finalized_state: &BeaconState<T>, spec: &ChainSpec) {
-        self.prune_attestations(finalized_state);
-        self.prune_proposer_slashings(finalized_state);
-        self.prune_attester_slashings(finalized_state, spec);
-        self.prune_voluntary_exits(finalized_state);
+    /// Prune all types of transactions given the latest finalized state, returning the number of
+    /// each removed.
+    pub fn prune_all(&self, finalized_state: &BeaconState<T>, spec: &ChainSpec) -> PruneReport {
+        PruneReport {
+            attestations_removed: self.prune_attestations(finalized_state),
+            proposer_slashings_removed: self.prune_proposer_slashings(finalized_state),
+            attester_slashings_removed: self.prune_attester_slashings(finalized_state, spec),
+            voluntary_exits_removed: self.prune_voluntary_exits(finalized_state),
+        }
     }
 
     /// Total number of voluntary exits in the pool.
     pub fn num_voluntary_exits(&self) -> usize {
         self.voluntary_exits.read().len()
     }
+
+    /// Returns the number of operations of each kind in the pool, taking each lock once.
+    pub fn stats(&self) -> PoolStats {
+        let (num_attestations, num_unique_attestation_data) = self
+            .attestations
+            .read_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .fold((0, 0), |(num_attestations, num_ids), attestations| {
+                (num_attestations + attestations.len(), num_ids + 1)
+            });
+
+        PoolStats {
+            num_attestations,
+            num_unique_attestation_data,
+            num_attester_slashings: self.num_attester_slashings(),
+            num_proposer_slashings: self.num_proposer_slashings(),
+            num_voluntary_exits: self.num_voluntary_exits(),
+        }
+    }
+
+    /// Returns a copy of every operation in the pool, consistent as of a single instant.
+    ///
+    /// ## Lock ordering
+    ///
+    /// A read lock is held on every part of the pool until all of them have been copied. They
+    /// are acquired in the same order as any method which holds several at once (e.g., eviction):
+    /// the attestation shards (in order), then attester slashings, then proposer slashings, then
+    /// voluntary exits. Methods which take these locks must not acquire them in any other order,
+    /// otherwise they may deadlock with this function.
+    pub fn snapshot(&self) -> OperationPoolSnapshot<T> {
+        let attestation_shards = self.attestations.read_all();
+        let attester_slashings = self.attester_slashings.read();
+        let proposer_slashings = self.proposer_slashings.read();
+        let voluntary_exits = self.voluntary_exits.read();
+
+        OperationPoolSnapshot {
+            attestations: attestation_shards
+                .iter()
+                .flat_map(|shard| shard.iter())
+                .map(|(id, attestations)| (id.clone(), attestations.clone()))
+                .collect(),
+            attester_slashings: attester_slashings.clone(),
+            proposer_slashings: proposer_slashings.clone(),
+            voluntary_exits: voluntary_exits.clone(),
+        }
+    }
+
+    /// Returns the indices of all validators with a voluntary exit in the pool, grouped by the
+    /// epoch of their exit.
+    ///
+    /// The indices for each epoch are sorted in ascending order.
+    pub fn exits_by_epoch(&self) -> BTreeMap<Epoch, Vec<u64>> {
+        let mut exits_by_epoch = BTreeMap::<_, Vec<_>>::new();
+
+        for exit in self.voluntary_exits.read().values() {
+            exits_by_epoch
+                .entry(exit.message.epoch)
+                .or_default()
+                .push(exit.message.validator_index);
+        }
+
+        for indices in exits_by_epoch.values_mut() {
+            indices.sort_unstable();
+        }
+
+        exits_by_epoch
+    }
+}
+
+/// Returns `true` if every member of the committee has signed `attestation`.
+/// Iterate over the buckets in `shards` for the current fork of `state`, which may hold
+/// attestations from the current or previous epoch.
+fn current_fork_buckets<'a, T: EthSpec>(
+    shards: &'a [RwLockReadGuard<'a, AttestationShard<T>>],
+    state: &BeaconState<T>,
+    spec: &ChainSpec,
+) -> impl Iterator<Item = (&'a AttestationId, &'a Vec<Attestation<T>>)> + 'a {
+    let prev_domain_bytes =
+        AttestationId::compute_domain_bytes(state.previous_epoch(), state, spec);
+    let curr_domain_bytes = AttestationId::compute_domain_bytes(state.current_epoch(), state, spec);
+
+    shards
+        .iter()
+        .flat_map(|shard| shard.iter())
+        .filter(move |(key, _)| {
+            key.domain_bytes_match(&prev_domain_bytes) || key.domain_bytes_match(&curr_domain_bytes)
+        })
+}
+
+/// Select up to `limit` attestations from `shards` for inclusion in a block at `target_slot`,
+/// maximising their coverage of validators.
+fn select_attestations<T: EthSpec>(
+    shards: &[RwLockReadGuard<AttestationShard<T>>],
+    state: &BeaconState<T>,
+    target_slot: Slot,
+    limit: usize,
+    spec: &ChainSpec,
+) -> Result<Vec<Attestation<T>>, OpPoolError> {
+    let active_indices = state
+        .get_cached_active_validator_indices(RelativeEpoch::Current)
+        .map_err(OpPoolError::GetAttestationsTotalBalanceError)?;
+    let total_active_balance = state
+        .get_total_balance(&active_indices, spec)
+        .map_err(OpPoolError::GetAttestationsTotalBalanceError)?;
+    let valid_attestations = current_fork_buckets(shards, state, spec)
+        .flat_map(|(_, attestations)| attestations)
+        // That can be included at the target slot...
+        .filter(|attestation| {
+            let attestation_slot = attestation.data.slot;
+            attestation_slot + spec.min_attestation_inclusion_delay <= target_slot
+                && target_slot <= attestation_slot + T::slots_per_epoch()
+        })
+        // That are valid...
+        .filter(|attestation| {
+            verify_attestation_for_state(state, attestation, VerifySignatures::False, spec).is_ok()
+        })
+        .flat_map(|att| AttMaxCover::new(att, state, total_active_balance, spec));
+
+    Ok(maximum_cover(valid_attestations, limit))
+}
+
+/// Select up to `max_proposer_slashings` proposer slashings and `max_attester_slashings`
+/// attester slashings from the given maps for inclusion in a block on top of `state` at `epoch`.
+///
+/// See `OperationPool::get_slashings_limited`.
+fn select_slashings<T: EthSpec>(
+    proposer_slashings_map: &HashMap<u64, ProposerSlashing>,
+    attester_slashings_map: &HashMap<(AttestationId, AttestationId), AttesterSlashing<T>>,
+    state: &BeaconState<T>,
+    epoch: Epoch,
+    max_proposer_slashings: usize,
+    max_attester_slashings: usize,
+    spec: &ChainSpec,
+) -> (Vec<ProposerSlashing>, Vec<AttesterSlashing<T>>) {
+    let mut candidate_proposer_slashings = proposer_slashings_map.values().collect::<Vec<_>>();
+
+    // Prefer slashing validators with higher effective balances, since they yield a larger
+    // whistleblower reward.
+    candidate_proposer_slashings.sort_by_key(|slashing| {
+        let effective_balance = state
+            .validators
+            .get(slashing.proposer_index as usize)
+            .map_or(0, |validator| validator.effective_balance);
+        (Reverse(effective_balance), slashing.proposer_index)
+    });
+
+    let proposer_slashings = filter_limit_operations(
+        candidate_proposer_slashings,
+        |slashing| {
+            state
+                .validators
+                .get(slashing.proposer_index as usize)
+                .map_or(false, |validator| !validator.slashed)
+        },
+        max_proposer_slashings,
+    );
+
+    // Set of validators to be slashed, so we don't attempt to construct invalid attester
+    // slashings.
+    let mut to_be_slashed = proposer_slashings
+        .iter()
+        .map(|s| s.proposer_index)
+        .collect::<HashSet<_>>();
+
+    let mut candidate_attester_slashings = attester_slashings_map
+        .iter()
+        .filter(|(id, slashing)| {
+            // Check the fork.
+            OperationPool::attester_slashing_id(slashing, state, spec) == **id
+        })
+        .filter_map(|(_, slashing)| {
+            let slashable_indices =
+                get_slashable_indices_modular(state, slashing, |index, validator| {
+                    validator.is_slashable_at(epoch) && !to_be_slashed.contains(&index)
+                })
+                .ok()?;
+            Some((slashable_indices, slashing))
+        })
+        .collect::<Vec<_>>();
+
+    // Prefer slashings which slash more validators, breaking ties by the lowest slashed
+    // validator index so that block production is deterministic. The slashable indices are
+    // sorted in ascending order.
+    candidate_attester_slashings.sort_by_key(|(slashable_indices, _)| {
+        (
+            Reverse(slashable_indices.len()),
+            slashable_indices.first().copied(),
+        )
+    });
+
+    let attester_slashings = candidate_attester_slashings
+        .into_iter()
+        .filter(|(_, slashing)| {
+            // Take all slashings that will slash 1 or more validators.
+            let slashed_validators =
+                get_slashable_indices_modular(state, slashing, |index, validator| {
+                    validator.is_slashable_at(epoch) && !to_be_slashed.contains(&index)
+                });
+
+            // Extend the `to_be_slashed` set so subsequent iterations don't try to include
+            // useless slashings.
+            if let Ok(validators) = slashed_validators {
+                to_be_slashed.extend(validators);
+                true
+            } else {
+                false
+            }
+        })
+        .take(max_attester_slashings)
+        .map(|(_, slashing)| slashing.clone())
+        .collect();
+
+    (proposer_slashings, attester_slashings)
+}
+
+/// Select voluntary exits from `voluntary_exits` for inclusion in a block at `epoch`.
+///
+/// See `OperationPool::get_voluntary_exits_for_epoch`.
+fn select_voluntary_exits<T: EthSpec>(
+    voluntary_exits: &HashMap<u64, SignedVoluntaryExit>,
+    state: &BeaconState<T>,
+    epoch: Epoch,
+    spec: &ChainSpec,
+) -> Vec<SignedVoluntaryExit> {
+    filter_limit_operations(
+        voluntary_exits.values(),
+        |exit| {
+            exit.message.epoch <= epoch
+                && verify_exit_time_independent_only(state, exit, VerifySignatures::False, spec)
+                    .is_ok()
+        },
+        T::MaxVoluntaryExits::to_usize(),
+    )
+}
+
+fn is_fully_aggregated<T: EthSpec>(attestation: &Attestation<T>) -> bool {
+    attestation.aggregation_bits.num_set_bits() == attestation.aggregation_bits.len()
 }
 
 /// Filter up to a maximum number of operations out of an iterator.
@@ -364,25 +1303,30 @@ where
 /// The keys in the map should be validator indices, which will be looked up
 /// in the state's validator registry and then passed to `prune_if`.
 /// Entries for unknown validators will be kept.
+///
+/// Returns the number of entries removed.
 fn prune_validator_hash_map<T, F, E: EthSpec>(
     map: &mut HashMap<u64, T>,
     prune_if: F,
     finalized_state: &BeaconState<E>,
-) where
+) -> usize
+where
     F: Fn(&Validator) -> bool,
 {
+    let num_before = map.len();
     map.retain(|&validator_index, _| {
         finalized_state
             .validators
             .get(validator_index as usize)
             .map_or(true, |validator| !prune_if(validator))
     });
+    num_before - map.len()
 }
 
 /// Compare two operation pools.
-impl<T: EthSpec + Default> PartialEq for OperationPool<T> {
+impl<T: EthSpec + Default, S> PartialEq for OperationPool<T, S> {
     fn eq(&self, other: &Self) -> bool {
-        *self.attestations.read() == *other.attestations.read()
+        self.attestations == other.attestations
             && *self.attester_slashings.read() == *other.attester_slashings.read()
             && *self.proposer_slashings.read() == *other.proposer_slashings.read()
             && *self.voluntary_exits.read() == *other.voluntary_exits.read()
@@ -392,7 +1336,6 @@ impl<T: EthSpec + Default> PartialEq for OperationPool<T> {
 // TODO: more tests
 #[cfg(all(test, not(debug_assertions)))]
 mod release_tests {
-    use super::attestation::earliest_attestation_validators;
     use super::*;
     use state_processing::common::{get_attesting_indices, get_base_reward};
     use std::collections::BTreeSet;
@@ -441,6 +1384,16 @@ mod release_tests {
         builder.build()
     }
 
+    /// Number of distinct attestation IDs in the pool.
+    fn num_attestation_ids<E: EthSpec, S>(op_pool: &OperationPool<E, S>) -> usize {
+        op_pool
+            .attestations
+            .read_all()
+            .iter()
+            .map(|shard| shard.len())
+            .sum()
+    }
+
     /// Test state for attestation-related tests.
     fn attestation_test_state<E: EthSpec>(
         num_committees: usize,
@@ -513,143 +1466,183 @@ mod release_tests {
         }
     }
 
-    /// End-to-end test of basic attestation handling.
+    /// The best attestation from each bucket should be the one with the most attesters.
+    /// As for `test_earliest_attestation`, but against the previous epoch's attestations.
     #[test]
-    fn attestation_aggregation_insert_get_prune() {
+    fn earliest_attestation_previous_epoch() {
         let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
-
-        let op_pool = OperationPool::new();
-
-        let slot = state.slot - 1;
-        let committees = state
+        let slot = state.slot - MainnetEthSpec::slots_per_epoch();
+        let bc = state
             .get_beacon_committees_at_slot(slot)
             .unwrap()
             .into_iter()
             .map(BeaconCommittee::into_owned)
-            .collect::<Vec<_>>();
+            .next()
+            .unwrap();
 
+        let att1 = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            ..2,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        let att2 = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            ..,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        assert_eq!(att1.data.target.epoch, state.previous_epoch());
+
+        // Pending attestations from the current epoch are not consulted.
+        state
+            .current_epoch_attestations
+            .push(PendingAttestation {
+                aggregation_bits: att1.aggregation_bits.clone(),
+                data: att1.data.clone(),
+                inclusion_delay: 0,
+                proposer_index: 0,
+            })
+            .unwrap();
         assert_eq!(
-            committees.len(),
-            1,
-            "we expect just one committee with this many validators"
+            crate::earliest_attestation_validators(&att2, state).num_set_bits(),
+            bc.committee.len()
         );
 
-        for bc in &committees {
-            let step_size = 2;
-            for i in (0..bc.committee.len()).step_by(step_size) {
-                let att = signed_attestation(
-                    &bc.committee,
-                    bc.index,
-                    keypairs,
-                    i..i + step_size,
-                    slot,
-                    state,
-                    spec,
-                    None,
-                );
-                op_pool.insert_attestation(att, state, spec).unwrap();
-            }
-        }
-
-        assert_eq!(op_pool.attestations.read().len(), committees.len());
-        assert_eq!(op_pool.num_attestations(), committees.len());
-
-        // Before the min attestation inclusion delay, get_attestations shouldn't return anything.
-        state.slot -= 1;
+        state
+            .previous_epoch_attestations
+            .push(PendingAttestation {
+                aggregation_bits: att1.aggregation_bits.clone(),
+                data: att1.data.clone(),
+                inclusion_delay: 0,
+                proposer_index: 0,
+            })
+            .unwrap();
         assert_eq!(
-            op_pool
-                .get_attestations(state, spec)
-                .expect("should have attestations")
-                .len(),
-            0
+            crate::earliest_attestation_validators(&att2, state).num_set_bits(),
+            bc.committee.len() - 2
         );
 
-        // Then once the delay has elapsed, we should get a single aggregated attestation.
-        state.slot += spec.min_attestation_inclusion_delay;
+        // An attestation from an older epoch covers no validators.
+        let mut old_att = att2;
+        old_att.data.target.epoch = state.previous_epoch() - 1;
+        assert!(crate::earliest_attestation_validators(&old_att, state).is_zero());
+    }
 
-        let block_attestations = op_pool
-            .get_attestations(state, spec)
-            .expect("Should have block attestations");
-        assert_eq!(block_attestations.len(), committees.len());
+    #[test]
+    fn best_attestation_per_id() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
-        let agg_att = &block_attestations[0];
-        assert_eq!(
-            agg_att.aggregation_bits.num_set_bits(),
-            spec.target_committee_size as usize
-        );
+        let op_pool = OperationPool::new();
 
-        // Prune attestations shouldn't do anything at this point.
-        op_pool.prune_attestations(state);
-        assert_eq!(op_pool.num_attestations(), committees.len());
+        let slots = [state.slot - 1, state.slot - 2];
+        for &slot in &slots {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                // Every attestation is signed by the first validator, so none can be aggregated.
+                for &n in &[2_usize, 6, 3] {
+                    let att = signed_attestation(
+                        &bc.committee,
+                        bc.index,
+                        keypairs,
+                        ..n,
+                        slot,
+                        state,
+                        spec,
+                        None,
+                    );
+                    op_pool.insert_attestation(att, state, spec).unwrap();
+                }
+            }
+        }
 
-        // But once we advance to more than an epoch after the attestation, it should prune it
-        // out of existence.
-        state.slot += 2 * MainnetEthSpec::slots_per_epoch();
-        op_pool.prune_attestations(state);
-        assert_eq!(op_pool.num_attestations(), 0);
+        assert_eq!(op_pool.num_attestations(), 3 * slots.len());
+
+        let best = op_pool.best_attestation_per_id(state, spec);
+        assert_eq!(best.len(), slots.len());
+
+        for (id, attestation) in &best {
+            assert_eq!(
+                id,
+                &AttestationId::from_data(&attestation.data, state, spec)
+            );
+            assert_eq!(attestation.aggregation_bits.num_set_bits(), 6);
+        }
+
+        let mut best_slots = best
+            .values()
+            .map(|attestation| attestation.data.slot)
+            .collect::<Vec<_>>();
+        best_slots.sort();
+        assert_eq!(best_slots, vec![slots[1], slots[0]]);
     }
 
-    /// Adding an attestation already in the pool should not increase the size of the pool.
+    /// The coverage of each bucket should reflect its largest aggregate.
     #[test]
-    fn attestation_duplicate() {
-        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+    fn attestation_coverage() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
         let op_pool = OperationPool::new();
+        assert_eq!(op_pool.attestation_coverage(state, spec), vec![]);
+
+        let slots = [state.slot - 1, state.slot - 2];
+        for &slot in &slots {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                // The attestations overlap, so none can be aggregated.
+                for range in vec![0..2, 1..6, 0..3] {
+                    let att = signed_attestation(
+                        &bc.committee,
+                        bc.index,
+                        keypairs,
+                        range,
+                        slot,
+                        state,
+                        spec,
+                        None,
+                    );
+                    op_pool.insert_attestation(att, state, spec).unwrap();
+                }
+            }
+        }
 
-        let slot = state.slot - 1;
-        let committees = state
-            .get_beacon_committees_at_slot(slot)
-            .unwrap()
-            .into_iter()
-            .map(BeaconCommittee::into_owned)
-            .collect::<Vec<_>>();
+        let coverage = op_pool.attestation_coverage(state, spec);
+        assert_eq!(coverage.len(), slots.len());
 
-        for bc in &committees {
-            let att = signed_attestation(
-                &bc.committee,
-                bc.index,
-                keypairs,
-                ..,
-                slot,
-                state,
-                spec,
-                None,
-            );
-            op_pool
-                .insert_attestation(att.clone(), state, spec)
-                .unwrap();
-            op_pool.insert_attestation(att, state, spec).unwrap();
+        let best = op_pool.best_attestation_per_id(state, spec);
+        for (id, num_attestations, max_attesters) in coverage {
+            assert_eq!(num_attestations, 3);
+            assert_eq!(max_attesters, 5);
+            assert_eq!(best[&id].aggregation_bits.num_set_bits(), max_attesters);
         }
-
-        assert_eq!(op_pool.num_attestations(), committees.len());
     }
 
-    /// Adding lots of attestations that only intersect pairwise should lead to two aggregate
-    /// attestations.
+    /// Attestations should be selected according to the inclusion window of the target slot,
+    /// rather than that of the state.
     #[test]
-    fn attestation_pairwise_overlapping() {
-        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+    fn get_attestations_for_future_slot() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
         let op_pool = OperationPool::new();
 
-        let slot = state.slot - 1;
-        let committees = state
-            .get_beacon_committees_at_slot(slot)
-            .unwrap()
-            .into_iter()
-            .map(BeaconCommittee::into_owned)
-            .collect::<Vec<_>>();
+        // One attestation which is too new to include at `state.slot`, and one which is only
+        // just old enough to still be included at `state.slot`.
+        let new_slot = state.slot;
+        let old_slot = state.slot - MainnetEthSpec::slots_per_epoch();
 
-        let step_size = 2;
-        for bc in &committees {
-            // Create attestations that overlap on `step_size` validators, like:
-            // {0,1,2,3}, {2,3,4,5}, {4,5,6,7}, ...
-            for i in (0..bc.committee.len() - step_size).step_by(step_size) {
+        for &slot in &[new_slot, old_slot] {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
                 let att = signed_attestation(
                     &bc.committee,
                     bc.index,
                     keypairs,
-                    i..i + 2 * step_size,
+                    ..,
                     slot,
                     state,
                     spec,
@@ -659,91 +1652,319 @@ mod release_tests {
             }
         }
 
-        // The attestations should get aggregated into two attestations that comprise all
-        // validators.
-        assert_eq!(op_pool.attestations.read().len(), committees.len());
-        assert_eq!(op_pool.num_attestations(), 2 * committees.len());
+        let current = op_pool
+            .get_attestations(state, spec)
+            .expect("should have attestations");
+        assert_eq!(current.len(), 1);
+        assert_eq!(current[0].data.slot, old_slot);
+
+        let future = op_pool
+            .get_attestations_for_slot(
+                state,
+                state.slot + spec.min_attestation_inclusion_delay,
+                spec,
+            )
+            .expect("should have attestations");
+        assert_eq!(future.len(), 1);
+        assert_eq!(future[0].data.slot, new_slot);
     }
 
-    /// Create a bunch of attestations signed by a small number of validators, and another
-    /// bunch signed by a larger number, such that there are at least `max_attestations`
-    /// signed by the larger number. Then, check that `get_attestations` only returns the
-    /// high-quality attestations. To ensure that no aggregation occurs, ALL attestations
-    /// are also signed by the 0th member of the committee.
     #[test]
-    fn attestation_get_max() {
-        let small_step_size = 2;
-        let big_step_size = 4;
-
-        let (ref mut state, ref keypairs, ref spec) =
-            attestation_test_state::<MainnetEthSpec>(big_step_size);
+    fn best_aggregates_ignore_inclusion_delay() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
         let op_pool = OperationPool::new();
 
-        let slot = state.slot - 1;
-        let committees = state
-            .get_beacon_committees_at_slot(slot)
-            .unwrap()
-            .into_iter()
-            .map(BeaconCommittee::into_owned)
-            .collect::<Vec<_>>();
-
-        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
-        let target_committee_size = spec.target_committee_size as usize;
-
-        let insert_attestations = |bc: &OwnedBeaconCommittee, step_size| {
-            for i in (0..target_committee_size).step_by(step_size) {
+        // Attestations which are too new to include at `state.slot`.
+        let slot = state.slot;
+        for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+            for &n in &[2_usize, 6, 3] {
                 let att = signed_attestation(
                     &bc.committee,
                     bc.index,
                     keypairs,
-                    i..i + step_size,
+                    ..n,
                     slot,
                     state,
                     spec,
-                    if i == 0 { None } else { Some(0) },
+                    None,
                 );
                 op_pool.insert_attestation(att, state, spec).unwrap();
             }
+        }
+        assert_eq!(op_pool.num_attestations(), 3);
+
+        assert_eq!(op_pool.get_attestations(state, spec).unwrap(), vec![]);
+
+        let best = op_pool.best_aggregates(state, spec);
+        assert_eq!(best.len(), 1);
+        assert_eq!(best[0].data.slot, slot);
+        assert_eq!(best[0].aggregation_bits.num_set_bits(), 6);
+
+        // Attestations from another fork are excluded.
+        let mut other_fork_state = state.clone();
+        other_fork_state.fork = Fork {
+            previous_version: [1, 0, 0, 0],
+            current_version: [1, 0, 0, 0],
+            epoch: Epoch::new(0),
         };
+        assert!(op_pool.best_aggregates(&other_fork_state, spec).is_empty());
+    }
 
-        for committee in &committees {
-            assert_eq!(committee.committee.len(), target_committee_size);
-            // Attestations signed by only 2-3 validators
-            insert_attestations(committee, small_step_size);
-            // Attestations signed by 4+ validators
-            insert_attestations(committee, big_step_size);
+    /// Inserting attestations past the byte budget should evict those with the fewest attesters,
+    /// down to the low-water mark.
+    #[test]
+    fn max_pool_bytes_evicts_smallest_attestations() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+
+        // Every attestation is signed by the first validator, so none can be aggregated, and
+        // each one has one more attester than the last.
+        let attestations = (2..8_usize)
+            .map(|n| {
+                signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    ..n,
+                    slot,
+                    state,
+                    spec,
+                    None,
+                )
+            })
+            .collect::<Vec<_>>();
+
+        let attestation_bytes = attestations[0].ssz_bytes_len();
+        let max_pool_bytes = 3 * attestation_bytes;
+        let op_pool = OperationPool::new().with_max_pool_bytes(max_pool_bytes);
+
+        for attestation in attestations {
+            op_pool
+                .insert_attestation(attestation, state, spec)
+                .unwrap();
+            assert!(op_pool.current_pool_bytes() <= max_pool_bytes);
         }
 
-        let num_small = target_committee_size / small_step_size;
-        let num_big = target_committee_size / big_step_size;
+        // The fourth and sixth inserts each evict two attestations, to get under 90% of the
+        // budget.
+        assert_eq!(op_pool.current_pool_bytes(), 2 * attestation_bytes);
+        assert_eq!(op_pool.num_attestations(), 2);
+        assert_eq!(op_pool.num_evicted_attestations(), 4);
 
-        assert_eq!(op_pool.attestations.read().len(), committees.len());
-        assert_eq!(
-            op_pool.num_attestations(),
-            (num_small + num_big) * committees.len()
-        );
-        assert!(op_pool.num_attestations() > max_attestations);
+        let mut num_attesters = op_pool
+            .attestations
+            .read_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flatten()
+            .map(|att| att.aggregation_bits.num_set_bits())
+            .collect::<Vec<_>>();
+        num_attesters.sort();
+        assert_eq!(num_attesters, vec![6, 7]);
+    }
 
-        state.slot += spec.min_attestation_inclusion_delay;
-        let best_attestations = op_pool
-            .get_attestations(state, spec)
-            .expect("should have best attestations");
-        assert_eq!(best_attestations.len(), max_attestations);
+    /// The running total of attestation bytes should match a recount after every kind of change
+    /// to the attestations.
+    #[test]
+    fn pool_bytes_track_attestation_changes() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
-        // All the best attestations should be signed by at least `big_step_size` (4) validators.
-        for att in &best_attestations {
-            assert!(att.aggregation_bits.num_set_bits() >= big_step_size);
+        let op_pool = OperationPool::new();
+        let recount = |op_pool: &OperationPool<MainnetEthSpec>| {
+            op_pool
+                .all_attestations()
+                .iter()
+                .map(Encode::ssz_bytes_len)
+                .sum::<usize>()
+        };
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .next()
+            .unwrap();
+        let attestation = |range: std::ops::Range<usize>| {
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        // The second attestation is aggregated into the first, and the third overlaps both so is
+        // stored separately.
+        let overlapping = attestation(1..3);
+        for attestation in vec![attestation(0..2), attestation(2..4), overlapping.clone()] {
+            op_pool
+                .insert_attestation(attestation, state, spec)
+                .unwrap();
+            assert_eq!(op_pool.current_pool_bytes(), recount(&op_pool));
         }
+        assert_eq!(op_pool.num_attestations(), 2);
+
+        op_pool.remove_attestation(&overlapping, state, spec);
+        assert_eq!(op_pool.num_attestations(), 1);
+        assert_eq!(op_pool.current_pool_bytes(), recount(&op_pool));
+
+        state.slot += 2 * MainnetEthSpec::slots_per_epoch();
+        op_pool.prune_attestations(state);
+        assert_eq!(op_pool.current_pool_bytes(), 0);
     }
 
+    /// Inserting buckets past the limit should evict those covering the fewest validators.
     #[test]
-    fn attestation_rewards() {
-        let small_step_size = 2;
-        let big_step_size = 4;
+    fn max_attestation_data_buckets_evicts_lowest_coverage() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let max_buckets = 2;
+        let op_pool = OperationPool::new().with_max_attestation_data_buckets(max_buckets);
+
+        // Each attestation is for a different slot, so each is in its own bucket.
+        for (i, &n) in [3, 1, 4, 2].iter().enumerate() {
+            let slot = state.slot - 1 - i as u64;
+            let bc = state
+                .get_beacon_committees_at_slot(slot)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+            let attestation = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..n,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool
+                .insert_attestation(attestation, state, spec)
+                .unwrap();
 
-        let (ref mut state, ref keypairs, ref spec) =
-            attestation_test_state::<MainnetEthSpec>(big_step_size);
+            assert!(op_pool.stats().num_unique_attestation_data <= max_buckets);
+        }
+
+        assert_eq!(op_pool.stats().num_unique_attestation_data, max_buckets);
+        assert_eq!(op_pool.num_evicted_attestations(), 2);
+
+        let mut num_attesters = op_pool
+            .all_attestations()
+            .iter()
+            .map(|att| att.aggregation_bits.num_set_bits())
+            .collect::<Vec<_>>();
+        num_attesters.sort();
+        assert_eq!(num_attesters, vec![3, 4]);
+    }
+
+    /// A bucket's place in the eviction order should reflect the validators it covers after
+    /// aggregation, not just those of its first attestation.
+    #[test]
+    fn max_attestation_data_buckets_tracks_aggregation() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let max_buckets = 2;
+        let op_pool = OperationPool::new().with_max_attestation_data_buckets(max_buckets);
+
+        let attestation = |i: u64, range: std::ops::Range<usize>| {
+            let slot = state.slot - 1 - i;
+            let bc = state
+                .get_beacon_committees_at_slot(slot)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        // The first bucket starts with the least coverage, but is aggregated to the most before
+        // the third bucket takes the pool over the limit.
+        for attestation in vec![
+            attestation(0, 0..1),
+            attestation(1, 0..2),
+            attestation(0, 1..3),
+            attestation(2, 0..2),
+        ] {
+            op_pool
+                .insert_attestation(attestation, state, spec)
+                .unwrap();
+        }
+
+        assert_eq!(op_pool.stats().num_unique_attestation_data, max_buckets);
+        assert_eq!(op_pool.num_evicted_attestations(), 1);
+
+        let mut num_attesters = op_pool
+            .all_attestations()
+            .iter()
+            .map(|att| att.aggregation_bits.num_set_bits())
+            .collect::<Vec<_>>();
+        num_attesters.sort();
+        assert_eq!(num_attesters, vec![2, 3]);
+    }
+
+    /// Inserting proposer slashings past the limit should evict those of the proposers with the
+    /// lowest effective balances.
+    #[test]
+    fn max_proposer_slashings_evicts_lowest_balance() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        state.validators[1].effective_balance -= spec.effective_balance_increment;
+        state.validators[2].effective_balance -= 2 * spec.effective_balance_increment;
+
+        let op_pool = OperationPool::<MainnetEthSpec>::new().with_max_proposer_slashings(2);
+
+        for &proposer_index in &[1, 0, 2, 3] {
+            let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                proposer_index,
+                &keypairs[proposer_index as usize].sk,
+                &state.fork,
+                spec,
+            );
+            op_pool
+                .insert_proposer_slashing(slashing, state, spec)
+                .unwrap();
+            assert!(op_pool.num_proposer_slashings() <= 2);
+        }
+
+        let mut retained = op_pool
+            .proposer_slashings
+            .read()
+            .keys()
+            .copied()
+            .collect::<Vec<_>>();
+        retained.sort();
+        assert_eq!(retained, vec![0, 3]);
+    }
+
+    /// End-to-end test of basic attestation handling.
+    #[test]
+    fn attestation_aggregation_insert_get_prune() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
 
         let op_pool = OperationPool::new();
 
@@ -755,17 +1976,15 @@ mod release_tests {
             .map(BeaconCommittee::into_owned)
             .collect::<Vec<_>>();
 
-        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
-        let target_committee_size = spec.target_committee_size as usize;
-
-        // Each validator will have a multiple of 1_000_000_000 wei.
-        // Safe from overflow unless there are about 18B validators (2^64 / 1_000_000_000).
-        for i in 0..state.validators.len() {
-            state.validators[i].effective_balance = 1_000_000_000 * i as u64;
-        }
+        assert_eq!(
+            committees.len(),
+            1,
+            "we expect just one committee with this many validators"
+        );
 
-        let insert_attestations = |bc: &OwnedBeaconCommittee, step_size| {
-            for i in (0..target_committee_size).step_by(step_size) {
+        for bc in &committees {
+            let step_size = 2;
+            for i in (0..bc.committee.len()).step_by(step_size) {
                 let att = signed_attestation(
                     &bc.committee,
                     bc.index,
@@ -774,66 +1993,2017 @@ mod release_tests {
                     slot,
                     state,
                     spec,
-                    if i == 0 { None } else { Some(0) },
+                    None,
                 );
                 op_pool.insert_attestation(att, state, spec).unwrap();
             }
-        };
-
-        for committee in &committees {
-            assert_eq!(committee.committee.len(), target_committee_size);
-            // Attestations signed by only 2-3 validators
-            insert_attestations(committee, small_step_size);
-            // Attestations signed by 4+ validators
-            insert_attestations(committee, big_step_size);
         }
 
-        let num_small = target_committee_size / small_step_size;
-        let num_big = target_committee_size / big_step_size;
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(op_pool.num_attestations(), committees.len());
 
-        assert_eq!(op_pool.attestations.read().len(), committees.len());
+        // Before the min attestation inclusion delay, get_attestations shouldn't return anything.
+        state.slot -= 1;
         assert_eq!(
-            op_pool.num_attestations(),
-            (num_small + num_big) * committees.len()
+            op_pool
+                .get_attestations(state, spec)
+                .expect("should have attestations")
+                .len(),
+            0
         );
-        assert!(op_pool.num_attestations() > max_attestations);
 
+        // Then once the delay has elapsed, we should get a single aggregated attestation.
         state.slot += spec.min_attestation_inclusion_delay;
-        let best_attestations = op_pool
-            .get_attestations(state, spec)
-            .expect("should have valid best attestations");
-        assert_eq!(best_attestations.len(), max_attestations);
 
-        let active_indices = state
-            .get_cached_active_validator_indices(RelativeEpoch::Current)
-            .unwrap();
-        let total_active_balance = state.get_total_balance(&active_indices, spec).unwrap();
+        let block_attestations = op_pool
+            .get_attestations(state, spec)
+            .expect("Should have block attestations");
+        assert_eq!(block_attestations.len(), committees.len());
 
-        // Set of indices covered by previous attestations in `best_attestations`.
-        let mut seen_indices = BTreeSet::new();
-        // Used for asserting that rewards are in decreasing order.
-        let mut prev_reward = u64::max_value();
+        let agg_att = &block_attestations[0];
+        assert_eq!(
+            agg_att.aggregation_bits.num_set_bits(),
+            spec.target_committee_size as usize
+        );
 
-        for att in &best_attestations {
-            let fresh_validators_bitlist = earliest_attestation_validators(att, state);
-            let att_indices =
-                get_attesting_indices(state, &att.data, &fresh_validators_bitlist).unwrap();
-            let fresh_indices = &att_indices - &seen_indices;
+        // Prune attestations shouldn't do anything at this point.
+        op_pool.prune_attestations(state);
+        assert_eq!(op_pool.num_attestations(), committees.len());
 
-            let rewards = fresh_indices
-                .iter()
-                .map(|validator_index| {
-                    get_base_reward(state, *validator_index as usize, total_active_balance, spec)
-                        .unwrap()
-                        / spec.proposer_reward_quotient
-                })
-                .sum();
+        // But once we advance to more than an epoch after the attestation, it should prune it
+        // out of existence.
+        state.slot += 2 * MainnetEthSpec::slots_per_epoch();
+        op_pool.prune_attestations(state);
+        assert_eq!(op_pool.num_attestations(), 0);
+    }
 
-            // Check that rewards are in decreasing order
-            assert!(prev_reward >= rewards);
+    /// A strategy that never aggregates should store every attestation separately.
+    #[test]
+    fn attestation_custom_aggregation_strategy() {
+        struct NeverAggregate;
 
-            prev_reward = rewards;
-            seen_indices.extend(fresh_indices);
+        impl<T: EthSpec> AggregationStrategy<T> for NeverAggregate {
+            fn should_aggregate(&self, _: &Attestation<T>, _: &Attestation<T>) -> bool {
+                false
+            }
         }
+
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::with_aggregation_strategy(NeverAggregate);
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let step_size = 2;
+        let mut num_inserted = 0;
+
+        for bc in &committees {
+            for i in (0..bc.committee.len()).step_by(step_size) {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + step_size,
+                    slot,
+                    state,
+                    spec,
+                    None,
+                );
+                op_pool.insert_attestation(att, state, spec).unwrap();
+                num_inserted += 1;
+            }
+        }
+
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(op_pool.num_attestations(), num_inserted);
+    }
+
+    /// Inserting attestations from many threads at once should not lose any of them.
+    #[test]
+    fn attestation_concurrent_insert() {
+        use std::sync::Arc;
+        use std::thread;
+
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let num_slots = 16;
+        let num_threads = 8;
+        let step_size = 2;
+
+        // Pairwise disjoint attestations for every committee in the `num_slots` prior slots.
+        let mut attestations = vec![];
+        let mut num_committees = 0;
+        for slot in (1..=num_slots).map(|i| state.slot - i) {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                num_committees += 1;
+                for i in (0..bc.committee.len()).step_by(step_size) {
+                    attestations.push(signed_attestation(
+                        &bc.committee,
+                        bc.index,
+                        keypairs,
+                        i..i + step_size,
+                        slot,
+                        state,
+                        spec,
+                        None,
+                    ));
+                }
+            }
+        }
+
+        let op_pool = Arc::new(OperationPool::new());
+        let shared_state = Arc::new(state.clone());
+        let chunk_size = (attestations.len() + num_threads - 1) / num_threads;
+
+        let handles = attestations
+            .chunks(chunk_size)
+            .map(|chunk| {
+                let chunk = chunk.to_vec();
+                let op_pool = op_pool.clone();
+                let state = shared_state.clone();
+                let spec = spec.clone();
+                thread::spawn(move || {
+                    for attestation in chunk {
+                        op_pool
+                            .insert_attestation(attestation, &state, &spec)
+                            .unwrap();
+                    }
+                })
+            })
+            .collect::<Vec<_>>();
+
+        for handle in handles {
+            handle.join().unwrap();
+        }
+
+        // Every committee should have a single, fully aggregated attestation.
+        assert_eq!(num_attestation_ids(&op_pool), num_committees);
+        assert_eq!(op_pool.num_attestations(), num_committees);
+        assert!(op_pool
+            .attestations
+            .read_all()
+            .iter()
+            .flat_map(|shard| shard.values())
+            .flatten()
+            .all(|attestation| attestation.aggregation_bits.num_set_bits()
+                == attestation.aggregation_bits.len()));
+    }
+
+    /// Attestations should be pruned as soon as they fall outside the inclusion window.
+    #[test]
+    fn attestation_prune_exact() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+            let att = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool.insert_attestation(att, state, spec).unwrap();
+        }
+        let num_attestations = op_pool.num_attestations();
+        assert!(num_attestations > 0);
+
+        // The last slot at which the attestations may be included.
+        state.slot = slot + MainnetEthSpec::slots_per_epoch();
+        op_pool.prune_attestations(state);
+        assert_eq!(op_pool.num_attestations(), num_attestations);
+
+        // The approximate pruning would retain the attestations at this point.
+        state.slot += 1;
+        op_pool.prune_attestations_retaining(state, 1);
+        assert_eq!(op_pool.num_attestations(), num_attestations);
+
+        op_pool.prune_attestations(state);
+        assert_eq!(op_pool.num_attestations(), 0);
+    }
+
+    /// Attestations should survive pruning for the given number of epochs.
+    #[test]
+    fn attestation_prune_retaining() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        for bc in &committees {
+            let att = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool.insert_attestation(att, state, spec).unwrap();
+        }
+
+        // The default retention would prune the attestations at this point.
+        state.slot += 3 * MainnetEthSpec::slots_per_epoch();
+        op_pool.prune_attestations_retaining(state, 3);
+        assert_eq!(op_pool.num_attestations(), committees.len());
+
+        state.slot += MainnetEthSpec::slots_per_epoch();
+        op_pool.prune_attestations_retaining(state, 3);
+        assert_eq!(op_pool.num_attestations(), 0);
+    }
+
+    #[test]
+    fn prune_all_report() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+            let att = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool.insert_attestation(att, state, spec).unwrap();
+        }
+        let num_attestations = op_pool.num_attestations();
+        assert!(num_attestations > 0);
+
+        for proposer_index in &[0, 3] {
+            let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                *proposer_index,
+                &keypairs[*proposer_index as usize].sk,
+                &state.fork,
+                spec,
+            );
+            op_pool
+                .proposer_slashings
+                .write()
+                .insert(*proposer_index, slashing);
+        }
+
+        let attester_slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[1, 2],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let id = OperationPool::attester_slashing_id(&attester_slashing, state, spec);
+        op_pool
+            .attester_slashings
+            .write()
+            .insert(id, attester_slashing);
+
+        for validator_index in &[4, 5] {
+            let exit = SignedVoluntaryExit {
+                message: VoluntaryExit {
+                    epoch: Epoch::new(0),
+                    validator_index: *validator_index,
+                },
+                signature: Signature::empty_signature(),
+            };
+            op_pool
+                .voluntary_exits
+                .write()
+                .insert(*validator_index, exit);
+        }
+
+        // Move past the inclusion window of the attestations, onto a new fork, with validator 3
+        // slashed and validator 4 exited.
+        state.slot += MainnetEthSpec::slots_per_epoch() + 1;
+        state.fork = Fork {
+            previous_version: [1, 0, 0, 0],
+            current_version: [1, 0, 0, 0],
+            epoch: Epoch::new(0),
+        };
+        state.validators[3].slashed = true;
+        state.validators[4].exit_epoch = state.current_epoch();
+
+        assert_eq!(
+            op_pool.prune_all(state, spec),
+            PruneReport {
+                attestations_removed: num_attestations,
+                proposer_slashings_removed: 1,
+                attester_slashings_removed: 1,
+                voluntary_exits_removed: 1,
+            }
+        );
+        assert_eq!(op_pool.num_attestations(), 0);
+        assert_eq!(op_pool.num_proposer_slashings(), 1);
+        assert_eq!(op_pool.num_attester_slashings(), 0);
+        assert_eq!(op_pool.num_voluntary_exits(), 1);
+
+        // Nothing more is removed by pruning again.
+        assert_eq!(op_pool.prune_all(state, spec), PruneReport::default());
+    }
+
+    /// Attestations with an invalid signature should not be inserted by the verifying insert.
+    #[test]
+    fn attestation_insert_verified() {
+        use state_processing::per_block_processing::errors::{
+            AttestationInvalid, BlockOperationError, IndexedAttestationInvalid,
+        };
+
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let att = |range| {
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        // Sign with the keys of a different set of attesters.
+        let mut bad_att = att(0..2);
+        bad_att.signature = att(2..4).signature;
+        assert_eq!(
+            op_pool.insert_attestation_verified(bad_att, state, spec),
+            Err(BlockOperationError::Invalid(
+                AttestationInvalid::BadIndexedAttestation(IndexedAttestationInvalid::BadSignature)
+            ))
+        );
+        assert_eq!(op_pool.num_attestations(), 0);
+
+        op_pool
+            .insert_attestation_verified(att(0..2), state, spec)
+            .unwrap();
+        assert_eq!(op_pool.num_attestations(), 1);
+    }
+
+    #[test]
+    fn all_attestations_and_for_slot() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slots = [state.slot - 1, state.slot - 2];
+        for &slot in &slots {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                // Two overlapping attestations, which are not aggregated.
+                for range in vec![0..2, 1..3] {
+                    let att = signed_attestation(
+                        &bc.committee,
+                        bc.index,
+                        keypairs,
+                        range,
+                        slot,
+                        state,
+                        spec,
+                        None,
+                    );
+                    op_pool.insert_attestation(att, state, spec).unwrap();
+                }
+            }
+        }
+
+        let all = op_pool.all_attestations();
+        assert_eq!(all.len(), op_pool.num_attestations());
+        assert_eq!(all.len(), 4);
+
+        for &slot in &slots {
+            let for_slot = op_pool.attestations_for_slot(slot);
+            assert_eq!(for_slot.len(), 2);
+            assert!(for_slot.iter().all(|att| att.data.slot == slot));
+            assert!(for_slot.iter().all(|att| all.contains(att)));
+        }
+        assert!(op_pool.attestations_for_slot(state.slot).is_empty());
+    }
+
+    /// Adding an attestation already in the pool should not increase the size of the pool.
+    #[test]
+    fn attestation_duplicate() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        for bc in &committees {
+            let att = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool
+                .insert_attestation(att.clone(), state, spec)
+                .unwrap();
+            op_pool.insert_attestation(att, state, spec).unwrap();
+        }
+
+        assert_eq!(op_pool.num_attestations(), committees.len());
+    }
+
+    #[test]
+    fn contains_attestation() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .next()
+            .unwrap();
+
+        let att = |range: std::ops::Range<usize>| {
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        assert!(!op_pool.contains_attestation(&att(0..2), state, spec));
+
+        op_pool.insert_attestation(att(0..2), state, spec).unwrap();
+
+        // The exact attestation and its subsets are present.
+        assert!(op_pool.contains_attestation(&att(0..2), state, spec));
+        assert!(op_pool.contains_attestation(&att(1..2), state, spec));
+
+        // Attestations with any other attester are absent.
+        assert!(!op_pool.contains_attestation(&att(0..3), state, spec));
+        assert!(!op_pool.contains_attestation(&att(2..3), state, spec));
+    }
+
+    /// Merging two pools should be equivalent to inserting everything into a single pool.
+    #[test]
+    fn merge_matches_single_pool() {
+        let (ref state, ref keypairs, ref mut spec) = attestation_test_state::<MainnetEthSpec>(2);
+        // Allow the genesis validators to exit.
+        spec.persistent_committee_period = 0;
+        let spec = &*spec;
+
+        let committee_at = |slot| {
+            state
+                .get_beacon_committees_at_slot(slot)
+                .unwrap()
+                .into_iter()
+                .map(BeaconCommittee::into_owned)
+                .collect::<Vec<_>>()
+        };
+        let slot = state.slot - 1;
+        let committees = committee_at(slot);
+        let other_slot = state.slot - 2;
+        let other_committee = committee_at(other_slot).remove(0);
+
+        let att = |bc: &OwnedBeaconCommittee, range: std::ops::Range<usize>, slot| {
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        // An overlapping attestation, a disjoint one which aggregates, a duplicate, and one for a
+        // bucket which is only in the other pool.
+        let ours = vec![
+            att(&committees[0], 0..2, slot),
+            att(&committees[1], 0..2, slot),
+        ];
+        let theirs = vec![
+            att(&committees[0], 0..2, slot),
+            att(&committees[0], 1..3, slot),
+            att(&committees[1], 2..4, slot),
+            att(&other_committee, 0..2, other_slot),
+        ];
+
+        let proposer_slashing = |proposer_index| {
+            TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                proposer_index,
+                &keypairs[0].sk,
+                &state.fork,
+                spec,
+            )
+        };
+        let exit = |validator_index| SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: Epoch::new(0),
+                validator_index,
+            },
+            signature: Signature::empty_signature(),
+        };
+        let unknown_validator = state.validators.len() as u64;
+
+        let op_pool = OperationPool::new();
+        let other = OperationPool::new();
+        let single = OperationPool::new();
+
+        for attestation in &ours {
+            op_pool
+                .insert_attestation(attestation.clone(), state, spec)
+                .unwrap();
+        }
+        for attestation in &theirs {
+            other
+                .insert_attestation(attestation.clone(), state, spec)
+                .unwrap();
+        }
+        for attestation in ours.iter().chain(theirs.iter()) {
+            single
+                .insert_attestation(attestation.clone(), state, spec)
+                .unwrap();
+        }
+
+        // Operations for unknown validators are dropped by the merge.
+        for &index in &[1, 2, unknown_validator] {
+            other
+                .proposer_slashings
+                .write()
+                .insert(index, proposer_slashing(index));
+            other.voluntary_exits.write().insert(index, exit(index));
+        }
+        op_pool
+            .proposer_slashings
+            .write()
+            .insert(1, proposer_slashing(1));
+        for &index in &[1, 2] {
+            single
+                .proposer_slashings
+                .write()
+                .insert(index, proposer_slashing(index));
+            single.voluntary_exits.write().insert(index, exit(index));
+        }
+
+        op_pool.merge(other, state, spec);
+
+        assert_eq!(op_pool.num_attestations(), 4);
+        assert!(op_pool == single);
+    }
+
+    /// Merging should only enforce the pool's limits once every bucket has been merged, so which
+    /// buckets survive does not depend on the order in which they are merged.
+    #[test]
+    fn merge_enforces_bucket_limit_once() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let attestation = |i: u64, range: std::ops::Range<usize>| {
+            let slot = state.slot - 1 - i;
+            let bc = state
+                .get_beacon_committees_at_slot(slot)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        let op_pool = OperationPool::new().with_max_attestation_data_buckets(2);
+        let other = OperationPool::new();
+
+        op_pool
+            .insert_attestation(attestation(0, 0..1), state, spec)
+            .unwrap();
+        // The first bucket only has the most coverage once aggregated with `op_pool`'s.
+        for attestation in vec![
+            attestation(1, 0..3),
+            attestation(2, 0..2),
+            attestation(0, 1..4),
+        ] {
+            other.insert_attestation(attestation, state, spec).unwrap();
+        }
+
+        op_pool.merge(other, state, spec);
+
+        assert_eq!(op_pool.stats().num_unique_attestation_data, 2);
+        assert_eq!(op_pool.num_evicted_attestations(), 1);
+
+        let mut num_attesters = op_pool
+            .all_attestations()
+            .iter()
+            .map(|att| att.aggregation_bits.num_set_bits())
+            .collect::<Vec<_>>();
+        num_attesters.sort();
+        assert_eq!(num_attesters, vec![3, 4]);
+    }
+
+    #[test]
+    fn remove_block_operations() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(2);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+        let att = |bc: &OwnedBeaconCommittee, range: std::ops::Range<usize>| {
+            signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                range,
+                slot,
+                state,
+                spec,
+                None,
+            )
+        };
+
+        // Overlapping attestations are not aggregated, so the first committee has two.
+        op_pool
+            .insert_attestation(att(&committees[0], 0..2), state, spec)
+            .unwrap();
+        op_pool
+            .insert_attestation(att(&committees[0], 1..3), state, spec)
+            .unwrap();
+        op_pool
+            .insert_attestation(att(&committees[1], 0..2), state, spec)
+            .unwrap();
+
+        let proposer_slashing = |proposer_index| {
+            TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                proposer_index,
+                &keypairs[proposer_index as usize].sk,
+                &state.fork,
+                spec,
+            )
+        };
+        let attester_slashing = |validator_indices: &[u64]| {
+            TestingAttesterSlashingBuilder::double_vote(
+                AttesterSlashingTestTask::Valid,
+                validator_indices,
+                |_, _| Signature::empty_signature(),
+                &state.fork,
+                spec,
+            )
+        };
+        let exit = |validator_index| SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: Epoch::new(0),
+                validator_index,
+            },
+            signature: Signature::empty_signature(),
+        };
+
+        for &index in &[1, 2] {
+            op_pool
+                .proposer_slashings
+                .write()
+                .insert(index, proposer_slashing(index));
+            op_pool.voluntary_exits.write().insert(index, exit(index));
+        }
+        for validator_indices in &[[0_u64, 1], [2, 3]] {
+            let slashing = attester_slashing(validator_indices);
+            let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+            op_pool.attester_slashings.write().insert(id, slashing);
+        }
+
+        let mut block = BeaconBlock::empty(spec);
+        block
+            .body
+            .attestations
+            .push(att(&committees[0], 0..2))
+            .unwrap();
+        block
+            .body
+            .proposer_slashings
+            .push(proposer_slashing(1))
+            .unwrap();
+        block
+            .body
+            .attester_slashings
+            .push(attester_slashing(&[0, 1]))
+            .unwrap();
+        block.body.voluntary_exits.push(exit(2)).unwrap();
+
+        op_pool.remove_block_operations(&block, state, spec);
+
+        // The partially-overlapping aggregate remains in the bucket.
+        assert_eq!(op_pool.num_attestations(), 2);
+        assert!(!op_pool.contains_attestation(&att(&committees[0], 0..1), state, spec));
+        assert!(op_pool.contains_attestation(&att(&committees[0], 1..3), state, spec));
+        assert!(op_pool.contains_attestation(&att(&committees[1], 0..2), state, spec));
+
+        assert!(!op_pool.contains_proposer_slashing(1));
+        assert!(op_pool.contains_proposer_slashing(2));
+        assert!(!op_pool.contains_attester_slashing(&attester_slashing(&[0, 1]), state, spec));
+        assert!(op_pool.contains_attester_slashing(&attester_slashing(&[2, 3]), state, spec));
+        assert!(op_pool.contains_voluntary_exit(1));
+        assert!(!op_pool.contains_voluntary_exit(2));
+
+        // Once every attestation in a bucket is covered, the bucket is dropped.
+        op_pool.remove_attestation(&att(&committees[0], 0..3), state, spec);
+        assert_eq!(num_attestation_ids(&op_pool), 1);
+    }
+
+    /// Adding lots of attestations that only intersect pairwise should lead to two aggregate
+    /// attestations.
+    #[test]
+    fn attestation_pairwise_overlapping() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let step_size = 2;
+        for bc in &committees {
+            // Create attestations that overlap on `step_size` validators, like:
+            // {0,1,2,3}, {2,3,4,5}, {4,5,6,7}, ...
+            for i in (0..bc.committee.len() - step_size).step_by(step_size) {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + 2 * step_size,
+                    slot,
+                    state,
+                    spec,
+                    None,
+                );
+                op_pool.insert_attestation(att, state, spec).unwrap();
+            }
+        }
+
+        // The attestations should get aggregated into two attestations that comprise all
+        // validators.
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(op_pool.num_attestations(), 2 * committees.len());
+    }
+
+    /// Attestations inserted after one signed by the whole committee should be ignored.
+    #[test]
+    fn attestation_fully_aggregated() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let step_size = 2;
+        for bc in &committees {
+            let full = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            assert!(is_fully_aggregated(&full));
+            op_pool.insert_attestation(full, state, spec).unwrap();
+
+            for i in (0..bc.committee.len() - step_size).step_by(step_size) {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + 2 * step_size,
+                    slot,
+                    state,
+                    spec,
+                    None,
+                );
+                assert!(!is_fully_aggregated(&att));
+                op_pool.insert_attestation(att, state, spec).unwrap();
+            }
+        }
+
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(op_pool.num_attestations(), committees.len());
+    }
+
+    /// Create a bunch of attestations signed by a small number of validators, and another
+    /// bunch signed by a larger number, such that there are at least `max_attestations`
+    /// signed by the larger number. Then, check that `get_attestations` only returns the
+    /// high-quality attestations. To ensure that no aggregation occurs, ALL attestations
+    /// are also signed by the 0th member of the committee.
+    #[test]
+    fn attestation_get_max() {
+        let small_step_size = 2;
+        let big_step_size = 4;
+
+        let (ref mut state, ref keypairs, ref spec) =
+            attestation_test_state::<MainnetEthSpec>(big_step_size);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
+        let target_committee_size = spec.target_committee_size as usize;
+
+        let insert_attestations = |bc: &OwnedBeaconCommittee, step_size| {
+            for i in (0..target_committee_size).step_by(step_size) {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + step_size,
+                    slot,
+                    state,
+                    spec,
+                    if i == 0 { None } else { Some(0) },
+                );
+                op_pool.insert_attestation(att, state, spec).unwrap();
+            }
+        };
+
+        for committee in &committees {
+            assert_eq!(committee.committee.len(), target_committee_size);
+            // Attestations signed by only 2-3 validators
+            insert_attestations(committee, small_step_size);
+            // Attestations signed by 4+ validators
+            insert_attestations(committee, big_step_size);
+        }
+
+        let num_small = target_committee_size / small_step_size;
+        let num_big = target_committee_size / big_step_size;
+
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(
+            op_pool.num_attestations(),
+            (num_small + num_big) * committees.len()
+        );
+        assert!(op_pool.num_attestations() > max_attestations);
+
+        state.slot += spec.min_attestation_inclusion_delay;
+        let best_attestations = op_pool
+            .get_attestations(state, spec)
+            .expect("should have best attestations");
+        assert_eq!(best_attestations.len(), max_attestations);
+
+        // All the best attestations should be signed by at least `big_step_size` (4) validators.
+        for att in &best_attestations {
+            assert!(att.aggregation_bits.num_set_bits() >= big_step_size);
+        }
+    }
+
+    /// Pools holding the same attestations of equal value should pack them identically, regardless
+    /// of the order they were inserted in (and the iteration order of the pools' maps). As in
+    /// `attestation_get_max`, all attestations are also signed by the 0th member of the committee
+    /// to prevent aggregation.
+    #[test]
+    fn attestation_get_deterministic() {
+        let num_committees = 4;
+        let step_size = 2;
+        let (ref mut state, ref keypairs, ref spec) =
+            attestation_test_state::<MainnetEthSpec>(num_committees);
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let target_committee_size = spec.target_committee_size as usize;
+        let attestations = committees
+            .iter()
+            .flat_map(|bc| {
+                (0..target_committee_size)
+                    .step_by(step_size)
+                    .map(move |i| (bc, i))
+            })
+            .map(|(bc, i)| {
+                signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + step_size,
+                    slot,
+                    state,
+                    spec,
+                    if i == 0 { None } else { Some(0) },
+                )
+            })
+            .collect::<Vec<_>>();
+        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
+        assert!(attestations.len() > max_attestations);
+
+        let forwards = OperationPool::new();
+        for att in attestations.iter().cloned() {
+            forwards.insert_attestation(att, state, spec).unwrap();
+        }
+        let backwards = OperationPool::new();
+        for att in attestations.into_iter().rev() {
+            backwards.insert_attestation(att, state, spec).unwrap();
+        }
+
+        state.slot += spec.min_attestation_inclusion_delay;
+        let forwards_best = forwards.get_attestations(state, spec).unwrap();
+        assert_eq!(forwards_best.len(), max_attestations);
+        assert_eq!(
+            forwards_best,
+            backwards.get_attestations(state, spec).unwrap()
+        );
+    }
+
+    /// `get_attestations_limited` should return exactly `limit` of the highest-value attestations.
+    #[test]
+    fn attestation_get_limited() {
+        let small_step_size = 2;
+        let big_step_size = 4;
+
+        let (ref mut state, ref keypairs, ref spec) =
+            attestation_test_state::<MainnetEthSpec>(big_step_size);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
+        let target_committee_size = spec.target_committee_size as usize;
+
+        for committee in &committees {
+            for &step_size in &[small_step_size, big_step_size] {
+                for i in (0..target_committee_size).step_by(step_size) {
+                    let att = signed_attestation(
+                        &committee.committee,
+                        committee.index,
+                        keypairs,
+                        i..i + step_size,
+                        slot,
+                        state,
+                        spec,
+                        if i == 0 { None } else { Some(0) },
+                    );
+                    op_pool.insert_attestation(att, state, spec).unwrap();
+                }
+            }
+        }
+
+        state.slot += spec.min_attestation_inclusion_delay;
+
+        let limit = 3;
+        assert!(limit < max_attestations);
+
+        let limited = op_pool
+            .get_attestations_limited(state, spec, limit)
+            .expect("should have limited attestations");
+        assert_eq!(limited.len(), limit);
+
+        // The attestations signed by only 2-3 validators are never the most valuable.
+        for att in &limited {
+            assert!(att.aggregation_bits.num_set_bits() >= big_step_size);
+        }
+
+        assert_eq!(
+            op_pool.get_attestations(state, spec).unwrap().len(),
+            max_attestations
+        );
+    }
+
+    #[test]
+    fn attestation_rewards() {
+        let small_step_size = 2;
+        let big_step_size = 4;
+
+        let (ref mut state, ref keypairs, ref spec) =
+            attestation_test_state::<MainnetEthSpec>(big_step_size);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let max_attestations = <MainnetEthSpec as EthSpec>::MaxAttestations::to_usize();
+        let target_committee_size = spec.target_committee_size as usize;
+
+        // Each validator will have a multiple of 1_000_000_000 wei.
+        // Safe from overflow unless there are about 18B validators (2^64 / 1_000_000_000).
+        for i in 0..state.validators.len() {
+            state.validators[i].effective_balance = 1_000_000_000 * i as u64;
+        }
+
+        let insert_attestations = |bc: &OwnedBeaconCommittee, step_size| {
+            for i in (0..target_committee_size).step_by(step_size) {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    i..i + step_size,
+                    slot,
+                    state,
+                    spec,
+                    if i == 0 { None } else { Some(0) },
+                );
+                op_pool.insert_attestation(att, state, spec).unwrap();
+            }
+        };
+
+        for committee in &committees {
+            assert_eq!(committee.committee.len(), target_committee_size);
+            // Attestations signed by only 2-3 validators
+            insert_attestations(committee, small_step_size);
+            // Attestations signed by 4+ validators
+            insert_attestations(committee, big_step_size);
+        }
+
+        let num_small = target_committee_size / small_step_size;
+        let num_big = target_committee_size / big_step_size;
+
+        assert_eq!(num_attestation_ids(&op_pool), committees.len());
+        assert_eq!(
+            op_pool.num_attestations(),
+            (num_small + num_big) * committees.len()
+        );
+        assert!(op_pool.num_attestations() > max_attestations);
+
+        state.slot += spec.min_attestation_inclusion_delay;
+        let best_attestations = op_pool
+            .get_attestations(state, spec)
+            .expect("should have valid best attestations");
+        assert_eq!(best_attestations.len(), max_attestations);
+
+        let active_indices = state
+            .get_cached_active_validator_indices(RelativeEpoch::Current)
+            .unwrap();
+        let total_active_balance = state.get_total_balance(&active_indices, spec).unwrap();
+
+        // Set of indices covered by previous attestations in `best_attestations`.
+        let mut seen_indices = BTreeSet::new();
+        // Used for asserting that rewards are in decreasing order.
+        let mut prev_reward = u64::max_value();
+
+        for att in &best_attestations {
+            let fresh_validators_bitlist = earliest_attestation_validators(att, state);
+            let att_indices =
+                get_attesting_indices(state, &att.data, &fresh_validators_bitlist).unwrap();
+            let fresh_indices = &att_indices - &seen_indices;
+
+            let rewards = fresh_indices
+                .iter()
+                .map(|validator_index| {
+                    get_base_reward(state, *validator_index as usize, total_active_balance, spec)
+                        .unwrap()
+                        / spec.proposer_reward_quotient
+                })
+                .sum();
+
+            // Check that rewards are in decreasing order
+            assert!(prev_reward >= rewards);
+
+            prev_reward = rewards;
+            seen_indices.extend(fresh_indices);
+        }
+    }
+
+    /// An attestation from fewer validators should be preferred if those validators have a
+    /// greater effective balance, since it yields a greater proposer reward.
+    #[test]
+    fn attestation_rewards_weighted_by_balance() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .next()
+            .unwrap();
+
+        // Only the first two members of the committee have a large effective balance.
+        for (i, &validator_index) in bc.committee.iter().enumerate() {
+            state.validators[validator_index].effective_balance = if i < 2 {
+                spec.max_effective_balance
+            } else {
+                spec.effective_balance_increment
+            };
+        }
+
+        // The attestations overlap on the third member, so they are not aggregated.
+        let small = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            0..3,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        let big = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            2..8,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        op_pool
+            .insert_attestation(big.clone(), state, spec)
+            .unwrap();
+        op_pool
+            .insert_attestation(small.clone(), state, spec)
+            .unwrap();
+        assert_eq!(op_pool.num_attestations(), 2);
+
+        state.slot += spec.min_attestation_inclusion_delay;
+        let best_attestations = op_pool
+            .get_attestations(state, spec)
+            .expect("should have valid best attestations");
+        assert_eq!(best_attestations, vec![small, big]);
+    }
+
+    /// Repeated calls to `get_attestations_cached` for the same state should be served from the
+    /// cache, until the attestations change or the state advances.
+    #[test]
+    fn attestation_get_cached() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+
+        let bc = &committees[0];
+        let att1 = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            ..2,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        let att2 = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            2..4,
+            slot,
+            state,
+            spec,
+            None,
+        );
+
+        op_pool.insert_attestation(att1, state, spec).unwrap();
+        state.slot += spec.min_attestation_inclusion_delay;
+
+        let first = op_pool.get_attestations_cached(state, spec).unwrap();
+        assert_eq!(first, op_pool.get_attestations(state, spec).unwrap());
+
+        // A second call for the same state is served from the cache.
+        let clear_cached_attestations = || {
+            if let Some(cached) = &mut *op_pool.attestations_cache.write() {
+                cached.attestations = vec![];
+            }
+        };
+        clear_cached_attestations();
+        assert_eq!(
+            op_pool.get_attestations_cached(state, spec).unwrap(),
+            vec![]
+        );
+
+        // Inserting an attestation outdates the cached result.
+        op_pool.insert_attestation(att2, state, spec).unwrap();
+        let second = op_pool.get_attestations_cached(state, spec).unwrap();
+        assert_eq!(second.len(), 1);
+        assert_eq!(second[0].aggregation_bits.num_set_bits(), 4);
+
+        // As does advancing the state.
+        clear_cached_attestations();
+        state.slot += 1;
+        assert_eq!(
+            op_pool.get_attestations_cached(state, spec).unwrap(),
+            second
+        );
+    }
+
+    #[test]
+    fn persisted_attester_slashings_dropped_on_fork_change() {
+        let (ref mut state, _, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::new();
+
+        let slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[0, 1],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+        op_pool.attester_slashings.write().insert(id, slashing);
+
+        let persisted = PersistedOperationPool::from_operation_pool(&op_pool);
+
+        // Reloading against the same fork retains the slashing.
+        assert_eq!(
+            persisted
+                .clone()
+                .into_operation_pool(OperationPool::new(), state, spec)
+                .num_attester_slashings(),
+            1
+        );
+
+        // Reloading against a different fork drops the slashing.
+        state.fork = Fork {
+            previous_version: [1, 0, 0, 0],
+            current_version: [1, 0, 0, 0],
+            epoch: Epoch::new(0),
+        };
+        assert_eq!(
+            persisted
+                .into_operation_pool(OperationPool::new(), state, spec)
+                .num_attester_slashings(),
+            0
+        );
+    }
+
+    #[test]
+    fn persisted_pool_round_trip_drops_stale_operations() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::new();
+
+        let slot = state.slot - 1;
+        let committees = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .map(BeaconCommittee::into_owned)
+            .collect::<Vec<_>>();
+        let bc = &committees[0];
+        let att = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            ..2,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        op_pool.insert_attestation(att, state, spec).unwrap();
+
+        let attester_slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[1, 2],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let id = OperationPool::attester_slashing_id(&attester_slashing, state, spec);
+        op_pool
+            .attester_slashings
+            .write()
+            .insert(id, attester_slashing);
+
+        for proposer_index in &[0, 3] {
+            let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                *proposer_index,
+                &keypairs[*proposer_index as usize].sk,
+                &state.fork,
+                spec,
+            );
+            op_pool
+                .proposer_slashings
+                .write()
+                .insert(*proposer_index, slashing);
+        }
+
+        // An exit for an unknown validator.
+        let exit = SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: Epoch::new(0),
+                validator_index: state.validators.len() as u64,
+            },
+            signature: Signature::empty_signature(),
+        };
+        op_pool
+            .voluntary_exits
+            .write()
+            .insert(exit.message.validator_index, exit);
+
+        let bytes = PersistedOperationPool::from_operation_pool(&op_pool).as_bytes();
+
+        // Restoring against the same state only drops the exit.
+        let restored = PersistedOperationPool::<MainnetEthSpec>::from_bytes(&bytes)
+            .unwrap()
+            .into_operation_pool(OperationPool::new(), state, spec);
+        op_pool.voluntary_exits.write().clear();
+        assert_eq!(restored, op_pool);
+
+        // Once validator 3 is slashed, its proposer slashing is stale.
+        state.validators[3].slashed = true;
+        let restored = PersistedOperationPool::<MainnetEthSpec>::from_bytes(&bytes)
+            .unwrap()
+            .into_operation_pool(OperationPool::new(), state, spec);
+        op_pool.proposer_slashings.write().remove(&3);
+        assert_eq!(restored, op_pool);
+        assert_eq!(restored.num_attestations(), 1);
+        assert_eq!(restored.num_attester_slashings(), 1);
+    }
+
+    /// A restored pool should keep the limits of the pool it is restored into, and enforce them
+    /// on the restored operations.
+    #[test]
+    fn persisted_pool_keeps_limits() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::new();
+
+        // Each attestation is for a different slot, so each is in its own bucket.
+        for (i, &n) in [1, 2].iter().enumerate() {
+            let slot = state.slot - 1 - i as u64;
+            let bc = state
+                .get_beacon_committees_at_slot(slot)
+                .unwrap()
+                .into_iter()
+                .next()
+                .unwrap();
+            let attestation = signed_attestation(
+                &bc.committee,
+                bc.index,
+                keypairs,
+                ..n,
+                slot,
+                state,
+                spec,
+                None,
+            );
+            op_pool
+                .insert_attestation(attestation, state, spec)
+                .unwrap();
+        }
+
+        let restored = PersistedOperationPool::from_operation_pool(&op_pool).into_operation_pool(
+            OperationPool::new().with_max_attestation_data_buckets(1),
+            state,
+            spec,
+        );
+        assert_eq!(restored.max_attestation_data_buckets, Some(1));
+        assert_eq!(restored.stats().num_unique_attestation_data, 1);
+        assert_eq!(
+            restored.all_attestations()[0]
+                .aggregation_bits
+                .num_set_bits(),
+            2
+        );
+    }
+
+    #[test]
+    fn persisted_pool_version() {
+        use ssz_derive::Encode;
+
+        /// The layout of `PersistedOperationPool` before it was versioned.
+        #[derive(Encode)]
+        struct PersistedOperationPoolV0<T: EthSpec> {
+            attestations: Vec<(AttestationId, Vec<Attestation<T>>)>,
+            attester_slashings: Vec<((AttestationId, AttestationId), AttesterSlashing<T>)>,
+            proposer_slashings: Vec<ProposerSlashing>,
+            voluntary_exits: Vec<SignedVoluntaryExit>,
+        }
+
+        let (ref state, _, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let persisted = PersistedOperationPool::from_operation_pool(&op_pool);
+        let decoded = PersistedOperationPool::from_bytes(&persisted.as_bytes()).unwrap();
+        assert_eq!(
+            decoded.into_operation_pool(OperationPool::new(), state, spec),
+            op_pool
+        );
+
+        let v0 = PersistedOperationPoolV0::<MainnetEthSpec> {
+            attestations: vec![],
+            attester_slashings: vec![],
+            proposer_slashings: vec![],
+            voluntary_exits: vec![],
+        };
+        match PersistedOperationPool::<MainnetEthSpec>::from_bytes(&v0.as_ssz_bytes()) {
+            Err(PersistenceError::UnsupportedVersion { expected, .. }) => {
+                assert_eq!(expected, PersistedOperationPool::<MainnetEthSpec>::VERSION)
+            }
+            other => panic!("v0 pool should be rejected, got {:?}", other),
+        }
+
+        assert!(PersistedOperationPool::<MainnetEthSpec>::from_bytes(&[]).is_err());
+    }
+
+    /// A pool from before attester slashing IDs were sorted should be migrated, rather than
+    /// having its slashings dropped for being stored under the wrong ID.
+    #[test]
+    fn persisted_pool_migrates_unsorted_attester_slashing_ids() {
+        let (ref state, _, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::new();
+
+        let slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[0, 1],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let (id_1, id_2) = OperationPool::attester_slashing_id(&slashing, state, spec);
+        op_pool
+            .attester_slashings
+            .write()
+            .insert((id_2, id_1), slashing);
+
+        // Version 1 has the same layout, so only the version (always first) needs changing.
+        let mut bytes = PersistedOperationPool::from_operation_pool(&op_pool).as_bytes();
+        bytes[..2].copy_from_slice(&1_u16.as_ssz_bytes());
+
+        let restored = PersistedOperationPool::<MainnetEthSpec>::from_bytes(&bytes)
+            .unwrap()
+            .into_operation_pool(OperationPool::new(), state, spec);
+        assert_eq!(restored.num_attester_slashings(), 1);
+    }
+
+    #[test]
+    fn voluntary_exits_by_epoch() {
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        for &(validator_index, epoch) in &[(3, 2), (1, 1), (0, 2), (2, 5)] {
+            let exit = SignedVoluntaryExit {
+                message: VoluntaryExit {
+                    epoch: Epoch::new(epoch),
+                    validator_index,
+                },
+                signature: Signature::empty_signature(),
+            };
+            op_pool
+                .voluntary_exits
+                .write()
+                .insert(validator_index, exit);
+        }
+
+        let expected = vec![
+            (Epoch::new(1), vec![1]),
+            (Epoch::new(2), vec![0, 3]),
+            (Epoch::new(5), vec![2]),
+        ]
+        .into_iter()
+        .collect::<BTreeMap<_, _>>();
+
+        assert_eq!(op_pool.exits_by_epoch(), expected);
+    }
+
+    #[test]
+    fn future_voluntary_exits() {
+        let (ref state, _, ref mut spec) = attestation_test_state::<MainnetEthSpec>(1);
+        // Allow the genesis validators to exit.
+        spec.persistent_committee_period = 0;
+        let spec = &*spec;
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let current_epoch = state.current_epoch();
+        for &(validator_index, epoch) in &[(0, current_epoch), (1, current_epoch + 1)] {
+            let exit = SignedVoluntaryExit {
+                message: VoluntaryExit {
+                    epoch,
+                    validator_index,
+                },
+                signature: Signature::empty_signature(),
+            };
+            op_pool
+                .voluntary_exits
+                .write()
+                .insert(validator_index, exit);
+        }
+        let validator_indices = |exits: Vec<SignedVoluntaryExit>| {
+            let mut indices = exits
+                .into_iter()
+                .map(|exit| exit.message.validator_index)
+                .collect::<Vec<_>>();
+            indices.sort_unstable();
+            indices
+        };
+
+        assert_eq!(
+            validator_indices(op_pool.get_voluntary_exits(state, spec)),
+            vec![0]
+        );
+        assert_eq!(
+            validator_indices(op_pool.get_voluntary_exits_for_epoch(state, spec, current_epoch)),
+            vec![0]
+        );
+        assert_eq!(
+            validator_indices(op_pool.get_voluntary_exits_for_epoch(
+                state,
+                spec,
+                current_epoch + 1
+            )),
+            vec![0, 1]
+        );
+        assert_eq!(
+            validator_indices(op_pool.pending_voluntary_exits(state)),
+            vec![1]
+        );
+
+        // Once the epoch of the exit is reached, it is no longer pending.
+        let mut next_state = state.clone();
+        next_state.slot = (current_epoch + 1).start_slot(MainnetEthSpec::slots_per_epoch());
+        assert_eq!(
+            validator_indices(op_pool.get_voluntary_exits(&next_state, spec)),
+            vec![0, 1]
+        );
+        assert!(op_pool.pending_voluntary_exits(&next_state).is_empty());
+    }
+
+    #[test]
+    fn pool_stats() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        assert_eq!(op_pool.stats(), PoolStats::default());
+
+        // Two overlapping attestations for each of two slots, which are not aggregated.
+        for &slot in &[state.slot - 1, state.slot - 2] {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                for range in vec![0..2, 1..3] {
+                    let att = signed_attestation(
+                        &bc.committee,
+                        bc.index,
+                        keypairs,
+                        range,
+                        slot,
+                        state,
+                        spec,
+                        None,
+                    );
+                    op_pool.insert_attestation(att, state, spec).unwrap();
+                }
+            }
+        }
+
+        for validator_index in 0..3 {
+            let slashing = TestingAttesterSlashingBuilder::double_vote(
+                AttesterSlashingTestTask::Valid,
+                &[validator_index],
+                |_, _| Signature::empty_signature(),
+                &state.fork,
+                spec,
+            );
+            let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+            op_pool.attester_slashings.write().insert(id, slashing);
+        }
+
+        let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+            ProposerSlashingTestTask::Valid,
+            0,
+            &keypairs[0].sk,
+            &state.fork,
+            spec,
+        );
+        op_pool.proposer_slashings.write().insert(0, slashing);
+
+        for validator_index in 0..4 {
+            let exit = SignedVoluntaryExit {
+                message: VoluntaryExit {
+                    epoch: Epoch::new(0),
+                    validator_index,
+                },
+                signature: Signature::empty_signature(),
+            };
+            op_pool
+                .voluntary_exits
+                .write()
+                .insert(validator_index, exit);
+        }
+
+        assert_eq!(
+            op_pool.stats(),
+            PoolStats {
+                num_attestations: 4,
+                num_unique_attestation_data: 2,
+                num_attester_slashings: 3,
+                num_proposer_slashings: 1,
+                num_voluntary_exits: 4,
+            }
+        );
+    }
+
+    #[test]
+    fn block_operations_match_individual_getters() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        assert_eq!(
+            op_pool.get_block_operations(state, spec),
+            Ok(BlockOperations::default())
+        );
+
+        for &slot in &[state.slot - 1, state.slot - 2] {
+            for bc in state.get_beacon_committees_at_slot(slot).unwrap() {
+                let att = signed_attestation(
+                    &bc.committee,
+                    bc.index,
+                    keypairs,
+                    ..2,
+                    slot,
+                    state,
+                    spec,
+                    None,
+                );
+                op_pool.insert_attestation(att, state, spec).unwrap();
+            }
+        }
+
+        // Validator 0 is slashed by both a proposer slashing and an attester slashing, so only the
+        // attester slashings for validators 1 and 2 should be included.
+        for validator_index in 0..3 {
+            let slashing = TestingAttesterSlashingBuilder::double_vote(
+                AttesterSlashingTestTask::Valid,
+                &[validator_index],
+                |_, _| Signature::empty_signature(),
+                &state.fork,
+                spec,
+            );
+            let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+            op_pool.attester_slashings.write().insert(id, slashing);
+        }
+
+        let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+            ProposerSlashingTestTask::Valid,
+            0,
+            &keypairs[0].sk,
+            &state.fork,
+            spec,
+        );
+        op_pool.proposer_slashings.write().insert(0, slashing);
+
+        let operations = op_pool.get_block_operations(state, spec).unwrap();
+        let (proposer_slashings, attester_slashings) = op_pool.get_slashings(state, spec);
+        assert_eq!(
+            operations,
+            BlockOperations {
+                attestations: op_pool.get_attestations(state, spec).unwrap(),
+                proposer_slashings,
+                attester_slashings,
+                voluntary_exits: op_pool.get_voluntary_exits(state, spec),
+            }
+        );
+        assert_eq!(operations.attestations.len(), 2);
+        assert_eq!(operations.proposer_slashings.len(), 1);
+        assert_eq!(operations.attester_slashings.len(), 2);
+    }
+
+    #[test]
+    fn snapshot_unaffected_by_later_inserts() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let slot = state.slot - 1;
+        let bc = state
+            .get_beacon_committees_at_slot(slot)
+            .unwrap()
+            .into_iter()
+            .next()
+            .unwrap();
+        let att = signed_attestation(
+            &bc.committee,
+            bc.index,
+            keypairs,
+            ..2,
+            slot,
+            state,
+            spec,
+            None,
+        );
+        op_pool.insert_attestation(att, state, spec).unwrap();
+
+        let snapshot = op_pool.snapshot();
+        assert_eq!(
+            snapshot.attestations.values().map(Vec::len).sum::<usize>(),
+            1
+        );
+        assert!(snapshot.proposer_slashings.is_empty());
+
+        let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+            ProposerSlashingTestTask::Valid,
+            0,
+            &keypairs[0].sk,
+            &state.fork,
+            spec,
+        );
+        op_pool
+            .insert_proposer_slashing(slashing, state, spec)
+            .unwrap();
+        assert_eq!(op_pool.num_proposer_slashings(), 1);
+
+        // The earlier snapshot does not reflect the insert, but a new one does.
+        assert!(snapshot.proposer_slashings.is_empty());
+        assert_eq!(op_pool.snapshot().proposer_slashings.len(), 1);
+        assert_eq!(op_pool.snapshot().attestations, snapshot.attestations);
+    }
+
+    #[test]
+    fn attester_slashing_id_ignores_order() {
+        let (ref state, _, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[0, 1],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let swapped = AttesterSlashing {
+            attestation_1: slashing.attestation_2.clone(),
+            attestation_2: slashing.attestation_1.clone(),
+        };
+
+        for slashing in vec![slashing, swapped] {
+            let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+            op_pool.attester_slashings.write().insert(id, slashing);
+        }
+
+        assert_eq!(op_pool.num_attester_slashings(), 1);
+    }
+
+    #[test]
+    fn contains_slashings_and_exits() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let proposer_slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+            ProposerSlashingTestTask::Valid,
+            1,
+            &keypairs[1].sk,
+            &state.fork,
+            spec,
+        );
+        let attester_slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[0, 1],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let other_attester_slashing = TestingAttesterSlashingBuilder::double_vote(
+            AttesterSlashingTestTask::Valid,
+            &[2, 3],
+            |_, _| Signature::empty_signature(),
+            &state.fork,
+            spec,
+        );
+        let exit = SignedVoluntaryExit {
+            message: VoluntaryExit {
+                epoch: Epoch::new(0),
+                validator_index: 2,
+            },
+            signature: Signature::empty_signature(),
+        };
+
+        assert!(!op_pool.contains_proposer_slashing(1));
+        assert!(!op_pool.contains_attester_slashing(&attester_slashing, state, spec));
+        assert!(!op_pool.contains_voluntary_exit(2));
+
+        op_pool
+            .proposer_slashings
+            .write()
+            .insert(1, proposer_slashing);
+        let id = OperationPool::attester_slashing_id(&attester_slashing, state, spec);
+        op_pool
+            .attester_slashings
+            .write()
+            .insert(id, attester_slashing.clone());
+        op_pool.voluntary_exits.write().insert(2, exit);
+
+        assert!(op_pool.contains_proposer_slashing(1));
+        assert!(!op_pool.contains_proposer_slashing(2));
+        assert!(op_pool.contains_attester_slashing(&attester_slashing, state, spec));
+        assert!(!op_pool.contains_attester_slashing(&other_attester_slashing, state, spec));
+        assert!(op_pool.contains_voluntary_exit(2));
+        assert!(!op_pool.contains_voluntary_exit(1));
+    }
+
+    #[test]
+    fn attester_slashings_tie_break_by_lowest_index() {
+        let (ref state, _, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+
+        let max_slashings = <MainnetEthSpec as EthSpec>::MaxAttesterSlashings::to_usize();
+
+        // One more slashing than can be included, each slashing a single validator.
+        let validator_indices = (0..=max_slashings as u64).rev().collect::<Vec<_>>();
+
+        for _ in 0..4 {
+            let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+            for &validator_index in &validator_indices {
+                let slashing = TestingAttesterSlashingBuilder::double_vote(
+                    AttesterSlashingTestTask::Valid,
+                    &[validator_index],
+                    |_, _| Signature::empty_signature(),
+                    &state.fork,
+                    spec,
+                );
+                let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+                op_pool.attester_slashings.write().insert(id, slashing);
+            }
+
+            let (_, attester_slashings) = op_pool.get_slashings(state, spec);
+            let slashed = attester_slashings
+                .iter()
+                .map(|slashing| slashing.attestation_1.attesting_indices[0])
+                .collect::<Vec<_>>();
+
+            assert_eq!(slashed, (0..max_slashings as u64).collect::<Vec<_>>());
+        }
+    }
+
+    #[test]
+    fn get_slashings_limited() {
+        let (ref state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let proposer_slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+            ProposerSlashingTestTask::Valid,
+            10,
+            &keypairs[10].sk,
+            &state.fork,
+            spec,
+        );
+        op_pool
+            .proposer_slashings
+            .write()
+            .insert(10, proposer_slashing);
+
+        for validator_indices in vec![vec![1_u64], vec![2, 3], vec![4, 5, 6], vec![7, 8, 9, 10]] {
+            let slashing = TestingAttesterSlashingBuilder::double_vote(
+                AttesterSlashingTestTask::Valid,
+                &validator_indices,
+                |_, _| Signature::empty_signature(),
+                &state.fork,
+                spec,
+            );
+            let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+            op_pool.attester_slashings.write().insert(id, slashing);
+        }
+
+        let first_slashed = |slashings: &[AttesterSlashing<MainnetEthSpec>]| {
+            slashings
+                .iter()
+                .map(|slashing| slashing.attestation_1.attesting_indices[0])
+                .collect::<Vec<_>>()
+        };
+
+        // Without the proposer slashing, the attester slashings which slash the most validators
+        // are chosen.
+        let (proposer_slashings, attester_slashings) =
+            op_pool.get_slashings_limited(state, spec, 0, 2);
+        assert!(proposer_slashings.is_empty());
+        assert_eq!(first_slashed(&attester_slashings), vec![7, 4]);
+
+        // With the proposer slashing, the largest attester slashing only slashes as many
+        // validators as the next, so the tie is broken by the lowest index.
+        let (proposer_slashings, attester_slashings) =
+            op_pool.get_slashings_limited(state, spec, 1, 3);
+        assert_eq!(proposer_slashings.len(), 1);
+        assert_eq!(first_slashed(&attester_slashings), vec![4, 7, 2]);
+
+        // The limits may exceed the number of slashings available.
+        let (proposer_slashings, attester_slashings) =
+            op_pool.get_slashings_limited(state, spec, 16, 16);
+        assert_eq!(proposer_slashings.len(), 1);
+        assert_eq!(attester_slashings.len(), 4);
+    }
+
+    #[test]
+    fn proposer_slashings_prefer_high_balance() {
+        let (ref mut state, ref keypairs, ref spec) = attestation_test_state::<MainnetEthSpec>(1);
+        let op_pool = OperationPool::<MainnetEthSpec>::new();
+
+        let max_slashings = <MainnetEthSpec as EthSpec>::MaxProposerSlashings::to_usize();
+        let low_balance_index = 3;
+
+        for proposer_index in 0..=max_slashings as u64 {
+            let slashing = TestingProposerSlashingBuilder::double_vote::<MainnetEthSpec>(
+                ProposerSlashingTestTask::Valid,
+                proposer_index,
+                &keypairs[proposer_index as usize].sk,
+                &state.fork,
+                spec,
+            );
+            op_pool
+                .proposer_slashings
+                .write()
+                .insert(proposer_index, slashing);
+        }
+
+        state.validators[low_balance_index].effective_balance -= spec.effective_balance_increment;
+
+        let (proposer_slashings, _) = op_pool.get_slashings(state, spec);
+
+        assert_eq!(proposer_slashings.len(), max_slashings);
+        assert!(proposer_slashings
+            .iter()
+            .all(|slashing| slashing.proposer_index != low_balance_index as u64));
     }
 }
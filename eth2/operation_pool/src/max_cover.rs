@@ -10,6 +10,8 @@ pub trait MaxCover {
     type Object;
     /// The type used to represent sets.
     type Set: Clone;
+    /// A key used to choose between items of equal score, so that the solution is deterministic.
+    type Key: Ord;
 
     /// Extract an object for inclusion in a solution.
     fn object(&self) -> Self::Object;
@@ -20,18 +22,24 @@ pub trait MaxCover {
     fn update_covering_set(&mut self, max_obj: &Self::Object, max_set: &Self::Set);
     /// The quality of this item's covering set, usually its cardinality.
     fn score(&self) -> usize;
+    /// The tie-breaking key for this item. Among items of equal score, the one with the smallest
+    /// key is selected first.
+    fn key(&self) -> Self::Key;
 }
 
 /// Helper struct to track which items of the input are still available for inclusion.
 /// Saves removing elements from the work vector.
-struct MaxCoverItem<T> {
+struct MaxCoverItem<T: MaxCover> {
     item: T,
+    /// The item's `key`, computed once up front.
+    key: T::Key,
     available: bool,
 }
 
-impl<T> MaxCoverItem<T> {
+impl<T: MaxCover> MaxCoverItem<T> {
     fn new(item: T) -> Self {
         MaxCoverItem {
+            key: item.key(),
             item,
             available: true,
         }
@@ -50,19 +58,24 @@ where
     // Construct an initial vec of all items, marked available.
     let mut all_items: Vec<_> = items_iter
         .into_iter()
+        .filter(|item| item.score() != 0)
         .map(MaxCoverItem::new)
-        .filter(|x| x.item.score() != 0)
         .collect();
 
     let mut result = vec![];
 
     for _ in 0..limit {
-        // Select the item with the maximum score.
+        // Select the item with the maximum score, breaking ties in favour of the smallest key so
+        // that the result doesn't depend on the order of `items_iter`.
         let (best_item, best_cover) = match all_items
             .iter_mut()
             .filter(|x| x.available && x.item.score() != 0)
-            .max_by_key(|x| x.item.score())
-        {
+            .max_by(|a, b| {
+                a.item
+                    .score()
+                    .cmp(&b.item.score())
+                    .then_with(|| b.key.cmp(&a.key))
+            }) {
             Some(x) => {
                 x.available = false;
                 (x.item.object(), x.item.covering_set().clone())
@@ -91,10 +104,11 @@ mod test {
 
     impl<T> MaxCover for HashSet<T>
     where
-        T: Clone + Eq + Hash,
+        T: Clone + Eq + Hash + Ord,
     {
         type Object = Self;
         type Set = Self;
+        type Key = Vec<T>;
 
         fn object(&self) -> Self {
             self.clone()
@@ -112,6 +126,12 @@ mod test {
         fn score(&self) -> usize {
             self.len()
         }
+
+        fn key(&self) -> Vec<T> {
+            let mut elements = self.iter().cloned().collect::<Vec<_>>();
+            elements.sort();
+            elements
+        }
     }
 
     fn example_system() -> Vec<HashSet<usize>> {
@@ -155,20 +175,21 @@ mod test {
     }
 
     // Optimal solution is the first three sets (quality 15) but our greedy algorithm
-    // will select the last three (quality 11). The comment at the end of each line
-    // shows that set's score at each iteration, with a * indicating that it will be chosen.
+    // will select the last, third and first sets (quality 12). The comment at the end of each
+    // line shows that set's score at each iteration, with a * indicating that it will be chosen.
+    // Ties are broken in favour of the set whose sorted elements compare smallest.
     #[test]
     fn suboptimal() {
         let sets = vec![
-            HashSet::from_iter(vec![0, 1, 8, 11, 14]), // 5, 3, 2
-            HashSet::from_iter(vec![2, 3, 7, 9, 10]),  // 5, 3, 2
-            HashSet::from_iter(vec![4, 5, 6, 12, 13]), // 5, 4, 2
-            HashSet::from_iter(vec![9, 10]),           // 4, 4, 2*
-            HashSet::from_iter(vec![5, 6, 7, 8]),      // 4, 4*
+            HashSet::from_iter(vec![0, 1, 8, 11, 14]), // 5, 3, 3*
+            HashSet::from_iter(vec![2, 3, 7, 9, 10]),  // 5, 3, 3
+            HashSet::from_iter(vec![4, 5, 6, 12, 13]), // 5, 4*
+            HashSet::from_iter(vec![9, 10]),           // 2, 2, 2
+            HashSet::from_iter(vec![5, 6, 7, 8]),      // 4, 4, 2
             HashSet::from_iter(vec![0, 1, 2, 3, 4]),   // 5*
         ];
         let cover = maximum_cover(sets, 3);
-        assert_eq!(quality(&cover), 11);
+        assert_eq!(quality(&cover), 12);
     }
 
     #[test]
@@ -186,4 +207,20 @@ mod test {
         assert_eq!(quality(&cover), 19);
         assert_eq!(cover.len(), 5);
     }
+
+    // Items of equal score are chosen by key, regardless of the order they are provided in.
+    #[test]
+    fn ties_broken_by_key() {
+        let sets = vec![
+            HashSet::from_iter(vec![7, 8]),
+            HashSet::from_iter(vec![1, 2]),
+            HashSet::from_iter(vec![4, 5]),
+        ];
+        let mut reversed = sets.clone();
+        reversed.reverse();
+
+        let cover = maximum_cover(sets.clone(), 2);
+        assert_eq!(cover, vec![sets[1].clone(), sets[2].clone()]);
+        assert_eq!(maximum_cover(reversed, 2), cover);
+    }
 }
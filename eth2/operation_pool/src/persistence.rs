@@ -1,10 +1,34 @@
 use crate::attestation_id::AttestationId;
-use crate::OperationPool;
+use crate::{AggregationStrategy, OperationPool};
 use parking_lot::RwLock;
 use serde_derive::{Deserialize, Serialize};
+use ssz::{Decode, DecodeError, Encode};
 use ssz_derive::{Decode, Encode};
+use state_processing::per_block_processing::{
+    verify_attester_slashing, verify_exit_time_independent_only, verify_proposer_slashing,
+    VerifySignatures,
+};
 use types::*;
 
+/// The number of bytes used to encode the version of a `PersistedOperationPool`.
+const VERSION_BYTES: usize = 2;
+
+#[derive(Debug, PartialEq)]
+pub enum Error {
+    /// The pool was persisted with a layout that cannot be migrated to the current one.
+    UnsupportedVersion {
+        found: u16,
+        expected: u16,
+    },
+    SszDecodeError(DecodeError),
+}
+
+impl From<DecodeError> for Error {
+    fn from(e: DecodeError) -> Error {
+        Error::SszDecodeError(e)
+    }
+}
+
 /// SSZ-serializable version of `OperationPool`.
 ///
 /// Operations are stored in arbitrary order, so it's not a good idea to compare instances
@@ -12,12 +36,16 @@ use types::*;
 #[derive(Clone, PartialEq, Debug, Encode, Decode, Serialize, Deserialize)]
 #[serde(bound = "T: EthSpec")]
 pub struct PersistedOperationPool<T: EthSpec> {
+    /// The layout version, always `Self::VERSION` for a pool created by this release.
+    ///
+    /// This must remain the first field, so that it can be read before decoding the rest.
+    version: u16,
     /// Mapping from attestation ID to attestation mappings.
     // We could save space by not storing the attestation ID, but it might
     // be difficult to make that roundtrip due to eager aggregation.
     attestations: Vec<(AttestationId, Vec<Attestation<T>>)>,
-    /// Attester slashings.
-    attester_slashings: Vec<AttesterSlashing<T>>,
+    /// Attester slashings, along with the fork-dependent IDs they were stored under.
+    attester_slashings: Vec<((AttestationId, AttestationId), AttesterSlashing<T>)>,
     /// Proposer slashings.
     proposer_slashings: Vec<ProposerSlashing>,
     /// Voluntary exits.
@@ -25,12 +53,22 @@ pub struct PersistedOperationPool<T: EthSpec> {
 }
 
 impl<T: EthSpec> PersistedOperationPool<T> {
+    /// The version of the layout of `Self`.
+    ///
+    /// Bump this whenever the layout (or the meaning of its contents) changes, and teach
+    /// `migrate` how to handle the older layout if possible.
+    ///
+    /// - 1: the first versioned layout.
+    /// - 2: the IDs of each attester slashing are sorted.
+    pub const VERSION: u16 = 2;
+
     /// Convert an `OperationPool` into serializable form.
-    pub fn from_operation_pool(operation_pool: &OperationPool<T>) -> Self {
+    pub fn from_operation_pool<S>(operation_pool: &OperationPool<T, S>) -> Self {
         let attestations = operation_pool
             .attestations
-            .read()
+            .read_all()
             .iter()
+            .flat_map(|shard| shard.iter())
             .map(|(att_id, att)| (att_id.clone(), att.clone()))
             .collect();
 
@@ -38,7 +76,7 @@ impl<T: EthSpec> PersistedOperationPool<T> {
             .attester_slashings
             .read()
             .iter()
-            .map(|(_, slashing)| slashing.clone())
+            .map(|(id, slashing)| (id.clone(), slashing.clone()))
             .collect();
 
         let proposer_slashings = operation_pool
@@ -56,6 +94,7 @@ impl<T: EthSpec> PersistedOperationPool<T> {
             .collect();
 
         Self {
+            version: Self::VERSION,
             attestations,
             attester_slashings,
             proposer_slashings,
@@ -63,39 +102,130 @@ impl<T: EthSpec> PersistedOperationPool<T> {
         }
     }
 
-    /// Reconstruct an `OperationPool`.
-    pub fn into_operation_pool(self, state: &BeaconState<T>, spec: &ChainSpec) -> OperationPool<T> {
-        let attestations = RwLock::new(self.attestations.into_iter().collect());
+    /// Returns the SSZ encoding of `self`, which begins with its version.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        self.as_ssz_bytes()
+    }
+
+    /// Decode bytes produced by `as_bytes`, migrating them from an older layout if necessary.
+    ///
+    /// Pools persisted before the version was introduced are rejected, since their first bytes
+    /// are an SSZ offset rather than any version this function knows how to migrate.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        let version_bytes =
+            bytes
+                .get(0..VERSION_BYTES)
+                .ok_or_else(|| DecodeError::BytesTooShort {
+                    need: VERSION_BYTES,
+                    got: bytes.len(),
+                })?;
+        let version = u16::from_ssz_bytes(version_bytes)?;
+
+        if version == Self::VERSION {
+            Ok(Self::from_ssz_bytes(bytes)?)
+        } else {
+            Self::migrate(version, bytes)
+        }
+    }
+
+    /// Decode `bytes`, which were persisted with a layout of the given `version`, into the
+    /// current layout.
+    ///
+    /// Layouts which cannot be migrated return an error, and the caller should start with an
+    /// empty pool.
+    fn migrate(version: u16, bytes: &[u8]) -> Result<Self, Error> {
+        match version {
+            // Version 1 has the same layout, but stored attester slashings under the IDs of their
+            // attestations in order, which `into_operation_pool` would drop as mismatched.
+            1 => {
+                let mut persisted = Self::from_ssz_bytes(bytes)?;
+                persisted.version = Self::VERSION;
+                for ((id_1, id_2), _) in &mut persisted.attester_slashings {
+                    if id_1 > id_2 {
+                        std::mem::swap(id_1, id_2);
+                    }
+                }
+                Ok(persisted)
+            }
+            _ => Err(Error::UnsupportedVersion {
+                found: version,
+                expected: Self::VERSION,
+            }),
+        }
+    }
+
+    /// Reconstruct an `OperationPool`, dropping operations which are no longer valid for `state`.
+    ///
+    /// The operations are restored into `operation_pool` (usually newly built and empty), keeping
+    /// its aggregation strategy and limits but replacing any operations it holds. Its limits are
+    /// then enforced, in case the persisted pool was built with larger ones.
+    ///
+    /// Slashings and exits are re-validated with the same checks used when they were inserted,
+    /// except that signatures are not re-verified (they were verified before being pooled).
+    /// Attester slashings whose IDs no longer match those derived from `state` (i.e., they were
+    /// stored under a different fork) are also dropped. Attestations are retained as-is, since
+    /// they are checked against the state when packed into a block.
+    pub fn into_operation_pool<S: AggregationStrategy<T>>(
+        self,
+        operation_pool: OperationPool<T, S>,
+        state: &BeaconState<T>,
+        spec: &ChainSpec,
+    ) -> OperationPool<T, S> {
+        let attestations = self.attestations.into_iter().collect();
         let attester_slashings = RwLock::new(
             self.attester_slashings
                 .into_iter()
-                .map(|slashing| {
-                    (
-                        OperationPool::attester_slashing_id(&slashing, state, spec),
-                        slashing,
-                    )
+                .filter(|(_, slashing)| {
+                    verify_attester_slashing(state, slashing, true, VerifySignatures::False, spec)
+                        .is_ok()
+                })
+                .filter_map(|(stored_id, slashing)| {
+                    let id = OperationPool::attester_slashing_id(&slashing, state, spec);
+                    if id == stored_id {
+                        Some((id, slashing))
+                    } else {
+                        None
+                    }
                 })
                 .collect(),
         );
         let proposer_slashings = RwLock::new(
             self.proposer_slashings
                 .into_iter()
+                .filter(|slashing| {
+                    verify_proposer_slashing(slashing, state, VerifySignatures::False, spec).is_ok()
+                })
                 .map(|slashing| (slashing.proposer_index, slashing))
                 .collect(),
         );
         let voluntary_exits = RwLock::new(
             self.voluntary_exits
                 .into_iter()
+                .filter(|exit| {
+                    verify_exit_time_independent_only(state, exit, VerifySignatures::False, spec)
+                        .is_ok()
+                })
                 .map(|exit| (exit.message.validator_index, exit))
                 .collect(),
         );
 
-        OperationPool {
+        let operation_pool = OperationPool {
             attestations,
+            attestations_cache: RwLock::new(None),
             attester_slashings,
             proposer_slashings,
             voluntary_exits,
-            _phantom: Default::default(),
-        }
+            num_evicted_attestations: Default::default(),
+            ..operation_pool
+        };
+
+        operation_pool.evict_proposer_slashings_over_limit(
+            &mut operation_pool.proposer_slashings.write(),
+            state,
+        );
+        operation_pool.evict_attestation_buckets_over_limit();
+        operation_pool.evict_attestations_over_budget();
+
+        operation_pool
     }
 }
@@ -10,7 +10,7 @@ pub use self::verify_attester_slashing::{
     get_slashable_indices, get_slashable_indices_modular, verify_attester_slashing,
 };
 pub use self::verify_proposer_slashing::verify_proposer_slashing;
-pub use block_signature_verifier::BlockSignatureVerifier;
+pub use block_signature_verifier::{block_signature_sets, BlockSignatureVerifier};
 pub use is_valid_indexed_attestation::is_valid_indexed_attestation;
 pub use verify_attestation::{
     verify_attestation_for_block_inclusion, verify_attestation_for_state,
@@ -24,7 +24,7 @@ pub mod block_processing_builder;
 mod block_signature_verifier;
 pub mod errors;
 mod is_valid_indexed_attestation;
-mod signature_sets;
+pub mod signature_sets;
 pub mod tests;
 mod verify_attestation;
 mod verify_attester_slashing;
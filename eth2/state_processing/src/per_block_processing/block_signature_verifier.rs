@@ -42,6 +42,24 @@ impl From<BlockOperationError<AttestationInvalid>> for Error {
     }
 }
 
+/// Returns a signature set for all* the signatures in the given `SignedBeaconBlock`, such that the
+/// block is only valid if every set is valid.
+///
+/// * : _Does not include any signatures in `block.body.deposits`. A block is still valid if it
+/// contains invalid signatures on deposits._
+///
+/// The sets may be verified in a single batch using `bls::verify_signature_sets`.
+pub fn block_signature_sets<'a, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    block: &'a SignedBeaconBlock<T>,
+    block_root: Option<Hash256>,
+    spec: &'a ChainSpec,
+) -> Result<Vec<SignatureSet<'a>>> {
+    let mut verifier = BlockSignatureVerifier::new(state, block, spec);
+    verifier.include_entire_block(block_root)?;
+    Ok(verifier.sets)
+}
+
 /// Reads the BLS signatures and keys from a `SignedBeaconBlock`, storing them as a `Vec<SignatureSet>`.
 ///
 /// This allows for optimizations related to batch BLS operations (see the
@@ -84,17 +102,26 @@ impl<'a, T: EthSpec> BlockSignatureVerifier<'a, T> {
     ) -> Result<()> {
         let mut verifier = Self::new(state, block, spec);
 
-        verifier.include_block_proposal(block_root)?;
-        verifier.include_randao_reveal()?;
-        verifier.include_proposer_slashings()?;
-        verifier.include_attester_slashings()?;
-        verifier.include_attestations()?;
+        verifier.include_entire_block(block_root)?;
+
+        verifier.verify()
+    }
+
+    /// Includes all* the signatures in `self.block` for verification.
+    ///
+    /// * : _Does not include any signatures in `block.body.deposits`._
+    fn include_entire_block(&mut self, block_root: Option<Hash256>) -> Result<()> {
+        self.include_block_proposal(block_root)?;
+        self.include_randao_reveal()?;
+        self.include_proposer_slashings()?;
+        self.include_attester_slashings()?;
+        self.include_attestations()?;
         /*
          * Deposits are not included because they can legally have invalid signatures.
          */
-        verifier.include_exits()?;
+        self.include_exits()?;
 
-        verifier.verify()
+        Ok(())
     }
 
     /// Verify all* the signatures that have been included in `self`, returning `Ok(())` if the
@@ -3,14 +3,16 @@
 //!
 //! This module exposes one function to extract each type of `SignatureSet` from a `BeaconBlock`.
 use bls::{G1Point, G1Ref, SignatureSet, SignedMessage};
+use lru::LruCache;
+use parking_lot::Mutex;
 use std::borrow::Cow;
 use std::convert::TryInto;
 use tree_hash::TreeHash;
 use types::{
-    AggregateSignature, AttesterSlashing, BeaconBlock, BeaconState, BeaconStateError, ChainSpec,
-    DepositData, Domain, EthSpec, Hash256, IndexedAttestation, ProposerSlashing, PublicKey,
-    Signature, SignedBeaconBlock, SignedBeaconBlockHeader, SignedRoot, SignedVoluntaryExit,
-    SigningRoot,
+    AggregatePublicKey, AggregateSignature, AttesterSlashing, BeaconBlock, BeaconState,
+    BeaconStateError, ChainSpec, Checkpoint, DepositData, Domain, EthSpec, Hash256,
+    IndexedAttestation, ProposerSlashing, PublicKey, Signature, SignedBeaconBlock,
+    SignedBeaconBlockHeader, SignedRoot, SignedVoluntaryExit, SigningRoot,
 };
 
 pub type Result<T> = std::result::Result<T, Error>;
@@ -39,6 +41,52 @@ impl From<BeaconStateError> for Error {
     }
 }
 
+/// The number of sets of attesting indices held by `AttestationPubkeyCache::default()`, which is
+/// roughly two epochs of committees on mainnet.
+pub const DEFAULT_ATTESTATION_PUBKEY_CACHE_SIZE: usize = 4_096;
+
+/// Memoizes the aggregate public key of each set of attesting indices given to
+/// `indexed_attestation_signature_set_with_cache`, so that verifying the same attestation
+/// repeatedly (e.g., when a block is re-processed after a re-org) doesn't look up and aggregate
+/// its keys again.
+///
+/// Forks may process different deposits, so the key at a validator index is only known to be the
+/// same between states with the same finalized checkpoint if the validator was eligible for
+/// activation by then. Entries are therefore keyed on the finalized checkpoint of the state as
+/// well as the indices, and sets with any index which is unknown to the state or not yet eligible
+/// as of its finalized checkpoint are never cached. At most `capacity` entries are held, evicting
+/// the least recently used.
+#[derive(Debug)]
+pub struct AttestationPubkeyCache {
+    /// A `Mutex` rather than an `RwLock`, since reading an `LruCache` updates its order.
+    aggregates: Mutex<LruCache<(Checkpoint, Vec<u64>), G1Point>>,
+}
+
+impl AttestationPubkeyCache {
+    /// Instantiate a cache holding at most `capacity` sets of attesting indices.
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            aggregates: Mutex::new(LruCache::new(capacity)),
+        }
+    }
+
+    /// Returns the number of sets of attesting indices in the cache.
+    pub fn len(&self) -> usize {
+        self.aggregates.lock().len()
+    }
+
+    /// Returns `true` if the cache is empty.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl Default for AttestationPubkeyCache {
+    fn default() -> Self {
+        Self::new(DEFAULT_ATTESTATION_PUBKEY_CACHE_SIZE)
+    }
+}
+
 /// A signature set that is valid if a block was signed by the expected block producer.
 pub fn block_proposal_signature_set<'a, T: EthSpec>(
     state: &'a BeaconState<T>,
@@ -104,13 +152,13 @@ pub fn proposer_slashing_signature_set<'a, T: EthSpec>(
     let proposer_index = proposer_slashing.proposer_index as usize;
 
     Ok((
-        block_header_signature_set(
+        block_header_signature_set_from_pubkey(
             state,
             &proposer_slashing.signed_header_1,
             validator_pubkey(state, proposer_index)?,
             spec,
         )?,
-        block_header_signature_set(
+        block_header_signature_set_from_pubkey(
             state,
             &proposer_slashing.signed_header_2,
             validator_pubkey(state, proposer_index)?,
@@ -119,8 +167,25 @@ pub fn proposer_slashing_signature_set<'a, T: EthSpec>(
     ))
 }
 
+/// Returns a signature set that is valid if the expected proposer for the slot of `signed_header`
+/// signed the header.
+pub fn block_header_signature_set<'a, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    signed_header: &'a SignedBeaconBlockHeader,
+    spec: &'a ChainSpec,
+) -> Result<SignatureSet<'a>> {
+    let proposer_index = state.get_beacon_proposer_index(signed_header.message.slot, spec)?;
+
+    block_header_signature_set_from_pubkey(
+        state,
+        signed_header,
+        validator_pubkey(state, proposer_index)?,
+        spec,
+    )
+}
+
 /// Returns a signature set that is valid if the given `pubkey` signed the `header`.
-fn block_header_signature_set<'a, T: EthSpec>(
+fn block_header_signature_set_from_pubkey<'a, T: EthSpec>(
     state: &'a BeaconState<T>,
     signed_header: &'a SignedBeaconBlockHeader,
     pubkey: Cow<'a, G1Point>,
@@ -152,11 +217,49 @@ pub fn indexed_attestation_signature_set<'a, 'b, T: EthSpec>(
     indexed_attestation: &'b IndexedAttestation<T>,
     spec: &'a ChainSpec,
 ) -> Result<SignatureSet<'a>> {
-    let pubkeys = indexed_attestation
-        .attesting_indices
-        .into_iter()
-        .map(|&validator_idx| Ok(validator_pubkey(state, validator_idx as usize)?))
-        .collect::<Result<_>>()?;
+    indexed_attestation_signature_set_with_cache(state, signature, indexed_attestation, None, spec)
+}
+
+/// Returns the signature set for the given `indexed_attestation`, reading the aggregate public key
+/// of its attesting validators from `pubkey_cache` (or adding it to the cache, if absent).
+pub fn indexed_attestation_signature_set_with_cache<'a, 'b, T: EthSpec>(
+    state: &'a BeaconState<T>,
+    signature: &'a AggregateSignature,
+    indexed_attestation: &'b IndexedAttestation<T>,
+    pubkey_cache: Option<&AttestationPubkeyCache>,
+    spec: &'a ChainSpec,
+) -> Result<SignatureSet<'a>> {
+    indexed_attestation_signature_set_from_pubkeys(
+        state,
+        |validator_idx| validator_pubkey(state, validator_idx),
+        signature,
+        indexed_attestation,
+        pubkey_cache,
+        spec,
+    )
+}
+
+/// As `indexed_attestation_signature_set_with_cache`, except that the public key of each attesting
+/// validator which is not covered by `pubkey_cache` is obtained from `get_pubkey`.
+pub fn indexed_attestation_signature_set_from_pubkeys<'a, 'b, T, F>(
+    state: &'a BeaconState<T>,
+    get_pubkey: F,
+    signature: &'a AggregateSignature,
+    indexed_attestation: &'b IndexedAttestation<T>,
+    pubkey_cache: Option<&AttestationPubkeyCache>,
+    spec: &'a ChainSpec,
+) -> Result<SignatureSet<'a>>
+where
+    T: EthSpec,
+    F: Fn(usize) -> Result<Cow<'a, G1Point>>,
+{
+    let pubkeys = || {
+        indexed_attestation
+            .attesting_indices
+            .into_iter()
+            .map(|&validator_idx| get_pubkey(validator_idx as usize))
+            .collect::<Result<Vec<_>>>()
+    };
 
     let domain = spec.get_domain(
         indexed_attestation.data.target.epoch,
@@ -164,8 +267,54 @@ pub fn indexed_attestation_signature_set<'a, 'b, T: EthSpec>(
         &state.fork,
     );
 
-    let message = indexed_attestation.data.signing_root(domain);
-    let signed_message = SignedMessage::new(pubkeys, message.as_bytes().to_vec());
+    let message = indexed_attestation
+        .data
+        .signing_root(domain)
+        .as_bytes()
+        .to_vec();
+
+    // Only use the cache if every index is known to `state` and its key was pinned by the
+    // finalized checkpoint, otherwise the keys are looked up (and errors raised) as without it.
+    let finalized_epoch = state.finalized_checkpoint.epoch;
+    let pubkey_cache = pubkey_cache.filter(|_| {
+        indexed_attestation
+            .attesting_indices
+            .iter()
+            .all(|&validator_idx| {
+                state
+                    .validators
+                    .get(validator_idx as usize)
+                    .map_or(false, |validator| {
+                        validator.activation_eligibility_epoch <= finalized_epoch
+                    })
+            })
+    });
+
+    let signed_message = if let Some(cache) = pubkey_cache {
+        let mut indices = indexed_attestation.attesting_indices.to_vec();
+        indices.sort_unstable();
+        let key = (state.finalized_checkpoint.clone(), indices);
+
+        let cached = cache.aggregates.lock().get(&key).cloned();
+        let aggregate = match cached {
+            Some(aggregate) => aggregate,
+            None => {
+                let mut aggregate = AggregatePublicKey::new();
+                pubkeys()?
+                    .iter()
+                    .for_each(|pubkey| aggregate.add_point(pubkey));
+                aggregate.affine();
+                let aggregate = aggregate.into_raw().point;
+
+                cache.aggregates.lock().put(key, aggregate.clone());
+                aggregate
+            }
+        };
+
+        SignedMessage::from_aggregate(Cow::Owned(aggregate), message)
+    } else {
+        SignedMessage::new(pubkeys()?, message)
+    };
 
     Ok(SignatureSet::new(signature, vec![signed_message]))
 }
@@ -222,6 +371,21 @@ pub fn deposit_signature_set<'a>(
     SignatureSet::single(signature, pubkey.g1_ref(), message.clone())
 }
 
+/// Returns a signature set for each of some deposit signatures, made with
+/// `deposit_pubkey_signature_message`.
+///
+/// The sets may be checked at once with `bls::verify_signature_sets`. They are deliberately not
+/// combined into a single set of an aggregate signature: an invalid deposit does not invalidate
+/// its block, and two invalid signatures over the same message may aggregate into a valid one.
+pub fn deposit_signature_sets<'a>(
+    pubkey_signature_messages: &'a [(PublicKey, Signature, Vec<u8>)],
+) -> Vec<SignatureSet<'a>> {
+    pubkey_signature_messages
+        .iter()
+        .map(deposit_signature_set)
+        .collect()
+}
+
 /// Returns a signature set that is valid if the `SignedVoluntaryExit` was signed by the indicated
 /// validator.
 pub fn exit_signature_set<'a, T: EthSpec>(
@@ -244,6 +408,11 @@ pub fn exit_signature_set<'a, T: EthSpec>(
 }
 
 /// Maps a validator index to a `PublicKey`.
+///
+/// The key is borrowed from the `state` if it has already been decompressed, otherwise it is
+/// decompressed on each call. Use `BeaconState::decompress_validator_pubkey` (or
+/// `BeaconState::decompress_validator_pubkeys`) to decompress the key once and store it in the
+/// state.
 pub fn validator_pubkey<'a, T: EthSpec>(
     state: &'a BeaconState<T>,
     validator_index: usize,
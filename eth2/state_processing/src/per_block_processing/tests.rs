@@ -1,8 +1,13 @@
 #![cfg(all(test, not(feature = "fake_crypto")))]
 
 use super::block_processing_builder::BlockProcessingBuilder;
+use super::block_signature_sets;
 use super::errors::*;
+use super::signature_sets::{self, AttestationPubkeyCache};
+use crate::common::get_indexed_attestation;
 use crate::{per_block_processing, BlockSignatureStrategy};
+use std::borrow::Cow;
+use std::cell::Cell;
 use types::test_utils::{
     AttestationTestTask, AttesterSlashingTestTask, DepositTestTask, ExitTestTask,
     ProposerSlashingTestTask,
@@ -113,6 +118,207 @@ fn invalid_block_signature() {
     );
 }
 
+#[test]
+fn block_header_signature_set() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let (block, state) = builder.build(None, None, &spec);
+
+    // The block and its header share a signing root, so the block signature is valid for the
+    // header.
+    let signed_header = SignedBeaconBlockHeader {
+        message: block.message.block_header(),
+        signature: block.signature.clone(),
+    };
+    assert!(
+        signature_sets::block_header_signature_set(&state, &signed_header, &spec)
+            .unwrap()
+            .is_valid()
+    );
+
+    // sign the header with a keypair that is not the expected proposer
+    let keypair = Keypair::random();
+    let signed_header = block
+        .message
+        .block_header()
+        .sign::<E>(&keypair.sk, &state.fork, &spec);
+    assert!(
+        !signature_sets::block_header_signature_set(&state, &signed_header, &spec)
+            .unwrap()
+            .is_valid()
+    );
+}
+
+#[test]
+fn block_signature_sets_batch() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let test_task = AttestationTestTask::Valid;
+    let (mut block, state) =
+        builder.build_with_n_attestations(test_task, NUM_ATTESTATIONS, None, None, &spec);
+
+    // The proposal, the randao reveal and each attestation.
+    let sets = block_signature_sets(&state, &block, None, &spec).unwrap();
+    assert_eq!(sets.len(), 2 + NUM_ATTESTATIONS as usize);
+    assert!(bls::verify_signature_sets(sets.into_iter()));
+
+    // Sign the randao reveal with the wrong key.
+    block.message.body.randao_reveal = Signature::new(&[42], &Keypair::random().sk);
+    let sets = block_signature_sets(&state, &block, None, &spec).unwrap();
+    assert!(!bls::verify_signature_sets(sets.into_iter()));
+}
+
+#[test]
+fn indexed_attestation_signature_set_pubkey_cache() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let test_task = AttestationTestTask::Valid;
+    let (block, state) = builder.build_with_n_attestations(test_task, 1, None, None, &spec);
+    let attestation = &block.message.body.attestations[0];
+    let indexed_attestation = get_indexed_attestation(&state, attestation).unwrap();
+    let num_attesters = indexed_attestation.attesting_indices.len();
+    assert!(num_attesters > 0);
+
+    let lookups = Cell::new(0);
+    let get_pubkey = |validator_index| {
+        lookups.set(lookups.get() + 1);
+        signature_sets::validator_pubkey(&state, validator_index)
+    };
+    let signature_set = |pubkey_cache: Option<&AttestationPubkeyCache>| {
+        signature_sets::indexed_attestation_signature_set_from_pubkeys(
+            &state,
+            &get_pubkey,
+            &indexed_attestation.signature,
+            &indexed_attestation,
+            pubkey_cache,
+            &spec,
+        )
+        .unwrap()
+    };
+
+    // Without a cache, the keys are looked up on every call.
+    assert!(signature_set(None).is_valid());
+    assert!(signature_set(None).is_valid());
+    assert_eq!(lookups.get(), 2 * num_attesters);
+
+    // With a cache, the keys are only looked up on the first call.
+    lookups.set(0);
+    let cache = AttestationPubkeyCache::default();
+    assert!(signature_set(Some(&cache)).is_valid());
+    assert!(signature_set(Some(&cache)).is_valid());
+    assert_eq!(lookups.get(), num_attesters);
+    assert_eq!(cache.len(), 1);
+
+    // Entries are keyed on the finalized checkpoint, so a state on another fork misses the cache.
+    // With capacity for a single entry, each miss evicts the other's entry.
+    let mut forked_state = state.clone();
+    forked_state.finalized_checkpoint.root = Hash256::from_low_u64_be(42);
+    let forked_signature_set = |pubkey_cache: Option<&AttestationPubkeyCache>| {
+        signature_sets::indexed_attestation_signature_set_from_pubkeys(
+            &forked_state,
+            &get_pubkey,
+            &indexed_attestation.signature,
+            &indexed_attestation,
+            pubkey_cache,
+            &spec,
+        )
+        .unwrap()
+    };
+
+    lookups.set(0);
+    let cache = AttestationPubkeyCache::new(1);
+    assert!(signature_set(Some(&cache)).is_valid());
+    assert!(forked_signature_set(Some(&cache)).is_valid());
+    assert!(signature_set(Some(&cache)).is_valid());
+    assert_eq!(lookups.get(), 3 * num_attesters);
+    assert_eq!(cache.len(), 1);
+
+    // Validators which were not eligible for activation as of the finalized checkpoint may have
+    // different keys on other forks, so sets including them are not cached.
+    let mut unfinalized_state = state.clone();
+    let first_attester = indexed_attestation.attesting_indices[0] as usize;
+    unfinalized_state.validators[first_attester].activation_eligibility_epoch =
+        unfinalized_state.finalized_checkpoint.epoch + 1;
+    let cache = AttestationPubkeyCache::default();
+    signature_sets::indexed_attestation_signature_set_from_pubkeys(
+        &unfinalized_state,
+        |validator_index| signature_sets::validator_pubkey(&unfinalized_state, validator_index),
+        &indexed_attestation.signature,
+        &indexed_attestation,
+        Some(&cache),
+        &spec,
+    )
+    .unwrap();
+    assert!(cache.is_empty());
+}
+
+#[test]
+fn indexed_attestation_signature_set_pubkey_cache_unknown_validator() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let test_task = AttestationTestTask::Valid;
+    let (block, state) = builder.build_with_n_attestations(test_task, 1, None, None, &spec);
+    let attestation = &block.message.body.attestations[0];
+    let mut indexed_attestation = get_indexed_attestation(&state, attestation).unwrap();
+
+    // The attestation is by the last validator, which a truncated state does not know about.
+    let last_index = state.validators.len() - 1;
+    indexed_attestation.attesting_indices = VariableList::from(vec![last_index as u64]);
+    let mut truncated_state = state.clone();
+    truncated_state.validators = VariableList::from(state.validators[..last_index].to_vec());
+
+    let signature_set = |state: &BeaconState<MainnetEthSpec>, pubkey_cache| {
+        signature_sets::indexed_attestation_signature_set_from_pubkeys(
+            state,
+            |validator_index| signature_sets::validator_pubkey(state, validator_index),
+            &indexed_attestation.signature,
+            &indexed_attestation,
+            pubkey_cache,
+            &spec,
+        )
+        .map(|_| ())
+    };
+
+    // Populate the cache from the full state, which has the same finalized checkpoint.
+    let cache = AttestationPubkeyCache::default();
+    assert_eq!(signature_set(&state, Some(&cache)), Ok(()));
+    assert_eq!(cache.len(), 1);
+
+    let expected = Err(signature_sets::Error::ValidatorUnknown(last_index as u64));
+    assert_eq!(signature_set(&truncated_state, None), expected);
+    assert_eq!(signature_set(&truncated_state, Some(&cache)), expected);
+}
+
+#[test]
+fn validator_pubkey_borrows_decompressed_key() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let (_, mut state) = builder.build(None, None, &spec);
+
+    // Replace the key with an equal one which has not been decompressed.
+    let bytes = state.validators[0].pubkey.as_bytes();
+    state.validators[0].pubkey = PublicKeyBytes::from_bytes(&bytes).unwrap();
+    assert!(state.validators[0].pubkey.decompressed().is_none());
+
+    if let Cow::Borrowed(_) = signature_sets::validator_pubkey(&state, 0).unwrap() {
+        panic!("key should not be decompressed");
+    }
+
+    // The first call decompresses the key into the state, the second borrows it.
+    let first: *const PublicKey = state.decompress_validator_pubkey(0).unwrap();
+    let second: *const PublicKey = state.decompress_validator_pubkey(0).unwrap();
+    assert_eq!(first, second);
+
+    if let Cow::Owned(_) = signature_sets::validator_pubkey(&state, 0).unwrap() {
+        panic!("key should be borrowed from the state");
+    }
+
+    assert_eq!(
+        state.decompress_validator_pubkey(VALIDATOR_COUNT),
+        Err(BeaconStateError::UnknownValidator)
+    );
+}
+
 #[test]
 fn invalid_randao_reveal_signature() {
     let spec = MainnetEthSpec::default_spec();
@@ -154,6 +360,33 @@ fn valid_4_deposits() {
     assert_eq!(result, Ok(()));
 }
 
+#[test]
+fn deposit_signature_sets_batch() {
+    let spec = MainnetEthSpec::default_spec();
+    let builder = get_builder(&spec, SLOT_OFFSET, VALIDATOR_COUNT);
+    let test_task = DepositTestTask::Valid;
+    let (block, _) = builder.build_with_n_deposits(4, test_task, None, None, &spec);
+
+    let mut messages = block
+        .message
+        .body
+        .deposits
+        .iter()
+        .map(|deposit| {
+            signature_sets::deposit_pubkey_signature_message(&deposit.data, &spec).unwrap()
+        })
+        .collect::<Vec<_>>();
+
+    let sets = signature_sets::deposit_signature_sets(&messages);
+    assert_eq!(sets.len(), 4);
+    assert!(bls::verify_signature_sets(sets.into_iter()));
+
+    // Sign one of the deposits with the wrong key.
+    messages[2].1 = Signature::new(&messages[2].2, &Keypair::random().sk);
+    let sets = signature_sets::deposit_signature_sets(&messages);
+    assert!(!bls::verify_signature_sets(sets.into_iter()));
+}
+
 #[test]
 fn invalid_deposit_deposit_count_too_big() {
     let spec = MainnetEthSpec::default_spec();
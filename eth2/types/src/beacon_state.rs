@@ -954,6 +954,22 @@ impl<T: EthSpec> BeaconState<T> {
         })
     }
 
+    /// Returns the decompressed public key of the validator at `validator_index`, decompressing
+    /// it (and storing the result in the state) if it has not already been decompressed.
+    ///
+    /// Does not check the validity of an already decompressed key.
+    pub fn decompress_validator_pubkey(
+        &mut self,
+        validator_index: usize,
+    ) -> Result<&PublicKey, Error> {
+        self.validators
+            .get_mut(validator_index)
+            .ok_or(Error::UnknownValidator)?
+            .pubkey
+            .decompress_cached()
+            .map_err(Error::InvalidValidatorPubkey)
+    }
+
     /// Clone the state whilst preserving only the selected caches.
     pub fn clone_with(&self, config: CloneConfig) -> Self {
         BeaconState {
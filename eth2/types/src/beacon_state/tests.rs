@@ -213,6 +213,112 @@ fn tree_hash_cache() {
     assert_eq!(root.as_bytes(), &state.tree_hash_root()[..]);
 }
 
+#[test]
+fn tree_hash_cache_bytes_version() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use cached_tree_hash::{encode_versioned, Error as CacheError};
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+    state.update_tree_hash_cache().unwrap();
+    let cache = state.tree_hash_cache.clone().unwrap();
+
+    let bytes = cache.as_bytes();
+    assert_eq!(BeaconTreeHashCache::from_bytes(&bytes), Ok(cache.clone()));
+
+    // A cache written with an older layout is rejected.
+    let stale = encode_versioned(BeaconTreeHashCache::VERSION - 1, &cache);
+    assert_eq!(
+        BeaconTreeHashCache::from_bytes(&stale),
+        Err(Error::CachedTreeHashError(
+            CacheError::InvalidCacheVersion {
+                found: BeaconTreeHashCache::VERSION - 1,
+                expected: BeaconTreeHashCache::VERSION,
+            }
+        ))
+    );
+}
+
+#[test]
+fn tree_hash_cache_validator_proof() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+    use cached_tree_hash::int_log;
+    use eth2_hashing::hash32_concat;
+    use tree_hash::TreeHash;
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+    for _ in 0..5 {
+        state
+            .validators
+            .push(Validator::random_for_test(&mut rng))
+            .unwrap();
+    }
+    let mut cache = BeaconTreeHashCache::new(&state);
+    assert_eq!(cache.root(), None);
+    assert_eq!(
+        cache.validator_proof(0),
+        Err(Error::TreeHashCacheNotInitialized)
+    );
+
+    let root = cache.recalculate_tree_hash_root(&state).unwrap();
+    assert_eq!(cache.root(), Some(root));
+
+    state.slot += 1;
+    cache.recalculate_tree_hash_root(&state).unwrap();
+
+    let list_depth = int_log(<MainnetEthSpec as EthSpec>::ValidatorRegistryLimit::to_usize());
+    for (index, validator) in state.validators.iter().enumerate() {
+        let proof = cache.validator_proof(index).unwrap();
+        // The list tree, the length mix-in and the 32-leaf tree of state fields.
+        assert_eq!(proof.len(), list_depth + 1 + 5);
+
+        // The validator's position within the list, then the left branch of the length mix-in,
+        // then the position of `validators` amongst the state fields.
+        let generalized_index = index | (10 << (list_depth + 1));
+
+        let mut node = validator.tree_hash_root();
+        for (i, sibling) in proof.iter().enumerate() {
+            node = if (generalized_index >> i) & 1 == 0 {
+                hash32_concat(&node, sibling.as_bytes()).to_vec()
+            } else {
+                hash32_concat(sibling.as_bytes(), &node).to_vec()
+            };
+        }
+        assert_eq!(node, state.tree_hash_root(), "index: {}", index);
+    }
+
+    assert_eq!(
+        cache.validator_proof(state.validators.len()),
+        Err(Error::UnknownValidator)
+    );
+}
+
+#[test]
+fn tree_hash_cache_diff() {
+    use crate::test_utils::{SeedableRng, TestRandom, XorShiftRng};
+
+    let mut rng = XorShiftRng::from_seed([42; 16]);
+
+    let mut state: FoundationBeaconState = BeaconState::random_for_test(&mut rng);
+    state.balances.push(32).unwrap();
+
+    let mut cache = BeaconTreeHashCache::new(&state);
+    let mut other = cache.clone();
+    assert_eq!(cache.diff(&other).len(), 20);
+
+    cache.recalculate_tree_hash_root(&state).unwrap();
+    other.recalculate_tree_hash_root(&state).unwrap();
+    assert!(cache.diff(&other).is_empty());
+
+    state.balances[0] += 1;
+    other.recalculate_tree_hash_root(&state).unwrap();
+    assert_eq!(cache.diff(&other), vec!["balances"]);
+    assert_eq!(other.diff(&cache), vec!["balances"]);
+}
+
 /// Tests committee-specific components
 #[cfg(test)]
 mod committees {
@@ -1,6 +1,8 @@
 use super::Error;
 use crate::{BeaconState, EthSpec, Hash256, Unsigned, Validator};
-use cached_tree_hash::{int_log, CacheArena, CachedTreeHash, TreeHashCache};
+use cached_tree_hash::{
+    decode_versioned, encode_versioned, int_log, CacheArena, CachedTreeHash, TreeHashCache,
+};
 use rayon::prelude::*;
 use ssz_derive::{Decode, Encode};
 use tree_hash::{mix_in_length, TreeHash};
@@ -13,6 +15,33 @@ use tree_hash::{mix_in_length, TreeHash};
 /// Do not set to 0.
 const VALIDATORS_PER_ARENA: usize = 4_096;
 
+/// The names of the fields of `BeaconState` which are included in its tree hash, in order.
+const FIELD_NAMES: [&str; 20] = [
+    "genesis_time",
+    "slot",
+    "fork",
+    "latest_block_header",
+    "block_roots",
+    "state_roots",
+    "historical_roots",
+    "eth1_data",
+    "eth1_data_votes",
+    "eth1_deposit_index",
+    "validators",
+    "balances",
+    "randao_mixes",
+    "slashings",
+    "previous_epoch_attestations",
+    "current_epoch_attestations",
+    "justification_bits",
+    "previous_justified_checkpoint",
+    "current_justified_checkpoint",
+    "finalized_checkpoint",
+];
+
+/// The index of `validators` amongst the fields of `BeaconState` (see `FIELD_NAMES`).
+const VALIDATORS_FIELD_INDEX: usize = 10;
+
 /// A cache that performs a caching tree hash of the entire `BeaconState` struct.
 #[derive(Debug, PartialEq, Clone, Default, Encode, Decode)]
 pub struct BeaconTreeHashCache {
@@ -29,9 +58,18 @@ pub struct BeaconTreeHashCache {
     balances: TreeHashCache,
     randao_mixes: TreeHashCache,
     slashings: TreeHashCache,
+    /// The root of each field of the state given to the last call to `recalculate_tree_hash_root`,
+    /// or empty if it has not been called.
+    field_roots: Vec<Hash256>,
 }
 
 impl BeaconTreeHashCache {
+    /// The version of the layout written by `as_bytes`.
+    ///
+    /// Bump this whenever the layout of `Self` changes (e.g., a field is added for a new fork), so
+    /// that stale serialized caches are rejected and rebuilt from the state.
+    pub const VERSION: u16 = 2;
+
     /// Instantiates a new cache.
     ///
     /// Allocates the necessary memory to store all of the cached Merkle trees but does perform any
@@ -62,9 +100,23 @@ impl BeaconTreeHashCache {
             balances,
             randao_mixes,
             slashings,
+            field_roots: vec![],
         }
     }
 
+    /// Returns the SSZ encoding of `self`, prefixed with `Self::VERSION`.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        encode_versioned(Self::VERSION, self)
+    }
+
+    /// Decodes bytes produced by `as_bytes`.
+    ///
+    /// Returns `cached_tree_hash::Error::InvalidCacheVersion` if the bytes were written with a
+    /// different `VERSION`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        Ok(decode_versioned(Self::VERSION, bytes)?)
+    }
+
     /// Updates the cache and returns the tree hash root for the given `state`.
     ///
     /// The provided `state` should be a descendant of the last `state` given to this function, or
@@ -130,7 +182,79 @@ impl BeaconTreeHashCache {
         leaves.append(&mut state.current_justified_checkpoint.tree_hash_root());
         leaves.append(&mut state.finalized_checkpoint.tree_hash_root());
 
-        Ok(Hash256::from_slice(&tree_hash::merkle_root(&leaves, 0)))
+        let root = Hash256::from_slice(&tree_hash::merkle_root(&leaves, 0));
+        self.field_roots = leaves.chunks(32).map(Hash256::from_slice).collect();
+
+        Ok(root)
+    }
+
+    /// Returns the tree hash root of the state given to the last call to
+    /// `recalculate_tree_hash_root`, or `None` if it has not been called.
+    pub fn root(&self) -> Option<Hash256> {
+        if self.field_roots.is_empty() {
+            None
+        } else {
+            Some(tree_hash::merkleize_roots(&self.field_roots))
+        }
+    }
+
+    /// Returns the Merkle branch from the validator at `index` up to the root of the state given
+    /// to the last call to `recalculate_tree_hash_root`.
+    ///
+    /// The branch is ordered from the sibling of the validator's root upwards: the siblings within
+    /// the list tree, then the list length, then the siblings of `validators` amongst the fields
+    /// of the state.
+    pub fn validator_proof(&self, index: usize) -> Result<Vec<Hash256>, Error> {
+        if self.field_roots.is_empty() {
+            return Err(Error::TreeHashCacheNotInitialized);
+        }
+
+        let num_validators = self.validators.values.len();
+        if index >= num_validators {
+            return Err(Error::UnknownValidator);
+        }
+
+        let mut proof = self
+            .validators
+            .list_cache
+            .proof(&self.validators.list_arena, index)?;
+
+        let mut length = [0; 32];
+        length[0..8].copy_from_slice(&(num_validators as u64).to_le_bytes());
+        proof.push(Hash256::from(length));
+
+        let (_, field_proof) =
+            tree_hash::merkle_proof(&self.field_roots_bytes(), 0, VALIDATORS_FIELD_INDEX)
+                .map_err(|_| Error::TreeHashCacheInconsistent)?;
+        proof.extend(field_proof.iter().map(|node| Hash256::from_slice(node)));
+
+        Ok(proof)
+    }
+
+    /// Returns the names of the fields of `BeaconState` whose roots differ between `self` and
+    /// `other`, as of the last call to `recalculate_tree_hash_root` on each.
+    ///
+    /// Intended for debugging a mismatched state root. Every field is returned if either cache
+    /// has not yet computed a root.
+    pub fn diff(&self, other: &Self) -> Vec<&'static str> {
+        if self.field_roots.is_empty() || other.field_roots.is_empty() {
+            return FIELD_NAMES.to_vec();
+        }
+
+        FIELD_NAMES
+            .iter()
+            .enumerate()
+            .filter(|(i, _)| self.field_roots.get(*i) != other.field_roots.get(*i))
+            .map(|(_, name)| *name)
+            .collect()
+    }
+
+    /// Returns `self.field_roots`, concatenated.
+    fn field_roots_bytes(&self) -> Vec<u8> {
+        self.field_roots
+            .iter()
+            .flat_map(|root| root.as_bytes().to_vec())
+            .collect()
     }
 }
 
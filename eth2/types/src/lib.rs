@@ -79,6 +79,6 @@ pub type Address = H160;
 
 pub use bls::{
     AggregatePublicKey, AggregateSignature, Keypair, PublicKey, PublicKeyBytes, SecretKey,
-    Signature, SignatureBytes,
+    Signature, SignatureBytes, BLS_SIG_BYTE_SIZE,
 };
 pub use ssz_types::{typenum, typenum::Unsigned, BitList, BitVector, FixedVector, VariableList};
@@ -0,0 +1,49 @@
+use bls::{G1Ref, G2Ref, Keypair, Signature, SignatureSet};
+use criterion::Criterion;
+use criterion::{black_box, criterion_group, criterion_main, Benchmark};
+
+/// Number of single-message sets to verify in each iteration.
+const NUM_SETS: usize = 64;
+
+fn signature_set_benches(c: &mut Criterion) {
+    let keypairs = (0..NUM_SETS).map(|_| Keypair::random()).collect::<Vec<_>>();
+    let messages = (0..NUM_SETS).map(|i| vec![i as u8; 32]).collect::<Vec<_>>();
+    let signatures = keypairs
+        .iter()
+        .zip(messages.iter())
+        .map(|(keypair, message)| Signature::new(message, &keypair.sk))
+        .collect::<Vec<_>>();
+
+    let verify_signatures = signatures.clone();
+    c.bench(
+        &format!("signature_set/{}_sets", NUM_SETS),
+        Benchmark::new("is_valid", move |b| {
+            b.iter(|| {
+                for ((signature, keypair), message) in verify_signatures
+                    .iter()
+                    .zip(keypairs.iter())
+                    .zip(messages.iter())
+                {
+                    let set = SignatureSet::single(signature, keypair.pk.g1_ref(), message.clone());
+                    black_box(set.is_valid());
+                }
+            })
+        })
+        .sample_size(10),
+    );
+
+    // The cost of the signature point clone performed by each `SignatureSet::is_valid` call.
+    c.bench(
+        &format!("signature_set/{}_sets", NUM_SETS),
+        Benchmark::new("signature_point_clone", move |b| {
+            b.iter(|| {
+                for signature in &signatures {
+                    black_box(signature.g2_ref().clone());
+                }
+            })
+        }),
+    );
+}
+
+criterion_group!(benches, signature_set_benches);
+criterion_main!(benches);
@@ -1,9 +1,12 @@
-use super::{PublicKey, BLS_PUBLIC_KEY_BYTE_SIZE};
+use super::{PublicKey, SignatureError, BLS_PUBLIC_KEY_BYTE_SIZE};
+use crate::signature_set::aggregate_public_keys;
+use amcl::bls381::{big::BIG, rom::CURVE_ORDER};
 use milagro_bls::{AggregatePublicKey as RawAggregatePublicKey, G1Point};
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
 use ssz::{Decode, DecodeError, Encode};
+use std::borrow::Cow;
 
 /// A BLS aggregate public key.
 ///
@@ -17,6 +20,36 @@ impl AggregatePublicKey {
         AggregatePublicKey(RawAggregatePublicKey::new())
     }
 
+    /// Aggregate all of `public_keys`, which are added in parallel if there are many of them and
+    /// the `rayon` feature is enabled.
+    ///
+    /// The keys are not validated, see `aggregate_checked`.
+    pub fn aggregate(public_keys: &[PublicKey]) -> Self {
+        let points = public_keys
+            .iter()
+            .map(|public_key| Cow::Borrowed(&public_key.as_raw().point))
+            .collect::<Vec<_>>();
+
+        AggregatePublicKey(RawAggregatePublicKey {
+            point: aggregate_public_keys(&points),
+        })
+    }
+
+    /// Identical to `aggregate`, except that each key is first checked to be a point in the G1
+    /// subgroup other than the point at infinity.
+    ///
+    /// Returns `SignatureError::InvalidPublicKey` for the first key which fails the check.
+    pub fn aggregate_checked(public_keys: &[PublicKey]) -> Result<Self, SignatureError> {
+        if let Some(index) = public_keys
+            .iter()
+            .position(|public_key| !is_valid_public_key(public_key))
+        {
+            return Err(SignatureError::InvalidPublicKey { index });
+        }
+
+        Ok(Self::aggregate(public_keys))
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         let pubkey = RawAggregatePublicKey::from_bytes(&bytes).map_err(|_| {
             DecodeError::BytesInvalid(
@@ -64,6 +97,14 @@ impl AggregatePublicKey {
     }
 }
 
+/// Returns `true` if the point of `public_key` is not the point at infinity, and multiplying it by
+/// the order of G1 yields the point at infinity.
+fn is_valid_public_key(public_key: &PublicKey) -> bool {
+    let point = public_key.as_raw().point.as_raw();
+    let order = BIG::new_ints(&CURVE_ORDER);
+    !point.is_infinity() && point.mul(&order).is_infinity()
+}
+
 impl_ssz!(
     AggregatePublicKey,
     BLS_PUBLIC_KEY_BYTE_SIZE,
@@ -94,3 +135,78 @@ impl<'de> Deserialize<'de> for AggregatePublicKey {
         Ok(agg_sig)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::super::{AggregateSignature, Keypair, Signature};
+    use super::*;
+
+    #[test]
+    pub fn test_ssz_round_trip() {
+        let keypairs = (0..4).map(|_| Keypair::random()).collect::<Vec<_>>();
+
+        let mut original = AggregatePublicKey::new();
+        keypairs
+            .iter()
+            .for_each(|keypair| original.add(&keypair.pk));
+
+        let bytes = original.as_ssz_bytes();
+        assert_eq!(bytes.len(), BLS_PUBLIC_KEY_BYTE_SIZE);
+        assert_eq!(bytes, original.as_bytes());
+
+        let decoded = AggregatePublicKey::from_ssz_bytes(&bytes).unwrap();
+
+        assert_eq!(original.as_bytes(), decoded.as_bytes());
+    }
+
+    #[test]
+    pub fn test_aggregate() {
+        let msg = &[42, 42];
+        let keypairs = (0..2).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let public_keys = keypairs
+            .iter()
+            .map(|keypair| keypair.pk.clone())
+            .collect::<Vec<_>>();
+
+        let mut aggregate_signature = AggregateSignature::new();
+        keypairs
+            .iter()
+            .for_each(|keypair| aggregate_signature.add(&Signature::new(msg, &keypair.sk)));
+
+        let aggregate = AggregatePublicKey::aggregate(&public_keys);
+        let checked = AggregatePublicKey::aggregate_checked(&public_keys).unwrap();
+        assert!(aggregate_signature.verify(msg, &aggregate));
+        assert_eq!(aggregate.as_bytes(), checked.as_bytes());
+
+        // The compressed encoding of a point on the curve with `x = 4`, which is not in the
+        // subgroup.
+        let mut bytes = vec![0; BLS_PUBLIC_KEY_BYTE_SIZE];
+        bytes[0] = 0b1000_0000;
+        bytes[BLS_PUBLIC_KEY_BYTE_SIZE - 1] = 4;
+        let malformed = PublicKey::from_bytes(&bytes).unwrap();
+
+        assert_eq!(
+            AggregatePublicKey::aggregate_checked(&[public_keys[0].clone(), malformed]).err(),
+            Some(SignatureError::InvalidPublicKey { index: 1 })
+        );
+    }
+
+    #[test]
+    pub fn test_decoded_key_verifies() {
+        let msg = &[42, 42];
+        let keypairs = (0..4).map(|_| Keypair::random()).collect::<Vec<_>>();
+
+        let mut aggregate_public_key = AggregatePublicKey::new();
+        let mut aggregate_signature = AggregateSignature::new();
+        keypairs.iter().for_each(|keypair| {
+            aggregate_public_key.add(&keypair.pk);
+            aggregate_signature.add(&Signature::new(msg, &keypair.sk));
+        });
+
+        let decoded =
+            AggregatePublicKey::from_ssz_bytes(&aggregate_public_key.as_ssz_bytes()).unwrap();
+
+        assert!(aggregate_signature.verify(msg, &aggregate_public_key));
+        assert!(aggregate_signature.verify(msg, &decoded));
+    }
+}
@@ -31,6 +31,9 @@ impl AggregateSignature {
     }
 
     /// Add (aggregate) a signature to the `AggregateSignature`.
+    ///
+    /// Starting from `AggregateSignature::new()` (the point at infinity), adding a single
+    /// signature yields that signature. Adding to an `empty_signature` does nothing.
     pub fn add(&mut self, signature: &Signature) {
         if !self.is_empty {
             self.aggregate_signature.add(signature.as_raw())
@@ -80,6 +83,23 @@ impl AggregateSignature {
             .verify_multiple(&msgs, &aggregate_public_keys[..])
     }
 
+    /// Verify this AggregateSignature against `messages[i]` signed by `aggregate_public_keys[i]`,
+    /// for each `i`.
+    ///
+    /// Unlike `verify_multiple`, returns `false` (rather than leaving the behaviour to the
+    /// underlying library) if `messages` and `aggregate_public_keys` differ in length, or are
+    /// empty.
+    pub fn aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        aggregate_public_keys: &[&AggregatePublicKey],
+    ) -> bool {
+        if messages.is_empty() || messages.len() != aggregate_public_keys.len() {
+            return false;
+        }
+        self.verify_multiple(messages, aggregate_public_keys)
+    }
+
     /// Return AggregateSignature as bytes
     pub fn as_bytes(&self) -> Vec<u8> {
         if self.is_empty {
@@ -178,7 +198,7 @@ impl<'de> Deserialize<'de> for AggregateSignature {
 
 #[cfg(test)]
 mod tests {
-    use super::super::{Keypair, Signature};
+    use super::super::{AggregatePublicKey, Keypair, Signature};
     use super::*;
     use ssz::Encode;
 
@@ -194,4 +214,100 @@ mod tests {
 
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    pub fn test_bytes_round_trip() {
+        let mut original = AggregateSignature::new();
+        for i in 0..2 {
+            let keypair = Keypair::random();
+            original.add(&Signature::new(&[i; 32], &keypair.sk));
+        }
+
+        let bytes = original.as_bytes();
+        assert_eq!(bytes.len(), BLS_AGG_SIG_BYTE_SIZE);
+        assert_eq!(AggregateSignature::from_bytes(&bytes).unwrap(), original);
+    }
+
+    #[test]
+    pub fn test_add_to_new_is_identity() {
+        let keypair = Keypair::random();
+        let signature = Signature::new(&[42; 32], &keypair.sk);
+
+        let mut aggregate = AggregateSignature::new();
+        aggregate.add(&signature);
+
+        assert_eq!(aggregate.as_bytes(), signature.as_bytes());
+    }
+
+    #[test]
+    pub fn test_incremental_matches_all_at_once() {
+        let msg = [7; 32];
+        let keypairs: Vec<Keypair> = (0..4).map(|_| Keypair::random()).collect();
+        let signatures: Vec<Signature> = keypairs
+            .iter()
+            .map(|keypair| Signature::new(&msg, &keypair.sk))
+            .collect();
+
+        let mut incremental = AggregateSignature::new();
+        for signature in &signatures {
+            incremental.add(signature);
+        }
+
+        // Aggregate each half separately (in reverse order), then combine the halves.
+        let mut all_at_once = AggregateSignature::new();
+        for half in signatures.chunks(2) {
+            let mut partial = AggregateSignature::new();
+            for signature in half.iter().rev() {
+                partial.add(signature);
+            }
+            all_at_once.add_aggregate(&partial);
+        }
+
+        assert_eq!(incremental.as_bytes(), all_at_once.as_bytes());
+
+        let mut aggregate_public_key = AggregatePublicKey::new();
+        for keypair in &keypairs {
+            aggregate_public_key.add(&keypair.pk);
+        }
+        assert!(incremental.verify(&msg, &aggregate_public_key));
+    }
+
+    #[test]
+    pub fn test_aggregate_verify() {
+        let keypairs: Vec<Keypair> = (0..3).map(|_| Keypair::random()).collect();
+        let messages: Vec<Vec<u8>> = (0..3).map(|i| vec![i; 32]).collect();
+
+        let mut signature = AggregateSignature::new();
+        let mut aggregate_public_keys = vec![];
+        for (keypair, message) in keypairs.iter().zip(&messages) {
+            signature.add(&Signature::new(message, &keypair.sk));
+
+            let mut aggregate_public_key = AggregatePublicKey::new();
+            aggregate_public_key.add(&keypair.pk);
+            aggregate_public_keys.push(aggregate_public_key);
+        }
+
+        let messages: Vec<&[u8]> = messages.iter().map(Vec::as_slice).collect();
+        let aggregate_public_keys: Vec<&AggregatePublicKey> =
+            aggregate_public_keys.iter().collect();
+
+        assert!(signature.aggregate_verify(&messages, &aggregate_public_keys));
+
+        // Keys paired with the wrong messages.
+        let mut swapped = aggregate_public_keys.clone();
+        swapped.swap(0, 1);
+        assert!(!signature.aggregate_verify(&messages, &swapped));
+
+        // Mismatched lengths.
+        assert!(!signature.aggregate_verify(&messages[..2], &aggregate_public_keys));
+        assert!(!signature.aggregate_verify(&messages, &aggregate_public_keys[..2]));
+        assert!(!signature.aggregate_verify(&[], &[]));
+    }
+
+    #[test]
+    pub fn test_empty_bytes_round_trip() {
+        let bytes = AggregateSignature::empty_signature().as_bytes();
+        assert_eq!(bytes, vec![0; BLS_AGG_SIG_BYTE_SIZE]);
+        assert!(AggregateSignature::from_bytes(&bytes).unwrap().is_empty());
+    }
 }
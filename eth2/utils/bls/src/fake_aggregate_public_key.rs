@@ -1,4 +1,4 @@
-use super::{PublicKey, BLS_PUBLIC_KEY_BYTE_SIZE};
+use super::{PublicKey, SignatureError, BLS_PUBLIC_KEY_BYTE_SIZE};
 use hex::encode as hex_encode;
 use milagro_bls::G1Point;
 use serde::de::{Deserialize, Deserializer};
@@ -22,12 +22,22 @@ impl FakeAggregatePublicKey {
         Self::zero()
     }
 
+    /// Creates a new all-zero's aggregate public key.
+    pub fn aggregate(_public_keys: &[PublicKey]) -> Self {
+        Self::zero()
+    }
+
+    /// Creates a new all-zero's aggregate public key, without checking `public_keys`.
+    pub fn aggregate_checked(_public_keys: &[PublicKey]) -> Result<Self, SignatureError> {
+        Ok(Self::zero())
+    }
+
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         if bytes.len() != BLS_PUBLIC_KEY_BYTE_SIZE {
-            Err(DecodeError::InvalidByteLength {
-                len: bytes.len(),
-                expected: BLS_PUBLIC_KEY_BYTE_SIZE,
-            })
+            Err(DecodeError::wrong_length(
+                bytes.len(),
+                BLS_PUBLIC_KEY_BYTE_SIZE,
+            ))
         } else {
             Ok(Self {
                 bytes: bytes.to_vec(),
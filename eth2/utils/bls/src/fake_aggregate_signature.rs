@@ -61,13 +61,22 @@ impl FakeAggregateSignature {
         true
     }
 
+    /// Returns `true` if `messages` and `aggregate_public_keys` are the same, non-zero length.
+    pub fn aggregate_verify(
+        &self,
+        messages: &[&[u8]],
+        aggregate_public_keys: &[&FakeAggregatePublicKey],
+    ) -> bool {
+        !messages.is_empty() && messages.len() == aggregate_public_keys.len()
+    }
+
     /// Convert bytes to fake BLS aggregate signature
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         if bytes.len() != BLS_AGG_SIG_BYTE_SIZE {
-            Err(DecodeError::InvalidByteLength {
-                len: bytes.len(),
-                expected: BLS_AGG_SIG_BYTE_SIZE,
-            })
+            Err(DecodeError::wrong_length(
+                bytes.len(),
+                BLS_AGG_SIG_BYTE_SIZE,
+            ))
         } else {
             Ok(Self {
                 bytes: bytes.to_vec(),
@@ -79,6 +88,16 @@ impl FakeAggregateSignature {
     pub fn as_bytes(&self) -> Vec<u8> {
         self.bytes.clone()
     }
+
+    /// Returns a new empty signature.
+    pub fn empty_signature() -> Self {
+        Self::zero()
+    }
+
+    /// Returns `true` if the signature bytes are all zero.
+    pub fn is_empty(&self) -> bool {
+        self.bytes.iter().all(|byte| *byte == 0)
+    }
 }
 
 impl_ssz!(
@@ -1,6 +1,8 @@
 use super::{SecretKey, BLS_PUBLIC_KEY_BYTE_SIZE};
 use milagro_bls::G1Point;
 use milagro_bls::PublicKey as RawPublicKey;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -55,6 +57,22 @@ impl FakePublicKey {
         })
     }
 
+    /// Converts each of `bytes` with `from_bytes`, returning the results in the same order.
+    ///
+    /// With the `rayon` feature enabled the keys are decompressed in parallel. A malformed key
+    /// only produces an error in its own position.
+    pub fn from_bytes_batch(bytes: &[&[u8]]) -> Vec<Result<Self, DecodeError>> {
+        #[cfg(feature = "rayon")]
+        {
+            bytes.par_iter().map(|b| Self::from_bytes(b)).collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            bytes.iter().map(|b| Self::from_bytes(b)).collect()
+        }
+    }
+
     /// Returns the FakePublicKey as (x, y) bytes
     pub fn as_uncompressed_bytes(&self) -> Vec<u8> {
         self.as_bytes()
@@ -1,10 +1,11 @@
-use super::{PublicKey, SecretKey, BLS_SIG_BYTE_SIZE};
+use super::{PublicKey, SecretKey, SignatureError, BLS_SIG_BYTE_SIZE};
 use hex::encode as hex_encode;
 use milagro_bls::G2Point;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::PrefixedHexVisitor;
 use ssz::{ssz_encode, Decode, DecodeError, Encode};
+use subtle::ConstantTimeEq;
 
 /// A single BLS signature.
 ///
@@ -43,6 +44,11 @@ impl FakeSignature {
         true
     }
 
+    /// _Always_ returns `Ok(true)`.
+    pub fn verify_checked(&self, _msg: &[u8], _pk: &PublicKey) -> Result<bool, SignatureError> {
+        Ok(true)
+    }
+
     pub fn as_raw(&self) -> &Self {
         &self
     }
@@ -60,10 +66,7 @@ impl FakeSignature {
     /// Convert bytes to fake BLS Signature
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         if bytes.len() != BLS_SIG_BYTE_SIZE {
-            Err(DecodeError::InvalidByteLength {
-                len: bytes.len(),
-                expected: BLS_SIG_BYTE_SIZE,
-            })
+            Err(DecodeError::wrong_length(bytes.len(), BLS_SIG_BYTE_SIZE))
         } else {
             let is_empty = bytes.iter().all(|x| *x == 0);
             Ok(Self {
@@ -74,6 +77,31 @@ impl FakeSignature {
         }
     }
 
+    /// Identical to `from_bytes`, since fake signatures have no point to check.
+    pub fn from_bytes_subgroup_checked(bytes: &[u8]) -> Result<Self, DecodeError> {
+        Self::from_bytes(bytes)
+    }
+
+    /// Decode a list of concatenated signatures, each exactly `BLS_SIG_BYTE_SIZE` bytes.
+    ///
+    /// Returns an error for the first invalid signature, if any.
+    pub fn from_bytes_batch(bytes: &[u8]) -> Result<Vec<Self>, DecodeError> {
+        let num_signatures = bytes.len() / BLS_SIG_BYTE_SIZE;
+
+        // A trailing partial signature would be completed by more bytes.
+        if bytes.len() != num_signatures * BLS_SIG_BYTE_SIZE {
+            return Err(DecodeError::BytesTooShort {
+                need: (num_signatures + 1) * BLS_SIG_BYTE_SIZE,
+                got: bytes.len(),
+            });
+        }
+
+        bytes
+            .chunks(BLS_SIG_BYTE_SIZE)
+            .map(Self::from_bytes)
+            .collect()
+    }
+
     pub fn as_bytes(&self) -> Vec<u8> {
         self.bytes.clone()
     }
@@ -87,6 +115,14 @@ impl FakeSignature {
     pub fn is_empty(&self) -> bool {
         self.is_empty
     }
+
+    /// Returns `true` if `self` and `other` have the same serialized bytes, taking an amount of
+    /// time which depends only on the number of bytes (not on their values).
+    ///
+    /// Use this instead of `==` when one of the signatures should be secret.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.as_bytes().ct_eq(&other.as_bytes()).into()
+    }
 }
 
 impl_ssz!(FakeSignature, BLS_SIG_BYTE_SIZE, "FakeSignature");
@@ -1,4 +1,5 @@
-use super::{PublicKey, SecretKey};
+use super::{PublicKey, SecretKey, BLS_SECRET_KEY_BYTE_SIZE};
+use eth2_hashing::hash;
 use serde_derive::{Deserialize, Serialize};
 use std::fmt;
 use std::hash::{Hash, Hasher};
@@ -17,6 +18,26 @@ impl Keypair {
         Keypair { sk, pk }
     }
 
+    /// Instantiate a Keypair deterministically from `seed`, such that the same seed always
+    /// produces the same keys.
+    ///
+    /// The secret key is the hash of `seed`, with the highest bits cleared so that it is less
+    /// than the curve order. Keys are only as secret as `seed`; do not use a guessable seed
+    /// outside of testing.
+    pub fn from_seed(seed: &[u8]) -> Self {
+        let hashed = hash(seed);
+
+        let mut key_bytes = vec![0; BLS_SECRET_KEY_BYTE_SIZE];
+        let start = BLS_SECRET_KEY_BYTE_SIZE - hashed.len();
+        key_bytes[start..].copy_from_slice(&hashed);
+        key_bytes[start] &= 0x1f;
+
+        let sk = SecretKey::from_bytes(&key_bytes)
+            .expect("Any 48 bytes less than the curve order should be a valid secret key");
+        let pk = PublicKey::from_secret_key(&sk);
+        Keypair { sk, pk }
+    }
+
     pub fn identifier(&self) -> String {
         self.pk.concatenated_hex_id()
     }
@@ -40,3 +61,20 @@ impl fmt::Display for Keypair {
         write!(f, "{}", self.pk)
     }
 }
+
+#[cfg(all(test, not(feature = "fake_crypto")))]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn from_seed_is_deterministic() {
+        let a = Keypair::from_seed(b"seed");
+
+        assert_eq!(Keypair::from_seed(b"seed"), a);
+        assert_eq!(PublicKey::from_secret_key(&a.sk), a.pk);
+
+        let b = Keypair::from_seed(b"other seed");
+        assert_ne!(b.sk, a.sk);
+        assert_ne!(b.pk, a.pk);
+    }
+}
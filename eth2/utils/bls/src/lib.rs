@@ -14,7 +14,12 @@ pub use crate::public_key_bytes::PublicKeyBytes;
 pub use crate::secret_key::SecretKey;
 pub use crate::signature_bytes::SignatureBytes;
 pub use milagro_bls::{compress_g2, hash_on_g2, G1Point};
-pub use signature_set::{verify_signature_sets, G1Ref, SignatureSet, SignedMessage};
+pub use signature_set::{
+    verify_signature_sets, verify_signature_sets_sequential, G1Ref, G2Ref, SignatureSet,
+    SignatureSetError, SignedMessage,
+};
+#[cfg(feature = "rayon")]
+pub use signature_set::verify_signature_sets_parallel;
 
 #[cfg(feature = "fake_crypto")]
 mod fake_aggregate_public_key;
@@ -59,6 +64,16 @@ pub const BLS_SIG_BYTE_SIZE: usize = 96;
 pub const BLS_SECRET_KEY_BYTE_SIZE: usize = 48;
 pub const BLS_PUBLIC_KEY_BYTE_SIZE: usize = 48;
 
+/// The reasons that a signature cannot be verified, as distinct from it being invalid.
+#[derive(Debug, PartialEq, Clone)]
+pub enum SignatureError {
+    /// The signature is empty, so there is no point to check against the message.
+    EmptySignature,
+    /// The public key at `index` is the point at infinity or is not in the G1 subgroup, so it
+    /// should not be aggregated.
+    InvalidPublicKey { index: usize },
+}
+
 use eth2_hashing::hash;
 use ssz::ssz_encode;
 
@@ -1,5 +1,9 @@
 macro_rules! impl_ssz {
     ($type: ident, $byte_size: expr, $item_str: expr) => {
+        impl_ssz!($type, $byte_size, $item_str, from_bytes);
+    };
+    // Decodes with `$type::$from_bytes`, rather than `$type::from_bytes`.
+    ($type: ident, $byte_size: expr, $item_str: expr, $from_bytes: ident) => {
         impl ssz::Encode for $type {
             fn is_ssz_fixed_len() -> bool {
                 true
@@ -32,9 +36,9 @@ macro_rules! impl_ssz {
                 let expected = <Self as ssz::Decode>::ssz_fixed_len();
 
                 if len != expected {
-                    Err(ssz::DecodeError::InvalidByteLength { len, expected })
+                    Err(ssz::DecodeError::wrong_length(len, expected))
                 } else {
-                    $type::from_bytes(bytes)
+                    $type::$from_bytes(bytes)
                 }
             }
         }
@@ -114,10 +118,7 @@ macro_rules! bytes_struct {
             fn get_bytes(bytes: &[u8]) -> Result<[u8; $byte_size], ssz::DecodeError> {
                 let mut result = [0; $byte_size];
                 if bytes.len() != $byte_size {
-                    Err(ssz::DecodeError::InvalidByteLength {
-                        len: bytes.len(),
-                        expected: $byte_size,
-                    })
+                    Err(ssz::DecodeError::wrong_length(bytes.len(), $byte_size))
                 } else {
                     result[..].copy_from_slice(bytes);
                     Ok(result)
@@ -132,6 +133,15 @@ macro_rules! bytes_struct {
             pub fn decompressed(&self) -> &Option<$type> {
                 &self.decompressed
             }
+
+            /// Returns the decompressed value, decompressing and storing it first if it has not
+            /// already been decompressed.
+            pub fn decompress_cached(&mut self) -> Result<&$type, ssz::DecodeError> {
+                if self.decompressed.is_none() {
+                    self.decompress()?;
+                }
+                Ok(self.decompressed.as_ref().expect("Decompressed above."))
+            }
         }
 
         impl std::fmt::Debug for $name {
@@ -1,5 +1,7 @@
 use super::{SecretKey, BLS_PUBLIC_KEY_BYTE_SIZE};
 use milagro_bls::PublicKey as RawPublicKey;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
@@ -45,6 +47,22 @@ impl PublicKey {
         Ok(PublicKey(pubkey))
     }
 
+    /// Converts each of `bytes` with `from_bytes`, returning the results in the same order.
+    ///
+    /// With the `rayon` feature enabled the keys are decompressed in parallel. A malformed key
+    /// only produces an error in its own position.
+    pub fn from_bytes_batch(bytes: &[&[u8]]) -> Vec<Result<Self, DecodeError>> {
+        #[cfg(feature = "rayon")]
+        {
+            bytes.par_iter().map(|b| Self::from_bytes(b)).collect()
+        }
+
+        #[cfg(not(feature = "rayon"))]
+        {
+            bytes.iter().map(|b| Self::from_bytes(b)).collect()
+        }
+    }
+
     /// Returns the PublicKey as (x, y) bytes
     pub fn as_uncompressed_bytes(&self) -> Vec<u8> {
         RawPublicKey::as_uncompressed_bytes(&mut self.0.clone())
@@ -159,4 +177,23 @@ mod tests {
         let bytes = ssz_encode(&original);
         assert_eq!(bytes.len(), BLS_PUBLIC_KEY_BYTE_SIZE);
     }
+
+    #[test]
+    pub fn test_from_bytes_batch() {
+        let mut inputs = (0..8)
+            .map(|_| PublicKey::from_secret_key(&SecretKey::random()).as_bytes())
+            .collect::<Vec<_>>();
+        // A key of the wrong length, and one which is not a point on the curve.
+        inputs[2].pop();
+        inputs[5] = vec![0xff; BLS_PUBLIC_KEY_BYTE_SIZE];
+
+        let input_refs = inputs.iter().map(Vec::as_slice).collect::<Vec<_>>();
+        let batch = PublicKey::from_bytes_batch(&input_refs);
+
+        assert_eq!(batch.len(), inputs.len());
+        for (i, (bytes, result)) in inputs.iter().zip(batch).enumerate() {
+            assert_eq!(result.is_err(), i == 2 || i == 5, "index: {}", i);
+            assert_eq!(result, PublicKey::from_bytes(bytes), "index: {}", i);
+        }
+    }
 }
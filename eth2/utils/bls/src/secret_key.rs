@@ -47,6 +47,22 @@ impl SecretKey {
     }
 }
 
+/// Overwrites the key with zeros, so that it does not linger in freed memory.
+///
+/// Only the memory of this instance is cleared; any copies made by moving or cloning the key are
+/// cleared when they are dropped in turn.
+impl Drop for SecretKey {
+    fn drop(&mut self) {
+        // Safe because the raw key is plain integer data for which all-zeros is a valid value,
+        // and `self.0` is a valid, aligned reference. A volatile write is used so that the
+        // compiler cannot elide the write to memory which is about to be freed.
+        unsafe {
+            std::ptr::write_volatile(&mut self.0, std::mem::zeroed());
+        }
+        std::sync::atomic::compiler_fence(std::sync::atomic::Ordering::SeqCst);
+    }
+}
+
 impl_ssz!(SecretKey, BLS_SECRET_KEY_BYTE_SIZE, "SecretKey");
 
 impl_tree_hash!(SecretKey, BLS_SECRET_KEY_BYTE_SIZE);
@@ -87,4 +103,20 @@ mod tests {
 
         assert_eq!(original, decoded);
     }
+
+    #[test]
+    pub fn test_zeroed_on_drop() {
+        let mut key = std::mem::ManuallyDrop::new(
+            SecretKey::from_bytes(b"jzjxxgjajfjrmgodszzsgqccmhnyvetcuxobhtynojtpdtbj").unwrap(),
+        );
+        let ptr = &*key as *const SecretKey as *const u8;
+        let len = std::mem::size_of::<SecretKey>();
+
+        // The memory remains owned by `key` after the drop, so it may still be read.
+        let read = || unsafe { std::slice::from_raw_parts(ptr, len).to_vec() };
+
+        assert!(read().iter().any(|byte| *byte != 0));
+        unsafe { std::mem::ManuallyDrop::drop(&mut key) };
+        assert!(read().iter().all(|byte| *byte == 0));
+    }
 }
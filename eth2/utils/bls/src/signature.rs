@@ -1,9 +1,11 @@
-use super::{PublicKey, SecretKey, BLS_SIG_BYTE_SIZE};
+use super::{PublicKey, SecretKey, SignatureError, BLS_SIG_BYTE_SIZE};
+use amcl::bls381::{big::BIG, rom::CURVE_ORDER};
 use milagro_bls::Signature as RawSignature;
 use serde::de::{Deserialize, Deserializer};
 use serde::ser::{Serialize, Serializer};
 use serde_hex::{encode as hex_encode, PrefixedHexVisitor};
 use ssz::{ssz_encode, Decode, DecodeError, Encode};
+use subtle::ConstantTimeEq;
 
 /// A single BLS signature.
 ///
@@ -25,11 +27,21 @@ impl Signature {
     }
 
     /// Verify the Signature against a PublicKey.
+    ///
+    /// Returns `false` if the signature is empty; see `verify_checked` to distinguish that case.
     pub fn verify(&self, msg: &[u8], pk: &PublicKey) -> bool {
-        if self.is_empty {
-            return false;
+        self.verify_checked(msg, pk).unwrap_or(false)
+    }
+
+    /// Verify the Signature against a PublicKey, returning `Ok(false)` only if the signature is
+    /// well-formed but does not sign `msg` with `pk`.
+    ///
+    /// Returns `Err(SignatureError::EmptySignature)` for an empty signature.
+    pub fn verify_checked(&self, msg: &[u8], pk: &PublicKey) -> Result<bool, SignatureError> {
+        if self.is_empty() {
+            return Err(SignatureError::EmptySignature);
         }
-        self.signature.verify(msg, pk.as_raw())
+        Ok(self.signature.verify(msg, pk.as_raw()))
     }
 
     /// Returns the underlying signature.
@@ -57,24 +69,75 @@ impl Signature {
     }
 
     // Convert bytes to BLS Signature
+    //
+    // All-zero bytes are decoded as `Signature::empty_signature()`. The compressed encoding of
+    // the point at infinity (i.e., the raw encoding of `Signature::empty_signature()`) is also
+    // empty, but is kept distinct so that it re-encodes to the same bytes; otherwise the tree hash
+    // root of a decoded object could differ from that of the bytes it was decoded from.
     pub fn from_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        for byte in bytes {
-            if *byte != 0 {
-                let raw_signature = RawSignature::from_bytes(&bytes).map_err(|_| {
-                    DecodeError::BytesInvalid(format!("Invalid Signature bytes: {:?}", bytes))
-                })?;
-                return Ok(Signature {
-                    signature: raw_signature,
-                    is_empty: false,
-                });
-            }
+        if bytes.iter().all(|byte| *byte == 0) {
+            return Ok(Signature::empty_signature());
+        }
+
+        let raw_signature = RawSignature::from_bytes(&bytes).map_err(|_| {
+            DecodeError::BytesInvalid(format!("Invalid Signature bytes: {:?}", bytes))
+        })?;
+        Ok(Signature {
+            signature: raw_signature,
+            is_empty: false,
+        })
+    }
+
+    /// Identical to `from_bytes`, except that the signature is also checked to lie in the
+    /// prime-order subgroup of G2.
+    ///
+    /// Decoding does not otherwise ensure this, so the check should be applied to any signature
+    /// from an untrusted source. It is used by the SSZ `Decode` impl. The check costs a scalar
+    /// multiplication, so `from_bytes` may be used for bytes which are already trusted.
+    pub fn from_bytes_subgroup_checked(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let signature = Self::from_bytes(bytes)?;
+
+        if signature.is_empty() || is_in_subgroup(&signature.signature) {
+            Ok(signature)
+        } else {
+            Err(DecodeError::BytesInvalid(
+                "signature not in subgroup".to_string(),
+            ))
         }
-        Ok(Signature::empty_signature())
     }
 
-    // Check for empty Signature
+    /// Decode a list of concatenated signatures, each exactly `BLS_SIG_BYTE_SIZE` bytes.
+    ///
+    /// Returns an error for the first invalid signature, if any.
+    pub fn from_bytes_batch(bytes: &[u8]) -> Result<Vec<Self>, DecodeError> {
+        let num_signatures = bytes.len() / BLS_SIG_BYTE_SIZE;
+
+        // A trailing partial signature would be completed by more bytes.
+        if bytes.len() != num_signatures * BLS_SIG_BYTE_SIZE {
+            return Err(DecodeError::BytesTooShort {
+                need: (num_signatures + 1) * BLS_SIG_BYTE_SIZE,
+                got: bytes.len(),
+            });
+        }
+
+        bytes
+            .chunks(BLS_SIG_BYTE_SIZE)
+            .map(Self::from_bytes)
+            .collect()
+    }
+
+    /// Returns `true` if the signature is either `Signature::empty_signature()` or was decoded from
+    /// the compressed encoding of the point at infinity.
     pub fn is_empty(&self) -> bool {
-        self.is_empty
+        self.is_empty || self.signature.point.as_raw().is_infinity()
+    }
+
+    /// Returns `true` if `self` and `other` have the same serialized bytes, taking an amount of
+    /// time which depends only on the number of bytes (not on their values).
+    ///
+    /// Use this instead of `==` when one of the signatures should be secret.
+    pub fn ct_eq(&self, other: &Self) -> bool {
+        self.as_bytes().ct_eq(&other.as_bytes()).into()
     }
 
     /// Display a signature as a hex string of its bytes.
@@ -84,7 +147,19 @@ impl Signature {
     }
 }
 
-impl_ssz!(Signature, BLS_SIG_BYTE_SIZE, "Signature");
+/// Returns `true` if multiplying the point of `signature` by the order of G2 yields the point at
+/// infinity.
+fn is_in_subgroup(signature: &RawSignature) -> bool {
+    let order = BIG::new_ints(&CURVE_ORDER);
+    signature.point.as_raw().mul(&order).is_infinity()
+}
+
+impl_ssz!(
+    Signature,
+    BLS_SIG_BYTE_SIZE,
+    "Signature",
+    from_bytes_subgroup_checked
+);
 
 impl_tree_hash!(Signature, BLS_SIG_BYTE_SIZE);
 
@@ -129,6 +204,20 @@ mod tests {
         assert_eq!(original, decoded);
     }
 
+    #[test]
+    pub fn test_verify_checked() {
+        let keypair = Keypair::random();
+        let signature = Signature::new(&[42, 42], &keypair.sk);
+
+        assert_eq!(signature.verify_checked(&[42, 42], &keypair.pk), Ok(true));
+        assert_eq!(signature.verify_checked(&[42, 43], &keypair.pk), Ok(false));
+        assert_eq!(
+            Signature::empty_signature().verify_checked(&[42, 42], &keypair.pk),
+            Err(SignatureError::EmptySignature)
+        );
+        assert!(!Signature::empty_signature().verify(&[42, 42], &keypair.pk));
+    }
+
     #[test]
     pub fn test_byte_size() {
         let keypair = Keypair::random();
@@ -138,6 +227,158 @@ mod tests {
         assert_eq!(bytes.len(), BLS_SIG_BYTE_SIZE);
     }
 
+    #[test]
+    pub fn test_ssz_fixed_len() {
+        assert!(<Signature as Encode>::is_ssz_fixed_len());
+        assert_eq!(<Signature as Encode>::ssz_fixed_len(), BLS_SIG_BYTE_SIZE);
+        assert!(<Signature as Decode>::is_ssz_fixed_len());
+        assert_eq!(<Signature as Decode>::ssz_fixed_len(), BLS_SIG_BYTE_SIZE);
+    }
+
+    #[test]
+    pub fn test_invalid_length() {
+        let keypair = Keypair::random();
+
+        let bytes = ssz_encode(&Signature::new(&[42, 42], &keypair.sk));
+
+        assert_eq!(
+            Signature::from_ssz_bytes(&bytes[..BLS_SIG_BYTE_SIZE - 1]),
+            Err(DecodeError::BytesTooShort {
+                need: BLS_SIG_BYTE_SIZE,
+                got: BLS_SIG_BYTE_SIZE - 1
+            })
+        );
+
+        let mut too_long = bytes.clone();
+        too_long.push(0);
+        assert_eq!(
+            Signature::from_ssz_bytes(&too_long),
+            Err(DecodeError::BytesTooLong {
+                expected: BLS_SIG_BYTE_SIZE,
+                got: BLS_SIG_BYTE_SIZE + 1
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_subgroup_check() {
+        let keypair = Keypair::random();
+
+        let signature = Signature::new(&[42, 42], &keypair.sk);
+        assert_eq!(
+            Signature::from_bytes_subgroup_checked(&signature.as_bytes()),
+            Ok(signature)
+        );
+        assert_eq!(
+            Signature::from_bytes_subgroup_checked(&Signature::empty_signature().as_bytes()),
+            Ok(Signature::empty_signature())
+        );
+
+        // The compressed encoding of a point on the curve with `x = 4` (or `x = 4i`, depending on
+        // the order of the coordinates), neither of which is in the subgroup.
+        let mut bytes = vec![0; BLS_SIG_BYTE_SIZE];
+        bytes[0] = 0b1000_0000;
+        bytes[BLS_SIG_BYTE_SIZE - 1] = 4;
+
+        assert!(Signature::from_bytes_subgroup_checked(&bytes).is_err());
+        assert!(Signature::from_ssz_bytes(&bytes).is_err());
+    }
+
+    #[test]
+    pub fn test_from_bytes_batch() {
+        let keypair = Keypair::random();
+
+        let signatures = (0..3)
+            .map(|i| Signature::new(&[i], &keypair.sk))
+            .collect::<Vec<_>>();
+        let mut bytes = signatures
+            .iter()
+            .flat_map(|signature| signature.as_bytes())
+            .collect::<Vec<_>>();
+
+        assert_eq!(Signature::from_bytes_batch(&bytes), Ok(signatures));
+
+        // A trailing partial signature.
+        bytes.extend_from_slice(&[1; BLS_SIG_BYTE_SIZE / 2]);
+        assert_eq!(
+            Signature::from_bytes_batch(&bytes),
+            Err(DecodeError::BytesTooShort {
+                need: BLS_SIG_BYTE_SIZE * 4,
+                got: BLS_SIG_BYTE_SIZE * 3 + BLS_SIG_BYTE_SIZE / 2
+            })
+        );
+    }
+
+    #[test]
+    pub fn test_serde_utils_matches_serde() {
+        use serde_derive::{Deserialize, Serialize};
+
+        #[derive(Debug, PartialEq, Serialize, Deserialize)]
+        struct Wrapper {
+            #[serde(with = "ssz::serde_utils")]
+            signature: Signature,
+        }
+
+        let keypair = Keypair::random();
+        for signature in &[
+            Signature::new(&[42, 42], &keypair.sk),
+            Signature::empty_signature(),
+        ] {
+            let wrapper = Wrapper {
+                signature: signature.clone(),
+            };
+
+            let json = serde_json::to_string(&wrapper).unwrap();
+            assert_eq!(
+                json,
+                format!(
+                    r#"{{"signature":{}}}"#,
+                    serde_json::to_string(signature).unwrap()
+                )
+            );
+            assert_eq!(serde_json::from_str::<Wrapper>(&json).unwrap(), wrapper);
+        }
+    }
+
+    #[test]
+    pub fn test_empty_signature_round_trip() {
+        let empty = Signature::empty_signature();
+
+        // Both encodings of an empty signature decode as empty and re-encode to the same bytes.
+        for bytes in &[empty.as_bytes(), empty.as_raw().as_bytes()] {
+            let decoded = Signature::from_ssz_bytes(bytes).unwrap();
+            assert!(decoded.is_empty());
+            assert_eq!(&decoded.as_ssz_bytes(), bytes);
+        }
+        assert_eq!(empty.as_bytes(), vec![0; BLS_SIG_BYTE_SIZE]);
+        assert_eq!(Signature::from_bytes(&empty.as_bytes()), Ok(empty.clone()));
+        assert_ne!(
+            Signature::from_bytes(&empty.as_raw().as_bytes()),
+            Ok(empty.clone())
+        );
+
+        // Other encodings with the infinity flags set are not empty.
+        let mut bytes = empty.as_raw().as_bytes();
+        bytes[BLS_SIG_BYTE_SIZE - 1] = 1;
+        assert!(Signature::from_bytes(&bytes).map_or(true, |signature| !signature.is_empty()));
+    }
+
+    #[test]
+    pub fn test_ct_eq() {
+        let keypair = Keypair::random();
+
+        let a = Signature::new(&[42, 42], &keypair.sk);
+        let b = Signature::new(&[42, 42], &keypair.sk);
+        let c = Signature::new(&[42, 43], &keypair.sk);
+        let empty = Signature::empty_signature();
+
+        for (x, y) in &[(&a, &b), (&a, &c), (&a, &empty), (&empty, &empty)] {
+            assert_eq!(x.ct_eq(y), x == y);
+        }
+        assert!(a.ct_eq(&b));
+        assert!(!a.ct_eq(&c));
+    }
+
     #[test]
     pub fn test_empty_signature() {
         let sig = Signature::empty_signature();
@@ -4,12 +4,38 @@ use std::borrow::Cow;
 
 #[cfg(not(feature = "fake_crypto"))]
 use milagro_bls::AggregateSignature as RawAggregateSignature;
+#[cfg(all(feature = "rayon", not(feature = "fake_crypto")))]
+use rayon::prelude::*;
 
 type Message = Vec<u8>;
 
+/// The length of each message, which is always the signing root of some object.
+const MESSAGE_LEN: usize = 32;
+
+/// The number of signing keys above which they are aggregated in parallel, when the `rayon`
+/// feature is enabled. Below this, the cost of dispatching to the thread pool outweighs the
+/// savings.
+#[cfg(all(feature = "rayon", not(feature = "fake_crypto")))]
+const PARALLEL_AGGREGATION_THRESHOLD: usize = 256;
+
+/// Reasons why a `SignatureSet` can't be verified at all, as opposed to its signature being
+/// invalid.
+#[derive(Clone, Debug, PartialEq)]
+pub enum SignatureSetError {
+    /// The set does not contain any messages.
+    NoMessages,
+    /// The aggregate public key for the message at `index` is the point at infinity (e.g., the
+    /// message has no signing keys).
+    AggregatePublicKeyAtInfinity { index: usize },
+    /// The message at `index` is `len` bytes, rather than the length of a signing root.
+    InvalidMessageLength { index: usize, len: usize },
+}
+
 #[derive(Clone, Debug)]
 pub struct SignedMessage<'a> {
     signing_keys: Vec<Cow<'a, G1Point>>,
+    /// The aggregate of `signing_keys`, if it was supplied by the caller.
+    aggregate: Option<Cow<'a, G1Point>>,
     message: Message,
 }
 
@@ -17,9 +43,47 @@ impl<'a> SignedMessage<'a> {
     pub fn new(signing_keys: Vec<Cow<'a, G1Point>>, message: Message) -> Self {
         Self {
             signing_keys,
+            aggregate: None,
+            message,
+        }
+    }
+
+    /// Instantiate a message with a pre-computed `aggregate` of the `signing_keys`, avoiding the
+    /// cost of aggregating the keys each time the message is verified.
+    ///
+    /// The caller must ensure that `aggregate` is the aggregate of `signing_keys`, it is not
+    /// checked.
+    pub fn with_aggregate(
+        signing_keys: Vec<Cow<'a, G1Point>>,
+        aggregate: Cow<'a, G1Point>,
+        message: Message,
+    ) -> Self {
+        Self {
+            signing_keys,
+            aggregate: Some(aggregate),
             message,
         }
     }
+
+    /// Instantiate a message signed by the given `aggregate` public key, without the individual
+    /// keys which it aggregates.
+    pub fn from_aggregate(aggregate: Cow<'a, G1Point>, message: Message) -> Self {
+        Self {
+            signing_keys: vec![],
+            aggregate: Some(aggregate),
+            message,
+        }
+    }
+
+    /// Returns the aggregate of the signing keys, computing it only if it wasn't supplied at
+    /// instantiation and there is more than one key.
+    fn aggregate_public_key(&self) -> Cow<'_, G1Point> {
+        match &self.aggregate {
+            Some(aggregate) => Cow::Borrowed(aggregate.as_ref()),
+            None if self.signing_keys.len() == 1 => Cow::Borrowed(self.signing_keys[0].as_ref()),
+            None => Cow::Owned(aggregate_public_keys(&self.signing_keys)),
+        }
+    }
 }
 
 #[derive(Clone, Debug)]
@@ -69,7 +133,54 @@ impl<'a> SignatureSet<'a> {
         }
     }
 
+    /// Checks that the set is well-formed, without doing any cryptographic operations.
+    ///
+    /// A set is well-formed if it has at least one message, every message has at least one signing
+    /// key (or a supplied aggregate) and every message is `MESSAGE_LEN` bytes. Unless the
+    /// `fake_crypto` feature is enabled, `is_valid` implies `validate_structure().is_ok()`; this
+    /// is intended to catch errors in the construction of a set before it is batch-verified.
+    pub fn validate_structure(&self) -> Result<(), SignatureSetError> {
+        if self.signed_messages.is_empty() {
+            return Err(SignatureSetError::NoMessages);
+        }
+
+        for (index, signed_message) in self.signed_messages.iter().enumerate() {
+            if signed_message.signing_keys.is_empty() && signed_message.aggregate.is_none() {
+                return Err(SignatureSetError::AggregatePublicKeyAtInfinity { index });
+            }
+
+            let len = signed_message.message.len();
+            if len != MESSAGE_LEN {
+                return Err(SignatureSetError::InvalidMessageLength { index, len });
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Returns `true` if the signature is valid across all of the messages.
+    ///
+    /// Equivalent to `verify`, except that sets which can't be verified are considered invalid.
+    #[cfg(not(feature = "fake_crypto"))]
+    pub fn is_valid(&self) -> bool {
+        self.verify().unwrap_or(false)
+    }
+
+    /// _Always_ returns `true`.
+    #[cfg(feature = "fake_crypto")]
     pub fn is_valid(&self) -> bool {
+        true
+    }
+
+    /// Returns `Ok(true)` if the signature is valid across all of the messages and `Ok(false)` if
+    /// it is not.
+    ///
+    /// Returns an error if the set is malformed (see `validate_structure`), so that callers can
+    /// distinguish a set which could never be valid from one with a bad signature.
+    #[cfg(not(feature = "fake_crypto"))]
+    pub fn verify(&self) -> Result<bool, SignatureSetError> {
+        self.validate_structure()?;
+
         let sig = milagro_bls::AggregateSignature {
             point: self.signature.clone(),
         };
@@ -77,29 +188,72 @@ impl<'a> SignatureSet<'a> {
         let mut messages: Vec<Vec<u8>> = vec![];
         let mut pubkeys = vec![];
 
-        self.signed_messages.iter().for_each(|signed_message| {
+        for (index, signed_message) in self.signed_messages.iter().enumerate() {
             messages.push(signed_message.message.clone());
 
-            let point = if signed_message.signing_keys.len() == 1 {
-                signed_message.signing_keys[0].clone().into_owned()
+            let point = signed_message.aggregate_public_key().into_owned();
+
+            if point.is_infinity() {
+                return Err(SignatureSetError::AggregatePublicKeyAtInfinity { index });
+            }
+
+            pubkeys.push(milagro_bls::AggregatePublicKey { point });
+        }
+
+        // When every message is the same (e.g., a committee signing one attestation) the keys
+        // can be aggregated and checked with a single pairing, which is much cheaper than
+        // `verify_multiple`.
+        if messages.windows(2).all(|pair| pair[0] == pair[1]) {
+            let aggregate = if pubkeys.len() == 1 {
+                pubkeys.remove(0)
             } else {
-                aggregate_public_keys(&signed_message.signing_keys)
+                let mut aggregate = AggregatePublicKey::new();
+                pubkeys
+                    .iter()
+                    .for_each(|pubkey| aggregate.add_point(&pubkey.point));
+                aggregate.affine();
+                aggregate.into_raw()
             };
 
-            pubkeys.push(milagro_bls::AggregatePublicKey { point });
-        });
+            // Distinct keys may still sum to the point at infinity, which must not be trusted.
+            if aggregate.point.is_infinity() {
+                return Ok(false);
+            }
+
+            return Ok(sig.verify(&messages[0], &aggregate));
+        }
 
         let pubkey_refs: Vec<&milagro_bls::AggregatePublicKey> =
             pubkeys.iter().map(std::borrow::Borrow::borrow).collect();
 
-        sig.verify_multiple(&messages, &pubkey_refs)
+        Ok(sig.verify_multiple(&messages, &pubkey_refs))
+    }
+
+    /// _Always_ returns `Ok(true)`, without aggregating any keys.
+    #[cfg(feature = "fake_crypto")]
+    pub fn verify(&self) -> Result<bool, SignatureSetError> {
+        Ok(true)
     }
 }
 
+/// Verifies all of the sets at once, returning `false` if any of them are invalid.
+///
+/// Each set is multiplied by a random scalar and the results are checked with a single
+/// multi-pairing, which is far cheaper than verifying each set on its own. The random scalars
+/// ensure that invalid sets cannot cancel each other out.
+///
+/// Returns `false` without any pairings if any set is malformed (see
+/// `SignatureSet::validate_structure`), so that batch and individual verification agree.
 #[cfg(not(feature = "fake_crypto"))]
 pub fn verify_signature_sets<'a>(iter: impl Iterator<Item = SignatureSet<'a>>) -> bool {
+    let sets = iter.collect::<Vec<_>>();
+
+    if sets.iter().any(|set| set.validate_structure().is_err()) {
+        return false;
+    }
+
     let rng = &mut rand::thread_rng();
-    RawAggregateSignature::verify_multiple_signatures(rng, iter.map(Into::into))
+    RawAggregateSignature::verify_multiple_signatures(rng, sets.into_iter().map(Into::into))
 }
 
 #[cfg(feature = "fake_crypto")]
@@ -107,6 +261,36 @@ pub fn verify_signature_sets<'a>(_iter: impl Iterator<Item = SignatureSet<'a>>)
     true
 }
 
+/// Verifies each of the sets independently, one after the other, returning `false` if any of them
+/// are invalid.
+///
+/// This is much slower than `verify_signature_sets` and is intended for debugging, since it does
+/// not rely on batch verification.
+#[cfg(not(feature = "fake_crypto"))]
+pub fn verify_signature_sets_sequential<'a>(
+    mut iter: impl Iterator<Item = SignatureSet<'a>>,
+) -> bool {
+    iter.all(|set| set.is_valid())
+}
+
+#[cfg(feature = "fake_crypto")]
+pub fn verify_signature_sets_sequential<'a>(_iter: impl Iterator<Item = SignatureSet<'a>>) -> bool {
+    true
+}
+
+/// Verifies each of the `sets` independently, spreading the work across the rayon thread pool.
+///
+/// Returns `false` if any of the `sets` are invalid.
+#[cfg(all(feature = "rayon", not(feature = "fake_crypto")))]
+pub fn verify_signature_sets_parallel(sets: &[SignatureSet]) -> bool {
+    sets.par_iter().all(SignatureSet::is_valid)
+}
+
+#[cfg(all(feature = "rayon", feature = "fake_crypto"))]
+pub fn verify_signature_sets_parallel(_sets: &[SignatureSet]) -> bool {
+    true
+}
+
 type VerifySet<'a> = (G2Point, Vec<G1Point>, Vec<Vec<u8>>);
 
 impl<'a> Into<VerifySet<'a>> for SignatureSet<'a> {
@@ -117,11 +301,7 @@ impl<'a> Into<VerifySet<'a>> for SignatureSet<'a> {
             .signed_messages
             .into_iter()
             .map(|signed_message| {
-                let key = if signed_message.signing_keys.len() == 1 {
-                    signed_message.signing_keys[0].clone().into_owned()
-                } else {
-                    aggregate_public_keys(&signed_message.signing_keys)
-                };
+                let key = signed_message.aggregate_public_key().into_owned();
 
                 (key, signed_message.message)
             })
@@ -132,7 +312,22 @@ impl<'a> Into<VerifySet<'a>> for SignatureSet<'a> {
 }
 
 /// Create an aggregate public key for a list of validators, failing if any key can't be found.
-fn aggregate_public_keys<'a>(public_keys: &'a [Cow<'a, G1Point>]) -> G1Point {
+///
+/// Large lists (e.g., the keys of a committee) are aggregated in parallel with the `rayon`
+/// feature, otherwise the keys are added one by one.
+pub(crate) fn aggregate_public_keys<'a>(public_keys: &'a [Cow<'a, G1Point>]) -> G1Point {
+    #[cfg(all(feature = "rayon", not(feature = "fake_crypto")))]
+    {
+        if public_keys.len() > PARALLEL_AGGREGATION_THRESHOLD {
+            return aggregate_public_keys_parallel(public_keys);
+        }
+    }
+
+    aggregate_public_keys_sequential(public_keys)
+}
+
+/// Aggregates `public_keys` by adding each key to the aggregate in turn.
+fn aggregate_public_keys_sequential(public_keys: &[Cow<G1Point>]) -> G1Point {
     let mut aggregate =
         public_keys
             .iter()
@@ -146,6 +341,26 @@ fn aggregate_public_keys<'a>(public_keys: &'a [Cow<'a, G1Point>]) -> G1Point {
     aggregate.into_raw().point
 }
 
+/// Aggregates `public_keys` by summing chunks of keys on the rayon thread pool, then summing the
+/// results of the chunks. The result is identical to `aggregate_public_keys_sequential`.
+#[cfg(all(feature = "rayon", not(feature = "fake_crypto")))]
+fn aggregate_public_keys_parallel(public_keys: &[Cow<G1Point>]) -> G1Point {
+    let mut aggregate = public_keys
+        .par_iter()
+        .fold(AggregatePublicKey::new, |mut aggregate, pubkey| {
+            aggregate.add_point(pubkey);
+            aggregate
+        })
+        .reduce(AggregatePublicKey::new, |mut aggregate, other| {
+            aggregate.add_point(&other.as_raw().point);
+            aggregate
+        });
+
+    aggregate.affine();
+
+    aggregate.into_raw().point
+}
+
 pub trait G1Ref {
     fn g1_ref(&self) -> Cow<'_, G1Point>;
 }
@@ -177,3 +392,315 @@ impl G2Ref for Signature {
         &self.as_raw().point
     }
 }
+
+#[cfg(all(test, not(feature = "fake_crypto")))]
+mod tests {
+    use super::*;
+    use crate::Keypair;
+
+    fn message(i: usize) -> Vec<u8> {
+        vec![i as u8; 32]
+    }
+
+    fn signature_sets<'a>(
+        signatures: &'a [Signature],
+        keypairs: &'a [Keypair],
+    ) -> Vec<SignatureSet<'a>> {
+        signatures
+            .iter()
+            .zip(keypairs.iter())
+            .enumerate()
+            .map(|(i, (signature, keypair))| {
+                SignatureSet::single(signature, keypair.pk.g1_ref(), message(i))
+            })
+            .collect()
+    }
+
+    fn signatures(keypairs: &[Keypair]) -> Vec<Signature> {
+        keypairs
+            .iter()
+            .enumerate()
+            .map(|(i, keypair)| Signature::new(&message(i), &keypair.sk))
+            .collect()
+    }
+
+    #[test]
+    fn batch_matches_sequential() {
+        let keypairs = (0..8).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let mut signatures = signatures(&keypairs);
+
+        let sets = signature_sets(&signatures, &keypairs);
+        assert!(verify_signature_sets_sequential(sets.clone().into_iter()));
+        assert!(verify_signature_sets(sets.into_iter()));
+
+        // A single invalid set causes the whole batch to be rejected, wherever it appears.
+        for i in 0..keypairs.len() {
+            let valid = signatures[i].clone();
+            signatures[i] = Signature::new(&message(i + 1), &keypairs[i].sk);
+
+            let sets = signature_sets(&signatures, &keypairs);
+            assert!(!verify_signature_sets_sequential(sets.clone().into_iter()));
+            assert!(
+                !verify_signature_sets(sets.into_iter()),
+                "invalid set: {:?}",
+                i
+            );
+
+            signatures[i] = valid;
+        }
+
+        // Swapping signatures between two sets is also rejected, even though the sum of the
+        // signatures is unchanged.
+        signatures.swap(0, 1);
+        let sets = signature_sets(&signatures, &keypairs);
+        assert!(!verify_signature_sets(sets.into_iter()));
+    }
+
+    #[test]
+    fn precomputed_aggregate_matches_computed() {
+        let keypairs = (0..4).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let keys = || keypairs.iter().map(|kp| kp.pk.g1_ref()).collect::<Vec<_>>();
+
+        let mut aggregate = AggregatePublicKey::new();
+        keypairs.iter().for_each(|kp| aggregate.add(&kp.pk));
+        aggregate.affine();
+
+        let mut signature = AggregateSignature::new();
+        keypairs
+            .iter()
+            .for_each(|kp| signature.add(&Signature::new(&message(0), &kp.sk)));
+
+        let set = |signed_message| SignatureSet::new(&signature, vec![signed_message]);
+        let computed = set(SignedMessage::new(keys(), message(0)));
+        let precomputed = set(SignedMessage::with_aggregate(
+            keys(),
+            aggregate.g1_ref(),
+            message(0),
+        ));
+
+        assert!(computed.is_valid());
+        assert!(precomputed.is_valid());
+        assert!(verify_signature_sets(
+            vec![computed, precomputed].into_iter()
+        ));
+
+        // The supplied aggregate is used in place of the signing keys.
+        let wrong = set(SignedMessage::with_aggregate(
+            keys(),
+            keypairs[0].pk.g1_ref(),
+            message(0),
+        ));
+        assert!(!wrong.is_valid());
+        assert!(!verify_signature_sets(vec![wrong].into_iter()));
+    }
+
+    #[test]
+    fn single_message_matches_multiple_messages() {
+        let keypairs = (0..8).map(|_| Keypair::random()).collect::<Vec<_>>();
+        let committee_keys = || keypairs.iter().map(|kp| kp.pk.g1_ref()).collect::<Vec<_>>();
+
+        let mut aggregate = AggregatePublicKey::new();
+        keypairs.iter().for_each(|kp| aggregate.add(&kp.pk));
+        aggregate.affine();
+
+        let mut valid = AggregateSignature::new();
+        keypairs
+            .iter()
+            .for_each(|kp| valid.add(&Signature::new(&message(0), &kp.sk)));
+
+        // One committee member signed the wrong message.
+        let mut tampered = AggregateSignature::new();
+        keypairs.iter().enumerate().for_each(|(i, kp)| {
+            let msg = if i == 3 { message(1) } else { message(0) };
+            tampered.add(&Signature::new(&msg, &kp.sk))
+        });
+
+        for (signature, is_valid) in vec![(&valid, true), (&tampered, false)] {
+            // A single message, dispatched to the single-message path.
+            let set = SignatureSet::new(
+                signature,
+                vec![SignedMessage::new(committee_keys(), message(0))],
+            );
+            assert_eq!(set.verify(), Ok(is_valid));
+
+            // Several signed messages with the same message also take the single-message path.
+            let keys = committee_keys();
+            let (first, second) = keys.split_at(4);
+            let set = SignatureSet::new(
+                signature,
+                vec![
+                    SignedMessage::new(first.to_vec(), message(0)),
+                    SignedMessage::new(second.to_vec(), message(0)),
+                ],
+            );
+            assert_eq!(set.verify(), Ok(is_valid));
+            assert_eq!(verify_signature_sets(vec![set].into_iter()), is_valid);
+
+            // The general multiple-message path.
+            assert_eq!(
+                signature.verify_multiple(&[&message(0)[..]], &[&aggregate]),
+                is_valid
+            );
+        }
+    }
+
+    #[test]
+    fn verify_rejects_malformed_sets() {
+        let keypair = Keypair::random();
+        let signature = Signature::new(&message(0), &keypair.sk);
+
+        let valid = SignatureSet::single(&signature, keypair.pk.g1_ref(), message(0));
+        assert_eq!(valid.verify(), Ok(true));
+
+        let wrong_message = SignatureSet::single(&signature, keypair.pk.g1_ref(), message(1));
+        assert_eq!(wrong_message.verify(), Ok(false));
+
+        let no_messages = SignatureSet::new(&signature, vec![]);
+        assert_eq!(no_messages.verify(), Err(SignatureSetError::NoMessages));
+        assert!(!no_messages.is_valid());
+
+        let bad_point = SignatureSet::new(
+            &signature,
+            vec![
+                SignedMessage::new(vec![keypair.pk.g1_ref()], message(0)),
+                SignedMessage::with_aggregate(
+                    vec![keypair.pk.g1_ref()],
+                    Cow::Owned(G1Point::new()),
+                    message(1),
+                ),
+            ],
+        );
+        assert_eq!(
+            bad_point.verify(),
+            Err(SignatureSetError::AggregatePublicKeyAtInfinity { index: 1 })
+        );
+        assert!(!bad_point.is_valid());
+
+        let no_signing_keys =
+            SignatureSet::new(&signature, vec![SignedMessage::new(vec![], message(0))]);
+        assert_eq!(
+            no_signing_keys.verify(),
+            Err(SignatureSetError::AggregatePublicKeyAtInfinity { index: 0 })
+        );
+    }
+
+    #[test]
+    fn validate_structure() {
+        let keypair = Keypair::random();
+        let signature = Signature::new(&message(0), &keypair.sk);
+
+        let valid = SignatureSet::single(&signature, keypair.pk.g1_ref(), message(0));
+        assert_eq!(valid.validate_structure(), Ok(()));
+
+        let no_messages = SignatureSet::new(&signature, vec![]);
+        assert_eq!(
+            no_messages.validate_structure(),
+            Err(SignatureSetError::NoMessages)
+        );
+
+        let no_signing_keys = SignatureSet::new(
+            &signature,
+            vec![
+                SignedMessage::new(vec![keypair.pk.g1_ref()], message(0)),
+                SignedMessage::new(vec![], message(1)),
+            ],
+        );
+        assert_eq!(
+            no_signing_keys.validate_structure(),
+            Err(SignatureSetError::AggregatePublicKeyAtInfinity { index: 1 })
+        );
+
+        // An aggregate without its individual keys is well-formed.
+        let aggregate_only = SignatureSet::new(
+            &signature,
+            vec![SignedMessage::from_aggregate(
+                keypair.pk.g1_ref(),
+                message(0),
+            )],
+        );
+        assert_eq!(aggregate_only.validate_structure(), Ok(()));
+
+        // A correctly signed message of the wrong length is rejected by `verify` too.
+        let short_message = vec![1; MESSAGE_LEN - 1];
+        let short_signature = Signature::new(&short_message, &keypair.sk);
+        let short = SignatureSet::single(&short_signature, keypair.pk.g1_ref(), short_message);
+        let error = SignatureSetError::InvalidMessageLength {
+            index: 0,
+            len: MESSAGE_LEN - 1,
+        };
+        assert_eq!(short.validate_structure(), Err(error.clone()));
+        assert_eq!(short.verify(), Err(error));
+        assert!(!short.is_valid());
+
+        // Batch verification rejects it too, even alongside a valid set.
+        assert!(!verify_signature_sets(vec![short.clone()].into_iter()));
+        assert!(!verify_signature_sets(vec![valid, short].into_iter()));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_aggregate_matches_sequential() {
+        let keypairs = (0..PARALLEL_AGGREGATION_THRESHOLD + 1)
+            .map(|_| Keypair::random())
+            .collect::<Vec<_>>();
+        let keys = keypairs.iter().map(|kp| kp.pk.g1_ref()).collect::<Vec<_>>();
+
+        let as_bytes = |point| milagro_bls::AggregatePublicKey { point }.as_bytes();
+        assert_eq!(
+            as_bytes(aggregate_public_keys_parallel(&keys)),
+            as_bytes(aggregate_public_keys_sequential(&keys))
+        );
+
+        let mut signature = AggregateSignature::new();
+        keypairs
+            .iter()
+            .for_each(|kp| signature.add(&Signature::new(&message(0), &kp.sk)));
+
+        let set = SignatureSet::new(&signature, vec![SignedMessage::new(keys, message(0))]);
+        assert!(set.is_valid());
+        assert!(verify_signature_sets(vec![set].into_iter()));
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_sequential() {
+        let keypairs = (0..8).map(|_| Keypair::random()).collect::<Vec<_>>();
+
+        let mut signatures = keypairs
+            .iter()
+            .enumerate()
+            .map(|(i, keypair)| Signature::new(&message(i), &keypair.sk))
+            .collect::<Vec<_>>();
+
+        let sets = signature_sets(&signatures, &keypairs);
+        assert!(verify_signature_sets_parallel(&sets));
+        assert!(verify_signature_sets(sets.into_iter()));
+
+        // Replace one signature with a signature across the wrong message.
+        signatures[3] = Signature::new(&message(4), &keypairs[3].sk);
+
+        let sets = signature_sets(&signatures, &keypairs);
+        assert!(!verify_signature_sets_parallel(&sets));
+        assert!(!verify_signature_sets(sets.into_iter()));
+    }
+}
+
+#[cfg(all(test, feature = "fake_crypto"))]
+mod fake_crypto_tests {
+    use super::*;
+    use crate::{Keypair, BLS_SIG_BYTE_SIZE};
+
+    #[test]
+    fn bogus_signature_is_valid() {
+        let keypair = Keypair::random();
+        let signature = Signature::from_bytes(&[42; BLS_SIG_BYTE_SIZE]).unwrap();
+
+        let set = SignatureSet::single(&signature, keypair.pk.g1_ref(), vec![1; 32]);
+        assert!(set.is_valid());
+        assert_eq!(set.verify(), Ok(true));
+
+        let empty = SignatureSet::new(&signature, vec![]);
+        assert!(empty.is_valid());
+        assert_eq!(empty.verify(), Ok(true));
+    }
+}
@@ -2,6 +2,7 @@ use crate::cache_arena;
 use crate::{Error, Hash256};
 use eth2_hashing::{hash32_concat, ZERO_HASHES};
 use ssz_derive::{Decode, Encode};
+use std::convert::TryInto;
 use tree_hash::BYTES_PER_CHUNK;
 
 type CacheArena = cache_arena::CacheArena<Hash256>;
@@ -18,6 +19,36 @@ pub struct TreeHashCache {
     /// The leaves are contained in `self.layers[self.depth]`, and each other layer `i`
     /// contains the parents of the nodes in layer `i + 1`.
     layers: Vec<CacheArenaAllocation>,
+    /// Statistics from the last update of the root, which are neither encoded nor compared.
+    #[cfg(feature = "cache_metrics")]
+    #[ssz(skip_serializing)]
+    #[ssz(skip_deserializing)]
+    last_stats: LastStats,
+}
+
+/// Counts of the work done by the last call to `TreeHashCache::update_merkle_root` (including
+/// via `recalculate_merkle_root` or `CachedTreeHash::recalculate_tree_hash_root`).
+#[cfg(feature = "cache_metrics")]
+#[derive(Debug, PartialEq, Clone, Copy, Default)]
+pub struct CacheStats {
+    /// The number of leaves which were dirty, and so were hashed up the tree.
+    pub leaves_recomputed: usize,
+    /// The number of leaves which were unchanged, and so were not hashed.
+    pub leaves_reused: usize,
+    /// The number of nodes above the leaves (including the root) which were recomputed.
+    pub nodes_recomputed: usize,
+}
+
+/// Wrapper for the `CacheStats` of a `TreeHashCache`, so that they don't affect its equality.
+#[cfg(feature = "cache_metrics")]
+#[derive(Debug, Clone, Default)]
+struct LastStats(CacheStats);
+
+#[cfg(feature = "cache_metrics")]
+impl PartialEq for LastStats {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
 impl TreeHashCache {
@@ -44,6 +75,8 @@ impl TreeHashCache {
             initialized: false,
             depth,
             layers,
+            #[cfg(feature = "cache_metrics")]
+            last_stats: LastStats::default(),
         }
     }
 
@@ -58,6 +91,10 @@ impl TreeHashCache {
     }
 
     /// Phase 1 of the algorithm: compute the indices of all dirty leaves.
+    ///
+    /// If there are fewer `leaves` than are currently cached, the tree is truncated first. If the
+    /// tree has been truncated since the root was last computed, the last leaf is marked dirty so
+    /// that the path from it to the root is recomputed.
     pub fn update_leaves(
         &mut self,
         arena: &mut CacheArena,
@@ -65,12 +102,13 @@ impl TreeHashCache {
     ) -> Result<Vec<usize>, Error> {
         let new_leaf_count = leaves.len();
 
-        if new_leaf_count < self.leaves().len(arena)? {
-            return Err(Error::CannotShrink);
-        } else if new_leaf_count > 2usize.pow(self.depth as u32) {
+        if new_leaf_count > 2usize.pow(self.depth as u32) {
             return Err(Error::TooManyLeaves);
         }
 
+        self.truncate(arena, new_leaf_count)?;
+        let stale_leaf = self.stale_right_edge(arena)?;
+
         // Update the existing leaves
         let mut dirty = self
             .leaves()
@@ -87,6 +125,12 @@ impl TreeHashCache {
             })
             .collect::<Vec<_>>();
 
+        if let Some(i) = stale_leaf {
+            if dirty.last() != Some(&i) {
+                dirty.push(i);
+            }
+        }
+
         // Push the rest of the new leaves (if any)
         dirty.extend(self.leaves().len(arena)?..new_leaf_count);
         self.leaves()
@@ -95,6 +139,62 @@ impl TreeHashCache {
         Ok(dirty)
     }
 
+    /// Alternative to `update_leaves` for callers which know which leaves may have changed.
+    ///
+    /// Each of `leaves` is an `(index, leaf)` pair, in ascending order of index, which either
+    /// replaces an existing leaf or is appended immediately after the last leaf. Leaves which
+    /// are not given are assumed to be up-to-date, so the cache must already be initialized.
+    ///
+    /// Returns the indices of the dirty leaves, for use with `update_merkle_root`.
+    pub fn update_leaves_at(
+        &mut self,
+        arena: &mut CacheArena,
+        leaves: impl Iterator<Item = (usize, [u8; BYTES_PER_CHUNK])>,
+    ) -> Result<Vec<usize>, Error> {
+        if !self.initialized {
+            return Err(Error::CacheInconsistent);
+        }
+
+        let max_leaves = 2usize.pow(self.depth as u32);
+        let stale_leaf = self.stale_right_edge(arena)?;
+        let mut previous = None;
+        let mut dirty = vec![];
+
+        for (i, new_leaf) in leaves {
+            if previous.map_or(false, |previous| i <= previous) {
+                return Err(Error::CacheInconsistent);
+            }
+            previous = Some(i);
+
+            let len = self.leaves().len(arena)?;
+            if i < len {
+                let leaf = self
+                    .leaves()
+                    .get_mut(arena, i)?
+                    .ok_or(Error::CacheInconsistent)?;
+                if leaf.as_bytes() != new_leaf {
+                    leaf.assign_from_slice(&new_leaf);
+                    dirty.push(i);
+                }
+            } else if i == len && len < max_leaves {
+                self.leaves().push(arena, Hash256::from_slice(&new_leaf))?;
+                dirty.push(i);
+            } else if i == len {
+                return Err(Error::TooManyLeaves);
+            } else {
+                return Err(Error::CacheInconsistent);
+            }
+        }
+
+        if let Some(i) = stale_leaf {
+            if let Err(position) = dirty.binary_search(&i) {
+                dirty.insert(position, i);
+            }
+        }
+
+        Ok(dirty)
+    }
+
     /// Phase 2: propagate changes upwards from the leaves of the tree, and compute the root.
     ///
     /// Returns an error if `dirty_indices` is inconsistent with the cache.
@@ -103,6 +203,16 @@ impl TreeHashCache {
         arena: &mut CacheArena,
         mut dirty_indices: Vec<usize>,
     ) -> Result<Hash256, Error> {
+        #[cfg(feature = "cache_metrics")]
+        {
+            let leaf_count = self.layers[self.depth].len(arena)?;
+            self.last_stats.0 = CacheStats {
+                leaves_recomputed: dirty_indices.len(),
+                leaves_reused: leaf_count.saturating_sub(dirty_indices.len()),
+                nodes_recomputed: 0,
+            };
+        }
+
         if dirty_indices.is_empty() {
             return Ok(self.root(arena));
         }
@@ -112,6 +222,11 @@ impl TreeHashCache {
         while depth > 0 {
             let new_dirty_indices = lift_dirty(&dirty_indices);
 
+            #[cfg(feature = "cache_metrics")]
+            {
+                self.last_stats.0.nodes_recomputed += new_dirty_indices.len();
+            }
+
             for &idx in &new_dirty_indices {
                 let left_idx = 2 * idx;
                 let right_idx = left_idx + 1;
@@ -151,11 +266,92 @@ impl TreeHashCache {
 
     /// Get the root of this cache, without doing any updates/computation.
     pub fn root(&self, arena: &CacheArena) -> Hash256 {
+        Hash256::from(*self.root_ref(arena))
+    }
+
+    /// Returns a reference to the root of the tree, avoiding the copy made by `root`.
+    pub fn root_ref<'a>(&self, arena: &'a CacheArena) -> &'a [u8; BYTES_PER_CHUNK] {
         self.layers[0]
             .get(arena, 0)
             .expect("cached tree should have a root layer")
-            .copied()
-            .unwrap_or_else(|| Hash256::from_slice(&ZERO_HASHES[self.depth]))
+            .map(Hash256::as_fixed_bytes)
+            .unwrap_or_else(|| {
+                ZERO_HASHES[self.depth][..]
+                    .try_into()
+                    .expect("zero hashes should be BYTES_PER_CHUNK bytes")
+            })
+    }
+
+    /// Returns the Merkle branch for the leaf at `index`, ordered from the leaf's sibling up to
+    /// the child of the root.
+    ///
+    /// The cache must be initialized and must not have been truncated since the root was last
+    /// computed, otherwise the interior nodes may be stale.
+    pub fn proof(&self, arena: &CacheArena, index: usize) -> Result<Vec<Hash256>, Error> {
+        if !self.initialized
+            || index >= self.layers[self.depth].len(arena)?
+            || self.stale_right_edge(arena)?.is_some()
+        {
+            return Err(Error::CacheInconsistent);
+        }
+
+        (1..=self.depth)
+            .rev()
+            .map(|layer| {
+                let sibling = (index >> (self.depth - layer)) ^ 1;
+                Ok(self.layers[layer]
+                    .get(arena, sibling)?
+                    .copied()
+                    .unwrap_or_else(|| Hash256::from_slice(&ZERO_HASHES[self.depth - layer])))
+            })
+            .collect()
+    }
+
+    /// Drop the leaves beyond the first `new_leaf_count`, along with the nodes above them.
+    ///
+    /// The right-most node of each layer which has lost some of the leaves beneath it is dropped
+    /// too, so that the next call to `recalculate_merkle_root` (or `update_merkle_root` via
+    /// `update_leaves_at`) recomputes the path from the new last leaf to the root against padding.
+    /// Has no effect if there are already `new_leaf_count` leaves or fewer.
+    pub fn truncate(&mut self, arena: &mut CacheArena, new_leaf_count: usize) -> Result<(), Error> {
+        if new_leaf_count >= self.leaves().len(arena)? {
+            return Ok(());
+        }
+
+        let depth = self.depth;
+        self.layers
+            .iter()
+            .enumerate()
+            .try_for_each(|(layer, nodes)| {
+                let mut len = nodes_per_layer(layer, depth, new_leaf_count);
+                if layer < depth && new_leaf_count % (1 << (depth - layer)) != 0 {
+                    len -= 1;
+                }
+                nodes.truncate(arena, len)
+            })?;
+        Ok(())
+    }
+
+    /// Returns the index of the last leaf if the path from it to the root is missing nodes due to
+    /// a call to `truncate`.
+    fn stale_right_edge(&self, arena: &CacheArena) -> Result<Option<usize>, Error> {
+        let leaf_count = self.layers[self.depth].len(arena)?;
+        if leaf_count == 0 {
+            return Ok(None);
+        }
+
+        for layer in 0..self.depth {
+            if self.layers[layer].len(arena)? < nodes_per_layer(layer, self.depth, leaf_count) {
+                return Ok(Some(leaf_count - 1));
+            }
+        }
+        Ok(None)
+    }
+
+    /// Returns the counts of the work done by the last update of the root.
+    #[cfg(feature = "cache_metrics")]
+    pub fn last_stats(&self) -> CacheStats {
+        self.last_stats.0
     }
 
     pub fn leaves(&mut self) -> &mut CacheArenaAllocation {
@@ -210,6 +210,18 @@ impl<T: Encode + Decode> CacheArenaAllocation<T> {
         Ok(())
     }
 
+    /// Shorten the allocation in `arena` to `len` values, dropping the rest. Has no effect if the
+    /// allocation is already `len` or shorter.
+    ///
+    /// An error is returned if this allocation is not known to the given `arena`.
+    pub fn truncate(&self, arena: &mut CacheArena<T>, len: usize) -> Result<(), Error> {
+        let current_len = arena.len(self.alloc_id)?;
+        if len < current_len {
+            arena.splice_forgetful(self.alloc_id, len..current_len, vec![])?;
+        }
+        Ok(())
+    }
+
     /// Push `item` to the end of the current allocation in `arena`.
     ///
     /// An error is returned if this allocation is not known to the given `arena`.
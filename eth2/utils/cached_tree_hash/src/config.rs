@@ -0,0 +1,45 @@
+#[cfg(feature = "rayon")]
+use rayon::{ThreadPool, ThreadPoolBuildError, ThreadPoolBuilder};
+#[cfg(feature = "rayon")]
+use std::sync::Arc;
+
+/// Determines how `CachedTreeHash::recalculate_tree_hash_root_with_config` schedules its work.
+///
+/// By default caches are updated serially, on the calling thread. With the `rayon` feature
+/// enabled, a thread pool may be provided to update independent caches (e.g., the value caches of
+/// a `MultiTreeHashCache`) in parallel, so that the number of threads used for hashing can be
+/// capped rather than using the global rayon pool.
+#[derive(Debug, Clone, Default)]
+pub struct CachedTreeHashConfig {
+    #[cfg(feature = "rayon")]
+    thread_pool: Option<Arc<ThreadPool>>,
+}
+
+impl CachedTreeHashConfig {
+    /// Update caches serially, on the calling thread.
+    pub fn serial() -> Self {
+        Self::default()
+    }
+
+    /// Update caches in parallel on `thread_pool`.
+    #[cfg(feature = "rayon")]
+    pub fn with_thread_pool(thread_pool: Arc<ThreadPool>) -> Self {
+        Self {
+            thread_pool: Some(thread_pool),
+        }
+    }
+
+    /// Update caches in parallel on a new thread pool of `num_threads` threads.
+    #[cfg(feature = "rayon")]
+    pub fn with_num_threads(num_threads: usize) -> Result<Self, ThreadPoolBuildError> {
+        let thread_pool = ThreadPoolBuilder::new().num_threads(num_threads).build()?;
+        Ok(Self::with_thread_pool(Arc::new(thread_pool)))
+    }
+
+    /// Returns the thread pool on which caches are updated, or `None` if they are updated
+    /// serially.
+    #[cfg(feature = "rayon")]
+    pub fn thread_pool(&self) -> Option<&ThreadPool> {
+        self.thread_pool.as_deref()
+    }
+}
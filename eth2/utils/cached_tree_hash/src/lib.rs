@@ -1,13 +1,21 @@
 mod cache;
 mod cache_arena;
+mod config;
 mod impls;
+mod multi_cache;
 #[cfg(test)]
 mod test;
+mod versioned;
 
 pub type CacheArena = cache_arena::CacheArena<Hash256>;
 
+#[cfg(feature = "cache_metrics")]
+pub use crate::cache::CacheStats;
 pub use crate::cache::TreeHashCache;
+pub use crate::config::CachedTreeHashConfig;
 pub use crate::impls::int_log;
+pub use crate::multi_cache::MultiTreeHashCache;
+pub use crate::versioned::{decode_versioned, encode_versioned};
 use ethereum_types::H256 as Hash256;
 use tree_hash::TreeHash;
 
@@ -15,14 +23,17 @@ use tree_hash::TreeHash;
 pub enum Error {
     /// Attempting to provide more than 2^depth leaves to a Merkle tree is disallowed.
     TooManyLeaves,
-    /// Shrinking a Merkle tree cache by providing it with less leaves than it currently has is
-    /// disallowed (for simplicity).
-    CannotShrink,
     /// Cache is inconsistent with the list of dirty indices provided.
     CacheInconsistent,
     CacheArenaError(cache_arena::Error),
     /// Unable to find left index in Merkle tree.
     MissingLeftIdx(usize),
+    /// A serialized cache has a different layout version to the one expected.
+    InvalidCacheVersion {
+        found: u16,
+        expected: u16,
+    },
+    SszDecodeError(ssz::DecodeError),
 }
 
 impl From<cache_arena::Error> for Error {
@@ -31,6 +42,12 @@ impl From<cache_arena::Error> for Error {
     }
 }
 
+impl From<ssz::DecodeError> for Error {
+    fn from(e: ssz::DecodeError) -> Error {
+        Error::SszDecodeError(e)
+    }
+}
+
 /// Trait for types which can make use of a cache to accelerate calculation of their tree hash root.
 pub trait CachedTreeHash<Cache>: TreeHash {
     /// Create a new cache appropriate for use with values of this type.
@@ -42,4 +59,17 @@ pub trait CachedTreeHash<Cache>: TreeHash {
         arena: &mut CacheArena,
         cache: &mut Cache,
     ) -> Result<Hash256, Error>;
+
+    /// Identical to `recalculate_tree_hash_root`, except that the work is scheduled according to
+    /// `config` (e.g., on a particular thread pool).
+    ///
+    /// Types without independent sub-caches to update concurrently may ignore `config`.
+    fn recalculate_tree_hash_root_with_config(
+        &self,
+        arena: &mut CacheArena,
+        cache: &mut Cache,
+        _config: &CachedTreeHashConfig,
+    ) -> Result<Hash256, Error> {
+        self.recalculate_tree_hash_root(arena, cache)
+    }
 }
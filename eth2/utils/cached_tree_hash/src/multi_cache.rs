@@ -0,0 +1,391 @@
+#[cfg(feature = "cache_metrics")]
+use crate::CacheStats;
+use crate::{
+    decode_versioned, encode_versioned, int_log, CacheArena, CachedTreeHash, CachedTreeHashConfig,
+    Error, Hash256, TreeHashCache,
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
+use ssz::{Decode, Encode};
+use ssz_derive::{Decode, Encode};
+use ssz_types::{typenum::Unsigned, VariableList};
+use tree_hash::{mix_in_length, BYTES_PER_CHUNK};
+
+/// The number of value caches which share a single `CacheArena`.
+///
+/// Each arena is updated by one thread at a time, so with the `rayon` feature enabled a list is
+/// hashed on at most `ceil(len / VALUES_PER_ARENA)` threads.
+///
+/// The value matches `VALIDATORS_PER_ARENA` in the `BeaconState` cache. Every arena is a separate
+/// allocation which is persisted alongside its caches, so a large value keeps the number of arenas
+/// (and their overhead) small for the lists which benefit most from the cache: those with many
+/// thousands of values, which are still split across enough arenas to keep several cores busy.
+/// The trade-off is that a list of fewer than 4,096 values uses a single arena and so is hashed
+/// serially, even with the `rayon` feature enabled.
+///
+/// Do not set to 0.
+const VALUES_PER_ARENA: usize = 4_096;
+
+/// Cache for a `VariableList` of values which themselves have caches of type `C`.
+///
+/// Stores a cache for each value, along with a cache for the Merkle tree of the list (whose leaves
+/// are the roots of the values). The value caches may themselves be `MultiTreeHashCache`s, so
+/// lists can be nested to any depth.
+///
+/// With the `rayon` feature enabled, the value caches are updated in parallel.
+#[derive(Debug, PartialEq, Clone, Default, Encode, Decode)]
+pub struct MultiTreeHashCache<C: Encode + Decode> {
+    list_cache: TreeHashCache,
+    /// The cache for each value, split into groups of `VALUES_PER_ARENA` with an arena for each.
+    value_caches: Vec<(CacheArena, Vec<C>)>,
+}
+
+impl<C: Encode + Decode> MultiTreeHashCache<C> {
+    /// The version of the layout written by `as_bytes`.
+    ///
+    /// Bump this whenever the layout of `Self` (or of the caches it contains) changes, so that
+    /// stale serialized caches are rejected.
+    pub const VERSION: u16 = 1;
+
+    /// Returns the SSZ encoding of `self`, prefixed with `Self::VERSION`.
+    pub fn as_bytes(&self) -> Vec<u8> {
+        encode_versioned(Self::VERSION, self)
+    }
+
+    /// Decodes bytes produced by `as_bytes`.
+    ///
+    /// Returns `Error::InvalidCacheVersion` if the bytes were written with a different `VERSION`.
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, Error> {
+        decode_versioned(Self::VERSION, bytes)
+    }
+}
+
+impl<C: Encode + Decode + Send> MultiTreeHashCache<C> {
+    /// Returns the number of values cached.
+    fn len(&self) -> usize {
+        self.value_caches.last().map_or(0, |last| {
+            // Subtraction cannot underflow because `.last()` ensures the `.len() > 0`.
+            (self.value_caches.len() - 1) * VALUES_PER_ARENA + last.1.len()
+        })
+    }
+
+    /// Drop the caches of values beyond the first `len`.
+    ///
+    /// The nodes of dropped caches are only reclaimed once their entire arena is dropped.
+    fn truncate_values(&mut self, len: usize) {
+        if len < self.len() {
+            let num_arenas = (len + VALUES_PER_ARENA - 1) / VALUES_PER_ARENA;
+            self.value_caches.truncate(num_arenas);
+            if let Some((_, caches)) = self.value_caches.last_mut() {
+                // Subtraction cannot underflow because `.last_mut()` ensures `num_arenas > 0`.
+                caches.truncate(len - (num_arenas - 1) * VALUES_PER_ARENA);
+            }
+        }
+    }
+
+    /// Returns the counts of the work done by the last update of the list's root, where each leaf
+    /// is the root of a value.
+    #[cfg(feature = "cache_metrics")]
+    pub fn last_stats(&self) -> CacheStats {
+        self.list_cache.last_stats()
+    }
+
+    /// Drop the caches of values beyond the first `len`, and truncate the list cache to match.
+    ///
+    /// The remaining value caches are untouched, so a subsequent call to
+    /// `recalculate_dirty_tree_hash_root` only needs to update the values which have changed.
+    pub fn truncate(&mut self, arena: &mut CacheArena, len: usize) -> Result<(), Error> {
+        self.truncate_values(len);
+        self.list_cache.truncate(arena, len)
+    }
+
+    /// Drop the caches of values beyond the end of `values` and create caches for any values
+    /// which are not yet cached.
+    fn resize<T: CachedTreeHash<C>>(&mut self, values: &[T]) {
+        self.truncate_values(values.len());
+
+        let num_cached = self.len();
+        for value in values.iter().skip(num_cached) {
+            if self
+                .value_caches
+                .last()
+                .map_or(true, |last| last.1.len() >= VALUES_PER_ARENA)
+            {
+                self.value_caches.push((CacheArena::default(), vec![]));
+            }
+
+            let (arena, caches) = self
+                .value_caches
+                .last_mut()
+                .expect("Cannot be empty, an arena was pushed if required.");
+            caches.push(value.new_tree_hash_cache(arena));
+        }
+    }
+
+    /// Update the cache for each of `values`, returning their roots in order.
+    ///
+    /// The caches must already have been resized to suit `values`. Without a `config`, each arena
+    /// is updated on the current rayon thread pool if the `rayon` feature is enabled. With a
+    /// `config`, the arenas are only updated in parallel if it has a thread pool (which the caller
+    /// must have installed), and each value is updated according to `config`.
+    fn value_roots<T: CachedTreeHash<C> + Sync>(
+        &mut self,
+        values: &[T],
+        config: Option<&CachedTreeHashConfig>,
+    ) -> Result<Vec<[u8; BYTES_PER_CHUNK]>, Error> {
+        let arena_roots = |(arena_index, (arena, caches)): (usize, &mut (CacheArena, Vec<C>))| {
+            values
+                .iter()
+                .skip(arena_index * VALUES_PER_ARENA)
+                .zip(caches.iter_mut())
+                .map(|(value, cache)| {
+                    match config {
+                        Some(config) => {
+                            value.recalculate_tree_hash_root_with_config(arena, cache, config)
+                        }
+                        None => value.recalculate_tree_hash_root(arena, cache),
+                    }
+                    .map(Hash256::to_fixed_bytes)
+                })
+                .collect::<Result<Vec<_>, _>>()
+        };
+
+        #[cfg(feature = "rayon")]
+        {
+            if config.map_or(true, |config| config.thread_pool().is_some()) {
+                let roots = self
+                    .value_caches
+                    .par_iter_mut()
+                    .enumerate()
+                    .map(&arena_roots)
+                    .collect::<Result<Vec<_>, _>>()?;
+                return Ok(roots.into_iter().flatten().collect());
+            }
+        }
+
+        let roots = self
+            .value_caches
+            .iter_mut()
+            .enumerate()
+            .map(&arena_roots)
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(roots.into_iter().flatten().collect())
+    }
+
+    /// Update the cache for all of `values`, returning the root of the list.
+    ///
+    /// See `value_roots` for how the values are updated with and without a `config`.
+    fn recalculate_root<T: CachedTreeHash<C> + Sync>(
+        &mut self,
+        arena: &mut CacheArena,
+        values: &[T],
+        config: Option<&CachedTreeHashConfig>,
+    ) -> Result<Hash256, Error> {
+        self.resize(values);
+        let value_roots = self.value_roots(values, config)?;
+
+        // Pipe the value roots into the list cache, then mix in the length.
+        let list_root = self
+            .list_cache
+            .recalculate_merkle_root(arena, value_roots.into_iter())?;
+
+        Ok(Hash256::from_slice(&mix_in_length(
+            list_root.as_bytes(),
+            values.len(),
+        )))
+    }
+
+    /// Recalculate the root of the list `values`, given that only the values at `dirty_indices`
+    /// (and any values which are not yet cached) have changed since the cache was last updated.
+    ///
+    /// Unlike `recalculate_tree_hash_root`, which updates the cache of every value, only the
+    /// caches of the dirty and new values are updated. If `values` has shrunk, the caches of the
+    /// values beyond its end are dropped. If the cache has not yet been initialized, all of the
+    /// values are updated instead.
+    pub fn recalculate_dirty_tree_hash_root<T: CachedTreeHash<C> + Sync>(
+        &mut self,
+        arena: &mut CacheArena,
+        values: &[T],
+        dirty_indices: &[usize],
+    ) -> Result<Hash256, Error> {
+        if !self.list_cache.initialized {
+            return self.recalculate_root(arena, values, None);
+        }
+
+        self.truncate(arena, values.len())?;
+        let num_cached = self.len();
+        self.resize(values);
+
+        let mut indices = dirty_indices
+            .iter()
+            .copied()
+            .filter(|&i| i < num_cached)
+            .collect::<Vec<_>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices.extend(num_cached..values.len());
+
+        let value_roots = indices
+            .into_iter()
+            .map(|i| {
+                let (value_arena, caches) = &mut self.value_caches[i / VALUES_PER_ARENA];
+                let root = values[i]
+                    .recalculate_tree_hash_root(value_arena, &mut caches[i % VALUES_PER_ARENA])?;
+                Ok((i, root.to_fixed_bytes()))
+            })
+            .collect::<Result<Vec<_>, Error>>()?;
+
+        let dirty = self
+            .list_cache
+            .update_leaves_at(arena, value_roots.into_iter())?;
+        let list_root = self.list_cache.update_merkle_root(arena, dirty)?;
+
+        Ok(Hash256::from_slice(&mix_in_length(
+            list_root.as_bytes(),
+            values.len(),
+        )))
+    }
+}
+
+impl<T, N, C> CachedTreeHash<MultiTreeHashCache<C>> for VariableList<T, N>
+where
+    T: CachedTreeHash<C> + Sync,
+    N: Unsigned,
+    C: Encode + Decode + Send,
+{
+    fn new_tree_hash_cache(&self, arena: &mut CacheArena) -> MultiTreeHashCache<C> {
+        let mut cache = MultiTreeHashCache {
+            list_cache: TreeHashCache::new(arena, int_log(N::to_usize()), self.len()),
+            value_caches: vec![],
+        };
+        cache.resize(self);
+        cache
+    }
+
+    fn recalculate_tree_hash_root(
+        &self,
+        arena: &mut CacheArena,
+        cache: &mut MultiTreeHashCache<C>,
+    ) -> Result<Hash256, Error> {
+        cache.recalculate_root(arena, self, None)
+    }
+
+    /// Updates the value caches on the thread pool of `config` if it has one, otherwise serially.
+    ///
+    /// Nested caches are updated within the same thread pool, or serially.
+    fn recalculate_tree_hash_root_with_config(
+        &self,
+        arena: &mut CacheArena,
+        cache: &mut MultiTreeHashCache<C>,
+        config: &CachedTreeHashConfig,
+    ) -> Result<Hash256, Error> {
+        let values = &self[..];
+
+        #[cfg(feature = "rayon")]
+        {
+            if let Some(thread_pool) = config.thread_pool() {
+                return thread_pool.install(|| cache.recalculate_root(arena, values, Some(config)));
+            }
+        }
+
+        cache.recalculate_root(arena, values, Some(config))
+    }
+}
+
+#[cfg(all(test, feature = "rayon"))]
+mod test {
+    use super::*;
+    use rand::{Rng, SeedableRng};
+    use rand_xorshift::XorShiftRng;
+    use ssz_types::typenum::{U16384, U4};
+    use tree_hash::TreeHash;
+
+    type List4 = VariableList<Hash256, U4>;
+    type LargeList = VariableList<List4, U16384>;
+
+    fn random_value<R: Rng>(rng: &mut R) -> List4 {
+        let len = rng.gen_range(0, 5);
+        List4::new(
+            (0..len)
+                .map(|_| Hash256::from_low_u64_le(rng.gen()))
+                .collect(),
+        )
+        .unwrap()
+    }
+
+    #[test]
+    fn parallel_matches_serial() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let arena = &mut CacheArena::default();
+
+        let mut list = LargeList::new(
+            (0..3 * VALUES_PER_ARENA + 5)
+                .map(|_| random_value(&mut rng))
+                .collect(),
+        )
+        .unwrap();
+        let mut serial: MultiTreeHashCache<TreeHashCache> = list.new_tree_hash_cache(arena);
+        let mut parallel = serial.clone();
+
+        for _ in 0..4 {
+            for _ in 0..64 {
+                let i = rng.gen_range(0, list.len());
+                list[i] = random_value(&mut rng);
+            }
+            for _ in 0..rng.gen_range(0, 16) {
+                list.push(random_value(&mut rng)).unwrap();
+            }
+
+            serial.resize(&list);
+            parallel.resize(&list);
+
+            assert_eq!(
+                serial
+                    .value_roots(&list, Some(&CachedTreeHashConfig::serial()))
+                    .unwrap(),
+                parallel.value_roots(&list, None).unwrap()
+            );
+        }
+
+        assert_eq!(
+            list.recalculate_tree_hash_root(arena, &mut parallel)
+                .unwrap(),
+            Hash256::from_slice(&list.tree_hash_root())
+        );
+    }
+
+    #[test]
+    fn thread_pool_matches_global_pool() {
+        let mut rng = XorShiftRng::from_seed([42; 16]);
+        let arena = &mut CacheArena::default();
+
+        let list = LargeList::new(
+            (0..2 * VALUES_PER_ARENA + 3)
+                .map(|_| random_value(&mut rng))
+                .collect(),
+        )
+        .unwrap();
+        let expected = Hash256::from_slice(&list.tree_hash_root());
+
+        let mut cache: MultiTreeHashCache<TreeHashCache> = list.new_tree_hash_cache(arena);
+        assert_eq!(
+            list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+            expected
+        );
+
+        for config in vec![
+            CachedTreeHashConfig::serial(),
+            CachedTreeHashConfig::with_num_threads(1).unwrap(),
+            CachedTreeHashConfig::with_num_threads(4).unwrap(),
+        ] {
+            let mut cache: MultiTreeHashCache<TreeHashCache> = list.new_tree_hash_cache(arena);
+            assert_eq!(
+                list.recalculate_tree_hash_root_with_config(arena, &mut cache, &config)
+                    .unwrap(),
+                expected,
+                "config: {:?}",
+                config
+            );
+        }
+    }
+}
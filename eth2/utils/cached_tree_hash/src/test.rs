@@ -1,11 +1,14 @@
 use crate::impls::hash256_iter;
-use crate::{CacheArena, CachedTreeHash, Error, Hash256, TreeHashCache};
+use crate::{
+    encode_versioned, CacheArena, CachedTreeHash, Error, Hash256, MultiTreeHashCache, TreeHashCache,
+};
 use eth2_hashing::ZERO_HASHES;
 use quickcheck_macros::quickcheck;
 use ssz_types::{
-    typenum::{Unsigned, U16, U255, U256, U257},
+    typenum::{Unsigned, U16, U255, U256, U257, U4, U8},
     FixedVector, VariableList,
 };
+use std::sync::atomic::{AtomicUsize, Ordering};
 use tree_hash::TreeHash;
 
 fn int_hashes(start: u64, end: u64) -> Vec<Hash256> {
@@ -15,6 +18,19 @@ fn int_hashes(start: u64, end: u64) -> Vec<Hash256> {
 type List16 = VariableList<Hash256, U16>;
 type Vector16 = FixedVector<Hash256, U16>;
 type Vector16u64 = FixedVector<u64, U16>;
+type List4 = VariableList<Hash256, U4>;
+type NestedList = VariableList<List4, U8>;
+type TwiceNestedList = VariableList<NestedList, U4>;
+
+fn nested_list(lens: &[u64]) -> NestedList {
+    NestedList::new(
+        lens.iter()
+            .enumerate()
+            .map(|(i, &len)| List4::new(int_hashes(i as u64, i as u64 + len)).unwrap())
+            .collect(),
+    )
+    .unwrap()
+}
 
 #[test]
 fn max_leaves() {
@@ -39,20 +55,101 @@ fn max_leaves() {
 }
 
 #[test]
-fn cannot_shrink() {
+fn shrink() {
     let arena = &mut CacheArena::default();
-    let init_len = 12;
-    let list1 = List16::new(int_hashes(0, init_len)).unwrap();
-    let list2 = List16::new(int_hashes(0, init_len - 1)).unwrap();
+    let mut list = List16::new(int_hashes(0, 3)).unwrap();
+    let mut cache = list.new_tree_hash_cache(arena);
+    list.recalculate_tree_hash_root(arena, &mut cache).unwrap();
+
+    // Grow the list, then shrink it to every length below that (including zero), with and without
+    // modifying the remaining values.
+    let grown = List16::new(int_hashes(0, 13)).unwrap();
+    for len in (0..13).rev() {
+        assert_eq!(
+            grown.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+            Hash256::from_slice(&grown.tree_hash_root())
+        );
+
+        let start = len % 2;
+        list = List16::new(int_hashes(start, start + len)).unwrap();
+
+        let fresh_arena = &mut CacheArena::default();
+        let mut fresh_cache = list.new_tree_hash_cache(fresh_arena);
+
+        assert_eq!(
+            list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+            list.recalculate_tree_hash_root(fresh_arena, &mut fresh_cache)
+                .unwrap(),
+            "len: {:?}",
+            len
+        );
+        assert_eq!(
+            list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+            Hash256::from_slice(&list.tree_hash_root())
+        );
+    }
+}
+
+#[test]
+fn truncate() {
+    let arena = &mut CacheArena::default();
+    let depth = 3;
+    let leaves = int_hashes(0, 8);
+
+    let mut cache = TreeHashCache::new(arena, depth, 0);
+    cache
+        .recalculate_merkle_root(arena, hash256_iter(&leaves))
+        .unwrap();
+    cache.truncate(arena, 3).unwrap();
+    assert_eq!(cache.proof(arena, 0), Err(Error::CacheInconsistent));
 
-    let mut cache = list1.new_tree_hash_cache(arena);
-    assert!(list1.recalculate_tree_hash_root(arena, &mut cache).is_ok());
+    let fresh_arena = &mut CacheArena::default();
+    let mut fresh_cache = TreeHashCache::new(fresh_arena, depth, 3);
     assert_eq!(
-        list2.recalculate_tree_hash_root(arena, &mut cache),
-        Err(Error::CannotShrink)
+        cache.recalculate_merkle_root(arena, hash256_iter(&leaves[..3])),
+        fresh_cache.recalculate_merkle_root(fresh_arena, hash256_iter(&leaves[..3]))
+    );
+
+    // Leaves provided to `update_leaves_at` are assumed to be up-to-date, but the path from the
+    // last one to the root is still recomputed after a truncation.
+    cache.truncate(arena, 2).unwrap();
+    let dirty = cache.update_leaves_at(arena, vec![].into_iter()).unwrap();
+    assert_eq!(dirty, vec![1]);
+    assert_eq!(
+        cache.update_merkle_root(arena, dirty),
+        fresh_cache.recalculate_merkle_root(fresh_arena, hash256_iter(&leaves[..2]))
     );
 }
 
+#[cfg(feature = "cache_metrics")]
+#[test]
+fn cache_stats() {
+    let arena = &mut CacheArena::default();
+    let mut list = List16::new(int_hashes(0, 16)).unwrap();
+    let mut cache = list.new_tree_hash_cache(arena);
+
+    list.recalculate_tree_hash_root(arena, &mut cache).unwrap();
+    assert_eq!(cache.last_stats().leaves_recomputed, 16);
+
+    // Only the mutated leaf and its four ancestors are recomputed.
+    list[5] = Hash256::from_low_u64_le(42);
+    assert_eq!(
+        list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+        Hash256::from_slice(&list.tree_hash_root())
+    );
+    assert_eq!(
+        cache.last_stats(),
+        crate::CacheStats {
+            leaves_recomputed: 1,
+            leaves_reused: 15,
+            nodes_recomputed: 4,
+        }
+    );
+
+    list.recalculate_tree_hash_root(arena, &mut cache).unwrap();
+    assert_eq!(cache.last_stats().leaves_recomputed, 0);
+}
+
 #[test]
 fn empty_leaves() {
     let arena = &mut CacheArena::default();
@@ -67,6 +164,53 @@ fn empty_leaves() {
     );
 }
 
+#[test]
+fn root_ref_matches_root() {
+    let arena = &mut CacheArena::default();
+    let depth = 4;
+
+    let mut cache = TreeHashCache::new(arena, depth, 0);
+    assert_eq!(cache.root_ref(arena), cache.root(arena).as_fixed_bytes());
+    assert_eq!(&cache.root_ref(arena)[..], &ZERO_HASHES[depth][..]);
+
+    let root = cache
+        .recalculate_merkle_root(arena, hash256_iter(&int_hashes(0, 5)))
+        .unwrap();
+    assert_eq!(cache.root_ref(arena), root.as_fixed_bytes());
+    assert_eq!(cache.root_ref(arena), cache.root(arena).as_fixed_bytes());
+}
+
+#[test]
+fn proof_matches_merkle_proof() {
+    let arena = &mut CacheArena::default();
+    let depth = 4;
+    let leaves = int_hashes(0, 5);
+    let bytes = leaves
+        .iter()
+        .flat_map(|leaf| leaf.as_bytes().to_vec())
+        .collect::<Vec<_>>();
+
+    let mut cache = TreeHashCache::new(arena, depth, 0);
+    assert_eq!(cache.proof(arena, 0), Err(Error::CacheInconsistent));
+
+    cache
+        .recalculate_merkle_root(arena, hash256_iter(&leaves))
+        .unwrap();
+
+    for i in 0..leaves.len() {
+        let (_, expected) = tree_hash::merkle_proof(&bytes, 1 << depth, i).unwrap();
+        let expected = expected
+            .iter()
+            .map(|node| Hash256::from_slice(node))
+            .collect::<Vec<_>>();
+        assert_eq!(cache.proof(arena, i), Ok(expected), "index: {}", i);
+    }
+    assert_eq!(
+        cache.proof(arena, leaves.len()),
+        Err(Error::CacheInconsistent)
+    );
+}
+
 #[test]
 fn fixed_vector_hash256() {
     let arena = &mut CacheArena::default();
@@ -109,6 +253,76 @@ fn variable_list_hash256() {
     );
 }
 
+#[test]
+fn nested_variable_list() {
+    let arena = &mut CacheArena::default();
+    let list = nested_list(&[2, 0, 4]);
+    let mut cache: MultiTreeHashCache<TreeHashCache> = list.new_tree_hash_cache(arena);
+
+    // Hash, then grow the list, modify values in place, and shrink it.
+    let lists = vec![
+        list,
+        nested_list(&[2, 0, 4, 1, 3, 4]),
+        nested_list(&[4, 1, 4, 1, 0, 4, 4, 2]),
+        nested_list(&[4, 1]),
+        nested_list(&[]),
+    ];
+
+    for list in lists {
+        assert_eq!(
+            list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+            Hash256::from_slice(&list.tree_hash_root())
+        );
+    }
+}
+
+#[test]
+fn twice_nested_variable_list() {
+    let arena = &mut CacheArena::default();
+    let mut list = TwiceNestedList::new(vec![nested_list(&[1, 2]), nested_list(&[])]).unwrap();
+    let mut cache: MultiTreeHashCache<MultiTreeHashCache<TreeHashCache>> =
+        list.new_tree_hash_cache(arena);
+
+    assert_eq!(
+        list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+        Hash256::from_slice(&list.tree_hash_root())
+    );
+
+    list[1] = nested_list(&[3, 3, 3]);
+    list.push(nested_list(&[4])).unwrap();
+
+    assert_eq!(
+        list.recalculate_tree_hash_root(arena, &mut cache).unwrap(),
+        Hash256::from_slice(&list.tree_hash_root())
+    );
+}
+
+#[test]
+fn multi_cache_bytes_version() {
+    type Cache = MultiTreeHashCache<TreeHashCache>;
+
+    let arena = &mut CacheArena::default();
+    let list = nested_list(&[1, 4, 0, 2]);
+    let mut cache: Cache = list.new_tree_hash_cache(arena);
+    list.recalculate_tree_hash_root(arena, &mut cache).unwrap();
+
+    let bytes = cache.as_bytes();
+    assert_eq!(Cache::from_bytes(&bytes), Ok(cache.clone()));
+
+    // A cache written with an older layout is rejected.
+    let stale = encode_versioned(Cache::VERSION - 1, &cache);
+    assert_eq!(
+        Cache::from_bytes(&stale),
+        Err(Error::InvalidCacheVersion {
+            found: Cache::VERSION - 1,
+            expected: Cache::VERSION
+        })
+    );
+
+    // As are bytes too short to contain a version.
+    assert!(Cache::from_bytes(&bytes[0..1]).is_err());
+}
+
 #[quickcheck]
 fn quickcheck_variable_list_h256_256(leaves_and_skips: Vec<(u64, bool)>) -> bool {
     variable_list_h256_test::<U256>(leaves_and_skips)
@@ -151,3 +365,95 @@ fn variable_list_h256_test<Len: Unsigned>(leaves_and_skips: Vec<(u64, bool)>) ->
     }
     true
 }
+
+/// The number of times that `Counted::recalculate_tree_hash_root` has been called.
+static NUM_RECALCULATIONS: AtomicUsize = AtomicUsize::new(0);
+
+/// A `Hash256` which counts the number of times its cached tree hash is recalculated.
+#[derive(Clone, Copy)]
+struct Counted(Hash256);
+
+impl TreeHash for Counted {
+    fn tree_hash_type() -> tree_hash::TreeHashType {
+        Hash256::tree_hash_type()
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        self.0.tree_hash_packed_encoding()
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        Hash256::tree_hash_packing_factor()
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        self.0.tree_hash_root()
+    }
+}
+
+impl CachedTreeHash<TreeHashCache> for Counted {
+    fn new_tree_hash_cache(&self, arena: &mut CacheArena) -> TreeHashCache {
+        TreeHashCache::new(arena, 0, 1)
+    }
+
+    fn recalculate_tree_hash_root(
+        &self,
+        _arena: &mut CacheArena,
+        _cache: &mut TreeHashCache,
+    ) -> Result<Hash256, Error> {
+        NUM_RECALCULATIONS.fetch_add(1, Ordering::SeqCst);
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn multi_cache_dirty_indices() {
+    type CountedList = VariableList<Counted, U256>;
+
+    let arena = &mut CacheArena::default();
+    let root = |list: &CountedList| {
+        let hashes = list.iter().map(|counted| counted.0).collect::<Vec<_>>();
+        Hash256::from_slice(&VariableList::<Hash256, U256>::from(hashes).tree_hash_root())
+    };
+
+    let mut list = CountedList::new(int_hashes(0, 100).into_iter().map(Counted).collect()).unwrap();
+    let mut cache: MultiTreeHashCache<TreeHashCache> = list.new_tree_hash_cache(arena);
+
+    // The first update visits every value, since the cache is uninitialized.
+    assert_eq!(
+        cache.recalculate_dirty_tree_hash_root(arena, &list, &[]),
+        Ok(root(&list))
+    );
+    let count = NUM_RECALCULATIONS.load(Ordering::SeqCst);
+    assert_eq!(count, 100);
+
+    // Only the modified value is visited.
+    list[42] = Counted(Hash256::repeat_byte(42));
+    assert_eq!(
+        cache.recalculate_dirty_tree_hash_root(arena, &list, &[42]),
+        Ok(root(&list))
+    );
+    assert_eq!(NUM_RECALCULATIONS.load(Ordering::SeqCst), count + 1);
+
+    // As are new values, even if they aren't marked dirty.
+    list.push(Counted(Hash256::repeat_byte(1))).unwrap();
+    assert_eq!(
+        cache.recalculate_dirty_tree_hash_root(arena, &list, &[]),
+        Ok(root(&list))
+    );
+    assert_eq!(NUM_RECALCULATIONS.load(Ordering::SeqCst), count + 2);
+
+    // Shrinking the list doesn't visit any of the remaining values.
+    list = CountedList::new(list.iter().take(50).copied().collect()).unwrap();
+    assert_eq!(
+        cache.recalculate_dirty_tree_hash_root(arena, &list, &[]),
+        Ok(root(&list))
+    );
+    assert_eq!(NUM_RECALCULATIONS.load(Ordering::SeqCst), count + 2);
+
+    // The result is the same as a full update.
+    assert_eq!(
+        list.recalculate_tree_hash_root(arena, &mut cache),
+        Ok(root(&list))
+    );
+}
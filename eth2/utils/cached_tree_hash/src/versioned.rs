@@ -0,0 +1,28 @@
+use crate::Error;
+use ssz::{Decode, Encode};
+use std::mem::size_of;
+
+/// Encode `value` as SSZ, prefixed with the `version` of its layout.
+///
+/// The result can be decoded with `decode_versioned`.
+pub fn encode_versioned<T: Encode>(version: u16, value: &T) -> Vec<u8> {
+    let mut bytes = version.as_ssz_bytes();
+    value.ssz_append(&mut bytes);
+    bytes
+}
+
+/// Decode a `T` from `bytes` produced by `encode_versioned`.
+///
+/// The version is checked before decoding the rest of `bytes`, so a value written with a different
+/// layout is reported as `Error::InvalidCacheVersion` rather than decoding into garbage (or failing
+/// part-way through).
+pub fn decode_versioned<T: Decode>(expected: u16, bytes: &[u8]) -> Result<T, Error> {
+    let version_len = size_of::<u16>();
+    let found = u16::from_ssz_bytes(bytes.get(0..version_len).unwrap_or(bytes))?;
+
+    if found == expected {
+        Ok(T::from_ssz_bytes(&bytes[version_len..])?)
+    } else {
+        Err(Error::InvalidCacheVersion { found, expected })
+    }
+}
@@ -0,0 +1,36 @@
+use criterion::Criterion;
+use criterion::{black_box, criterion_group, criterion_main, Benchmark};
+use eth2_hashing::{hash, hash32_concat};
+
+// Run once as-is and once with `--features sha2-asm` to compare the `ring` and `sha2` backends.
+fn bench_hash(c: &mut Criterion, len: usize) {
+    let input: Vec<u8> = (0..len).map(|byte| byte as u8).collect();
+
+    c.bench(
+        &format!("hash/{}_bytes", len),
+        Benchmark::new("hash", move |b| b.iter(|| black_box(hash(&input)))),
+    );
+}
+
+fn bench_hash32_concat(c: &mut Criterion) {
+    let h1 = [1; 32];
+    let h2 = [2; 32];
+
+    c.bench(
+        "hash32_concat",
+        Benchmark::new("hash32_concat", move |b| {
+            b.iter(|| black_box(hash32_concat(&h1, &h2)))
+        }),
+    );
+}
+
+fn all_benches(c: &mut Criterion) {
+    bench_hash32_concat(c);
+
+    for &len in &[64, 1024, 1024 * 1024] {
+        bench_hash(c, len);
+    }
+}
+
+criterion_group!(benches, all_benches,);
+criterion_main!(benches);
@@ -3,11 +3,15 @@
 //! The purpose of this crate is to provide an abstraction to whatever hash function Ethereum
 //! 2.0 is using. The hash function has been subject to change during the specification process, so
 //! defining it once in this crate makes it easy to replace.
+//!
+//! The SHA256 implementation is also chosen here: `ring` by default, or `sha2` on `wasm32` and
+//! when the `sha2-asm` feature is enabled (which uses its assembly implementation where the
+//! platform supports it).
 
-#[cfg(not(target_arch = "wasm32"))]
+#[cfg(not(any(target_arch = "wasm32", feature = "sha2-asm")))]
 use ring::digest::{digest, Context, SHA256};
 
-#[cfg(target_arch = "wasm32")]
+#[cfg(any(target_arch = "wasm32", feature = "sha2-asm"))]
 use sha2::{Digest, Sha256};
 
 #[cfg(feature = "zero_hash_cache")]
@@ -15,49 +19,50 @@ use lazy_static::lazy_static;
 
 /// Returns the digest of `input`.
 ///
-/// Uses `ring::digest::SHA256`.
+/// Uses `ring::digest::SHA256`, or `sha2::Sha256` with the `sha2-asm` feature.
 pub fn hash(input: &[u8]) -> Vec<u8> {
-    #[cfg(not(target_arch = "wasm32"))]
+    #[cfg(not(any(target_arch = "wasm32", feature = "sha2-asm")))]
     let h = digest(&SHA256, input).as_ref().into();
 
-    #[cfg(target_arch = "wasm32")]
+    #[cfg(any(target_arch = "wasm32", feature = "sha2-asm"))]
     let h = Sha256::digest(input).as_ref().into();
 
     h
 }
 
-/// Compute the hash of two slices concatenated.
-///
-/// # Panics
-///
-/// Will panic if either `h1` or `h2` are not 32 bytes in length.
-#[cfg(not(target_arch = "wasm32"))]
-pub fn hash32_concat(h1: &[u8], h2: &[u8]) -> [u8; 32] {
+/// Compute the hash of all of `slices` concatenated, without allocating the concatenation.
+#[cfg(not(any(target_arch = "wasm32", feature = "sha2-asm")))]
+pub fn hash_many(slices: &[&[u8]]) -> [u8; 32] {
     let mut context = Context::new(&SHA256);
-    context.update(h1);
-    context.update(h2);
+    for slice in slices {
+        context.update(slice);
+    }
 
     let mut output = [0; 32];
     output[..].copy_from_slice(context.finish().as_ref());
     output
 }
 
-/// Compute the hash of two slices concatenated.
-///
-/// # Panics
-///
-/// Will panic if either `h1` or `h2` are not 32 bytes in length.
-#[cfg(target_arch = "wasm32")]
-pub fn hash32_concat(h1: &[u8], h2: &[u8]) -> [u8; 32] {
-    let mut preimage = [0; 64];
-    preimage[0..32].copy_from_slice(h1);
-    preimage[32..64].copy_from_slice(h2);
+/// Compute the hash of all of `slices` concatenated, without allocating the concatenation.
+#[cfg(any(target_arch = "wasm32", feature = "sha2-asm"))]
+pub fn hash_many(slices: &[&[u8]]) -> [u8; 32] {
+    let mut hasher = Sha256::new();
+    for slice in slices {
+        hasher.input(slice);
+    }
 
     let mut output = [0; 32];
-    output[..].copy_from_slice(&hash(&preimage));
+    output[..].copy_from_slice(hasher.result().as_ref());
     output
 }
 
+/// Compute the hash of two slices concatenated.
+///
+/// Equivalent to `hash_many(&[h1, h2])`.
+pub fn hash32_concat(h1: &[u8], h2: &[u8]) -> [u8; 32] {
+    hash_many(&[h1, h2])
+}
+
 /// The max index that can be used with `ZERO_HASHES`.
 #[cfg(feature = "zero_hash_cache")]
 pub const ZERO_HASHES_MAX_INDEX: usize = 48;
@@ -95,6 +100,39 @@ mod tests {
         assert_eq!(expected, output);
     }
 
+    #[cfg_attr(not(target_arch = "wasm32"), test)]
+    #[cfg_attr(target_arch = "wasm32", wasm_bindgen_test)]
+    fn test_hash_many() {
+        let a = [1; 32];
+        let b = [2; 32];
+        let c = [3, 4, 5];
+
+        assert_eq!(hash_many(&[&a, &b]), hash32_concat(&a, &b));
+        assert_eq!(
+            &hash_many(&[&a, &b, &c])[..],
+            &hash(&[&a[..], &b[..], &c[..]].concat())[..]
+        );
+        assert_eq!(&hash_many(&[])[..], &hash(&[])[..]);
+    }
+
+    /// The `sha2` backend must agree with `ring`, so that the choice of backend never changes a
+    /// tree hash root.
+    #[cfg(all(feature = "sha2-asm", not(target_arch = "wasm32")))]
+    #[test]
+    fn sha2_matches_ring() {
+        use ring::digest::{digest, SHA256};
+
+        for len in 0..=130 {
+            let input: Vec<u8> = (0..len).map(|i| i as u8).collect();
+            assert_eq!(hash(&input), digest(&SHA256, &input).as_ref().to_vec());
+        }
+
+        let h1 = [1; 32];
+        let h2 = [2; 32];
+        let expected = digest(&SHA256, &[&h1[..], &h2[..]].concat());
+        assert_eq!(&hash32_concat(&h1, &h2)[..], expected.as_ref());
+    }
+
     #[cfg(feature = "zero_hash_cache")]
     mod zero_hash {
         use super::*;
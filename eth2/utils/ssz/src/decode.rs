@@ -5,8 +5,17 @@ pub mod impls;
 /// Returned when SSZ decoding fails.
 #[derive(Debug, PartialEq, Clone)]
 pub enum DecodeError {
-    /// The bytes supplied were too short to be decoded into the specified type.
+    /// The length of the bytes supplied was invalid for some reason other than being too short
+    /// or too long, e.g., the fixed-length portion of a list was not a multiple of
+    /// `BYTES_PER_LENGTH_OFFSET`.
     InvalidByteLength { len: usize, expected: usize },
+    /// Fewer bytes than the type (or the remainder of a container or list) requires were
+    /// supplied.
+    ///
+    /// Unlike other errors, this may be resolved by supplying more bytes.
+    BytesTooShort { need: usize, got: usize },
+    /// More bytes than the type (or the remainder of a container) occupies were supplied.
+    BytesTooLong { expected: usize, got: usize },
     /// The given bytes were too short to be read as a length prefix.
     InvalidLengthPrefix { len: usize, expected: usize },
     /// A length offset pointed to a byte that was out-of-bounds (OOB).
@@ -18,8 +27,75 @@ pub enum DecodeError {
     /// length items (i.e., `length[0] < BYTES_PER_LENGTH_OFFSET`).
     /// - When decoding variable-length items, the `n`'th offset was less than the `n-1`'th offset.
     OutOfBoundsByte { i: usize },
+    /// The item at `index` of a list of variable-length items was given zero bytes, although its
+    /// type has a non-zero fixed length.
+    ZeroLengthItem { index: usize },
     /// The given bytes were invalid for some application-level reason.
     BytesInvalid(String),
+    /// The named field of a container could not be decoded.
+    FieldError {
+        field: &'static str,
+        error: Box<DecodeError>,
+    },
+    /// The item at `location` (e.g., a list index such as `[3]`) could not be decoded.
+    Context {
+        location: String,
+        source: Box<DecodeError>,
+    },
+}
+
+impl DecodeError {
+    /// Wrap `self` in a `DecodeError::Context`, recording that it occurred at `location`.
+    ///
+    /// Intended to be called from within `map_err`, so nothing is allocated unless decoding fails.
+    pub fn with_context(self, location: impl Into<String>) -> Self {
+        DecodeError::Context {
+            location: location.into(),
+            source: Box::new(self),
+        }
+    }
+
+    /// Returns `BytesTooShort` or `BytesTooLong` for an item of `expected` bytes that was given
+    /// `len` bytes, where `len != expected`.
+    pub fn wrong_length(len: usize, expected: usize) -> Self {
+        if len < expected {
+            DecodeError::BytesTooShort {
+                need: expected,
+                got: len,
+            }
+        } else {
+            DecodeError::BytesTooLong { expected, got: len }
+        }
+    }
+}
+
+/// Displays the path to the item which failed followed by the underlying error, e.g.,
+/// `body.attestations[3].signature: BytesInvalid(..)`.
+impl std::fmt::Display for DecodeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        let mut path = String::new();
+        let mut error = self;
+
+        loop {
+            let (segment, source) = match error {
+                DecodeError::FieldError { field, error } => (*field, error),
+                DecodeError::Context { location, source } => (location.as_str(), source),
+                _ => break,
+            };
+
+            if !path.is_empty() && !segment.starts_with('[') {
+                path.push('.');
+            }
+            path.push_str(segment);
+            error = source;
+        }
+
+        if path.is_empty() {
+            write!(f, "{:?}", error)
+        } else {
+            write!(f, "{}: {:?}", path, error)
+        }
+    }
 }
 
 /// Provides SSZ decoding (de-serialization) via the `from_ssz_bytes(&bytes)` method.
@@ -46,6 +122,99 @@ pub trait Decode: Sized {
     /// The supplied bytes must be the exact length required to decode `Self`, excess bytes will
     /// result in an error.
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError>;
+
+    /// Attempts to decode `Self` from the start of `bytes`, returning it along with the number of
+    /// bytes consumed.
+    ///
+    /// Fixed-length objects consume exactly `ssz_fixed_len()` bytes and ignore any that follow,
+    /// so a sequence of them may be decoded one after another.
+    ///
+    /// SSZ does not delimit variable-length objects (their end is only known from an enclosing
+    /// offset), so they are assumed to occupy the whole of `bytes`. Decoding several requires
+    /// some external framing, such as a length prefix before each.
+    fn from_ssz_bytes_with_consumed(bytes: &[u8]) -> Result<(Self, usize), DecodeError> {
+        let len = if Self::is_ssz_fixed_len() {
+            Self::ssz_fixed_len()
+        } else {
+            bytes.len()
+        };
+
+        let item_bytes = bytes
+            .get(0..len)
+            .ok_or_else(|| DecodeError::BytesTooShort {
+                need: len,
+                got: bytes.len(),
+            })?;
+
+        Self::from_ssz_bytes(item_bytes).map(|item| (item, len))
+    }
+
+    /// Decodes `bytes` as an SSZ list of `Self` into `out`, reusing its allocation rather than
+    /// allocating a new `Vec` as `Vec::<Self>::from_ssz_bytes` does.
+    ///
+    /// `out` is cleared first. On success it contains the decoded items, otherwise it is left
+    /// empty. Either way its capacity is retained.
+    fn from_ssz_bytes_into(bytes: &[u8], out: &mut Vec<Self>) -> Result<(), DecodeError> {
+        let result = impls::decode_list_into(bytes, out);
+        if result.is_err() {
+            out.clear();
+        }
+        result
+    }
+}
+
+/// Provides SSZ decoding into types which borrow from the given bytes, rather than copying them.
+///
+/// Every `Decode` type implements `DecodeRef` by copying, so containers may mix borrowed and owned
+/// fields. Types such as `SszBytesRef` implement it by holding a sub-slice of `bytes`.
+pub trait DecodeRef<'a>: Sized {
+    /// Returns `true` if this object has a fixed-length.
+    ///
+    /// See `Decode::is_ssz_fixed_len`.
+    fn is_ssz_fixed_len() -> bool;
+
+    /// The number of bytes this object occupies in the fixed-length portion of the SSZ bytes.
+    ///
+    /// See `Decode::ssz_fixed_len`.
+    fn ssz_fixed_len() -> usize {
+        BYTES_PER_LENGTH_OFFSET
+    }
+
+    /// Attempts to decode `Self` from `bytes`, which `Self` may continue to borrow.
+    ///
+    /// The supplied bytes must be the exact length required to decode `Self`, excess bytes will
+    /// result in an error.
+    fn from_ssz_bytes_ref(bytes: &'a [u8]) -> Result<Self, DecodeError>;
+}
+
+impl<'a, T: Decode> DecodeRef<'a> for T {
+    fn is_ssz_fixed_len() -> bool {
+        <T as Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T as Decode>::ssz_fixed_len()
+    }
+
+    fn from_ssz_bytes_ref(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        T::from_ssz_bytes(bytes)
+    }
+}
+
+/// A variable-length list of bytes which borrows from the SSZ bytes it was decoded from.
+///
+/// Encoded identically to a `Vec<u8>`, but decoding (via `DecodeRef`) does not copy the bytes.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Hash)]
+pub struct SszBytesRef<'a>(pub &'a [u8]);
+
+impl<'a> DecodeRef<'a> for SszBytesRef<'a> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes_ref(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        Ok(SszBytesRef(bytes))
+    }
 }
 
 #[derive(Copy, Clone, Debug)]
@@ -71,10 +240,16 @@ impl<'a> SszDecoderBuilder<'a> {
     /// Instantiate a new builder that should build a `SszDecoder` over the given `bytes` which
     /// are assumed to be the SSZ encoding of some object.
     pub fn new(bytes: &'a [u8]) -> Self {
+        Self::with_capacity(bytes, 0, 0)
+    }
+
+    /// Instantiate a new builder, reserving space for `num_items` items of which `num_offsets`
+    /// are variable-length.
+    fn with_capacity(bytes: &'a [u8], num_items: usize, num_offsets: usize) -> Self {
         Self {
             bytes,
-            items: vec![],
-            offsets: vec![],
+            items: Vec::with_capacity(num_items),
+            offsets: Vec::with_capacity(num_offsets),
             items_index: 0,
         }
     }
@@ -82,44 +257,119 @@ impl<'a> SszDecoderBuilder<'a> {
     /// Declares that some type `T` is the next item in `bytes`.
     pub fn register_type<T: Decode>(&mut self) -> Result<(), DecodeError> {
         if T::is_ssz_fixed_len() {
-            let start = self.items_index;
-            self.items_index += T::ssz_fixed_len();
+            self.register_type_with_length::<T>(T::ssz_fixed_len())
+        } else {
+            self.register_anonymous_variable_length_item()
+        }
+    }
 
-            let slice = self.bytes.get(start..self.items_index).ok_or_else(|| {
-                DecodeError::InvalidByteLength {
-                    len: self.bytes.len(),
-                    expected: self.items_index,
-                }
-            })?;
+    /// Declares that the next item in `bytes` is of some variable-length type, without naming
+    /// that type.
+    ///
+    /// Useful in combination with `SszDecoder::next_builder` to descend into a nested container
+    /// without defining (or decoding) the whole of it.
+    pub fn register_anonymous_variable_length_item(&mut self) -> Result<(), DecodeError> {
+        let offset = read_offset(&self.bytes[self.items_index..])?;
 
-            self.items.push(slice);
-        } else {
-            let offset = read_offset(&self.bytes[self.items_index..])?;
+        let previous_offset = self
+            .offsets
+            .last()
+            .map(|o| o.offset)
+            .unwrap_or_else(|| BYTES_PER_LENGTH_OFFSET);
+
+        if (previous_offset > offset) || (offset > self.bytes.len()) {
+            return Err(DecodeError::OutOfBoundsByte { i: offset });
+        }
 
-            let previous_offset = self
-                .offsets
-                .last()
-                .map(|o| o.offset)
-                .unwrap_or_else(|| BYTES_PER_LENGTH_OFFSET);
+        self.offsets.push(Offset {
+            position: self.items.len(),
+            offset,
+        });
 
-            if (previous_offset > offset) || (offset > self.bytes.len()) {
-                return Err(DecodeError::OutOfBoundsByte { i: offset });
-            }
+        // Push an empty slice into items; it will be replaced later.
+        self.items.push(&[]);
 
-            self.offsets.push(Offset {
-                position: self.items.len(),
-                offset,
-            });
+        self.items_index += BYTES_PER_LENGTH_OFFSET;
 
-            // Push an empty slice into items; it will be replaced later.
-            self.items.push(&[]);
+        Ok(())
+    }
 
-            self.items_index += BYTES_PER_LENGTH_OFFSET;
+    /// Declares that some type `T`, which may borrow from `bytes`, is the next item in `bytes`.
+    pub fn register_type_ref<T: DecodeRef<'a>>(&mut self) -> Result<(), DecodeError> {
+        if T::is_ssz_fixed_len() {
+            self.register_fixed_length_item(T::ssz_fixed_len())
+        } else {
+            self.register_anonymous_variable_length_item()
         }
+    }
+
+    /// Declares that some type `T` is the next item in `bytes` and that it occupies exactly `len`
+    /// bytes of the fixed-length portion.
+    ///
+    /// The given `len` is trusted instead of `T::ssz_fixed_len()`, allowing types with an
+    /// unsuitable `Decode` impl (e.g., foreign types) to be embedded in a container.
+    pub fn register_type_with_length<T: Decode>(&mut self, len: usize) -> Result<(), DecodeError> {
+        self.register_fixed_length_item(len)
+    }
+
+    /// Declares that the next item in `bytes` occupies exactly `len` bytes of the fixed-length
+    /// portion.
+    ///
+    /// On error, the builder is left as it was before the call.
+    fn register_fixed_length_item(&mut self, len: usize) -> Result<(), DecodeError> {
+        let start = self.items_index;
+        let end = start.checked_add(len).ok_or_else(|| {
+            DecodeError::BytesInvalid(format!(
+                "Item of {} bytes at index {} overflows usize",
+                len, start
+            ))
+        })?;
+
+        let slice = self
+            .bytes
+            .get(start..end)
+            .ok_or_else(|| DecodeError::BytesTooShort {
+                need: end,
+                got: self.bytes.len(),
+            })?;
+
+        self.items.push(slice);
+        self.items_index = end;
 
         Ok(())
     }
 
+    /// Returns `true` if the fixed-length portion of `bytes` extends beyond the items registered
+    /// so far.
+    ///
+    /// The fixed-length portion ends at the first offset, or at the end of `bytes` if no
+    /// variable-length items have been registered.
+    pub fn has_remaining_fixed_bytes(&self) -> bool {
+        self.items_index < self.fixed_end()
+    }
+
+    /// Declares that the remainder of the fixed-length portion of `bytes` is the final item, so
+    /// that the trailing fields of a newer version of a container can be preserved without being
+    /// understood.
+    ///
+    /// The item may be decoded as a `Vec<u8>` (or `SszBytesRef`) and re-encoded verbatim with
+    /// `SszEncoder::append_rest`. If the container has variable-length items, only unknown
+    /// fixed-length fields can be preserved, since any bytes following the last known offset are
+    /// attributed to the last known variable-length item.
+    pub fn register_rest(&mut self) -> Result<(), DecodeError> {
+        self.register_fixed_length_item(self.fixed_end().saturating_sub(self.items_index))
+    }
+
+    /// Returns the index of the byte following the fixed-length portion of `bytes`.
+    ///
+    /// The fixed-length portion ends at the first offset, or at the end of `bytes` if no
+    /// variable-length items have been registered.
+    fn fixed_end(&self) -> usize {
+        self.offsets
+            .first()
+            .map_or(self.bytes.len(), |offset| offset.offset)
+    }
+
     fn finalize(&mut self) -> Result<(), DecodeError> {
         if !self.offsets.is_empty() {
             // Check to ensure the first offset points to the byte immediately following the
@@ -146,9 +396,9 @@ impl<'a> SszDecoderBuilder<'a> {
         } else {
             // If the container is fixed-length, ensure there are no excess bytes.
             if self.items_index != self.bytes.len() {
-                return Err(DecodeError::InvalidByteLength {
-                    len: self.bytes.len(),
+                return Err(DecodeError::BytesTooLong {
                     expected: self.items_index,
+                    got: self.bytes.len(),
                 });
             }
         }
@@ -160,7 +410,123 @@ impl<'a> SszDecoderBuilder<'a> {
     pub fn build(mut self) -> Result<SszDecoder<'a>, DecodeError> {
         self.finalize()?;
 
-        Ok(SszDecoder { items: self.items })
+        // Each variable-length item also occupies an offset in the fixed-length portion.
+        let mut item_lens = self.items.iter().map(|item| item.len()).collect::<Vec<_>>();
+        for offset in &self.offsets {
+            item_lens[offset.position] += BYTES_PER_LENGTH_OFFSET;
+        }
+
+        Ok(SszDecoder {
+            items: self.items,
+            item_lens,
+            bytes_consumed: 0,
+        })
+    }
+}
+
+/// The kind of an item registered with an `SszLayout`.
+#[derive(Debug, PartialEq, Clone, Copy)]
+enum LayoutItem {
+    /// An item occupying exactly this many bytes of the fixed-length portion.
+    Fixed(usize),
+    /// A variable-length item, occupying an offset in the fixed-length portion.
+    Variable,
+}
+
+/// The sequence of items in some SSZ container, which may be applied to many buffers.
+///
+/// Registering types with an `SszLayout` once, rather than with a new `SszDecoderBuilder` for
+/// each buffer, avoids re-deriving the length of each item for every buffer of the same type.
+/// Applying the layout to `bytes` is equivalent to registering the same types with
+/// `SszDecoderBuilder::new(bytes)` and calling `build`, returning the same errors.
+///
+/// ## Example
+///
+/// ```rust
+/// use ssz::{Encode, SszLayout};
+///
+/// let mut layout = SszLayout::new();
+/// layout.register_type::<u64>();
+/// layout.register_type::<Vec<u16>>();
+///
+/// for b in 0..3 {
+///     let bytes = (42_u64, vec![b; b as usize]).as_ssz_bytes();
+///     let mut decoder = layout.decode(&bytes).unwrap();
+///     assert_eq!(decoder.decode_next::<u64>(), Ok(42));
+///     assert_eq!(decoder.decode_next::<Vec<u16>>(), Ok(vec![b; b as usize]));
+/// }
+/// ```
+#[derive(Debug, PartialEq, Clone, Default)]
+pub struct SszLayout {
+    items: Vec<LayoutItem>,
+    num_offsets: usize,
+}
+
+impl SszLayout {
+    /// Instantiate a layout without any items.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declares that some type `T` is the next item. See `SszDecoderBuilder::register_type`.
+    pub fn register_type<T: Decode>(&mut self) {
+        if T::is_ssz_fixed_len() {
+            self.register_fixed_length_item(T::ssz_fixed_len())
+        } else {
+            self.register_anonymous_variable_length_item()
+        }
+    }
+
+    /// Declares that some type `T`, which may borrow from the decoded bytes, is the next item. See
+    /// `SszDecoderBuilder::register_type_ref`.
+    pub fn register_type_ref<'a, T: DecodeRef<'a>>(&mut self) {
+        if T::is_ssz_fixed_len() {
+            self.register_fixed_length_item(T::ssz_fixed_len())
+        } else {
+            self.register_anonymous_variable_length_item()
+        }
+    }
+
+    /// Declares that some type `T` is the next item and that it occupies exactly `len` bytes of
+    /// the fixed-length portion. See `SszDecoderBuilder::register_type_with_length`.
+    pub fn register_type_with_length<T: Decode>(&mut self, len: usize) {
+        self.register_fixed_length_item(len)
+    }
+
+    /// Declares that the next item is of some variable-length type, without naming that type. See
+    /// `SszDecoderBuilder::register_anonymous_variable_length_item`.
+    pub fn register_anonymous_variable_length_item(&mut self) {
+        self.items.push(LayoutItem::Variable);
+        self.num_offsets += 1;
+    }
+
+    fn register_fixed_length_item(&mut self, len: usize) {
+        self.items.push(LayoutItem::Fixed(len))
+    }
+
+    /// Returns the number of items registered.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// Returns `true` if no items have been registered.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// Split `bytes` into the registered items, returning a `SszDecoder` over them.
+    pub fn decode<'a>(&self, bytes: &'a [u8]) -> Result<SszDecoder<'a>, DecodeError> {
+        let mut builder =
+            SszDecoderBuilder::with_capacity(bytes, self.items.len(), self.num_offsets);
+
+        for item in &self.items {
+            match item {
+                LayoutItem::Fixed(len) => builder.register_fixed_length_item(*len)?,
+                LayoutItem::Variable => builder.register_anonymous_variable_length_item()?,
+            }
+        }
+
+        builder.build()
     }
 }
 
@@ -205,16 +571,67 @@ impl<'a> SszDecoderBuilder<'a> {
 /// ```
 pub struct SszDecoder<'a> {
     items: Vec<&'a [u8]>,
+    /// The number of bytes occupied by each of `items`, including its offset (if any).
+    item_lens: Vec<usize>,
+    bytes_consumed: usize,
 }
 
 impl<'a> SszDecoder<'a> {
+    /// Returns the number of bytes occupied by the items taken from this decoder so far.
+    ///
+    /// A variable-length item occupies its offset as well as its value, so once every item has
+    /// been taken this is the length of the bytes given to the builder.
+    pub fn bytes_consumed(&self) -> usize {
+        self.bytes_consumed
+    }
+
+    /// Removes and returns the bytes of the next item.
+    fn next_item(&mut self) -> &'a [u8] {
+        self.bytes_consumed += self.item_lens.remove(0);
+        self.items.remove(0)
+    }
+
     /// Decodes the next item.
     ///
     /// # Panics
     ///
     /// Panics when attempting to decode more items than actually exist.
     pub fn decode_next<T: Decode>(&mut self) -> Result<T, DecodeError> {
-        T::from_ssz_bytes(self.items.remove(0))
+        T::from_ssz_bytes(self.next_item())
+    }
+
+    /// Decodes the next item into a type which may borrow from the bytes given to the builder.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to decode more items than actually exist.
+    pub fn decode_next_ref<T: DecodeRef<'a>>(&mut self) -> Result<T, DecodeError> {
+        T::from_ssz_bytes_ref(self.next_item())
+    }
+
+    /// Decodes the next item, which is the named `field` of a container.
+    ///
+    /// Identical to `decode_next`, except that an error is wrapped in a `DecodeError::FieldError`
+    /// to identify the field which failed.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to decode more items than actually exist.
+    pub fn decode_next_field<T: Decode>(&mut self, field: &'static str) -> Result<T, DecodeError> {
+        self.decode_next().map_err(|error| DecodeError::FieldError {
+            field,
+            error: Box::new(error),
+        })
+    }
+
+    /// Returns a builder over the bytes of the next item, so that a nested container may be
+    /// decoded field-by-field. Fields which are never decoded are not checked for validity.
+    ///
+    /// # Panics
+    ///
+    /// Panics when attempting to take more items than actually exist.
+    pub fn next_builder(&mut self) -> SszDecoderBuilder<'a> {
+        SszDecoderBuilder::new(self.next_item())
     }
 }
 
@@ -237,6 +654,10 @@ fn read_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
 
 /// Decode bytes as a little-endian usize, returning an `Err` if `bytes.len() !=
 /// BYTES_PER_LENGTH_OFFSET`.
+///
+/// The result is at most `MAX_LENGTH_VALUE` and, since `MAX_LENGTH_VALUE` fits in a `usize` on all
+/// supported targets, is never truncated. Callers must still check it against the length of the
+/// bytes it indexes.
 fn decode_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
     let len = bytes.len();
     let expected = BYTES_PER_LENGTH_OFFSET;
@@ -250,3 +671,317 @@ fn decode_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
         Ok(u32::from_le_bytes(array) as usize)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn register_type_with_length() {
+        // A `u16`, followed by three bytes of a foreign type, followed by a `u32`.
+        let bytes = vec![1, 0, 2, 3, 4, 5, 0, 0, 0];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u16>().unwrap();
+        builder.register_type_with_length::<Vec<u8>>(3).unwrap();
+        builder.register_type::<u32>().unwrap();
+
+        let mut decoder = builder.build().unwrap();
+
+        assert_eq!(decoder.decode_next::<u16>(), Ok(1));
+        assert_eq!(decoder.decode_next::<Vec<u8>>(), Ok(vec![2, 3, 4]));
+        assert_eq!(decoder.decode_next::<u32>(), Ok(5));
+    }
+
+    #[test]
+    fn layout_matches_builder() {
+        // A container `{ a: u16, b: Vec<u8>, c: u32, d: Vec<u16> }`.
+        let mut layout = SszLayout::new();
+        layout.register_type::<u16>();
+        layout.register_type::<Vec<u8>>();
+        layout.register_type::<u32>();
+        layout.register_type::<Vec<u16>>();
+        assert_eq!(layout.len(), 4);
+
+        let build = |bytes| {
+            let mut builder = SszDecoderBuilder::new(bytes);
+            builder.register_type::<u16>()?;
+            builder.register_type::<Vec<u8>>()?;
+            builder.register_type::<u32>()?;
+            builder.register_type::<Vec<u16>>()?;
+            builder.build()
+        };
+
+        let decode_all = |decoder: Result<SszDecoder, DecodeError>| -> Result<_, DecodeError> {
+            let mut decoder = decoder?;
+            Ok((
+                decoder.decode_next::<u16>()?,
+                decoder.decode_next::<Vec<u8>>()?,
+                decoder.decode_next::<u32>()?,
+                decoder.decode_next::<Vec<u16>>()?,
+                decoder.bytes_consumed(),
+            ))
+        };
+
+        let mut buffers = (0..4)
+            .map(|i| (i as u16, vec![i as u8; i], i as u32, vec![i as u16; 2]).as_ssz_bytes())
+            .collect::<Vec<_>>();
+        // Truncated within a fixed-length item, truncated within an offset, and with an invalid
+        // `Vec<u16>`.
+        buffers.push(buffers[1][..8].to_vec());
+        buffers.push(buffers[1][..13].to_vec());
+        buffers.push(buffers[1][..buffers[1].len() - 1].to_vec());
+
+        for bytes in &buffers {
+            assert_eq!(
+                decode_all(layout.decode(bytes)),
+                decode_all(build(bytes)),
+                "bytes: {:?}",
+                bytes
+            );
+        }
+
+        assert_eq!(
+            decode_all(layout.decode(&buffers[2])),
+            Ok((2, vec![2, 2], 2, vec![2, 2], buffers[2].len()))
+        );
+        for bytes in &buffers[4..] {
+            assert!(decode_all(layout.decode(bytes)).is_err());
+        }
+    }
+
+    #[test]
+    fn register_rest_round_trip() {
+        // A fixed-length container `{ a: u16 }`, with a `u32` and a `u8` which are unknown.
+        let bytes = (7_u16, 42_u32, 3_u8).as_ssz_bytes();
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+        builder.register_type::<u16>().unwrap();
+        builder.register_rest().unwrap();
+        let mut decoder = builder.build().unwrap();
+
+        let a = decoder.decode_next::<u16>().unwrap();
+        let rest = decoder.decode_next::<Vec<u8>>().unwrap();
+        assert_eq!(a, 7);
+        assert_eq!(rest, vec![42, 0, 0, 0, 3]);
+
+        let mut encoded = vec![];
+        let mut encoder = SszEncoder::container(&mut encoded, 2 + rest.len());
+        encoder.append(&a);
+        encoder.append_rest(&rest);
+        encoder.finalize();
+        assert_eq!(encoded, bytes);
+
+        // A container `{ a: u16, b: Vec<u8> }`, with a `u32` which is unknown.
+        let bytes = (7_u16, vec![1_u8, 2, 3], 42_u32).as_ssz_bytes();
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+        builder.register_type::<u16>().unwrap();
+        builder.register_type::<Vec<u8>>().unwrap();
+        builder.register_rest().unwrap();
+        let mut decoder = builder.build().unwrap();
+
+        let a = decoder.decode_next::<u16>().unwrap();
+        let b = decoder.decode_next::<Vec<u8>>().unwrap();
+        let rest = decoder.decode_next_ref::<SszBytesRef>().unwrap();
+        assert_eq!(b, vec![1, 2, 3]);
+        assert_eq!(rest.0, &[42, 0, 0, 0]);
+
+        let mut encoded = vec![];
+        let mut encoder =
+            SszEncoder::container(&mut encoded, 2 + BYTES_PER_LENGTH_OFFSET + rest.0.len());
+        encoder.append(&a);
+        encoder.append(&b);
+        encoder.append_rest(rest.0);
+        encoder.finalize();
+        assert_eq!(encoded, bytes);
+
+        // Without any unknown fields, the rest is empty.
+        let bytes = 7_u16.as_ssz_bytes();
+        let mut builder = SszDecoderBuilder::new(&bytes);
+        builder.register_type::<u16>().unwrap();
+        builder.register_rest().unwrap();
+        let mut decoder = builder.build().unwrap();
+        assert_eq!(decoder.decode_next::<u16>(), Ok(7));
+        assert_eq!(decoder.decode_next::<Vec<u8>>(), Ok(vec![]));
+    }
+
+    #[test]
+    fn nested_partial_decode() {
+        // A container `{ slot: u64, data: Vec<u16> }` where `data` is not a valid `Vec<u16>`.
+        let mut inner = vec![];
+        let mut encoder = SszEncoder::container(&mut inner, 8 + BYTES_PER_LENGTH_OFFSET);
+        encoder.append(&42_u64);
+        encoder.append(&vec![1_u8, 2, 3]);
+        encoder.finalize();
+
+        // A container `{ a: u8, inner: Inner, b: Vec<u8> }`.
+        let mut bytes = vec![];
+        let mut encoder = SszEncoder::container(&mut bytes, 1 + BYTES_PER_LENGTH_OFFSET * 2);
+        encoder.append(&7_u8);
+        encoder.append(&inner);
+        encoder.append(&vec![9_u8, 9]);
+        encoder.finalize();
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+        builder.register_type::<u8>().unwrap();
+        builder.register_anonymous_variable_length_item().unwrap();
+        builder.register_type::<Vec<u8>>().unwrap();
+        let mut decoder = builder.build().unwrap();
+
+        assert_eq!(decoder.decode_next::<u8>(), Ok(7));
+
+        let mut inner_builder = decoder.next_builder();
+        inner_builder.register_type::<u64>().unwrap();
+        inner_builder.register_type::<Vec<u16>>().unwrap();
+        let mut inner_decoder = inner_builder.build().unwrap();
+
+        assert_eq!(inner_decoder.decode_next::<u64>(), Ok(42));
+        assert!(inner_decoder.decode_next::<Vec<u16>>().is_err());
+
+        assert_eq!(decoder.decode_next::<Vec<u8>>(), Ok(vec![9, 9]));
+    }
+
+    #[test]
+    fn builder_rejects_decreasing_offsets() {
+        // A `u8`, followed by three `Vec<u8>` whose offsets are 13, 14 and then 13.
+        let bytes = vec![1, 13, 0, 0, 0, 14, 0, 0, 0, 13, 0, 0, 0, 2, 3];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u8>().unwrap();
+        builder.register_type::<Vec<u8>>().unwrap();
+        builder.register_type::<Vec<u8>>().unwrap();
+        assert_eq!(
+            builder.register_type::<Vec<u8>>(),
+            Err(DecodeError::OutOfBoundsByte { i: 13 })
+        );
+    }
+
+    #[test]
+    fn builder_rejects_offset_past_end() {
+        // A `u8`, followed by a `Vec<u8>` with an offset of `u32::max_value()`.
+        let mut bytes = vec![1];
+        bytes.extend_from_slice(&u32::max_value().to_le_bytes());
+        bytes.extend_from_slice(&[2, 3]);
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u8>().unwrap();
+        assert_eq!(
+            builder.register_type::<Vec<u8>>(),
+            Err(DecodeError::OutOfBoundsByte {
+                i: u32::max_value() as usize
+            })
+        );
+    }
+
+    #[test]
+    fn builder_rejects_first_offset_past_fixed_bytes() {
+        // A `u8`, followed by a `Vec<u8>` whose offset skips the first variable byte.
+        let bytes = vec![1, 6, 0, 0, 0, 2, 3];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u8>().unwrap();
+        builder.register_type::<Vec<u8>>().unwrap();
+        assert_eq!(
+            builder.build().err(),
+            Some(DecodeError::OutOfBoundsByte { i: 6 })
+        );
+    }
+
+    #[test]
+    fn builder_rejects_unconsumed_fixed_bytes() {
+        // A `u16` and a `u32`, followed by a trailing byte.
+        let bytes = vec![1, 0, 2, 0, 0, 0, 3];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u16>().unwrap();
+        builder.register_type::<u32>().unwrap();
+        assert_eq!(
+            builder.build().err(),
+            Some(DecodeError::BytesTooLong {
+                expected: 6,
+                got: 7
+            })
+        );
+    }
+
+    #[test]
+    fn decoder_bytes_consumed() {
+        // A `u8`, a `Vec<u16>` and a `u16`.
+        let bytes = vec![1, 7, 0, 0, 0, 3, 0, 4, 0, 5, 0];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u8>().unwrap();
+        builder.register_type::<Vec<u16>>().unwrap();
+        builder.register_type::<u16>().unwrap();
+        let mut decoder = builder.build().unwrap();
+        assert_eq!(decoder.bytes_consumed(), 0);
+
+        assert_eq!(decoder.decode_next::<u8>(), Ok(1));
+        assert_eq!(decoder.bytes_consumed(), 1);
+        assert_eq!(decoder.decode_next::<Vec<u16>>(), Ok(vec![4, 5]));
+        assert_eq!(decoder.bytes_consumed(), 9);
+        assert_eq!(decoder.decode_next::<u16>(), Ok(3));
+        assert_eq!(decoder.bytes_consumed(), bytes.len());
+    }
+
+    #[test]
+    fn builder_rejects_too_few_items() {
+        // A `u8` and a `u32`, of which only the `u8` is registered.
+        let bytes = vec![1, 2, 0, 0, 0];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u8>().unwrap();
+        assert_eq!(
+            builder.build().err(),
+            Some(DecodeError::BytesTooLong {
+                expected: 1,
+                got: 5
+            })
+        );
+    }
+
+    #[test]
+    fn register_type_with_length_too_long() {
+        let bytes = vec![1, 0, 2];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u16>().unwrap();
+        assert_eq!(
+            builder.register_type_with_length::<Vec<u8>>(2),
+            Err(DecodeError::BytesTooShort { need: 4, got: 3 })
+        );
+    }
+
+    #[test]
+    fn register_type_with_length_overflow() {
+        let bytes = vec![1, 0, 2];
+
+        let mut builder = SszDecoderBuilder::new(&bytes);
+
+        builder.register_type::<u16>().unwrap();
+        assert_eq!(
+            builder.register_type_with_length::<Vec<u8>>(usize::max_value()),
+            Err(DecodeError::BytesInvalid(format!(
+                "Item of {} bytes at index 2 overflows usize",
+                usize::max_value()
+            )))
+        );
+
+        // Failed registrations do not advance the builder.
+        builder.register_type_with_length::<Vec<u8>>(2).unwrap_err();
+        builder.register_type::<u8>().unwrap();
+
+        let mut decoder = builder.build().unwrap();
+        assert_eq!(decoder.decode_next::<u16>(), Ok(1));
+        assert_eq!(decoder.decode_next::<u8>(), Ok(2));
+    }
+}
@@ -1,6 +1,10 @@
 use super::*;
-use core::num::NonZeroUsize;
+use core::num::{NonZeroU32, NonZeroU64, NonZeroUsize, Wrapping};
+use core::ops::Range;
 use ethereum_types::{H256, U128, U256};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
 
 macro_rules! impl_decodable_for_uint {
     ($type: ident, $bit_size: expr) => {
@@ -18,7 +22,7 @@ macro_rules! impl_decodable_for_uint {
                 let expected = <Self as Decode>::ssz_fixed_len();
 
                 if len != expected {
-                    Err(DecodeError::InvalidByteLength { len, expected })
+                    Err(DecodeError::wrong_length(len, expected))
                 } else {
                     let mut array: [u8; $bit_size / 8] = std::default::Default::default();
                     array.clone_from_slice(bytes);
@@ -34,6 +38,7 @@ impl_decodable_for_uint!(u8, 8);
 impl_decodable_for_uint!(u16, 16);
 impl_decodable_for_uint!(u32, 32);
 impl_decodable_for_uint!(u64, 64);
+impl_decodable_for_uint!(u128, 128);
 
 #[cfg(target_pointer_width = "32")]
 impl_decodable_for_uint!(usize, 32);
@@ -202,7 +207,7 @@ impl Decode for bool {
         let expected = <Self as Decode>::ssz_fixed_len();
 
         if len != expected {
-            Err(DecodeError::InvalidByteLength { len, expected })
+            Err(DecodeError::wrong_length(len, expected))
         } else {
             match bytes[0] {
                 0b0000_0000 => Ok(false),
@@ -216,30 +221,109 @@ impl Decode for bool {
     }
 }
 
-impl Decode for NonZeroUsize {
+macro_rules! impl_decode_for_non_zero {
+    ($type: ident, $inner: ty) => {
+        /// Decoded identically to the inner integer, except that zero is rejected.
+        impl Decode for $type {
+            fn is_ssz_fixed_len() -> bool {
+                <$inner as Decode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <$inner as Decode>::ssz_fixed_len()
+            }
+
+            fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+                let x = <$inner>::from_ssz_bytes(bytes)?;
+
+                $type::new(x).ok_or_else(|| {
+                    DecodeError::BytesInvalid(
+                        concat!(stringify!($type), " cannot be zero.").to_string(),
+                    )
+                })
+            }
+        }
+    };
+}
+
+impl_decode_for_non_zero!(NonZeroUsize, usize);
+impl_decode_for_non_zero!(NonZeroU32, u32);
+impl_decode_for_non_zero!(NonZeroU64, u64);
+
+/// Decoded identically to the wrapped value.
+impl<T: Decode> Decode for Wrapping<T> {
     fn is_ssz_fixed_len() -> bool {
-        <usize as Decode>::is_ssz_fixed_len()
+        <T as Decode>::is_ssz_fixed_len()
     }
 
     fn ssz_fixed_len() -> usize {
-        <usize as Decode>::ssz_fixed_len()
+        <T as Decode>::ssz_fixed_len()
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        let x = usize::from_ssz_bytes(bytes)?;
+        T::from_ssz_bytes(bytes).map(Wrapping)
+    }
+}
+
+/// Decoded from the SSZ container `(start, end)`, rejecting ranges where `end < start`.
+impl<T: Decode + PartialOrd> Decode for Range<T> {
+    fn is_ssz_fixed_len() -> bool {
+        <(T, T) as Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <(T, T) as Decode>::ssz_fixed_len()
+    }
 
-        if x == 0 {
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let (start, end) = <(T, T)>::from_ssz_bytes(bytes)?;
+
+        if end < start {
             Err(DecodeError::BytesInvalid(
-                "NonZeroUsize cannot be zero.".to_string(),
+                "Range end cannot be less than start".to_string(),
             ))
         } else {
-            // `unwrap` is safe here as `NonZeroUsize::new()` succeeds if `x > 0` and this path
-            // never executes when `x == 0`.
-            Ok(NonZeroUsize::new(x).unwrap())
+            Ok(start..end)
         }
     }
 }
 
+impl<T> Decode for PhantomData<T> {
+    fn is_ssz_fixed_len() -> bool {
+        true
+    }
+
+    fn ssz_fixed_len() -> usize {
+        0
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        if bytes.is_empty() {
+            Ok(PhantomData)
+        } else {
+            Err(DecodeError::wrong_length(bytes.len(), 0))
+        }
+    }
+}
+
+/// Always decoded as `Cow::Owned`.
+impl<'a, T: ToOwned + ?Sized> Decode for Cow<'a, T>
+where
+    T::Owned: Decode,
+{
+    fn is_ssz_fixed_len() -> bool {
+        <T::Owned as Decode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T::Owned as Decode>::ssz_fixed_len()
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        <T::Owned>::from_ssz_bytes(bytes).map(Cow::Owned)
+    }
+}
+
 /// The SSZ union type.
 impl<T: Decode> Decode for Option<T> {
     fn is_ssz_fixed_len() -> bool {
@@ -248,9 +332,9 @@ impl<T: Decode> Decode for Option<T> {
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
         if bytes.len() < BYTES_PER_LENGTH_OFFSET {
-            return Err(DecodeError::InvalidByteLength {
-                len: bytes.len(),
-                expected: BYTES_PER_LENGTH_OFFSET,
+            return Err(DecodeError::BytesTooShort {
+                need: BYTES_PER_LENGTH_OFFSET,
+                got: bytes.len(),
             });
         }
 
@@ -284,7 +368,7 @@ impl Decode for H256 {
         let expected = <Self as Decode>::ssz_fixed_len();
 
         if len != expected {
-            Err(DecodeError::InvalidByteLength { len, expected })
+            Err(DecodeError::wrong_length(len, expected))
         } else {
             Ok(H256::from_slice(bytes))
         }
@@ -305,7 +389,7 @@ impl Decode for U256 {
         let expected = <Self as Decode>::ssz_fixed_len();
 
         if len != expected {
-            Err(DecodeError::InvalidByteLength { len, expected })
+            Err(DecodeError::wrong_length(len, expected))
         } else {
             Ok(U256::from_little_endian(bytes))
         }
@@ -326,43 +410,93 @@ impl Decode for U128 {
         let expected = <Self as Decode>::ssz_fixed_len();
 
         if len != expected {
-            Err(DecodeError::InvalidByteLength { len, expected })
+            Err(DecodeError::wrong_length(len, expected))
         } else {
             Ok(U128::from_little_endian(bytes))
         }
     }
 }
 
-macro_rules! impl_decodable_for_u8_array {
-    ($len: expr) => {
-        impl Decode for [u8; $len] {
+/// Decoded from an SSZ vector (i.e., without a length prefix) of exactly the length of the array.
+///
+/// The items are decoded directly into the array, which is written out as one expression per
+/// index (`$idx`) since arrays of non-`Copy` items cannot otherwise be built without `unsafe`.
+macro_rules! impl_decodable_for_array {
+    ($len: expr, $($idx: expr),+) => {
+        impl<T: Decode> Decode for [T; $len] {
             fn is_ssz_fixed_len() -> bool {
-                true
+                <T as Decode>::is_ssz_fixed_len()
             }
 
             fn ssz_fixed_len() -> usize {
-                $len
+                if <Self as Decode>::is_ssz_fixed_len() {
+                    <T as Decode>::ssz_fixed_len()
+                        .checked_mul($len)
+                        .expect("ssz_fixed_len of array overflows usize")
+                } else {
+                    BYTES_PER_LENGTH_OFFSET
+                }
             }
 
             fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-                let len = bytes.len();
-                let expected = <Self as Decode>::ssz_fixed_len();
+                if <T as Decode>::is_ssz_fixed_len() {
+                    let len = bytes.len();
+                    let expected = <Self as Decode>::ssz_fixed_len();
 
-                if len != expected {
-                    Err(DecodeError::InvalidByteLength { len, expected })
+                    if len != expected {
+                        return Err(DecodeError::wrong_length(len, expected));
+                    }
+
+                    // Zero-length items are each decoded from an empty slice.
+                    let item_len = <T as Decode>::ssz_fixed_len();
+                    let mut items = (0..$len).map(|i| {
+                        T::from_ssz_bytes(&bytes[i * item_len..(i + 1) * item_len])
+                            .map_err(|e| e.with_context(format!("[{}]", i)))
+                    });
+
+                    Ok([$(next_array_item(&mut items, $idx, $len)?),+])
                 } else {
-                    let mut array: [u8; $len] = [0; $len];
-                    array.copy_from_slice(&bytes[..]);
+                    let mut items = SszListIter::<T>::new(bytes)?;
+
+                    if items.len() != $len {
+                        return Err(DecodeError::BytesInvalid(format!(
+                            "Expected {} items for array, got {}",
+                            $len,
+                            items.len()
+                        )));
+                    }
 
-                    Ok(array)
+                    Ok([$(next_array_item(&mut items, $idx, $len)?),+])
                 }
             }
         }
     };
 }
 
-impl_decodable_for_u8_array!(4);
-impl_decodable_for_u8_array!(32);
+/// Returns the next of the `len` items of an array, which is at `index`.
+fn next_array_item<T>(
+    items: &mut impl Iterator<Item = Result<T, DecodeError>>,
+    index: usize,
+    len: usize,
+) -> Result<T, DecodeError> {
+    items.next().unwrap_or_else(|| {
+        Err(DecodeError::BytesInvalid(format!(
+            "Expected {} items for array, got {}",
+            len, index
+        )))
+    })
+}
+
+impl_decodable_for_array!(1, 0);
+impl_decodable_for_array!(2, 0, 1);
+impl_decodable_for_array!(3, 0, 1, 2);
+impl_decodable_for_array!(4, 0, 1, 2, 3);
+impl_decodable_for_array!(8, 0, 1, 2, 3, 4, 5, 6, 7);
+impl_decodable_for_array!(16, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15);
+impl_decodable_for_array!(
+    32, 0, 1, 2, 3, 4, 5, 6, 7, 8, 9, 10, 11, 12, 13, 14, 15, 16, 17, 18, 19, 20, 21, 22, 23, 24,
+    25, 26, 27, 28, 29, 30, 31
+);
 
 impl<T: Decode> Decode for Vec<T> {
     fn is_ssz_fixed_len() -> bool {
@@ -370,16 +504,96 @@ impl<T: Decode> Decode for Vec<T> {
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
-        if bytes.is_empty() {
-            Ok(vec![])
-        } else if T::is_ssz_fixed_len() {
-            bytes
-                .chunks(T::ssz_fixed_len())
-                .map(|chunk| T::from_ssz_bytes(chunk))
-                .collect()
-        } else {
-            decode_list_of_variable_length_items(bytes)
+        let mut values = vec![];
+        decode_list_into(bytes, &mut values)?;
+        Ok(values)
+    }
+}
+
+/// Clears `out` and decodes `bytes` into it as a list of `T`.
+///
+/// Implementation of `Decode::from_ssz_bytes_into`, which leaves `out` empty if an error occurs.
+pub(crate) fn decode_list_into<T: Decode>(
+    bytes: &[u8],
+    out: &mut Vec<T>,
+) -> Result<(), DecodeError> {
+    decode_bounded_list_into(bytes, usize::max_value(), out)
+}
+
+/// Decodes `bytes` as a list of at most `max_len` items of `T`, for either fixed- or
+/// variable-length `T`.
+///
+/// Unlike `Vec::<T>::from_ssz_bytes`, this is suitable for untrusted input: the number of items
+/// is checked against `max_len` before any items are allocated or decoded, returning
+/// `DecodeError::BytesInvalid` if there are too many.
+pub fn decode_bounded_list<T: Decode>(bytes: &[u8], max_len: usize) -> Result<Vec<T>, DecodeError> {
+    let mut values = vec![];
+    decode_bounded_list_into(bytes, max_len, &mut values)?;
+    Ok(values)
+}
+
+/// Clears `out` and decodes `bytes` into it as a list of at most `max_len` items of `T`.
+fn decode_bounded_list_into<T: Decode>(
+    bytes: &[u8],
+    max_len: usize,
+    out: &mut Vec<T>,
+) -> Result<(), DecodeError> {
+    out.clear();
+
+    if bytes.is_empty() {
+        Ok(())
+    } else if T::is_ssz_fixed_len() {
+        let item_len = T::ssz_fixed_len();
+
+        // The number of zero-length items cannot be inferred from the length of `bytes`.
+        if item_len == 0 {
+            return Err(zero_length_items_error());
+        }
+
+        let num_items = (bytes.len() + item_len - 1) / item_len;
+
+        if num_items > max_len {
+            return Err(list_too_long_error(num_items, max_len));
+        }
+
+        out.reserve(num_items);
+        for (i, chunk) in bytes.chunks(item_len).enumerate() {
+            out.push(T::from_ssz_bytes(chunk).map_err(|e| e.with_context(format!("[{}]", i)))?);
+        }
+        Ok(())
+    } else {
+        decode_variable_length_items_into(bytes, max_len, out)
+    }
+}
+
+fn zero_length_items_error() -> DecodeError {
+    DecodeError::BytesInvalid("Cannot decode a non-empty list of zero-length items".to_string())
+}
+
+fn list_too_long_error(num_items: usize, max_len: usize) -> DecodeError {
+    DecodeError::BytesInvalid(format!(
+        "List of {} items exceeds maximum of {}",
+        num_items, max_len
+    ))
+}
+
+/// Decoded from a list of `(key, value)` pairs, rejecting lists where the keys are not in strictly
+/// ascending order so that each map has exactly one encoding.
+impl<K: Decode + Ord, V: Decode> Decode for BTreeMap<K, V> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, DecodeError> {
+        let entries = <Vec<(K, V)>>::from_ssz_bytes(bytes)?;
+
+        if entries.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err(DecodeError::BytesInvalid(
+                "BTreeMap keys must be unique and in ascending order".to_string(),
+            ));
         }
+
+        Ok(entries.into_iter().collect())
     }
 }
 
@@ -391,33 +605,49 @@ impl<T: Decode> Decode for Vec<T> {
 pub fn decode_list_of_variable_length_items<T: Decode>(
     bytes: &[u8],
 ) -> Result<Vec<T>, DecodeError> {
-    let mut next_variable_byte = read_offset(bytes)?;
+    decode_list_of_variable_length_items_bounded(bytes, usize::max_value())
+}
 
-    // The value of the first offset must not point back into the same bytes that defined
-    // it.
-    if next_variable_byte < BYTES_PER_LENGTH_OFFSET {
-        return Err(DecodeError::OutOfBoundsByte {
-            i: next_variable_byte,
-        });
-    }
+/// Decodes `bytes` as if it were a list of at most `max_len` variable-length items.
+///
+/// The number of items is implied by the first offset, so it is checked against `max_len` (and
+/// the length of `bytes`) before any items are allocated or decoded.
+pub fn decode_list_of_variable_length_items_bounded<T: Decode>(
+    bytes: &[u8],
+    max_len: usize,
+) -> Result<Vec<T>, DecodeError> {
+    let mut values = vec![];
+    decode_variable_length_items_into(bytes, max_len, &mut values)?;
+    Ok(values)
+}
 
+/// Decodes `bytes` in the same manner as `decode_list_of_variable_length_items_bounded`, pushing
+/// the items to `out` (which should be empty).
+fn decode_variable_length_items_into<T: Decode>(
+    bytes: &[u8],
+    max_len: usize,
+    values: &mut Vec<T>,
+) -> Result<(), DecodeError> {
+    let mut next_variable_byte = read_first_offset(bytes)?;
     let num_items = next_variable_byte / BYTES_PER_LENGTH_OFFSET;
 
-    // The fixed-length section must be a clean multiple of `BYTES_PER_LENGTH_OFFSET`.
-    if next_variable_byte != num_items * BYTES_PER_LENGTH_OFFSET {
-        return Err(DecodeError::InvalidByteLength {
-            len: next_variable_byte,
-            expected: num_items * BYTES_PER_LENGTH_OFFSET,
-        });
+    if num_items > max_len {
+        return Err(list_too_long_error(num_items, max_len));
     }
 
-    let mut values = Vec::with_capacity(num_items);
+    values.reserve(num_items);
     for i in 1..=num_items {
         let slice_option = if i == num_items {
             bytes.get(next_variable_byte..)
         } else {
             let offset = read_offset(&bytes[(i * BYTES_PER_LENGTH_OFFSET)..])?;
 
+            // Offsets must be non-decreasing. By induction every offset is then at least the
+            // first, so no item can alias the bytes of the offsets themselves.
+            if offset < next_variable_byte {
+                return Err(DecodeError::OutOfBoundsByte { i: offset });
+            }
+
             let start = next_variable_byte;
             next_variable_byte = offset;
 
@@ -428,10 +658,162 @@ pub fn decode_list_of_variable_length_items<T: Decode>(
             i: next_variable_byte,
         })?;
 
-        values.push(T::from_ssz_bytes(slice)?);
+        // An empty item can only be valid if its type has some variable-length or empty encoding.
+        if slice.is_empty() && T::is_ssz_fixed_len() && T::ssz_fixed_len() > 0 {
+            return Err(DecodeError::ZeroLengthItem { index: i - 1 });
+        }
+
+        values.push(T::from_ssz_bytes(slice).map_err(|e| e.with_context(format!("[{}]", i - 1)))?);
     }
 
-    Ok(values)
+    Ok(())
+}
+
+/// Reads the first offset of a list of variable-length items, which marks the end of the
+/// fixed-length section, ensuring it is a multiple of `BYTES_PER_LENGTH_OFFSET` that lies within
+/// `bytes`.
+fn read_first_offset(bytes: &[u8]) -> Result<usize, DecodeError> {
+    let first_offset = read_offset(bytes)?;
+
+    // The value of the first offset must not point back into the same bytes that defined
+    // it.
+    if first_offset < BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::OutOfBoundsByte { i: first_offset });
+    }
+
+    let num_items = first_offset / BYTES_PER_LENGTH_OFFSET;
+
+    // The fixed-length section must be a clean multiple of `BYTES_PER_LENGTH_OFFSET`.
+    if first_offset != num_items * BYTES_PER_LENGTH_OFFSET {
+        return Err(DecodeError::InvalidByteLength {
+            len: first_offset,
+            expected: num_items * BYTES_PER_LENGTH_OFFSET,
+        });
+    }
+
+    // The fixed-length section must fit within the bytes, otherwise the number of items is
+    // nonsensical and should not be allocated.
+    if first_offset > bytes.len() {
+        return Err(DecodeError::OutOfBoundsByte { i: first_offset });
+    }
+
+    Ok(first_offset)
+}
+
+/// Lazily decodes the items of an SSZ list of `T`, without collecting them into a `Vec`.
+///
+/// For variable-length `T` the offsets are all read (and checked) by `SszListIter::new`, but each
+/// item is only decoded when it is yielded. Iteration ends after the first item which fails to
+/// decode.
+pub struct SszListIter<'a, T> {
+    bytes: &'a [u8],
+    /// The byte ranges of the items, if `T` is variable-length.
+    ///
+    /// Item `i` spans `offsets[i]..offsets[i + 1]`, with the length of `bytes` as the final entry.
+    offsets: Vec<usize>,
+    num_items: usize,
+    index: usize,
+    _phantom: PhantomData<T>,
+}
+
+impl<'a, T: Decode> SszListIter<'a, T> {
+    /// Prepare to decode `bytes` as a list of `T`, returning an error if the length of `bytes` or
+    /// its offsets are invalid.
+    pub fn new(bytes: &'a [u8]) -> Result<Self, DecodeError> {
+        let mut offsets = vec![];
+
+        let num_items = if bytes.is_empty() {
+            0
+        } else if T::is_ssz_fixed_len() {
+            let item_len = T::ssz_fixed_len();
+
+            if item_len == 0 {
+                return Err(zero_length_items_error());
+            }
+
+            let num_items = bytes.len() / item_len;
+
+            // A trailing partial item would be completed by more bytes.
+            if bytes.len() != num_items * item_len {
+                return Err(DecodeError::BytesTooShort {
+                    need: (num_items + 1) * item_len,
+                    got: bytes.len(),
+                });
+            }
+
+            num_items
+        } else {
+            let first_offset = read_first_offset(bytes)?;
+            let num_items = first_offset / BYTES_PER_LENGTH_OFFSET;
+
+            offsets.reserve(num_items + 1);
+            offsets.push(first_offset);
+            for i in 1..num_items {
+                let offset = read_offset(&bytes[(i * BYTES_PER_LENGTH_OFFSET)..])?;
+
+                // Offsets must be non-decreasing and lie within `bytes`.
+                if offset < offsets[i - 1] || offset > bytes.len() {
+                    return Err(DecodeError::OutOfBoundsByte { i: offset });
+                }
+
+                offsets.push(offset);
+            }
+            offsets.push(bytes.len());
+
+            num_items
+        };
+
+        Ok(Self {
+            bytes,
+            offsets,
+            num_items,
+            index: 0,
+            _phantom: PhantomData,
+        })
+    }
+
+    /// Returns the total number of items in the list, including those already yielded.
+    pub fn len(&self) -> usize {
+        self.num_items
+    }
+
+    /// Returns `true` if the list has no items.
+    pub fn is_empty(&self) -> bool {
+        self.num_items == 0
+    }
+}
+
+impl<'a, T: Decode> Iterator for SszListIter<'a, T> {
+    type Item = Result<T, DecodeError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.index >= self.num_items {
+            return None;
+        }
+
+        let i = self.index;
+        let range = if self.offsets.is_empty() {
+            let item_len = T::ssz_fixed_len();
+            i * item_len..(i + 1) * item_len
+        } else {
+            self.offsets[i]..self.offsets[i + 1]
+        };
+
+        let result =
+            T::from_ssz_bytes(&self.bytes[range]).map_err(|e| e.with_context(format!("[{}]", i)));
+        self.index = if result.is_ok() {
+            i + 1
+        } else {
+            self.num_items
+        };
+
+        Some(result)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let remaining = self.num_items - self.index;
+        (remaining, Some(remaining))
+    }
 }
 
 #[cfg(test)]
@@ -441,41 +823,545 @@ mod tests {
     // Note: decoding of valid bytes is generally tested "indirectly" in the `/tests` dir, by
     // encoding then decoding the element.
 
+    #[test]
+    fn bounded_list_at_max_len() {
+        let max_len = 4;
+
+        let fixed = vec![1_u16, 2, 3, 4];
+        assert_eq!(
+            decode_bounded_list::<u16>(&fixed.as_ssz_bytes(), max_len),
+            Ok(fixed.clone())
+        );
+        let mut too_long = fixed;
+        too_long.push(5);
+        assert_eq!(
+            decode_bounded_list::<u16>(&too_long.as_ssz_bytes(), max_len),
+            Err(DecodeError::BytesInvalid(
+                "List of 5 items exceeds maximum of 4".to_string()
+            ))
+        );
+
+        let variable = vec![vec![1_u8], vec![], vec![2, 3], vec![4]];
+        assert_eq!(
+            decode_bounded_list::<Vec<u8>>(&variable.as_ssz_bytes(), max_len),
+            Ok(variable.clone())
+        );
+        let mut too_long = variable;
+        too_long.push(vec![5]);
+        assert_eq!(
+            decode_bounded_list::<Vec<u8>>(&too_long.as_ssz_bytes(), max_len),
+            Err(DecodeError::BytesInvalid(
+                "List of 5 items exceeds maximum of 4".to_string()
+            ))
+        );
+
+        assert_eq!(decode_bounded_list::<u16>(&[], 0), Ok(vec![]));
+    }
+
+    #[test]
+    fn bounded_list_checks_len_before_decoding() {
+        // The items are invalid, but there are too many of them to be decoded at all.
+        let bytes = vec![2_u8; 5];
+        assert_eq!(
+            decode_bounded_list::<bool>(&bytes, 4),
+            Err(DecodeError::BytesInvalid(
+                "List of 5 items exceeds maximum of 4".to_string()
+            ))
+        );
+        assert!(decode_bounded_list::<bool>(&bytes, 5).is_err());
+    }
+
+    #[test]
+    fn list_item_error_context() {
+        let mut invalid = vec![vec![true], vec![false, true]].as_ssz_bytes();
+        *invalid.last_mut().unwrap() = 2;
+
+        let error = <Vec<Vec<bool>>>::from_ssz_bytes(&invalid).unwrap_err();
+        assert_eq!(
+            error,
+            DecodeError::BytesInvalid("Out-of-range for boolean: 2".to_string())
+                .with_context("[1]")
+                .with_context("[1]")
+        );
+        assert_eq!(
+            error.to_string(),
+            "[1][1]: BytesInvalid(\"Out-of-range for boolean: 2\")"
+        );
+
+        // The lazy iterator reports the same location.
+        let mut iter = SszListIter::<Vec<bool>>::new(&invalid).unwrap();
+        assert!(iter.next().unwrap().is_ok());
+        assert_eq!(iter.next().unwrap(), Err(error));
+    }
+
+    #[test]
+    fn bytes_ref_borrows() {
+        let bytes = vec![1_u8, 2, 3, 4, 5].as_ssz_bytes();
+        let decoded = SszBytesRef::from_ssz_bytes_ref(&bytes).unwrap();
+        assert_eq!(decoded.0, &[1, 2, 3, 4, 5]);
+        assert_eq!(decoded.0.as_ptr(), bytes.as_ptr());
+        assert_eq!(decoded.as_ssz_bytes(), bytes);
+
+        // Within a container, the field points into the bytes of the container.
+        let bytes = (42_u64, vec![6_u8, 7, 8], 9_u16).as_ssz_bytes();
+        let mut builder = SszDecoderBuilder::new(&bytes);
+        builder.register_type_ref::<u64>().unwrap();
+        builder.register_type_ref::<SszBytesRef>().unwrap();
+        builder.register_type_ref::<u16>().unwrap();
+        let mut decoder = builder.build().unwrap();
+
+        assert_eq!(decoder.decode_next_ref::<u64>(), Ok(42));
+        let field: SszBytesRef = decoder.decode_next_ref().unwrap();
+        assert_eq!(decoder.decode_next_ref::<u16>(), Ok(9));
+        assert_eq!(field.0, &[6, 7, 8]);
+        assert_eq!(field.0.as_ptr(), bytes[bytes.len() - 3..].as_ptr());
+    }
+
+    #[test]
+    fn variable_length_list_too_long() {
+        // The first offset implies over a billion items, but only a few bytes follow.
+        let mut bytes = (u32::max_value() - 3).as_ssz_bytes();
+        bytes.extend_from_slice(&[0; 4]);
+
+        assert_eq!(
+            decode_list_of_variable_length_items::<Vec<u8>>(&bytes),
+            Err(DecodeError::OutOfBoundsByte {
+                i: u32::max_value() as usize - 3
+            })
+        );
+
+        // Three items, where the first offset is within bounds.
+        let bytes = vec![vec![1_u8], vec![2], vec![3]].as_ssz_bytes();
+
+        assert_eq!(
+            decode_list_of_variable_length_items_bounded::<Vec<u8>>(&bytes, 2),
+            Err(DecodeError::BytesInvalid(
+                "List of 3 items exceeds maximum of 2".to_string()
+            ))
+        );
+        assert_eq!(
+            decode_list_of_variable_length_items_bounded::<Vec<u8>>(&bytes, 3),
+            Ok(vec![vec![1], vec![2], vec![3]])
+        );
+    }
+
+    #[test]
+    fn random_offset_tables() {
+        // A tiny xorshift generator, so the test is deterministic.
+        let mut state = 0x2545_f491_u32;
+        let mut next = move || {
+            state ^= state << 13;
+            state ^= state >> 17;
+            state ^= state << 5;
+            state
+        };
+
+        for _ in 0..10_000 {
+            let num_offsets = next() as usize % 5 + 1;
+            let data_len = next() as usize % 12;
+
+            // Offsets which are mostly near the fixed-length section, so that some are valid.
+            let mut bytes = vec![];
+            for _ in 0..num_offsets {
+                let offset = next() % (num_offsets as u32 * 4 + data_len as u32 + 2);
+                bytes.extend_from_slice(&offset.as_ssz_bytes());
+            }
+            bytes.extend((0..data_len).map(|_| next() as u8));
+
+            match <Vec<Vec<u8>>>::from_ssz_bytes(&bytes) {
+                // A successful decoding must be canonical and agree with `SszListIter`.
+                Ok(values) => {
+                    assert_eq!(values.as_ssz_bytes(), bytes);
+                    assert_eq!(
+                        SszListIter::<Vec<u8>>::new(&bytes)
+                            .unwrap()
+                            .collect::<Result<Vec<_>, _>>(),
+                        Ok(values)
+                    );
+                }
+                Err(_) => assert!(SszListIter::<Vec<u8>>::new(&bytes)
+                    .map_or(true, |iter| iter.collect::<Result<Vec<_>, _>>().is_err())),
+            }
+        }
+
+        // The second offset points back into the offsets.
+        let mut bytes = 8_u32.as_ssz_bytes();
+        bytes.extend_from_slice(&4_u32.as_ssz_bytes());
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_bytes(&bytes),
+            Err(DecodeError::OutOfBoundsByte { i: 4 })
+        );
+    }
+
+    #[test]
+    fn zero_length_item() {
+        // Two `u16` items, where the second has equal start and end offsets.
+        let bytes = vec![8, 0, 0, 0, 10, 0, 0, 0, 1, 0];
+        assert_eq!(
+            decode_list_of_variable_length_items::<u16>(&bytes),
+            Err(DecodeError::ZeroLengthItem { index: 1 })
+        );
+
+        // The first item is empty.
+        let bytes = vec![8, 0, 0, 0, 8, 0, 0, 0, 1, 0];
+        assert_eq!(
+            decode_list_of_variable_length_items::<u16>(&bytes),
+            Err(DecodeError::ZeroLengthItem { index: 0 })
+        );
+
+        // An empty item is a valid encoding of variable-length and zero-length types.
+        let bytes = vec![vec![], vec![1_u8], vec![]].as_ssz_bytes();
+        assert_eq!(
+            decode_list_of_variable_length_items::<Vec<u8>>(&bytes),
+            Ok(vec![vec![], vec![1], vec![]])
+        );
+        let bytes = vec![4, 0, 0, 0];
+        assert_eq!(
+            decode_list_of_variable_length_items::<PhantomData<u8>>(&bytes),
+            Ok(vec![PhantomData])
+        );
+    }
+
+    #[test]
+    fn list_iter_fixed_len() {
+        let items: Vec<u16> = vec![1, 2, 3, 0xffff];
+        let bytes = items.as_ssz_bytes();
+
+        let iter = SszListIter::<u16>::new(&bytes).unwrap();
+        assert_eq!(iter.size_hint(), (4, Some(4)));
+        assert_eq!(iter.collect::<Result<Vec<_>, _>>(), Ok(items));
+
+        assert_eq!(
+            SszListIter::<u16>::new(&[0; 3]).err(),
+            Some(DecodeError::BytesTooShort { need: 4, got: 3 })
+        );
+    }
+
+    /// An offset of `u32::max_value()` is `MAX_LENGTH_VALUE` (and, on 32-bit targets,
+    /// `usize::max_value()`), which must be rejected without overflowing.
+    #[test]
+    fn max_offset_rejected() {
+        let max = u32::max_value() as usize;
+        assert_eq!(max, MAX_LENGTH_VALUE);
+
+        let mut bytes = vec![vec![1_u8], vec![2]].as_ssz_bytes();
+        bytes[4..8].copy_from_slice(&u32::max_value().to_le_bytes());
+
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_bytes(&bytes),
+            Err(DecodeError::OutOfBoundsByte { i: max })
+        );
+        assert_eq!(
+            SszListIter::<Vec<u8>>::new(&bytes).err(),
+            Some(DecodeError::OutOfBoundsByte { i: max })
+        );
+
+        // The largest first offset which is a multiple of `BYTES_PER_LENGTH_OFFSET`.
+        let bytes = (max - 3).to_le_bytes()[..BYTES_PER_LENGTH_OFFSET].to_vec();
+        assert_eq!(
+            <Vec<Vec<u8>>>::from_ssz_bytes(&bytes),
+            Err(DecodeError::OutOfBoundsByte { i: max - 3 })
+        );
+    }
+
+    #[test]
+    fn list_iter_invalid_offsets() {
+        // The second offset points beyond the end of the bytes.
+        let mut bytes = vec![vec![1_u8], vec![2]].as_ssz_bytes();
+        bytes[4] = 100;
+        assert_eq!(
+            SszListIter::<Vec<u8>>::new(&bytes).err(),
+            Some(DecodeError::OutOfBoundsByte { i: 100 })
+        );
+
+        // The second offset is less than the first.
+        let mut bytes = vec![vec![1_u8], vec![2]].as_ssz_bytes();
+        bytes[4] = 7;
+        assert_eq!(
+            SszListIter::<Vec<u8>>::new(&bytes).err(),
+            Some(DecodeError::OutOfBoundsByte { i: 7 })
+        );
+    }
+
+    #[test]
+    fn list_iter_stops_after_error() {
+        // The second item is an invalid `bool`.
+        let mut iter = SszListIter::<bool>::new(&[1, 2, 0]).unwrap();
+
+        assert_eq!(iter.next(), Some(Ok(true)));
+        assert!(iter.next().unwrap().is_err());
+        assert_eq!(iter.next(), None);
+    }
+
+    #[test]
+    fn phantom_data() {
+        assert_eq!(<PhantomData<u64>>::from_ssz_bytes(&[]), Ok(PhantomData));
+        assert_eq!(
+            <PhantomData<u64>>::from_ssz_bytes(&[0]),
+            Err(DecodeError::BytesTooLong {
+                expected: 0,
+                got: 1
+            })
+        );
+    }
+
+    #[test]
+    fn btree_map_unordered_keys() {
+        let error = Err(DecodeError::BytesInvalid(
+            "BTreeMap keys must be unique and in ascending order".to_string(),
+        ));
+
+        let unordered = vec![(2_u64, 20_u16), (1, 10)].as_ssz_bytes();
+        assert_eq!(BTreeMap::<u64, u16>::from_ssz_bytes(&unordered), error);
+
+        let duplicated = vec![(1_u64, 10_u16), (1, 20)].as_ssz_bytes();
+        assert_eq!(BTreeMap::<u64, u16>::from_ssz_bytes(&duplicated), error);
+
+        let ordered = vec![(1_u64, 10_u16), (2, 20)].as_ssz_bytes();
+        assert_eq!(
+            BTreeMap::<u64, u16>::from_ssz_bytes(&ordered),
+            Ok(vec![(1, 10), (2, 20)].into_iter().collect())
+        );
+    }
+
+    #[test]
+    fn inverted_range() {
+        assert_eq!(
+            <Range<u64>>::from_ssz_bytes(&(2_u64, 1_u64).as_ssz_bytes()),
+            Err(DecodeError::BytesInvalid(
+                "Range end cannot be less than start".to_string()
+            ))
+        );
+
+        assert_eq!(
+            <Range<u64>>::from_ssz_bytes(&(1_u64, 1_u64).as_ssz_bytes()),
+            Ok(1..1)
+        );
+    }
+
+    #[test]
+    fn uints_wrong_length() {
+        macro_rules! assert_wrong_lengths {
+            ($type: ident, $len: expr) => {
+                assert_eq!(
+                    $type::from_ssz_bytes(&[0; $len - 1]),
+                    Err(DecodeError::BytesTooShort {
+                        need: $len,
+                        got: $len - 1
+                    })
+                );
+                assert_eq!(
+                    $type::from_ssz_bytes(&[0; $len + 1]),
+                    Err(DecodeError::BytesTooLong {
+                        expected: $len,
+                        got: $len + 1
+                    })
+                );
+                assert_eq!(
+                    $type::from_ssz_bytes(&[1; $len]),
+                    Ok($type::from_le_bytes([1; $len]))
+                );
+            };
+        }
+
+        assert_wrong_lengths!(u8, 1);
+        assert_wrong_lengths!(u16, 2);
+        assert_wrong_lengths!(u32, 4);
+        assert_wrong_lengths!(u64, 8);
+        assert_wrong_lengths!(u128, 16);
+    }
+
+    #[test]
+    fn option() {
+        assert_eq!(<Option<u16>>::from_ssz_bytes(&[0, 0, 0, 0]), Ok(None));
+        assert_eq!(
+            <Option<u16>>::from_ssz_bytes(&[1, 0, 0, 0, 42, 0]),
+            Ok(Some(42))
+        );
+        assert_eq!(
+            <Option<Vec<u16>>>::from_ssz_bytes(&[1, 0, 0, 0, 1, 0, 2, 0]),
+            Ok(Some(vec![1, 2]))
+        );
+        assert_eq!(
+            <Option<u16>>::from_ssz_bytes(&[2, 0, 0, 0, 42, 0]),
+            Err(DecodeError::BytesInvalid(
+                "2 is not a valid union index for Option<T>".to_string()
+            ))
+        );
+        assert_eq!(
+            <Option<u16>>::from_ssz_bytes(&[0, 0, 0]),
+            Err(DecodeError::BytesTooShort {
+                need: BYTES_PER_LENGTH_OFFSET,
+                got: 3
+            })
+        );
+    }
+
+    #[test]
+    fn u256() {
+        assert_eq!(<U256 as Decode>::ssz_fixed_len(), 32);
+        assert_eq!(U256::from_ssz_bytes(&[0; 32]), Ok(U256::zero()));
+        assert_eq!(U256::from_ssz_bytes(&[255; 32]), Ok(U256::max_value()));
+
+        let mut bytes = [0; 32];
+        bytes[0] = 1;
+        assert_eq!(U256::from_ssz_bytes(&bytes), Ok(U256::from(1)));
+
+        assert_eq!(
+            U256::from_ssz_bytes(&[0; 31]),
+            Err(DecodeError::BytesTooShort { need: 32, got: 31 })
+        );
+        assert_eq!(
+            U256::from_ssz_bytes(&[0; 33]),
+            Err(DecodeError::BytesTooLong {
+                expected: 32,
+                got: 33
+            })
+        );
+    }
+
+    #[test]
+    fn u64_too_short() {
+        assert_eq!(
+            u64::from_ssz_bytes(&[0; 7]),
+            Err(DecodeError::BytesTooShort { need: 8, got: 7 })
+        );
+        assert_eq!(
+            u64::from_ssz_bytes(&[]),
+            Err(DecodeError::BytesTooShort { need: 8, got: 0 })
+        );
+    }
+
+    #[test]
+    fn u64_too_long() {
+        assert_eq!(
+            u64::from_ssz_bytes(&[0; 9]),
+            Err(DecodeError::BytesTooLong {
+                expected: 8,
+                got: 9
+            })
+        );
+    }
+
     #[test]
     fn invalid_u8_array_4() {
         assert_eq!(
             <[u8; 4]>::from_ssz_bytes(&[0; 3]),
-            Err(DecodeError::InvalidByteLength {
-                len: 3,
-                expected: 4
-            })
+            Err(DecodeError::BytesTooShort { need: 4, got: 3 })
         );
 
         assert_eq!(
             <[u8; 4]>::from_ssz_bytes(&[0; 5]),
-            Err(DecodeError::InvalidByteLength {
-                len: 5,
-                expected: 4
+            Err(DecodeError::BytesTooLong {
+                expected: 4,
+                got: 5
+            })
+        );
+    }
+
+    #[test]
+    fn fixed_len_array() {
+        let bytes = [1_u16, 2, 3, 4].as_ssz_bytes();
+
+        assert_eq!(<[u16; 4]>::from_ssz_bytes(&bytes), Ok([1, 2, 3, 4]));
+        assert_eq!(
+            <[u16; 4]>::from_ssz_bytes(&bytes[..6]),
+            Err(DecodeError::BytesTooShort { need: 8, got: 6 })
+        );
+        assert_eq!(
+            <[u16; 3]>::from_ssz_bytes(&bytes),
+            Err(DecodeError::BytesTooLong {
+                expected: 6,
+                got: 8
+            })
+        );
+    }
+
+    #[test]
+    fn variable_len_array() {
+        let bytes = vec![vec![1_u16], vec![2, 3]].as_ssz_bytes();
+
+        assert_eq!(
+            <[Vec<u16>; 2]>::from_ssz_bytes(&bytes),
+            Ok([vec![1], vec![2, 3]])
+        );
+        assert_eq!(
+            <[Vec<u16>; 3]>::from_ssz_bytes(&bytes),
+            Err(DecodeError::BytesInvalid(
+                "Expected 3 items for array, got 2".to_string()
+            ))
+        );
+    }
+
+    #[test]
+    fn array_of_zero_length_items() {
+        assert_eq!(
+            <[PhantomData<u8>; 4]>::from_ssz_bytes(&[]),
+            Ok([PhantomData; 4])
+        );
+        assert_eq!(
+            <[PhantomData<u8>; 4]>::from_ssz_bytes(&[1]),
+            Err(DecodeError::BytesTooLong {
+                expected: 0,
+                got: 1
             })
         );
+        assert_eq!(
+            <[[PhantomData<u8>; 2]; 2]>::from_ssz_bytes(&[]),
+            Ok([[PhantomData; 2]; 2])
+        );
+    }
+
+    #[test]
+    fn array_of_non_default_items() {
+        let bytes = [1_u32, 2].as_ssz_bytes();
+
+        assert_eq!(
+            <[NonZeroU32; 2]>::from_ssz_bytes(&bytes),
+            Ok([NonZeroU32::new(1).unwrap(), NonZeroU32::new(2).unwrap()])
+        );
+        assert_eq!(
+            <[NonZeroU32; 2]>::from_ssz_bytes(&[1, 0, 0, 0, 0, 0, 0, 0]),
+            Err(DecodeError::Context {
+                location: "[1]".to_string(),
+                source: Box::new(DecodeError::BytesInvalid(
+                    "NonZeroU32 cannot be zero.".to_string()
+                )),
+            })
+        );
+    }
+
+    #[test]
+    fn invalid_u8_array_32() {
+        assert_eq!(
+            <[u8; 32]>::from_ssz_bytes(&[0; 31]),
+            Err(DecodeError::BytesTooShort { need: 32, got: 31 })
+        );
+
+        assert_eq!(
+            <[u8; 32]>::from_ssz_bytes(&[0; 33]),
+            Err(DecodeError::BytesTooLong {
+                expected: 32,
+                got: 33
+            })
+        );
+
+        assert_eq!(<[u8; 32]>::from_ssz_bytes(&[5; 32]), Ok([5; 32]));
     }
 
     #[test]
     fn invalid_bool() {
         assert_eq!(
             bool::from_ssz_bytes(&[0; 2]),
-            Err(DecodeError::InvalidByteLength {
-                len: 2,
-                expected: 1
+            Err(DecodeError::BytesTooLong {
+                expected: 1,
+                got: 2
             })
         );
 
         assert_eq!(
             bool::from_ssz_bytes(&[]),
-            Err(DecodeError::InvalidByteLength {
-                len: 0,
-                expected: 1
-            })
+            Err(DecodeError::BytesTooShort { need: 1, got: 0 })
         );
 
         if let Err(DecodeError::BytesInvalid(_)) = bool::from_ssz_bytes(&[2]) {
@@ -483,24 +1369,31 @@ mod tests {
         } else {
             panic!("Did not return error on invalid bool val")
         }
+
+        for byte in 2..=u8::max_value() {
+            assert_eq!(
+                bool::from_ssz_bytes(&[byte]),
+                Err(DecodeError::BytesInvalid(format!(
+                    "Out-of-range for boolean: {}",
+                    byte
+                )))
+            );
+        }
     }
 
     #[test]
     fn invalid_h256() {
         assert_eq!(
             H256::from_ssz_bytes(&[0; 33]),
-            Err(DecodeError::InvalidByteLength {
-                len: 33,
-                expected: 32
+            Err(DecodeError::BytesTooLong {
+                expected: 32,
+                got: 33
             })
         );
 
         assert_eq!(
             H256::from_ssz_bytes(&[0; 31]),
-            Err(DecodeError::InvalidByteLength {
-                len: 31,
-                expected: 32
-            })
+            Err(DecodeError::BytesTooShort { need: 32, got: 31 })
         );
     }
 
@@ -578,25 +1471,19 @@ mod tests {
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[255]),
-            Err(DecodeError::InvalidByteLength {
-                len: 1,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 1 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[]),
-            Err(DecodeError::InvalidByteLength {
-                len: 0,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 0 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[0, 1, 2]),
-            Err(DecodeError::InvalidByteLength {
-                len: 3,
-                expected: 2
+            Err(DecodeError::BytesTooLong {
+                expected: 2,
+                got: 3
             })
         );
     }
@@ -614,29 +1501,112 @@ mod tests {
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[255]),
-            Err(DecodeError::InvalidByteLength {
-                len: 1,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 1 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[]),
-            Err(DecodeError::InvalidByteLength {
-                len: 0,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 0 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[0, 1, 2]),
-            Err(DecodeError::InvalidByteLength {
-                len: 3,
-                expected: 2
+            Err(DecodeError::BytesTooLong {
+                expected: 2,
+                got: 3
             })
         );
     }
 
+    #[test]
+    fn from_ssz_bytes_into_reuses_capacity() {
+        let bytes = vec![0u16, 1, 2, 3].as_ssz_bytes();
+        let mut out = Vec::with_capacity(16);
+        out.push(42u16);
+        let ptr = out.as_ptr();
+
+        assert_eq!(u16::from_ssz_bytes_into(&bytes, &mut out), Ok(()));
+        assert_eq!(Ok(out.clone()), <Vec<u16>>::from_ssz_bytes(&bytes));
+        assert_eq!(out.capacity(), 16);
+        assert_eq!(out.as_ptr(), ptr);
+
+        let bytes = vec![vec![1u8], vec![], vec![2, 3]].as_ssz_bytes();
+        let mut out = Vec::with_capacity(16);
+        out.push(vec![42u8]);
+        let ptr = out.as_ptr();
+
+        assert_eq!(<Vec<u8>>::from_ssz_bytes_into(&bytes, &mut out), Ok(()));
+        assert_eq!(Ok(out.clone()), <Vec<Vec<u8>>>::from_ssz_bytes(&bytes));
+        assert_eq!(out.capacity(), 16);
+        assert_eq!(out.as_ptr(), ptr);
+
+        // The buffer is left empty on failure.
+        assert!(<Vec<u8>>::from_ssz_bytes_into(&bytes[1..], &mut out).is_err());
+        assert!(out.is_empty());
+        assert_eq!(out.capacity(), 16);
+    }
+
+    #[test]
+    fn list_of_zero_length_items() {
+        let error = DecodeError::BytesInvalid(
+            "Cannot decode a non-empty list of zero-length items".to_string(),
+        );
+
+        assert_eq!(
+            <Vec<PhantomData<u8>>>::from_ssz_bytes(&[1]),
+            Err(error.clone())
+        );
+        assert_eq!(
+            decode_bounded_list::<PhantomData<u8>>(&[1, 2], 4),
+            Err(error.clone())
+        );
+
+        let mut out = vec![PhantomData];
+        assert_eq!(
+            PhantomData::<u8>::from_ssz_bytes_into(&[1], &mut out),
+            Err(error)
+        );
+        assert!(out.is_empty());
+
+        assert_eq!(<Vec<PhantomData<u8>>>::from_ssz_bytes(&[]), Ok(vec![]));
+    }
+
+    #[test]
+    fn non_zero_integers() {
+        assert_eq!(
+            NonZeroU32::from_ssz_bytes(&[1, 0, 0, 0]),
+            Ok(NonZeroU32::new(1).unwrap())
+        );
+        assert_eq!(
+            NonZeroU32::from_ssz_bytes(&[0; 4]),
+            Err(DecodeError::BytesInvalid(
+                "NonZeroU32 cannot be zero.".to_string()
+            ))
+        );
+        assert_eq!(
+            NonZeroU32::from_ssz_bytes(&[1, 0]),
+            Err(DecodeError::BytesTooShort { need: 4, got: 2 })
+        );
+
+        assert_eq!(
+            NonZeroU64::from_ssz_bytes(&[0, 1, 0, 0, 0, 0, 0, 0]),
+            Ok(NonZeroU64::new(256).unwrap())
+        );
+        assert_eq!(
+            NonZeroU64::from_ssz_bytes(&[0; 8]),
+            Err(DecodeError::BytesInvalid(
+                "NonZeroU64 cannot be zero.".to_string()
+            ))
+        );
+
+        assert_eq!(
+            NonZeroUsize::from_ssz_bytes(&0_usize.as_ssz_bytes()),
+            Err(DecodeError::BytesInvalid(
+                "NonZeroUsize cannot be zero.".to_string()
+            ))
+        );
+    }
+
     #[test]
     fn u16() {
         assert_eq!(<u16>::from_ssz_bytes(&[0, 0]), Ok(0));
@@ -646,25 +1616,19 @@ mod tests {
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[255]),
-            Err(DecodeError::InvalidByteLength {
-                len: 1,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 1 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[]),
-            Err(DecodeError::InvalidByteLength {
-                len: 0,
-                expected: 2
-            })
+            Err(DecodeError::BytesTooShort { need: 2, got: 0 })
         );
 
         assert_eq!(
             <u16>::from_ssz_bytes(&[0, 1, 2]),
-            Err(DecodeError::InvalidByteLength {
-                len: 3,
-                expected: 2
+            Err(DecodeError::BytesTooLong {
+                expected: 2,
+                got: 3
             })
         );
     }
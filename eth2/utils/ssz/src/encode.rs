@@ -1,7 +1,16 @@
 use super::*;
+use std::io::{self, Write};
 
 mod impls;
 
+/// Returned when checked SSZ encoding fails.
+#[derive(Debug, PartialEq, Clone)]
+pub enum EncodeError {
+    /// The encoding would be `len` bytes, so some offset or length within it may not fit in
+    /// `BYTES_PER_LENGTH_OFFSET` bytes.
+    TooLong { len: usize },
+}
+
 /// Provides SSZ encoding (serialization) via the `as_ssz_bytes(&self)` method.
 ///
 /// See `examples/` for manual implementations or the crate root for implementations using
@@ -14,10 +23,27 @@ pub trait Encode {
 
     /// Append the encoding `self` to `buf`.
     ///
+    /// Any existing bytes in `buf` are left untouched, so many objects may be encoded into a
+    /// single buffer without allocating a `Vec` for each of them.
+    ///
     /// Note, variable length objects need only to append their "variable length" portion, they do
     /// not need to provide their offset.
     fn ssz_append(&self, buf: &mut Vec<u8>);
 
+    /// Append the encodings of `items`, one after another, to `buf`.
+    ///
+    /// Used by lists and vectors of fixed-length items. The default implementation appends each
+    /// item in turn; `u8` overrides it so that a byte slice is copied at once.
+    #[doc(hidden)]
+    fn ssz_append_slice(items: &[Self], buf: &mut Vec<u8>)
+    where
+        Self: Sized,
+    {
+        for item in items {
+            item.ssz_append(buf);
+        }
+    }
+
     /// The number of bytes this object occupies in the fixed-length portion of the SSZ bytes.
     ///
     /// By default, this is set to `BYTES_PER_LENGTH_OFFSET` which is suitable for variable length
@@ -37,12 +63,23 @@ pub trait Encode {
     ///
     /// The default implementation of this method should suffice for most cases.
     fn as_ssz_bytes(&self) -> Vec<u8> {
-        let mut buf = vec![];
+        let mut buf = Vec::with_capacity(self.ssz_bytes_len());
 
         self.ssz_append(&mut buf);
 
         buf
     }
+
+    /// Write the full-form encoding of this object to `writer`.
+    ///
+    /// The default implementation encodes `self` into an intermediate buffer, which suffices for
+    /// small objects. Lists and containers write each of their items to `writer` directly, so
+    /// that a large object need not be encoded into memory in its entirety.
+    ///
+    /// Many small writes may be made to `writer`, so it should be buffered.
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(&self.as_ssz_bytes())
+    }
 }
 
 /// Allow for encoding an ordered series of distinct or indistinct objects as SSZ bytes.
@@ -86,6 +123,7 @@ pub trait Encode {
 /// ```
 pub struct SszEncoder<'a> {
     offset: usize,
+    start: usize,
     buf: &'a mut Vec<u8>,
     variable_bytes: Vec<u8>,
 }
@@ -104,12 +142,16 @@ impl<'a> SszEncoder<'a> {
 
         Self {
             offset: num_fixed_bytes,
+            start: buf.len(),
             buf,
             variable_bytes: vec![],
         }
     }
 
     /// Append some `item` to the SSZ bytes.
+    ///
+    /// Space for a variable-length item is reserved up front using `Encode::ssz_bytes_len`, so
+    /// that it is not reallocated as it is encoded.
     pub fn append<T: Encode>(&mut self, item: &T) {
         if T::is_ssz_fixed_len() {
             item.ssz_append(&mut self.buf);
@@ -117,10 +159,34 @@ impl<'a> SszEncoder<'a> {
             self.buf
                 .append(&mut encode_length(self.offset + self.variable_bytes.len()));
 
+            self.variable_bytes.reserve(item.ssz_bytes_len());
             item.ssz_append(&mut self.variable_bytes);
         }
     }
 
+    /// Append `bytes` verbatim to the fixed-length portion, such as the unknown trailing fields
+    /// captured by `SszDecoderBuilder::register_rest`.
+    ///
+    /// This must be the last item appended, and `bytes.len()` must be included in the
+    /// `num_fixed_bytes` given when `self` was instantiated.
+    pub fn append_rest(&mut self, bytes: &[u8]) {
+        self.buf.extend_from_slice(bytes);
+    }
+
+    /// Returns the number of bytes appended by `self` so far, including both the fixed-length
+    /// bytes and any variable-length bytes that are yet to be written by `finalize`.
+    ///
+    /// Bytes that were already present in the buffer before `self` was instantiated are not
+    /// counted.
+    pub fn len(&self) -> usize {
+        self.buf.len() - self.start + self.variable_bytes.len()
+    }
+
+    /// Returns `true` if no bytes have been appended by `self`.
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
     /// Write the variable bytes to `self.bytes`.
     ///
     /// This method must be called after the final `append(..)` call when serializing
@@ -132,6 +198,91 @@ impl<'a> SszEncoder<'a> {
     }
 }
 
+/// Allow for writing an ordered series of distinct or indistinct objects as SSZ bytes to some
+/// `io::Write`, without first encoding them into memory.
+///
+/// Unlike `SszEncoder`, variable-length items are not buffered. Instead, the offsets are computed
+/// from `Encode::ssz_bytes_len`, so **`append_fixed(..)` must be called for every item, followed
+/// by `append_variable(..)` for every item, in the same order**.
+///
+/// ## Example
+///
+/// Use `SszWriter` to produce identical output to `foo.as_ssz_bytes()`:
+///
+/// ```rust
+/// use ssz_derive::{Encode, Decode};
+/// use ssz::{Decode, Encode, SszWriter};
+///
+/// #[derive(PartialEq, Debug, Encode, Decode)]
+/// struct Foo {
+///     a: u64,
+///     b: Vec<u16>,
+/// }
+///
+/// fn ssz_write_example() {
+///     let foo = Foo {
+///         a: 42,
+///         b: vec![1, 3, 3, 7]
+///     };
+///
+///     let mut buf: Vec<u8> = vec![];
+///     let offset = <u64 as Encode>::ssz_fixed_len() + <Vec<u16> as Encode>::ssz_fixed_len();
+///
+///     let mut writer = SszWriter::container(&mut buf, offset);
+///
+///     writer.append_fixed(&foo.a).unwrap();
+///     writer.append_fixed(&foo.b).unwrap();
+///
+///     writer.append_variable(&foo.a).unwrap();
+///     writer.append_variable(&foo.b).unwrap();
+///
+///     assert_eq!(foo.as_ssz_bytes(), buf);
+/// }
+///
+/// ```
+pub struct SszWriter<'a, W> {
+    offset: usize,
+    writer: &'a mut W,
+}
+
+impl<'a, W: Write> SszWriter<'a, W> {
+    /// Instantiate a new writer for writing a SSZ list.
+    ///
+    /// Identical to `Self::container`.
+    pub fn list(writer: &'a mut W, num_fixed_bytes: usize) -> Self {
+        Self::container(writer, num_fixed_bytes)
+    }
+
+    /// Instantiate a new writer for writing a SSZ container.
+    pub fn container(writer: &'a mut W, num_fixed_bytes: usize) -> Self {
+        Self {
+            offset: num_fixed_bytes,
+            writer,
+        }
+    }
+
+    /// Write the fixed-length portion of `item`: the item itself if it is fixed-length, otherwise
+    /// its offset.
+    pub fn append_fixed<T: Encode>(&mut self, item: &T) -> io::Result<()> {
+        if T::is_ssz_fixed_len() {
+            item.ssz_write(self.writer)
+        } else {
+            self.writer.write_all(&encode_length(self.offset))?;
+            self.offset += item.ssz_bytes_len();
+            Ok(())
+        }
+    }
+
+    /// Write the variable-length portion of `item`, if any.
+    pub fn append_variable<T: Encode>(&mut self, item: &T) -> io::Result<()> {
+        if T::is_ssz_fixed_len() {
+            Ok(())
+        } else {
+            item.ssz_write(self.writer)
+        }
+    }
+}
+
 /// Encode `index` as a little-endian byte vec of `BYTES_PER_LENGTH_OFFSET` length.
 ///
 /// If `len` is larger than `2 ^ BYTES_PER_LENGTH_OFFSET`, a `debug_assert` is raised.
@@ -141,7 +292,9 @@ pub fn encode_union_index(index: usize) -> Vec<u8> {
 
 /// Encode `len` as a little-endian byte vec of `BYTES_PER_LENGTH_OFFSET` length.
 ///
-/// If `len` is larger than `2 ^ BYTES_PER_LENGTH_OFFSET`, a `debug_assert` is raised.
+/// If `len` is larger than `MAX_LENGTH_VALUE`, a `debug_assert` is raised. Otherwise the
+/// high-order bytes are silently dropped; use `ssz_encode_checked` to reject such objects before
+/// encoding them.
 pub fn encode_length(len: usize) -> Vec<u8> {
     // Note: it is possible for `len` to be larger than what can be encoded in
     // `BYTES_PER_LENGTH_OFFSET` bytes, triggering this debug assertion.
@@ -164,7 +317,11 @@ pub fn encode_length(len: usize) -> Vec<u8> {
     // error during testing.
     //
     // If you have a different opinion, feel free to start an issue and tag @paulhauner.
-    debug_assert!(len <= MAX_LENGTH_VALUE);
+    debug_assert!(
+        len <= MAX_LENGTH_VALUE,
+        "Length or offset of {} exceeds MAX_LENGTH_VALUE",
+        len
+    );
 
     len.to_le_bytes()[0..BYTES_PER_LENGTH_OFFSET].to_vec()
 }
@@ -185,6 +342,56 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_encoder_len() {
+        let mut buf = vec![42];
+        let offset = <u16 as Encode>::ssz_fixed_len() + <Vec<u8> as Encode>::ssz_fixed_len();
+
+        let mut encoder = SszEncoder::container(&mut buf, offset);
+        assert!(encoder.is_empty());
+
+        encoder.append(&1_u16);
+        assert_eq!(encoder.len(), 2);
+
+        encoder.append(&vec![1_u8, 2, 3]);
+        assert_eq!(encoder.len(), offset + 3);
+
+        let len = encoder.len();
+        encoder.finalize();
+
+        assert_eq!(buf.len(), len + 1);
+        assert_eq!(&buf[1..], &(1_u16, vec![1_u8, 2, 3]).as_ssz_bytes()[..]);
+    }
+
+    #[test]
+    #[cfg(target_pointer_width = "64")]
+    fn test_encode_checked_rejects_too_long() {
+        /// Reports a length greater than `MAX_LENGTH_VALUE`, without encoding anything.
+        struct TooLong;
+
+        impl Encode for TooLong {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                MAX_LENGTH_VALUE + 1
+            }
+
+            fn ssz_append(&self, _buf: &mut Vec<u8>) {
+                panic!("should not be encoded")
+            }
+        }
+
+        assert_eq!(
+            ssz_encode_checked(&TooLong),
+            Err(EncodeError::TooLong {
+                len: MAX_LENGTH_VALUE + 1
+            })
+        );
+        assert_eq!(ssz_encode_checked(&vec![1_u8, 2]), Ok(vec![1, 2]));
+    }
+
     #[test]
     #[should_panic]
     #[cfg(debug_assertions)]
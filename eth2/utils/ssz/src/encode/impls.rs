@@ -1,9 +1,13 @@
 use super::*;
-use core::num::NonZeroUsize;
+use core::num::{NonZeroU32, NonZeroU64, NonZeroUsize, Wrapping};
+use core::ops::Range;
 use ethereum_types::{H256, U128, U256};
+use std::borrow::Cow;
+use std::collections::BTreeMap;
+use std::marker::PhantomData;
 
 macro_rules! impl_encodable_for_uint {
-    ($type: ident, $bit_size: expr) => {
+    ($type: ident, $bit_size: expr $(, $extra: item)*) => {
         impl Encode for $type {
             fn is_ssz_fixed_len() -> bool {
                 true
@@ -20,14 +24,27 @@ macro_rules! impl_encodable_for_uint {
             fn ssz_append(&self, buf: &mut Vec<u8>) {
                 buf.extend_from_slice(&self.to_le_bytes());
             }
+
+            fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                writer.write_all(&self.to_le_bytes())
+            }
+
+            $($extra)*
         }
     };
 }
 
-impl_encodable_for_uint!(u8, 8);
+impl_encodable_for_uint!(
+    u8,
+    8,
+    fn ssz_append_slice(items: &[Self], buf: &mut Vec<u8>) {
+        buf.extend_from_slice(items);
+    }
+);
 impl_encodable_for_uint!(u16, 16);
 impl_encodable_for_uint!(u32, 32);
 impl_encodable_for_uint!(u64, 64);
+impl_encodable_for_uint!(u128, 128);
 
 #[cfg(target_pointer_width = "32")]
 impl_encodable_for_uint!(usize, 32);
@@ -236,31 +253,137 @@ impl<T: Encode> Encode for Vec<T> {
     }
 
     fn ssz_bytes_len(&self) -> usize {
-        if <T as Encode>::is_ssz_fixed_len() {
-            <T as Encode>::ssz_fixed_len() * self.len()
+        sequence_ssz_bytes_len(self)
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        sequence_ssz_append(self, buf)
+    }
+
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        sequence_ssz_write(self, writer)
+    }
+}
+
+/// Encoded identically to a `Vec<T>` of the same items.
+impl<T: Encode> Encode for [T] {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        sequence_ssz_bytes_len(self)
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        sequence_ssz_append(self, buf)
+    }
+
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        sequence_ssz_write(self, writer)
+    }
+}
+
+/// Encoded identically to a `Vec<u8>` of the same bytes.
+impl<'a> Encode for SszBytesRef<'a> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.0.len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        buf.extend_from_slice(self.0)
+    }
+
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        writer.write_all(self.0)
+    }
+}
+
+/// Encoded identically to the borrowed value.
+impl<'a, T: Encode + ToOwned + ?Sized> Encode for Cow<'a, T> {
+    fn is_ssz_fixed_len() -> bool {
+        <T as Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T as Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.as_ref().ssz_bytes_len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        self.as_ref().ssz_append(buf)
+    }
+
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        self.as_ref().ssz_write(writer)
+    }
+}
+
+/// Encoded as a list of `(key, value)` pairs, in ascending order of key.
+///
+/// The entries are encoded straight from the map, without collecting them into a list of pairs.
+impl<K: Encode + Ord, V: Encode> Encode for BTreeMap<K, V> {
+    fn is_ssz_fixed_len() -> bool {
+        false
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        if <(K, V) as Encode>::is_ssz_fixed_len() {
+            <(K, V) as Encode>::ssz_fixed_len() * self.len()
         } else {
-            let mut len = self.iter().map(|item| item.ssz_bytes_len()).sum();
-            len += BYTES_PER_LENGTH_OFFSET * self.len();
-            len
+            self.iter()
+                .map(|(key, value)| BYTES_PER_LENGTH_OFFSET + map_entry_ssz_bytes_len(key, value))
+                .sum()
         }
     }
 
     fn ssz_append(&self, buf: &mut Vec<u8>) {
-        if T::is_ssz_fixed_len() {
-            buf.reserve(T::ssz_fixed_len() * self.len());
-
-            for item in self {
-                item.ssz_append(buf);
+        if <(K, V) as Encode>::is_ssz_fixed_len() {
+            buf.reserve(<(K, V) as Encode>::ssz_fixed_len() * self.len());
+            for (key, value) in self {
+                map_entry_ssz_append(key, value, buf);
             }
         } else {
-            let mut encoder = SszEncoder::list(buf, self.len() * BYTES_PER_LENGTH_OFFSET);
+            // Reserve the offsets up front, then fill in each one as its entry is appended.
+            let start = buf.len();
+            buf.resize(start + self.len() * BYTES_PER_LENGTH_OFFSET, 0);
 
-            for item in self {
-                encoder.append(item);
+            for (i, (key, value)) in self.iter().enumerate() {
+                let offset = encode_length(buf.len() - start);
+                let position = start + i * BYTES_PER_LENGTH_OFFSET;
+                buf[position..position + BYTES_PER_LENGTH_OFFSET].copy_from_slice(&offset);
+
+                map_entry_ssz_append(key, value, buf);
+            }
+        }
+    }
+
+    fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        if !<(K, V) as Encode>::is_ssz_fixed_len() {
+            let mut offset = self.len() * BYTES_PER_LENGTH_OFFSET;
+            for (key, value) in self {
+                writer.write_all(&encode_length(offset))?;
+                offset += map_entry_ssz_bytes_len(key, value);
             }
+        }
 
-            encoder.finalize();
+        for (key, value) in self {
+            let mut ssz_writer =
+                SszWriter::container(writer, K::ssz_fixed_len() + V::ssz_fixed_len());
+            ssz_writer.append_fixed(key)?;
+            ssz_writer.append_fixed(value)?;
+            ssz_writer.append_variable(key)?;
+            ssz_writer.append_variable(value)?;
         }
+
+        Ok(())
     }
 }
 
@@ -282,24 +405,103 @@ impl Encode for bool {
     }
 }
 
-impl Encode for NonZeroUsize {
+macro_rules! impl_encode_for_non_zero {
+    ($type: ident, $inner: ty) => {
+        /// Encoded identically to the inner integer.
+        impl Encode for $type {
+            fn is_ssz_fixed_len() -> bool {
+                <$inner as Encode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <$inner as Encode>::ssz_fixed_len()
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                std::mem::size_of::<$inner>()
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                self.get().ssz_append(buf)
+            }
+        }
+    };
+}
+
+impl_encode_for_non_zero!(NonZeroUsize, usize);
+impl_encode_for_non_zero!(NonZeroU32, u32);
+impl_encode_for_non_zero!(NonZeroU64, u64);
+
+/// Encoded identically to the wrapped value.
+impl<T: Encode> Encode for Wrapping<T> {
+    fn is_ssz_fixed_len() -> bool {
+        <T as Encode>::is_ssz_fixed_len()
+    }
+
+    fn ssz_fixed_len() -> usize {
+        <T as Encode>::ssz_fixed_len()
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        self.0.ssz_bytes_len()
+    }
+
+    fn ssz_append(&self, buf: &mut Vec<u8>) {
+        self.0.ssz_append(buf)
+    }
+}
+
+/// Encoded as the SSZ container `(start, end)`.
+impl<T: Encode> Encode for Range<T> {
     fn is_ssz_fixed_len() -> bool {
-        <usize as Encode>::is_ssz_fixed_len()
+        <T as Encode>::is_ssz_fixed_len()
     }
 
     fn ssz_fixed_len() -> usize {
-        <usize as Encode>::ssz_fixed_len()
+        if <Self as Encode>::is_ssz_fixed_len() {
+            <T as Encode>::ssz_fixed_len() * 2
+        } else {
+            BYTES_PER_LENGTH_OFFSET
+        }
     }
 
     fn ssz_bytes_len(&self) -> usize {
-        std::mem::size_of::<usize>()
+        if <Self as Encode>::is_ssz_fixed_len() {
+            <Self as Encode>::ssz_fixed_len()
+        } else {
+            BYTES_PER_LENGTH_OFFSET * 2 + self.start.ssz_bytes_len() + self.end.ssz_bytes_len()
+        }
     }
 
     fn ssz_append(&self, buf: &mut Vec<u8>) {
-        self.get().ssz_append(buf)
+        let offset = <T as Encode>::ssz_fixed_len() * 2;
+
+        let mut encoder = SszEncoder::container(buf, offset);
+
+        encoder.append(&self.start);
+        encoder.append(&self.end);
+
+        encoder.finalize();
     }
 }
 
+/// Encoded as zero bytes, so it occupies no space in a container.
+impl<T> Encode for PhantomData<T> {
+    fn is_ssz_fixed_len() -> bool {
+        true
+    }
+
+    fn ssz_fixed_len() -> usize {
+        0
+    }
+
+    fn ssz_bytes_len(&self) -> usize {
+        0
+    }
+
+    fn ssz_append(&self, _buf: &mut Vec<u8>) {}
+}
+
 impl Encode for H256 {
     fn is_ssz_fixed_len() -> bool {
         true
@@ -362,35 +564,188 @@ impl Encode for U128 {
     }
 }
 
-macro_rules! impl_encodable_for_u8_array {
+/// Encoded as an SSZ vector (i.e., without a length prefix).
+macro_rules! impl_encodable_for_array {
     ($len: expr) => {
-        impl Encode for [u8; $len] {
+        impl<T: Encode> Encode for [T; $len] {
             fn is_ssz_fixed_len() -> bool {
-                true
+                <T as Encode>::is_ssz_fixed_len()
             }
 
             fn ssz_fixed_len() -> usize {
-                $len
+                if <Self as Encode>::is_ssz_fixed_len() {
+                    <T as Encode>::ssz_fixed_len()
+                        .checked_mul($len)
+                        .expect("ssz_fixed_len of array overflows usize")
+                } else {
+                    BYTES_PER_LENGTH_OFFSET
+                }
             }
 
             fn ssz_bytes_len(&self) -> usize {
-                $len
+                sequence_ssz_bytes_len(&self[..])
             }
 
             fn ssz_append(&self, buf: &mut Vec<u8>) {
-                buf.extend_from_slice(&self[..]);
+                sequence_ssz_append(&self[..], buf)
+            }
+
+            fn ssz_write<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+                sequence_ssz_write(&self[..], writer)
             }
         }
     };
 }
 
-impl_encodable_for_u8_array!(4);
-impl_encodable_for_u8_array!(32);
+impl_encodable_for_array!(1);
+impl_encodable_for_array!(2);
+impl_encodable_for_array!(3);
+impl_encodable_for_array!(4);
+impl_encodable_for_array!(8);
+impl_encodable_for_array!(16);
+impl_encodable_for_array!(32);
+
+/// Returns the length of the SSZ encoding of a `BTreeMap` entry, as though it were the tuple
+/// `(key, value)`.
+fn map_entry_ssz_bytes_len<K: Encode, V: Encode>(key: &K, value: &V) -> usize {
+    let mut len = K::ssz_fixed_len() + V::ssz_fixed_len();
+    if !K::is_ssz_fixed_len() {
+        len += key.ssz_bytes_len();
+    }
+    if !V::is_ssz_fixed_len() {
+        len += value.ssz_bytes_len();
+    }
+    len
+}
+
+/// Appends the SSZ encoding of a `BTreeMap` entry to `buf`, as though it were the tuple
+/// `(key, value)`.
+fn map_entry_ssz_append<K: Encode, V: Encode>(key: &K, value: &V, buf: &mut Vec<u8>) {
+    let mut offset = K::ssz_fixed_len() + V::ssz_fixed_len();
+
+    if K::is_ssz_fixed_len() {
+        key.ssz_append(buf);
+    } else {
+        buf.extend_from_slice(&encode_length(offset));
+        offset += key.ssz_bytes_len();
+    }
+    if V::is_ssz_fixed_len() {
+        value.ssz_append(buf);
+    } else {
+        buf.extend_from_slice(&encode_length(offset));
+    }
+
+    if !K::is_ssz_fixed_len() {
+        key.ssz_append(buf);
+    }
+    if !V::is_ssz_fixed_len() {
+        value.ssz_append(buf);
+    }
+}
+
+/// Returns the length of the SSZ encoding of `items` as a list or vector (i.e., without a length
+/// prefix).
+fn sequence_ssz_bytes_len<T: Encode>(items: &[T]) -> usize {
+    if <T as Encode>::is_ssz_fixed_len() {
+        <T as Encode>::ssz_fixed_len() * items.len()
+    } else {
+        let mut len = items.iter().map(|item| item.ssz_bytes_len()).sum();
+        len += BYTES_PER_LENGTH_OFFSET * items.len();
+        len
+    }
+}
+
+/// Appends the SSZ encoding of `items` as a list or vector (i.e., without a length prefix) to
+/// `buf`.
+fn sequence_ssz_append<T: Encode>(items: &[T], buf: &mut Vec<u8>) {
+    if T::is_ssz_fixed_len() {
+        buf.reserve(T::ssz_fixed_len() * items.len());
+        T::ssz_append_slice(items, buf);
+    } else {
+        let mut encoder = SszEncoder::list(buf, items.len() * BYTES_PER_LENGTH_OFFSET);
+
+        for item in items {
+            encoder.append(item);
+        }
+
+        encoder.finalize();
+    }
+}
+
+/// Writes the SSZ encoding of `items` as a list or vector (i.e., without a length prefix) to
+/// `writer`.
+fn sequence_ssz_write<T: Encode, W: Write>(items: &[T], writer: &mut W) -> io::Result<()> {
+    if T::is_ssz_fixed_len() {
+        for item in items {
+            item.ssz_write(writer)?;
+        }
+    } else {
+        let mut ssz_writer = SszWriter::list(writer, items.len() * BYTES_PER_LENGTH_OFFSET);
+
+        for item in items {
+            ssz_writer.append_fixed(item)?;
+        }
+
+        for item in items {
+            ssz_writer.append_variable(item)?;
+        }
+    }
+
+    Ok(())
+}
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn slice_of_u8() {
+        let vec: Vec<u8> = vec![0, 1, 2, 3];
+        assert_eq!(vec[..].as_ssz_bytes(), vec.as_ssz_bytes());
+        let slice: &[u8] = &vec[1..];
+        assert_eq!(slice.as_ssz_bytes(), vec![1, 2, 3]);
+        assert_eq!(vec[..0].as_ssz_bytes(), vec![]);
+
+        let nested: Vec<Vec<u8>> = vec![vec![1], vec![], vec![2, 3]];
+        assert_eq!(nested[..].as_ssz_bytes(), nested.as_ssz_bytes());
+        assert_eq!(nested[..].ssz_bytes_len(), nested.ssz_bytes_len());
+    }
+
+    #[test]
+    fn ssz_bytes_len_matches_encoding() {
+        fn check<T: Encode>(item: T) {
+            assert_eq!(item.ssz_bytes_len(), item.as_ssz_bytes().len());
+        }
+
+        check(42_u64);
+        check(vec![1_u16, 2, 3]);
+        check(vec![vec![1_u8], vec![], vec![2, 3]]);
+        check(vec![vec![vec![1_u16], vec![]], vec![], vec![vec![2, 3, 4]]]);
+        check(vec![Some(vec![1_u8]), None]);
+        check((1_u8, vec![2_u64], vec![vec![3_u32]]));
+
+        let mut map = BTreeMap::new();
+        map.insert(1_u64, vec![vec![2_u8]]);
+        map.insert(3, vec![]);
+        check(map);
+    }
+
+    #[test]
+    fn cow() {
+        let vec: Vec<u8> = vec![1, 2, 3];
+
+        let borrowed: Cow<[u8]> = Cow::Borrowed(&vec[..]);
+        assert_eq!(borrowed.as_ssz_bytes(), vec.as_ssz_bytes());
+        assert_eq!(borrowed.ssz_bytes_len(), vec.ssz_bytes_len());
+
+        let owned: Cow<[u8]> = Cow::Owned(vec.clone());
+        assert_eq!(owned.as_ssz_bytes(), vec.as_ssz_bytes());
+
+        let fixed: Cow<u64> = Cow::Owned(42);
+        assert!(<Cow<u64> as Encode>::is_ssz_fixed_len());
+        assert_eq!(fixed.as_ssz_bytes(), 42_u64.as_ssz_bytes());
+    }
+
     #[test]
     fn vec_of_u8() {
         let vec: Vec<u8> = vec![];
@@ -403,6 +758,45 @@ mod tests {
         assert_eq!(vec.as_ssz_bytes(), vec![0, 1, 2, 3]);
     }
 
+    #[test]
+    fn ssz_append_to_shared_buffer() {
+        let a: Vec<Vec<u8>> = vec![vec![0, 1, 2], vec![11, 22, 33]];
+        let b: u64 = 42;
+
+        let mut buf = vec![];
+        a.ssz_append(&mut buf);
+        b.ssz_append(&mut buf);
+
+        let mut expected = a.as_ssz_bytes();
+        expected.append(&mut b.as_ssz_bytes());
+        assert_eq!(buf, expected);
+    }
+
+    #[test]
+    fn ssz_write_matches_as_ssz_bytes() {
+        fn assert_write_matches<T: Encode>(item: T) {
+            let mut buf = vec![];
+            item.ssz_write(&mut buf).unwrap();
+            assert_eq!(buf, item.as_ssz_bytes());
+        }
+
+        assert_write_matches(42_u64);
+        assert_write_matches(vec![1_u16, 2, 3]);
+        assert_write_matches(vec![vec![0_u8, 1, 2], vec![], vec![11, 22, 33]]);
+        assert_write_matches(vec![Some(vec![1_u16]), None, Some(vec![])]);
+        assert_write_matches([vec![1_u8], vec![2, 3]]);
+        assert_write_matches((42_u64, vec![vec![1_u16], vec![2, 3]], 3_u8));
+    }
+
+    #[test]
+    fn as_ssz_bytes_is_presized() {
+        let vec: Vec<Vec<u8>> = vec![vec![0, 1, 2], vec![11, 22, 33]];
+        let bytes = vec.as_ssz_bytes();
+
+        assert_eq!(bytes.len(), vec.ssz_bytes_len());
+        assert_eq!(bytes.capacity(), vec.ssz_bytes_len());
+    }
+
     #[test]
     fn vec_of_vec_of_u8() {
         let vec: Vec<Vec<u8>> = vec![];
@@ -465,6 +859,18 @@ mod tests {
         assert_eq!((!0_u32).as_ssz_bytes(), vec![255, 255, 255, 255]);
     }
 
+    #[test]
+    fn non_zero_integers() {
+        let x = NonZeroU32::new(258).unwrap();
+        assert_eq!(x.as_ssz_bytes(), 258_u32.as_ssz_bytes());
+        assert_eq!(<NonZeroU32 as Encode>::ssz_fixed_len(), 4);
+
+        let x = NonZeroU64::new(u64::max_value()).unwrap();
+        assert_eq!(x.as_ssz_bytes(), vec![255; 8]);
+        assert_eq!(<NonZeroU64 as Encode>::ssz_fixed_len(), 8);
+        assert_eq!(x.ssz_bytes_len(), 8);
+    }
+
     #[test]
     fn ssz_encode_u64() {
         assert_eq!(1_u64.as_ssz_bytes(), vec![1, 0, 0, 0, 0, 0, 0, 0]);
@@ -474,6 +880,31 @@ mod tests {
         );
     }
 
+    #[test]
+    fn ssz_encode_u128() {
+        assert_eq!(0_u128.as_ssz_bytes(), vec![0; 16]);
+        assert_eq!(
+            1_u128.as_ssz_bytes(),
+            vec![1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0]
+        );
+        assert_eq!(
+            (1_u128 << 120).as_ssz_bytes(),
+            vec![0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 1]
+        );
+        assert_eq!(u128::max_value().as_ssz_bytes(), vec![255; 16]);
+    }
+
+    #[test]
+    fn ssz_encode_u256() {
+        assert_eq!(U256::zero().as_ssz_bytes(), vec![0; 32]);
+
+        let mut expected = vec![0; 32];
+        expected[0] = 1;
+        assert_eq!(U256::from(1).as_ssz_bytes(), expected);
+
+        assert_eq!(U256::max_value().as_ssz_bytes(), vec![255; 32]);
+    }
+
     #[test]
     fn ssz_encode_usize() {
         assert_eq!(1_usize.as_ssz_bytes(), vec![1, 0, 0, 0, 0, 0, 0, 0]);
@@ -502,11 +933,47 @@ mod tests {
         assert_eq!(H256::from_slice(&bytes).as_ssz_bytes(), bytes);
     }
 
+    #[test]
+    fn ssz_append_h256_is_single_copy() {
+        let mut buf = vec![42];
+        H256::from([7; 32]).ssz_append(&mut buf);
+
+        let mut expected = vec![42];
+        expected.extend_from_slice(&[7; 32]);
+
+        assert_eq!(buf, expected);
+        assert_eq!(H256::zero().ssz_bytes_len(), 32);
+    }
+
+    #[test]
+    fn ssz_encode_u8_array_32() {
+        assert_eq!([0_u8; 32].as_ssz_bytes(), vec![0; 32]);
+        assert_eq!([1_u8; 32].as_ssz_bytes(), vec![1; 32]);
+
+        let mut buf = vec![42];
+        [3_u8; 32].ssz_append(&mut buf);
+        assert_eq!(buf.len(), 33);
+        assert_eq!(&buf[1..], &[3; 32][..]);
+    }
+
     #[test]
     fn ssz_encode_u8_array_4() {
-        assert_eq!([0, 0, 0, 0].as_ssz_bytes(), vec![0; 4]);
-        assert_eq!([1, 0, 0, 0].as_ssz_bytes(), vec![1, 0, 0, 0]);
-        assert_eq!([1, 2, 3, 4].as_ssz_bytes(), vec![1, 2, 3, 4]);
+        assert_eq!([0_u8, 0, 0, 0].as_ssz_bytes(), vec![0; 4]);
+        assert_eq!([1_u8, 0, 0, 0].as_ssz_bytes(), vec![1, 0, 0, 0]);
+        assert_eq!([1_u8, 2, 3, 4].as_ssz_bytes(), vec![1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn ssz_append_slice() {
+        let mut buf = vec![42];
+        u8::ssz_append_slice(&[1, 2, 3], &mut buf);
+        assert_eq!(buf, vec![42, 1, 2, 3]);
+
+        let mut buf = vec![42];
+        u16::ssz_append_slice(&[1, 2], &mut buf);
+        assert_eq!(buf, vec![42, 1, 0, 2, 0]);
+
+        assert_eq!([[1_u8, 2], [3, 4]].as_ssz_bytes(), vec![1, 2, 3, 4]);
     }
 
     #[test]
@@ -0,0 +1,201 @@
+//! Length-delimited framing for a stream of SSZ objects, such as a file of many blocks.
+//!
+//! Each frame is the SSZ encoding of an object, prefixed with its length as a 4-byte
+//! little-endian integer. SSZ encodings do not record their own length, so the prefix is what
+//! allows a reader to find the boundary between objects.
+
+use crate::Encode;
+use std::io::{self, Read, Write};
+
+/// The number of bytes used to represent the length of a frame.
+pub const FRAME_LENGTH_BYTES: usize = 4;
+
+/// The largest frame that a `FramedReader` will read, unless configured otherwise.
+pub const DEFAULT_MAX_FRAME_SIZE: usize = 16 * 1024 * 1024;
+
+/// Write the SSZ encoding of `val` to `writer` as a single frame.
+///
+/// Returns an error of kind `InvalidInput` if the encoding is too long for its length to be
+/// represented in `FRAME_LENGTH_BYTES`.
+pub fn write_framed<T: Encode, W: Write>(val: &T, writer: &mut W) -> io::Result<()> {
+    let len = val.ssz_bytes_len();
+
+    if len > u32::max_value() as usize {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidInput,
+            format!("Frame of {} bytes is too long", len),
+        ));
+    }
+
+    writer.write_all(&(len as u32).to_le_bytes())?;
+    val.ssz_write(writer)
+}
+
+/// Reads the frames written by `write_framed`, yielding the payload of each (i.e., the SSZ bytes
+/// of an object, which the caller should decode).
+///
+/// Iteration ends when `reader` is exhausted at a frame boundary. Reaching the end part-way
+/// through a frame yields an error of kind `UnexpectedEof`, and a frame which declares a length
+/// greater than the maximum frame size yields an error of kind `InvalidData` before any of it is
+/// read. No more frames are yielded after an error, since the boundary of the next frame is
+/// unknown.
+pub struct FramedReader<R> {
+    reader: R,
+    max_frame_size: usize,
+    finished: bool,
+}
+
+impl<R: Read> FramedReader<R> {
+    /// Read frames from `reader`, rejecting any larger than `DEFAULT_MAX_FRAME_SIZE`.
+    ///
+    /// Many small reads may be made from `reader`, so it should be buffered.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            max_frame_size: DEFAULT_MAX_FRAME_SIZE,
+            finished: false,
+        }
+    }
+
+    /// Reject frames which declare a length greater than `max_frame_size`, so that a corrupt or
+    /// malicious stream cannot cause an arbitrarily large allocation.
+    pub fn with_max_frame_size(mut self, max_frame_size: usize) -> Self {
+        self.max_frame_size = max_frame_size;
+        self
+    }
+
+    /// Returns the underlying reader.
+    pub fn into_inner(self) -> R {
+        self.reader
+    }
+
+    /// Read the next frame, returning `Ok(None)` if `self.reader` is exhausted.
+    fn read_frame(&mut self) -> io::Result<Option<Vec<u8>>> {
+        let mut len_bytes = [0; FRAME_LENGTH_BYTES];
+
+        // Read the length manually, so that an exhausted reader can be distinguished from a
+        // truncated length.
+        let mut num_read = 0;
+        while num_read < FRAME_LENGTH_BYTES {
+            match self.reader.read(&mut len_bytes[num_read..]) {
+                Ok(0) if num_read == 0 => return Ok(None),
+                Ok(0) => {
+                    return Err(io::Error::new(
+                        io::ErrorKind::UnexpectedEof,
+                        "Stream ended within a frame length",
+                    ))
+                }
+                Ok(n) => num_read += n,
+                Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+                Err(e) => return Err(e),
+            }
+        }
+
+        let len = u32::from_le_bytes(len_bytes) as usize;
+
+        if len > self.max_frame_size {
+            return Err(io::Error::new(
+                io::ErrorKind::InvalidData,
+                format!(
+                    "Frame of {} bytes exceeds maximum of {}",
+                    len, self.max_frame_size
+                ),
+            ));
+        }
+
+        let mut payload = vec![0; len];
+        self.reader.read_exact(&mut payload)?;
+
+        Ok(Some(payload))
+    }
+}
+
+impl<R: Read> Iterator for FramedReader<R> {
+    type Item = io::Result<Vec<u8>>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+
+        let result = self.read_frame();
+        self.finished = match result {
+            Ok(Some(_)) => false,
+            Ok(None) | Err(_) => true,
+        };
+        result.transpose()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Decode;
+
+    #[test]
+    fn round_trip() {
+        let values: Vec<Vec<u16>> = vec![vec![1, 2, 3], vec![], vec![42], vec![0; 300]];
+
+        let mut bytes = vec![];
+        for value in &values {
+            write_framed(value, &mut bytes).unwrap();
+        }
+        assert_eq!(&bytes[0..FRAME_LENGTH_BYTES], &[6, 0, 0, 0]);
+
+        let decoded = FramedReader::new(&bytes[..])
+            .map(|payload| Vec::<u16>::from_ssz_bytes(&payload.unwrap()).unwrap())
+            .collect::<Vec<_>>();
+        assert_eq!(decoded, values);
+    }
+
+    #[test]
+    fn truncated_stream() {
+        let mut bytes = vec![];
+        write_framed(&vec![1_u8, 2, 3], &mut bytes).unwrap();
+        write_framed(&vec![4_u8, 5, 6], &mut bytes).unwrap();
+
+        // Within the payload of the second frame.
+        let mut reader = FramedReader::new(&bytes[..bytes.len() - 1]);
+        assert_eq!(reader.next().unwrap().unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            reader.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert!(reader.next().is_none());
+
+        // Within the length of the second frame.
+        let mut reader = FramedReader::new(&bytes[..9]);
+        assert_eq!(reader.next().unwrap().unwrap(), vec![1, 2, 3]);
+        assert_eq!(
+            reader.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::UnexpectedEof
+        );
+        assert!(reader.next().is_none());
+    }
+
+    #[test]
+    fn max_frame_size() {
+        let mut bytes = vec![];
+        write_framed(&vec![0_u8; 8], &mut bytes).unwrap();
+        write_framed(&vec![0_u8; 9], &mut bytes).unwrap();
+
+        let mut reader = FramedReader::new(&bytes[..]).with_max_frame_size(8);
+        assert_eq!(reader.next().unwrap().unwrap(), vec![0; 8]);
+        assert_eq!(
+            reader.next().unwrap().unwrap_err().kind(),
+            io::ErrorKind::InvalidData
+        );
+        assert!(reader.next().is_none());
+
+        // A declared length is rejected without attempting to read (or allocate) the frame.
+        let bytes = u32::max_value().to_le_bytes();
+        assert_eq!(
+            FramedReader::new(&bytes[..])
+                .next()
+                .unwrap()
+                .unwrap_err()
+                .kind(),
+            io::ErrorKind::InvalidData
+        );
+    }
+}
@@ -36,11 +36,18 @@
 
 mod decode;
 mod encode;
+pub mod framing;
+#[cfg(feature = "serde")]
+pub mod serde_utils;
 
 pub use decode::{
-    impls::decode_list_of_variable_length_items, Decode, DecodeError, SszDecoder, SszDecoderBuilder,
+    impls::{
+        decode_bounded_list, decode_list_of_variable_length_items,
+        decode_list_of_variable_length_items_bounded, SszListIter,
+    },
+    Decode, DecodeError, DecodeRef, SszBytesRef, SszDecoder, SszDecoderBuilder, SszLayout,
 };
-pub use encode::{Encode, SszEncoder};
+pub use encode::{Encode, EncodeError, SszEncoder, SszWriter};
 
 /// The number of bytes used to represent an offset.
 pub const BYTES_PER_LENGTH_OFFSET: usize = 4;
@@ -59,3 +66,34 @@ where
 {
     val.as_ssz_bytes()
 }
+
+/// Convenience function to SSZ encode an object supporting ssz::Encode, returning an error if
+/// the encoding is longer than `MAX_LENGTH_VALUE`.
+///
+/// Every offset within an object points into its own encoding, so checking the total length
+/// ensures that no offset or length is truncated to `BYTES_PER_LENGTH_OFFSET` bytes (which
+/// `encode_length` only `debug_assert`s against).
+pub fn ssz_encode_checked<T>(val: &T) -> Result<Vec<u8>, EncodeError>
+where
+    T: Encode,
+{
+    let len = val.ssz_bytes_len();
+
+    if len > MAX_LENGTH_VALUE {
+        Err(EncodeError::TooLong { len })
+    } else {
+        Ok(val.as_ssz_bytes())
+    }
+}
+
+/// Convenience function to write the SSZ encoding of an object supporting ssz::Encode to
+/// `writer`.
+///
+/// Equivalent to `val.ssz_write(writer)`.
+pub fn ssz_encode_to_writer<T, W>(val: &T, writer: &mut W) -> std::io::Result<()>
+where
+    T: Encode,
+    W: std::io::Write,
+{
+    val.ssz_write(writer)
+}
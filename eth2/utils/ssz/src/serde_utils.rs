@@ -0,0 +1,81 @@
+//! Serde (de)serialization of any `Encode`/`Decode` type as the `0x`-prefixed hex of its SSZ
+//! bytes, for use with `#[serde(with = "ssz::serde_utils")]`.
+//!
+//! This is the same format as the hand-written serde implementations of the `bls` types (e.g.,
+//! `Signature`). Requires the `serde` feature.
+//!
+//! ## Example
+//!
+//! ```rust
+//! use serde_derive::{Deserialize, Serialize};
+//!
+//! #[derive(Serialize, Deserialize)]
+//! # #[serde(crate = "serde_crate")]
+//! struct Foo {
+//!     #[serde(with = "ssz::serde_utils")]
+//!     a: Vec<u16>,
+//! }
+//! ```
+use crate::{Decode, Encode};
+use serde_crate::de::{self, Deserializer};
+use serde_crate::Serializer;
+use serde_hex::{encode, PrefixedHexVisitor};
+
+/// Serializes `value` as the `0x`-prefixed hex of its SSZ bytes.
+pub fn serialize<T, S>(value: &T, serializer: S) -> Result<S::Ok, S::Error>
+where
+    T: Encode,
+    S: Serializer,
+{
+    serializer.serialize_str(&encode(value.as_ssz_bytes()))
+}
+
+/// Deserializes a value from the `0x`-prefixed hex of its SSZ bytes.
+pub fn deserialize<'de, T, D>(deserializer: D) -> Result<T, D::Error>
+where
+    T: Decode,
+    D: Deserializer<'de>,
+{
+    let bytes = deserializer.deserialize_str(PrefixedHexVisitor)?;
+    T::from_ssz_bytes(&bytes).map_err(|e| de::Error::custom(format!("invalid ssz ({:?})", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_derive::{Deserialize, Serialize};
+
+    #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    #[serde(crate = "serde_crate")]
+    struct Wrapper {
+        #[serde(with = "crate::serde_utils")]
+        value: Vec<u16>,
+    }
+
+    #[test]
+    fn round_trip() {
+        let wrapper = Wrapper {
+            value: vec![1, 0xabcd],
+        };
+
+        let json = serde_json::to_string(&wrapper).unwrap();
+        assert_eq!(json, r#"{"value":"0x0100cdab"}"#);
+        assert_eq!(serde_json::from_str::<Wrapper>(&json).unwrap(), wrapper);
+
+        let empty = Wrapper { value: vec![] };
+        let json = serde_json::to_string(&empty).unwrap();
+        assert_eq!(json, r#"{"value":"0x"}"#);
+        assert_eq!(serde_json::from_str::<Wrapper>(&json).unwrap(), empty);
+    }
+
+    #[test]
+    fn invalid_strings() {
+        // Missing prefix, invalid hex and invalid SSZ (an odd number of bytes for a `Vec<u16>`).
+        for json in &[
+            r#"{"value":"0100"}"#,
+            r#"{"value":"0xzz"}"#,
+            r#"{"value":"0x010002"}"#,
+        ] {
+            assert!(serde_json::from_str::<Wrapper>(json).is_err(), "{}", json);
+        }
+    }
+}
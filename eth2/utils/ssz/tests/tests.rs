@@ -1,5 +1,5 @@
 use ethereum_types::H256;
-use ssz::{Decode, DecodeError, Encode};
+use ssz::{Decode, DecodeError, Encode, SszListIter};
 use ssz_derive::{Decode, Encode};
 
 #[allow(clippy::zero_prefixed_literal)]
@@ -28,6 +28,114 @@ mod round_trip {
         round_trip(items);
     }
 
+    #[test]
+    fn u8_array_32() {
+        let items: Vec<[u8; 32]> = vec![[0; 32], [1; 32], [255; 32]];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn h256_array_4() {
+        let items: Vec<[H256; 4]> = vec![
+            [H256::zero(); 4],
+            [
+                H256::zero(),
+                H256::from([1; 32]),
+                H256::random(),
+                H256::random(),
+            ],
+        ];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn vec_array_2() {
+        let items: Vec<[Vec<u16>; 2]> = vec![
+            [vec![], vec![]],
+            [vec![1], vec![]],
+            [vec![], vec![1, 2, 3]],
+            [vec![4, 5], vec![6]],
+        ];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn range() {
+        let items: Vec<std::ops::Range<u64>> = vec![0..0, 0..1, 42..1337, 0..u64::max_value()];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn wrapping_u64() {
+        use std::num::Wrapping;
+
+        let items: Vec<Wrapping<u64>> = vec![
+            Wrapping(0),
+            Wrapping(42),
+            Wrapping(u64::max_value()) + Wrapping(1),
+        ];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn vec_of_wrapping_u64() {
+        use std::num::Wrapping;
+
+        let items: Vec<Vec<Wrapping<u64>>> = vec![vec![], vec![Wrapping(1), Wrapping(2)]];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn btree_map() {
+        use std::collections::BTreeMap;
+
+        let items: Vec<BTreeMap<u64, u16>> = vec![
+            BTreeMap::new(),
+            vec![(42, 1)].into_iter().collect(),
+            vec![(3, 0), (1, 2), (2, u16::max_value())]
+                .into_iter()
+                .collect(),
+        ];
+        round_trip(items);
+
+        let items: Vec<BTreeMap<u8, Vec<u8>>> = vec![
+            BTreeMap::new(),
+            vec![(1, vec![]), (0, vec![1, 2, 3])].into_iter().collect(),
+        ];
+        round_trip(items);
+    }
+
+    #[test]
+    fn option_u16() {
+        let items: Vec<Option<u16>> = vec![None, Some(0), Some(42), Some(u16::max_value())];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn option_vec_h256() {
+        let items: Vec<Option<Vec<H256>>> = vec![
+            None,
+            Some(vec![]),
+            Some(vec![H256::zero(), H256::from([1; 32]), H256::random()]),
+        ];
+
+        round_trip(items);
+    }
+
+    #[test]
+    fn u128() {
+        let items: Vec<u128> = vec![0, 1, 1 << 64, u128::max_value()];
+
+        round_trip(items);
+    }
+
     #[test]
     fn h256() {
         let items: Vec<H256> = vec![H256::zero(), H256::from([1; 32]), H256::random()];
@@ -76,6 +184,31 @@ mod round_trip {
         round_trip(items);
     }
 
+    #[test]
+    fn cow_bytes() {
+        use std::borrow::Cow;
+
+        let bytes = vec![0_u8, 1, 2, 3];
+        let items: Vec<Cow<[u8]>> = vec![
+            Cow::Borrowed(&bytes[..]),
+            Cow::Owned(bytes.clone()),
+            Cow::Borrowed(&[]),
+        ];
+
+        for item in items {
+            let encoded = item.as_ssz_bytes();
+            assert_eq!(encoded, item.to_vec().as_ssz_bytes());
+            assert_eq!(item.ssz_bytes_len(), encoded.len());
+
+            let decoded = <Cow<[u8]>>::from_ssz_bytes(&encoded).unwrap();
+            assert!(match decoded {
+                Cow::Owned(_) => true,
+                Cow::Borrowed(_) => false,
+            });
+            assert_eq!(decoded, item);
+        }
+    }
+
     #[derive(Debug, PartialEq, Encode, Decode)]
     struct FixedLen {
         a: u16,
@@ -108,6 +241,52 @@ mod round_trip {
         }
     }
 
+    #[test]
+    fn fixed_len_struct_appends_fixed_len() {
+        let mut buf = vec![42];
+        FixedLen { a: 1, b: 2, c: 3 }.ssz_append(&mut buf);
+        assert_eq!(buf.len(), 1 + <FixedLen as Encode>::ssz_fixed_len());
+    }
+
+    /// Claims to be one byte long, but encodes to two.
+    #[derive(Debug, PartialEq)]
+    struct BadFixedLen(u16);
+
+    impl Encode for BadFixedLen {
+        fn is_ssz_fixed_len() -> bool {
+            true
+        }
+
+        fn ssz_fixed_len() -> usize {
+            1
+        }
+
+        fn ssz_bytes_len(&self) -> usize {
+            1
+        }
+
+        fn ssz_append(&self, buf: &mut Vec<u8>) {
+            self.0.ssz_append(buf)
+        }
+    }
+
+    #[derive(Debug, PartialEq, Encode)]
+    struct ContainsBadFixedLen {
+        a: u8,
+        b: BadFixedLen,
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic(expected = "ContainsBadFixedLen appended 3 bytes but has an ssz_fixed_len of 2")]
+    fn fixed_len_struct_inconsistent_field() {
+        ContainsBadFixedLen {
+            a: 1,
+            b: BadFixedLen(2),
+        }
+        .as_ssz_bytes();
+    }
+
     #[test]
     fn fixed_len_excess_bytes() {
         let fixed = FixedLen { a: 1, b: 2, c: 3 };
@@ -117,9 +296,9 @@ mod round_trip {
 
         assert_eq!(
             FixedLen::from_ssz_bytes(&bytes),
-            Err(DecodeError::InvalidByteLength {
-                len: 15,
+            Err(DecodeError::BytesTooLong {
                 expected: 14,
+                got: 15,
             })
         );
     }
@@ -135,6 +314,97 @@ mod round_trip {
         round_trip(items);
     }
 
+    #[test]
+    fn with_consumed_u64s() {
+        let mut bytes = 1_u64.as_ssz_bytes();
+        bytes.append(&mut 2_u64.as_ssz_bytes());
+
+        let (first, consumed) = u64::from_ssz_bytes_with_consumed(&bytes).unwrap();
+        assert_eq!((first, consumed), (1, 8));
+
+        let (second, consumed) = u64::from_ssz_bytes_with_consumed(&bytes[consumed..]).unwrap();
+        assert_eq!((second, consumed), (2, 8));
+
+        assert_eq!(
+            u64::from_ssz_bytes_with_consumed(&bytes[12..]),
+            Err(DecodeError::BytesTooShort { need: 8, got: 4 })
+        );
+    }
+
+    #[test]
+    fn with_consumed_fixed_len_structs() {
+        let items = vec![FixedLen { a: 1, b: 2, c: 3 }, FixedLen { a: 4, b: 5, c: 6 }];
+        let bytes: Vec<u8> = items.iter().flat_map(Encode::as_ssz_bytes).collect();
+
+        let mut decoded = vec![];
+        let mut remaining = &bytes[..];
+        while !remaining.is_empty() {
+            let (item, consumed) = FixedLen::from_ssz_bytes_with_consumed(remaining).unwrap();
+            assert_eq!(consumed, 14);
+            decoded.push(item);
+            remaining = &remaining[consumed..];
+        }
+
+        assert_eq!(decoded, items);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct WithBool {
+        a: u16,
+        flag: bool,
+    }
+
+    #[test]
+    fn field_error() {
+        let bytes = vec![01, 00, 02];
+        let error = WithBool::from_ssz_bytes(&bytes).unwrap_err();
+
+        assert_eq!(
+            error,
+            DecodeError::FieldError {
+                field: "flag",
+                error: Box::new(DecodeError::BytesInvalid(
+                    "Out-of-range for boolean: 2".to_string()
+                )),
+            }
+        );
+        assert!(format!("{:?}", error).contains("flag"));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct WithBoolList {
+        items: Vec<WithBool>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct WithNestedBool {
+        a: u16,
+        body: WithBoolList,
+    }
+
+    #[test]
+    fn nested_error_path() {
+        let nested = WithNestedBool {
+            a: 1,
+            body: WithBoolList {
+                items: vec![
+                    WithBool { a: 2, flag: true },
+                    WithBool { a: 3, flag: false },
+                ],
+            },
+        };
+
+        // Corrupt the `flag` of the final item.
+        let mut bytes = nested.as_ssz_bytes();
+        *bytes.last_mut().unwrap() = 2;
+
+        let error = WithNestedBool::from_ssz_bytes(&bytes).unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "body.items[1].flag: BytesInvalid(\"Out-of-range for boolean: 2\")"
+        );
+    }
+
     #[derive(Debug, PartialEq, Encode, Decode)]
     struct VariableLen {
         a: u16,
@@ -142,6 +412,49 @@ mod round_trip {
         c: u32,
     }
 
+    #[test]
+    fn with_consumed_variable_len_structs() {
+        let items = vec![
+            VariableLen {
+                a: 1,
+                b: vec![2, 3],
+                c: 4,
+            },
+            VariableLen {
+                a: 5,
+                b: vec![],
+                c: 6,
+            },
+        ];
+
+        // A lone variable-length item consumes all of the bytes.
+        let bytes = items[0].as_ssz_bytes();
+        let (item, consumed) = VariableLen::from_ssz_bytes_with_consumed(&bytes).unwrap();
+        assert_eq!((&item, consumed), (&items[0], bytes.len()));
+
+        // Concatenated variable-length items must be framed, here by a `u32` length prefix.
+        let mut bytes = vec![];
+        for item in &items {
+            bytes.append(&mut (item.ssz_bytes_len() as u32).as_ssz_bytes());
+            bytes.append(&mut item.as_ssz_bytes());
+        }
+
+        let mut decoded = vec![];
+        let mut remaining = &bytes[..];
+        while !remaining.is_empty() {
+            let (len, consumed) = u32::from_ssz_bytes_with_consumed(remaining).unwrap();
+            remaining = &remaining[consumed..];
+
+            let (item, consumed) =
+                VariableLen::from_ssz_bytes_with_consumed(&remaining[..len as usize]).unwrap();
+            assert_eq!(consumed, len as usize);
+            decoded.push(item);
+            remaining = &remaining[consumed..];
+        }
+
+        assert_eq!(decoded, items);
+    }
+
     #[test]
     fn offset_into_fixed_bytes() {
         let bytes = vec![
@@ -254,6 +567,29 @@ mod round_trip {
         round_trip(items);
     }
 
+    #[test]
+    fn list_iter_of_variable_len_struct() {
+        let items: Vec<VariableLen> = (0..5)
+            .map(|i| VariableLen {
+                a: i,
+                b: (0..i).collect(),
+                c: u32::from(i) * 7,
+            })
+            .collect();
+        let bytes = items.as_ssz_bytes();
+
+        let iter = SszListIter::<VariableLen>::new(&bytes).unwrap();
+        assert_eq!(iter.len(), items.len());
+        assert_eq!(
+            iter.collect::<Result<Vec<_>, _>>(),
+            <Vec<VariableLen>>::from_ssz_bytes(&bytes)
+        );
+
+        let empty = SszListIter::<VariableLen>::new(&[]).unwrap();
+        assert!(empty.is_empty());
+        assert_eq!(empty.count(), 0);
+    }
+
     #[derive(Debug, PartialEq, Encode, Decode)]
     struct ThreeVariableLen {
         a: u16,
@@ -288,6 +624,137 @@ mod round_trip {
         );
     }
 
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct FixedLenV1 {
+        a: u16,
+        b: u64,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct FixedLenV2 {
+        a: u16,
+        b: u64,
+        #[ssz(default_on_missing)]
+        c: u32,
+    }
+
+    #[test]
+    fn default_on_missing_fixed_len() {
+        let v1 = FixedLenV1 { a: 1, b: 2 };
+
+        assert_eq!(
+            FixedLenV2::from_ssz_bytes(&v1.as_ssz_bytes()),
+            Ok(FixedLenV2 { a: 1, b: 2, c: 0 })
+        );
+
+        // A partially-present trailing field is still an error.
+        let mut bytes = v1.as_ssz_bytes();
+        bytes.push(3);
+        assert!(FixedLenV2::from_ssz_bytes(&bytes).is_err());
+
+        round_trip(vec![FixedLenV2 { a: 1, b: 2, c: 3 }]);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct VariableLenV1 {
+        a: u16,
+        b: Vec<u16>,
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct VariableLenV2 {
+        a: u16,
+        b: Vec<u16>,
+        #[ssz(default_on_missing)]
+        c: u64,
+        #[ssz(default_on_missing)]
+        d: Vec<u16>,
+    }
+
+    #[test]
+    fn default_on_missing_variable_len() {
+        let v1 = VariableLenV1 {
+            a: 1,
+            b: vec![2, 3],
+        };
+
+        assert_eq!(
+            VariableLenV2::from_ssz_bytes(&v1.as_ssz_bytes()),
+            Ok(VariableLenV2 {
+                a: 1,
+                b: vec![2, 3],
+                c: 0,
+                d: vec![],
+            })
+        );
+
+        round_trip(vec![VariableLenV2 {
+            a: 1,
+            b: vec![2, 3],
+            c: 4,
+            d: vec![5],
+        }]);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct WithPhantom<T> {
+        a: u64,
+        _p: std::marker::PhantomData<T>,
+    }
+
+    #[test]
+    fn phantom_data() {
+        let item = WithPhantom::<u8> {
+            a: 42,
+            _p: std::marker::PhantomData,
+        };
+
+        assert!(<WithPhantom<u8> as Encode>::is_ssz_fixed_len());
+        assert_eq!(<WithPhantom<u8> as Encode>::ssz_fixed_len(), 8);
+        assert_eq!(item.as_ssz_bytes(), 42_u64.as_ssz_bytes());
+
+        round_trip(vec![item]);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Skipped {
+        a: u16,
+        #[ssz(skip)]
+        b: std::marker::PhantomData<u64>,
+        c: Vec<u16>,
+        #[ssz(skip)]
+        d: bool,
+    }
+
+    #[test]
+    fn skip() {
+        let skipped = Skipped {
+            a: 1,
+            b: std::marker::PhantomData,
+            c: vec![2, 3],
+            d: true,
+        };
+        let equivalent = VariableLenV1 {
+            a: 1,
+            b: vec![2, 3],
+        };
+
+        assert_eq!(
+            <Skipped as Encode>::ssz_fixed_len(),
+            <VariableLenV1 as Encode>::ssz_fixed_len()
+        );
+        assert_eq!(skipped.ssz_bytes_len(), equivalent.ssz_bytes_len());
+        assert_eq!(skipped.as_ssz_bytes(), equivalent.as_ssz_bytes());
+
+        assert_eq!(
+            Skipped::from_ssz_bytes(&skipped.as_ssz_bytes()),
+            Ok(Skipped {
+                d: false,
+                ..skipped
+            })
+        );
+    }
+
     #[derive(Debug, PartialEq, Encode, Decode)]
     struct TwoVariableLenOptions {
         a: u16,
@@ -378,4 +845,197 @@ mod round_trip {
 
         round_trip(vec);
     }
+
+    #[test]
+    fn tuple_with_variable_len_struct() {
+        let variable = |a, b: Vec<u16>, c| VariableLen { a, b, c };
+
+        let vec: Vec<(u64, Vec<u8>, VariableLen, u8, Vec<u8>, VariableLen)> = vec![
+            (
+                0,
+                vec![],
+                variable(0, vec![], 0),
+                0,
+                vec![],
+                variable(0, vec![], 0),
+            ),
+            (
+                42,
+                vec![1, 2],
+                variable(1, vec![3], 4),
+                5,
+                vec![6],
+                variable(7, vec![8, 9, 10], 11),
+            ),
+            (
+                u64::max_value(),
+                vec![],
+                variable(u16::max_value(), vec![12], u32::max_value()),
+                u8::max_value(),
+                vec![13, 14, 15],
+                variable(16, vec![], 17),
+            ),
+        ];
+
+        round_trip(vec);
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Nested {
+        a: VariableLen,
+        b: u64,
+        c: Vec<ThreeVariableLen>,
+    }
+
+    #[test]
+    fn ssz_write_matches_as_ssz_bytes() {
+        let nested = Nested {
+            a: VariableLen {
+                a: 1,
+                b: vec![2, 3],
+                c: 4,
+            },
+            b: 5,
+            c: vec![
+                ThreeVariableLen {
+                    a: 6,
+                    b: vec![],
+                    c: vec![7],
+                    d: vec![8, 9],
+                },
+                ThreeVariableLen {
+                    a: 10,
+                    b: vec![11],
+                    c: vec![],
+                    d: vec![],
+                },
+            ],
+        };
+
+        let mut buf = vec![];
+        ssz::ssz_encode_to_writer(&nested, &mut buf).unwrap();
+
+        assert_eq!(buf, nested.as_ssz_bytes());
+        assert_eq!(Nested::from_ssz_bytes(&buf), Ok(nested));
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    enum FixedOrVariableLen {
+        Fixed(FixedLen),
+        Variable(VariableLen),
+    }
+
+    #[test]
+    fn enum_encoding() {
+        let fixed = FixedLen { a: 1, b: 2, c: 3 };
+        let mut expected = vec![0];
+        expected.append(&mut fixed.as_ssz_bytes());
+        assert_eq!(FixedOrVariableLen::Fixed(fixed).as_ssz_bytes(), expected);
+
+        let variable = VariableLen {
+            a: 1,
+            b: vec![2],
+            c: 3,
+        };
+        let mut expected = vec![1];
+        expected.append(&mut variable.as_ssz_bytes());
+        assert_eq!(
+            FixedOrVariableLen::Variable(variable).as_ssz_bytes(),
+            expected
+        );
+
+        assert!(!<FixedOrVariableLen as Encode>::is_ssz_fixed_len());
+        assert!(!<FixedOrVariableLen as Decode>::is_ssz_fixed_len());
+    }
+
+    #[test]
+    fn enum_round_trip() {
+        let items = || {
+            vec![
+                FixedOrVariableLen::Fixed(FixedLen { a: 0, b: 0, c: 0 }),
+                FixedOrVariableLen::Fixed(FixedLen { a: 1, b: 2, c: 3 }),
+                FixedOrVariableLen::Variable(VariableLen {
+                    a: 0,
+                    b: vec![],
+                    c: 0,
+                }),
+                FixedOrVariableLen::Variable(VariableLen {
+                    a: 1,
+                    b: vec![2, 3, 4],
+                    c: 5,
+                }),
+            ]
+        };
+
+        round_trip(items());
+        round_trip(vec![items()]);
+    }
+
+    #[test]
+    fn enum_invalid_bytes() {
+        assert_eq!(
+            FixedOrVariableLen::from_ssz_bytes(&[]),
+            Err(DecodeError::BytesTooShort { need: 1, got: 0 })
+        );
+
+        let mut bytes = FixedOrVariableLen::Fixed(FixedLen { a: 1, b: 2, c: 3 }).as_ssz_bytes();
+        bytes[0] = 2;
+        assert_eq!(
+            FixedOrVariableLen::from_ssz_bytes(&bytes),
+            Err(DecodeError::BytesInvalid(
+                "2 is not a valid selector for FixedOrVariableLen".to_string()
+            ))
+        );
+
+        // The selector for `Fixed`, with a truncated value.
+        bytes[0] = 0;
+        bytes.pop();
+        assert_eq!(
+            FixedOrVariableLen::from_ssz_bytes(&bytes),
+            Err(DecodeError::BytesTooShort { need: 14, got: 13 })
+        );
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[ssz(transparent)]
+    struct TransparentFixed(u64);
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    #[ssz(transparent)]
+    struct TransparentVariable {
+        inner: Vec<u16>,
+    }
+
+    #[test]
+    fn transparent() {
+        let fixed = TransparentFixed(42);
+        assert_eq!(fixed.as_ssz_bytes(), 42_u64.as_ssz_bytes());
+        assert!(<TransparentFixed as Encode>::is_ssz_fixed_len());
+        assert_eq!(<TransparentFixed as Decode>::ssz_fixed_len(), 8);
+        round_trip(vec![TransparentFixed(0), fixed]);
+
+        let inner = vec![1, 2, 3];
+        let variable = TransparentVariable {
+            inner: inner.clone(),
+        };
+        assert_eq!(variable.as_ssz_bytes(), inner.as_ssz_bytes());
+        assert!(!<TransparentVariable as Encode>::is_ssz_fixed_len());
+        round_trip(vec![TransparentVariable { inner: vec![] }, variable]);
+
+        // Errors are those of the inner type.
+        assert_eq!(
+            TransparentVariable::from_ssz_bytes(&[1, 0, 2]),
+            <Vec<u16>>::from_ssz_bytes(&[1, 0, 2]).map(|inner| TransparentVariable { inner })
+        );
+    }
+
+    #[test]
+    fn tuple_fixed_len() {
+        assert!(<(u64, u8, [u8; 4]) as Encode>::is_ssz_fixed_len());
+        assert_eq!(<(u64, u8, [u8; 4]) as Encode>::ssz_fixed_len(), 13);
+        assert!(<(u64, u8, [u8; 4]) as Decode>::is_ssz_fixed_len());
+
+        assert!(!<(u64, Vec<u8>) as Encode>::is_ssz_fixed_len());
+        assert!(!<(u64, VariableLen) as Decode>::is_ssz_fixed_len());
+    }
 }
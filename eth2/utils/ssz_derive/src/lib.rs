@@ -2,6 +2,14 @@
 //! Provides procedural derive macros for the `Encode` and `Decode` traits of the `eth2_ssz` crate.
 //!
 //! Supports field attributes, see each derive macro for more information.
+//!
+//! Enums whose variants each have a single unnamed field are encoded as SSZ unions: a one-byte
+//! selector (the index of the variant) followed by the encoding of the variant's value.
+//!
+//! Generic structs are supported: the generics and `where` clause of the struct are copied onto
+//! the generated impl. No `Encode`/`Decode` bounds are added to the type parameters (e.g., a
+//! `T: EthSpec` is never itself encoded), so any bounds required by the field types must be
+//! declared on the struct.
 
 extern crate proc_macro;
 
@@ -9,6 +17,9 @@ use proc_macro::TokenStream;
 use quote::quote;
 use syn::{parse_macro_input, DeriveInput};
 
+/// The maximum number of variants of an enum, so that each selector fits in a byte.
+const MAX_UNION_VARIANTS: usize = 128;
+
 /// Returns a Vec of `syn::Ident` for each named field in the struct, whilst filtering out fields
 /// that should not be serialized.
 ///
@@ -49,22 +60,104 @@ fn get_serializable_field_types<'a>(struct_data: &'a syn::DataStruct) -> Vec<&'a
         .collect()
 }
 
+/// Returns true if some field has an attribute declaring it should be neither serialized nor
+/// deserialized.
+///
+/// The field attribute is: `#[ssz(skip)]`
+fn should_skip(field: &syn::Field) -> bool {
+    field
+        .attrs
+        .iter()
+        .any(|attr| attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(skip)")
+}
+
 /// Returns true if some field has an attribute declaring it should not be serialized.
 ///
-/// The field attribute is: `#[ssz(skip_serializing)]`
+/// The field attribute is: `#[ssz(skip_serializing)]` or `#[ssz(skip)]`
 fn should_skip_serializing(field: &syn::Field) -> bool {
-    field.attrs.iter().any(|attr| {
-        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(skip_serializing)"
+    should_skip(field)
+        || field.attrs.iter().any(|attr| {
+            attr.path.is_ident("ssz")
+                && attr.tts.to_string().replace(" ", "") == "(skip_serializing)"
+        })
+}
+
+/// Returns true if the struct has an attribute declaring it should be encoded identically to its
+/// only field.
+///
+/// The struct attribute is: `#[ssz(transparent)]`
+fn is_transparent(item: &DeriveInput) -> bool {
+    item.attrs.iter().any(|attr| {
+        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(transparent)"
     })
 }
 
-/// Implements `ssz::Encode` for some `struct`.
+/// Returns the member (i.e., the ident or index) and the type of the only field of a transparent
+/// struct.
+///
+/// # Panics
+/// A struct without exactly one field will raise a panic at compile time.
+fn get_transparent_field(struct_data: &syn::DataStruct) -> (syn::Member, &syn::Type) {
+    if struct_data.fields.iter().count() != 1 {
+        panic!("ssz_derive only supports transparent structs with exactly one field.");
+    }
+
+    let field = struct_data.fields.iter().next().expect("length is 1");
+    let member = match &field.ident {
+        Some(ident) => syn::Member::Named(ident.clone()),
+        None => syn::Member::Unnamed(syn::Index::from(0)),
+    };
+
+    (member, &field.ty)
+}
+
+/// Returns the ident and the type of the single unnamed field of each variant of an enum.
+///
+/// # Panics
+/// Any variant without exactly one unnamed field, or an enum without any variants or with more
+/// than `MAX_UNION_VARIANTS` variants, will raise a panic at compile time.
+fn get_enum_variants<'a>(enum_data: &'a syn::DataEnum) -> Vec<(&'a syn::Ident, &'a syn::Type)> {
+    if enum_data.variants.is_empty() || enum_data.variants.len() > MAX_UNION_VARIANTS {
+        panic!(
+            "ssz_derive only supports enums with between 1 and {} variants.",
+            MAX_UNION_VARIANTS
+        );
+    }
+
+    enum_data
+        .variants
+        .iter()
+        .map(|variant| match &variant.fields {
+            syn::Fields::Unnamed(fields) if fields.unnamed.len() == 1 => {
+                let field = fields.unnamed.iter().next().expect("length is 1");
+                (&variant.ident, &field.ty)
+            }
+            _ => panic!("ssz_derive only supports enum variants with a single unnamed field."),
+        })
+        .collect()
+}
+
+/// Implements `ssz::Encode` for some `struct` or `enum`.
 ///
 /// Fields are encoded in the order they are defined.
 ///
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: a struct with exactly one (named or unnamed) field is encoded
+/// identically to that field, rather than as a container of it.
+///
 /// ## Field attributes
 ///
 /// - `#[ssz(skip_serializing)]`: the field will not be serialized.
+/// - `#[ssz(skip)]`: the field will not be serialized, nor will it count towards the fixed length
+/// or offsets of the container. Shorthand for `skip_serializing` and `skip_deserializing`.
+///
+/// ## Fixed-length consistency
+///
+/// For a struct where every field is fixed-length, `ssz_append` debug-asserts that it appended
+/// exactly `ssz_fixed_len()` bytes. This catches a (hand-implemented) field type whose
+/// `ssz_fixed_len()` disagrees with its encoding, which would otherwise silently misalign
+/// decoding. The check can't be made at compile time since `ssz_fixed_len` is not a `const fn`.
 #[proc_macro_derive(Encode, attributes(ssz))]
 pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
@@ -74,9 +167,14 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
 
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_encode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
+    if is_transparent(&item) {
+        return ssz_encode_derive_transparent(&item, struct_data);
+    }
+
     let field_idents = get_serializable_named_field_idents(&struct_data);
     let field_idents_a = get_serializable_named_field_idents(&struct_data);
     let field_types_a = get_serializable_field_types(&struct_data);
@@ -84,6 +182,9 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
     let field_types_d = field_types_a.clone();
     let field_types_e = field_types_a.clone();
     let field_types_f = field_types_a.clone();
+    let field_types_g = field_types_a.clone();
+    let field_idents_fixed = field_idents.clone();
+    let field_idents_variable = field_idents.clone();
 
     let output = quote! {
         impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
@@ -124,6 +225,7 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
             }
 
             fn ssz_append(&self, buf: &mut Vec<u8>) {
+                let start = buf.len();
                 let offset = #(
                         <#field_types_f as ssz::Encode>::ssz_fixed_len() +
                     )*
@@ -136,6 +238,126 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
                 )*
 
                 encoder.finalize();
+
+                debug_assert!(
+                    !<Self as ssz::Encode>::is_ssz_fixed_len()
+                        || buf.len() - start == <Self as ssz::Encode>::ssz_fixed_len(),
+                    "{} appended {} bytes but has an ssz_fixed_len of {}",
+                    stringify!(#name),
+                    buf.len() - start,
+                    <Self as ssz::Encode>::ssz_fixed_len()
+                );
+            }
+
+            fn ssz_write<SszWriteTarget: std::io::Write>(
+                &self,
+                writer: &mut SszWriteTarget,
+            ) -> std::io::Result<()> {
+                let offset = #(
+                        <#field_types_g as ssz::Encode>::ssz_fixed_len() +
+                    )*
+                        0;
+
+                let mut ssz_writer = ssz::SszWriter::container(writer, offset);
+
+                #(
+                    ssz_writer.append_fixed(&self.#field_idents_fixed)?;
+                )*
+
+                #(
+                    ssz_writer.append_variable(&self.#field_idents_variable)?;
+                )*
+
+                Ok(())
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Encode` for a `#[ssz(transparent)]` struct, delegating to its only field.
+fn ssz_encode_derive_transparent(item: &DeriveInput, struct_data: &syn::DataStruct) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+    let (member, ty) = get_transparent_field(struct_data);
+
+    let output = quote! {
+        impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                <#ty as ssz::Encode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <#ty as ssz::Encode>::ssz_fixed_len()
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                <#ty as ssz::Encode>::ssz_bytes_len(&self.#member)
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                <#ty as ssz::Encode>::ssz_append(&self.#member, buf)
+            }
+
+            fn ssz_write<SszWriteTarget: std::io::Write>(
+                &self,
+                writer: &mut SszWriteTarget,
+            ) -> std::io::Result<()> {
+                <#ty as ssz::Encode>::ssz_write(&self.#member, writer)
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Encode` for an `enum`, as an SSZ union.
+fn ssz_encode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let variants = get_enum_variants(enum_data);
+    let bytes_len_arms = variants
+        .iter()
+        .map(|(ident, _)| {
+            quote! {
+                #name::#ident(inner) => 1 + inner.ssz_bytes_len(),
+            }
+        })
+        .collect::<Vec<_>>();
+    let append_arms = variants
+        .iter()
+        .enumerate()
+        .map(|(selector, (ident, _))| {
+            let selector = selector as u8;
+            quote! {
+                #name::#ident(inner) => {
+                    buf.push(#selector);
+                    inner.ssz_append(buf);
+                }
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let output = quote! {
+        impl #impl_generics ssz::Encode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn ssz_bytes_len(&self) -> usize {
+                match self {
+                    #(
+                        #bytes_len_arms
+                    )*
+                }
+            }
+
+            fn ssz_append(&self, buf: &mut Vec<u8>) {
+                match self {
+                    #(
+                        #append_arms
+                    )*
+                }
             }
         }
     };
@@ -144,23 +366,48 @@ pub fn ssz_encode_derive(input: TokenStream) -> TokenStream {
 
 /// Returns true if some field has an attribute declaring it should not be deserialized.
 ///
-/// The field attribute is: `#[ssz(skip_deserializing)]`
+/// The field attribute is: `#[ssz(skip_deserializing)]` or `#[ssz(skip)]`
 fn should_skip_deserializing(field: &syn::Field) -> bool {
+    should_skip(field)
+        || field.attrs.iter().any(|attr| {
+            attr.path.is_ident("ssz")
+                && attr.tts.to_string().replace(" ", "") == "(skip_deserializing)"
+        })
+}
+
+/// Returns true if some field has an attribute declaring it should be instantiated from `Default`
+/// when it is absent from the end of the input bytes.
+///
+/// The field attribute is: `#[ssz(default_on_missing)]`
+fn should_default_on_missing(field: &syn::Field) -> bool {
     field.attrs.iter().any(|attr| {
-        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(skip_deserializing)"
+        attr.path.is_ident("ssz") && attr.tts.to_string().replace(" ", "") == "(default_on_missing)"
     })
 }
 
-/// Implements `ssz::Decode` for some `struct`.
+/// Implements `ssz::Decode` for some `struct` or `enum`.
+///
+/// Fields are decoded in the order they are defined. An error whilst decoding a field is returned
+/// as a `DecodeError::FieldError` naming that field.
 ///
-/// Fields are decoded in the order they are defined.
+/// ## Struct attributes
+///
+/// - `#[ssz(transparent)]`: a struct with exactly one (named or unnamed) field is decoded
+/// identically to that field. Errors are returned as-is, without a `DecodeError::FieldError`.
 ///
 /// ## Field attributes
 ///
 /// - `#[ssz(skip_deserializing)]`: during de-serialization the field will be instantiated from a
 /// `Default` implementation. The decoder will assume that the field was not serialized at all
 /// (e.g., if it has been serialized, an error will be raised instead of `Default` overriding it).
-#[proc_macro_derive(Decode)]
+/// - `#[ssz(default_on_missing)]`: if the fixed-length portion of the input bytes ends before this
+/// field, it will be instantiated from a `Default` implementation. Only trailing fields may have
+/// this attribute. This allows decoding the bytes of an older version of a top-level container
+/// that lacked the field; the field is always encoded.
+/// - `#[ssz(skip)]`: the field is instantiated from a `Default` implementation, as with
+/// `skip_deserializing`. Use this for fields which are also absent from the encoding (e.g.,
+/// cached values or `PhantomData`).
+#[proc_macro_derive(Decode, attributes(ssz))]
 pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
     let item = parse_macro_input!(input as DeriveInput);
 
@@ -169,13 +416,19 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
 
     let struct_data = match &item.data {
         syn::Data::Struct(s) => s,
-        _ => panic!("ssz_derive only supports structs."),
+        syn::Data::Enum(e) => return ssz_decode_derive_enum(&item, e),
+        _ => panic!("ssz_derive only supports structs and enums."),
     };
 
+    if is_transparent(&item) {
+        return ssz_decode_derive_transparent(&item, struct_data);
+    }
+
     let mut register_types = vec![];
     let mut decodes = vec![];
     let mut is_fixed_lens = vec![];
     let mut fixed_lens = vec![];
+    let mut seen_default_on_missing = false;
 
     // Build quotes for fields that should be deserialized and those that should be built from
     // `Default`.
@@ -187,15 +440,53 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
                     decodes.push(quote! {
                         #ident: <_>::default()
                     });
+                } else if should_default_on_missing(field) {
+                    // Field should be decoded if present, otherwise use a `Default` impl.
+                    seen_default_on_missing = true;
+
+                    let ty = &field.ty;
+                    let is_present =
+                        syn::Ident::new(&format!("{}_is_present", ident), ident.span());
+
+                    register_types.push(quote! {
+                        let #is_present = builder.has_remaining_fixed_bytes();
+                        if #is_present {
+                            builder.register_type::<#ty>()?;
+                        }
+                    });
+
+                    let field_name = ident.to_string();
+
+                    decodes.push(quote! {
+                        #ident: if #is_present {
+                            decoder.decode_next_field(#field_name)?
+                        } else {
+                            <_>::default()
+                        }
+                    });
+
+                    is_fixed_lens.push(quote! {
+                        <#ty as ssz::Decode>::is_ssz_fixed_len()
+                    });
+
+                    fixed_lens.push(quote! {
+                        <#ty as ssz::Decode>::ssz_fixed_len()
+                    });
                 } else {
+                    if seen_default_on_missing {
+                        panic!("ssz_derive only supports default_on_missing on trailing fields.");
+                    }
+
                     let ty = &field.ty;
 
                     register_types.push(quote! {
                         builder.register_type::<#ty>()?;
                     });
 
+                    let field_name = ident.to_string();
+
                     decodes.push(quote! {
-                        #ident: decoder.decode_next()?
+                        #ident: decoder.decode_next_field(#field_name)?
                     });
 
                     is_fixed_lens.push(quote! {
@@ -250,3 +541,72 @@ pub fn ssz_decode_derive(input: TokenStream) -> TokenStream {
     };
     output.into()
 }
+
+/// Implements `ssz::Decode` for a `#[ssz(transparent)]` struct, delegating to its only field.
+fn ssz_decode_derive_transparent(item: &DeriveInput, struct_data: &syn::DataStruct) -> TokenStream {
+    let name = &item.ident;
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+    let (member, ty) = get_transparent_field(struct_data);
+
+    let output = quote! {
+        impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                <#ty as ssz::Decode>::is_ssz_fixed_len()
+            }
+
+            fn ssz_fixed_len() -> usize {
+                <#ty as ssz::Decode>::ssz_fixed_len()
+            }
+
+            fn from_ssz_bytes(bytes: &[u8]) -> std::result::Result<Self, ssz::DecodeError> {
+                <#ty as ssz::Decode>::from_ssz_bytes(bytes).map(|inner| Self { #member: inner })
+            }
+        }
+    };
+    output.into()
+}
+
+/// Implements `ssz::Decode` for an `enum`, as an SSZ union.
+///
+/// A selector which does not correspond to a variant is a `DecodeError::BytesInvalid`.
+fn ssz_decode_derive_enum(item: &DeriveInput, enum_data: &syn::DataEnum) -> TokenStream {
+    let name = &item.ident;
+    let name_str = name.to_string();
+    let (impl_generics, ty_generics, where_clause) = &item.generics.split_for_impl();
+
+    let decode_arms = get_enum_variants(enum_data)
+        .into_iter()
+        .enumerate()
+        .map(|(selector, (ident, ty))| {
+            let selector = selector as u8;
+            quote! {
+                #selector => Ok(#name::#ident(<#ty as ssz::Decode>::from_ssz_bytes(body)?)),
+            }
+        })
+        .collect::<Vec<_>>();
+
+    let output = quote! {
+        impl #impl_generics ssz::Decode for #name #ty_generics #where_clause {
+            fn is_ssz_fixed_len() -> bool {
+                false
+            }
+
+            fn from_ssz_bytes(bytes: &[u8]) -> std::result::Result<Self, ssz::DecodeError> {
+                let (selector, body) = bytes.split_first().ok_or_else(|| {
+                    ssz::DecodeError::BytesTooShort { need: 1, got: 0 }
+                })?;
+
+                match *selector {
+                    #(
+                        #decode_arms
+                    )*
+                    other => Err(ssz::DecodeError::BytesInvalid(format!(
+                        "{} is not a valid selector for {}",
+                        other, #name_str
+                    ))),
+                }
+            }
+        }
+    };
+    output.into()
+}
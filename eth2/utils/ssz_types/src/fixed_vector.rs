@@ -204,6 +204,10 @@ where
             encoder.finalize();
         }
     }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.vec.ssz_write(writer)
+    }
 }
 
 impl<T, N: Unsigned> ssz::Decode for FixedVector<T, N>
@@ -223,11 +227,18 @@ where
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
-        if bytes.is_empty() {
-            Err(ssz::DecodeError::InvalidByteLength {
-                len: 0,
-                expected: 1,
-            })
+        if T::is_ssz_fixed_len() && T::ssz_fixed_len() == 0 {
+            // Every item of a zero-length type is encoded as no bytes at all.
+            if bytes.is_empty() {
+                Ok(vec![].into())
+            } else {
+                Err(ssz::DecodeError::BytesTooLong {
+                    expected: 0,
+                    got: bytes.len(),
+                })
+            }
+        } else if bytes.is_empty() {
+            Err(ssz::DecodeError::BytesTooShort { need: 1, got: 0 })
         } else if T::is_ssz_fixed_len() {
             bytes
                 .chunks(T::ssz_fixed_len())
@@ -332,6 +343,20 @@ mod test {
         ssz_round_trip::<FixedVector<u16, U8>>(vec![0; 8].into());
     }
 
+    #[test]
+    fn ssz_zero_length_items() {
+        type Vector = FixedVector<PhantomData<u8>, U4>;
+
+        ssz_round_trip::<Vector>(vec![].into());
+        assert_eq!(
+            Vector::from_ssz_bytes(&[1]),
+            Err(DecodeError::BytesTooLong {
+                expected: 0,
+                got: 1
+            })
+        );
+    }
+
     #[test]
     fn tree_hash_u8() {
         let fixed: FixedVector<u8, U0> = FixedVector::from(vec![]);
@@ -215,6 +215,10 @@ where
     fn ssz_append(&self, buf: &mut Vec<u8>) {
         self.vec.ssz_append(buf)
     }
+
+    fn ssz_write<W: std::io::Write>(&self, writer: &mut W) -> std::io::Result<()> {
+        self.vec.ssz_write(writer)
+    }
 }
 
 impl<T, N: Unsigned> ssz::Decode for VariableList<T, N>
@@ -230,7 +234,11 @@ where
     }
 
     fn from_ssz_bytes(bytes: &[u8]) -> Result<Self, ssz::DecodeError> {
-        let vec = <Vec<T>>::from_ssz_bytes(bytes)?;
+        let vec = if bytes.is_empty() || T::is_ssz_fixed_len() {
+            <Vec<T>>::from_ssz_bytes(bytes)?
+        } else {
+            ssz::decode_list_of_variable_length_items_bounded(bytes, N::to_usize())?
+        };
 
         Self::new(vec).map_err(|e| ssz::DecodeError::BytesInvalid(format!("VariableList {:?}", e)))
     }
@@ -240,6 +248,7 @@ where
 mod test {
     use super::*;
     use ssz::*;
+    use ssz_derive::{Decode, Encode};
     use tree_hash::{merkle_root, TreeHash};
     use tree_hash_derive::TreeHash;
     use typenum::*;
@@ -318,6 +327,46 @@ mod test {
         round_trip::<VariableList<u16, U8>>(vec![0; 8].into());
     }
 
+    trait Spec: std::fmt::Debug + PartialEq {
+        type MaxLen: Unsigned;
+    }
+
+    #[derive(Debug, PartialEq)]
+    struct SmallSpec;
+
+    impl Spec for SmallSpec {
+        type MaxLen = U4;
+    }
+
+    #[derive(Debug, PartialEq, Encode, Decode)]
+    struct Generic<T: Spec, U>
+    where
+        U: Encode + Decode,
+    {
+        a: VariableList<u8, T::MaxLen>,
+        b: U,
+        #[ssz(skip)]
+        _phantom: std::marker::PhantomData<T>,
+    }
+
+    #[test]
+    fn derive_generic() {
+        round_trip(Generic::<SmallSpec, u16> {
+            a: vec![1, 2, 3].into(),
+            b: 4,
+            _phantom: std::marker::PhantomData,
+        });
+
+        let mut too_long = Generic::<SmallSpec, u16> {
+            a: vec![].into(),
+            b: 0,
+            _phantom: std::marker::PhantomData,
+        }
+        .as_ssz_bytes();
+        too_long.extend_from_slice(&[0; 5]);
+        assert!(Generic::<SmallSpec, u16>::from_ssz_bytes(&too_long).is_err());
+    }
+
     fn root_with_length(bytes: &[u8], len: usize) -> Vec<u8> {
         let root = merkle_root(bytes, 0);
         tree_hash::mix_in_length(&root, len)
@@ -1,6 +1,9 @@
 use criterion::Criterion;
 use criterion::{black_box, criterion_group, criterion_main, Benchmark};
 use lazy_static::lazy_static;
+use tree_hash::{
+    merkle_root, merkleize_padded, merkleize_padded_root, mix_in_length, TreeHash, BYTES_PER_CHUNK,
+};
 use types::test_utils::{generate_deterministic_keypairs, TestingBeaconStateBuilder};
 use types::{BeaconState, EthSpec, Keypair, MainnetEthSpec, MinimalEthSpec};
 
@@ -82,7 +85,58 @@ fn bench_suite<T: EthSpec>(c: &mut Criterion, spec_desc: &str, validator_count:
     );
 }
 
+/// Compare `merkleize_padded` to `merkleize_padded_root`, which skips the final `Vec` allocation.
+fn bench_merkleize(c: &mut Criterion, num_chunks: usize) {
+    let bytes: Vec<u8> = (0..num_chunks * BYTES_PER_CHUNK)
+        .map(|byte| byte as u8)
+        .collect();
+    let bytes_root = bytes.clone();
+
+    c.bench(
+        &format!("merkleize/{}_chunks", num_chunks),
+        Benchmark::new("merkleize_padded", move |b| {
+            b.iter(|| black_box(merkleize_padded(&bytes, 0).unwrap()))
+        })
+        .with_function("merkleize_padded_root", move |b| {
+            b.iter(|| black_box(merkleize_padded_root(&bytes_root, 0).unwrap()))
+        })
+        .sample_size(10),
+    );
+}
+
+/// Compare the `Vec<u8>` root, which merkleizes the bytes in place, to packing each byte
+/// individually before merkleizing.
+fn bench_bytes(c: &mut Criterion, len: usize) {
+    let bytes: Vec<u8> = (0..len).map(|byte| byte as u8).collect();
+    let bytes_naive = bytes.clone();
+
+    c.bench(
+        &format!("vec_u8/{}_bytes", len),
+        Benchmark::new("packed", move |b| {
+            b.iter(|| black_box(bytes.tree_hash_root()))
+        })
+        .with_function("naive", move |b| {
+            b.iter(|| {
+                let packed: Vec<u8> = bytes_naive
+                    .iter()
+                    .flat_map(TreeHash::tree_hash_packed_encoding)
+                    .collect();
+                black_box(mix_in_length(&merkle_root(&packed, 0), packed.len()))
+            })
+        })
+        .sample_size(10),
+    );
+}
+
 fn all_benches(c: &mut Criterion) {
+    for &num_chunks in &[1, 2, 9, 1024] {
+        bench_merkleize(c, num_chunks);
+    }
+
+    for &len in &[32, 1024, 1024 * 1024] {
+        bench_bytes(c, len);
+    }
+
     bench_suite::<MinimalEthSpec>(c, "minimal", 100_000);
     bench_suite::<MinimalEthSpec>(c, "minimal", 300_000);
 
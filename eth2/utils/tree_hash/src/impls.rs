@@ -73,6 +73,30 @@ macro_rules! impl_for_u8_array {
 impl_for_u8_array!(4);
 impl_for_u8_array!(32);
 
+/// Hashed as a list of bytes with no maximum length, i.e., the bytes are packed into chunks
+/// without any padding chunks and the length is mixed in.
+///
+/// A bounded `List[uint8, N]` is padded to the chunk count of its limit, so it has a different
+/// root. Use `ssz_types::VariableList` for those.
+impl TreeHash for Vec<u8> {
+    fn tree_hash_type() -> TreeHashType {
+        TreeHashType::List
+    }
+
+    fn tree_hash_packed_encoding(&self) -> Vec<u8> {
+        unreachable!("List should never be packed.")
+    }
+
+    fn tree_hash_packing_factor() -> usize {
+        unreachable!("List should never be packed.")
+    }
+
+    fn tree_hash_root(&self) -> Vec<u8> {
+        // The bytes are already their own packed encoding, so they are merkleized in place.
+        mix_in_length(&merkle_root(self, 0), self.len())
+    }
+}
+
 impl TreeHash for U128 {
     fn tree_hash_type() -> TreeHashType {
         TreeHashType::Basic
@@ -171,4 +195,41 @@ mod test {
             ]
         );
     }
+
+    fn reference_root(bytes: &[u8]) -> Vec<u8> {
+        merkleize_standard(bytes).unwrap()[0..BYTES_PER_CHUNK].to_vec()
+    }
+
+    #[test]
+    fn u8_arrays() {
+        let bytes4 = [1, 2, 3, 4];
+        assert_eq!(bytes4.tree_hash_root(), reference_root(&bytes4));
+
+        let mut bytes32 = [0; 32];
+        for (i, byte) in bytes32.iter_mut().enumerate() {
+            *byte = i as u8;
+        }
+        assert_eq!(bytes32.tree_hash_root(), reference_root(&bytes32));
+    }
+
+    #[test]
+    fn vec_u8() {
+        for &len in &[0, 1, 31, 32, 33, 64, 100, 1025] {
+            let bytes: Vec<u8> = (0..len).map(|i| i as u8).collect();
+
+            // The naive root, packing each byte individually.
+            let packed: Vec<u8> = bytes
+                .iter()
+                .flat_map(TreeHash::tree_hash_packed_encoding)
+                .collect();
+            assert_eq!(packed, bytes);
+
+            assert_eq!(
+                bytes.tree_hash_root(),
+                mix_in_length(&reference_root(&packed), len),
+                "len: {}",
+                len
+            );
+        }
+    }
 }
@@ -2,19 +2,38 @@ pub mod impls;
 mod merkleize_padded;
 mod merkleize_standard;
 
-pub use merkleize_padded::merkleize_padded;
+pub use merkleize_padded::{
+    merkle_proof, merkleize_from_chunks, merkleize_hashes, merkleize_padded,
+    merkleize_padded_fixed, merkleize_padded_root, merkleize_padded_with_layers,
+    merkleize_padded_with_scratch, merkleize_roots, zero_hash, TreeShape, MAX_TREE_DEPTH,
+};
 pub use merkleize_standard::merkleize_standard;
 
 pub const BYTES_PER_CHUNK: usize = 32;
 pub const HASHSIZE: usize = 32;
 pub const MERKLE_HASH_CHUNK: usize = 2 * BYTES_PER_CHUNK;
 
+#[derive(Debug, PartialEq, Clone)]
+pub enum Error {
+    /// The tree would have a depth larger than `MAX_TREE_DEPTH`, so there are no cached padding
+    /// nodes for `height`.
+    MaxTreeDepthExceeded { height: usize },
+    /// The leaf index for a proof is not in the tree.
+    LeafIndexOutOfBounds { index: usize, num_leaves: usize },
+}
+
 /// Alias to `merkleize_padded(&bytes, minimum_chunk_count)`
 ///
 /// If `minimum_chunk_count < bytes / BYTES_PER_CHUNK`, padding will be added for the difference
 /// between the two.
+///
+/// ## Panics
+///
+/// If the tree would be deeper than `MAX_TREE_DEPTH`. This is only possible with a
+/// `minimum_chunk_count` (or `bytes`) larger than `2^MAX_TREE_DEPTH` chunks, which `TreeHash`
+/// implementations never produce. Use `merkleize_padded` to handle this case as an error.
 pub fn merkle_root(bytes: &[u8], minimum_chunk_count: usize) -> Vec<u8> {
-    merkleize_padded(&bytes, minimum_chunk_count)
+    merkleize_padded(&bytes, minimum_chunk_count).expect("Tree should not exceed MAX_TREE_DEPTH")
 }
 
 /// Returns the node created by hashing `root` and `length`.
@@ -27,6 +46,17 @@ pub fn mix_in_length(root: &[u8], length: usize) -> Vec<u8> {
     eth2_hashing::hash32_concat(root, &length_bytes)[..].to_vec()
 }
 
+/// Returns the node created by hashing `root` and `selector`.
+///
+/// Used for inserting the selector of a union above the root of its value, as `mix_in_length`
+/// does for the length of a list.
+pub fn mix_in_selector(root: &[u8], selector: u8) -> Vec<u8> {
+    let mut selector_bytes = vec![selector];
+    selector_bytes.resize(BYTES_PER_CHUNK, 0);
+
+    eth2_hashing::hash32_concat(root, &selector_bytes)[..].to_vec()
+}
+
 #[derive(Debug, PartialEq, Clone)]
 pub enum TreeHashType {
     Basic,
@@ -106,4 +136,37 @@ mod test {
 
         assert_eq!(mix_in_length(&[42; BYTES_PER_CHUNK], 42), hash);
     }
+
+    #[test]
+    fn mix_selector() {
+        // A `Union[None, uint16]`, where the `None` variant has a zero root.
+        assert_eq!(
+            mix_in_selector(&[0; BYTES_PER_CHUNK], 0),
+            zero_hash(1).unwrap()
+        );
+
+        // The `uint16` variant, with the value `0xaabb`.
+        let mut root = vec![0xbb, 0xaa];
+        root.resize(BYTES_PER_CHUNK, 0);
+
+        let expected = [
+            0x01, 0x65, 0x50, 0xf6, 0x36, 0xd5, 0x8c, 0xac, 0x23, 0x44, 0x70, 0x3d, 0x63, 0x6a,
+            0x92, 0x05, 0xc8, 0x37, 0x0c, 0x12, 0x20, 0x51, 0x0a, 0x4c, 0x00, 0x53, 0xda, 0x00,
+            0x77, 0x1e, 0x4c, 0x6c,
+        ];
+        assert_eq!(mix_in_selector(&root, 1), expected);
+    }
+
+    #[test]
+    fn standard_matches_padded() {
+        for num_bytes in 0..=17 * BYTES_PER_CHUNK + 1 {
+            let bytes = (0..num_bytes).map(|i| i as u8).collect::<Vec<_>>();
+            assert_eq!(
+                &merkleize_standard(&bytes).unwrap()[0..HASHSIZE],
+                &merkleize_padded(&bytes, 0).unwrap()[..],
+                "num_bytes: {}",
+                num_bytes
+            );
+        }
+    }
 }
@@ -1,11 +1,21 @@
-use super::BYTES_PER_CHUNK;
-use eth2_hashing::{hash, hash32_concat, ZERO_HASHES, ZERO_HASHES_MAX_INDEX};
+use super::{Error, BYTES_PER_CHUNK};
+use eth2_hashing::{hash32_concat, ZERO_HASHES, ZERO_HASHES_MAX_INDEX};
+use ethereum_types::H256 as Hash256;
+#[cfg(feature = "rayon")]
+use rayon::prelude::*;
 
 /// The size of the cache that stores padding nodes for a given height.
 ///
-/// Currently, we panic if we encounter a tree with a height larger than `MAX_TREE_DEPTH`.
+/// Merkleizing a tree with a depth larger than `MAX_TREE_DEPTH` returns
+/// `Error::MaxTreeDepthExceeded`.
 pub const MAX_TREE_DEPTH: usize = ZERO_HASHES_MAX_INDEX;
 
+/// The minimum number of nodes in a layer before it is hashed across the rayon thread pool.
+///
+/// Only used with the `rayon` feature. Below this, the overhead of spawning tasks outweighs the
+/// hashing itself.
+const PARALLEL_THRESHOLD: usize = 1024;
+
 /// Merkleize `bytes` and return the root, optionally padding the tree out to `min_leaves` number of
 /// leaves.
 ///
@@ -34,148 +44,692 @@ pub const MAX_TREE_DEPTH: usize = ZERO_HASHES_MAX_INDEX;
 ///
 /// _Note: there are some minor memory overheads, including a handful of usizes and a list of
 /// `MAX_TREE_DEPTH` hashes as `lazy_static` constants._
-pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Vec<u8> {
+///
+/// ## Parallelism
+///
+/// With the `rayon` feature enabled, each layer with at least `PARALLEL_THRESHOLD` nodes is hashed
+/// in parallel. Layers above the first are then written to a temporary buffer rather than
+/// in-place, so the memory footprint of those rounds is roughly one and a half times that of the
+/// serial path.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_padded(bytes: &[u8], min_leaves: usize) -> Result<Vec<u8>, Error> {
+    merkleize_padded_root(bytes, min_leaves).map(|root| root.as_bytes().to_vec())
+}
+
+/// Merkleize `bytes` in the same manner as `merkleize_padded`, returning the root as a `Hash256`
+/// rather than a `Vec<u8>`.
+///
+/// Prefer this when the result is going to be used as a `Hash256` anyway, as it avoids allocating
+/// and copying from a `Vec<u8>`.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_padded_root(bytes: &[u8], min_leaves: usize) -> Result<Hash256, Error> {
+    merkleize_padded_with_threshold(bytes, min_leaves, PARALLEL_THRESHOLD, &mut vec![])
+}
+
+/// Merkleize `bytes` in the same manner as `merkleize_padded_root`, using `scratch` as the buffer
+/// for each round of hashes instead of allocating a new one.
+///
+/// The contents of `scratch` are overwritten. It is grown as required, so when reused across calls
+/// its capacity becomes that needed by the widest tree seen (one chunk per pair of leaf chunks in
+/// `bytes`) and no further allocations are made for the rounds hashed in-place.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_padded_with_scratch(
+    bytes: &[u8],
+    min_leaves: usize,
+    scratch: &mut Vec<u8>,
+) -> Result<Hash256, Error> {
+    merkleize_padded_with_threshold(bytes, min_leaves, PARALLEL_THRESHOLD, scratch)
+}
+
+/// The shape of the tree that `merkleize_padded` builds for a given number of bytes and
+/// `min_leaves`.
+///
+/// This allows callers with a statically-known layout (e.g., a fixed-length vector) to compute the
+/// leaf count and height of the tree once and pass them to `merkleize_padded_fixed`, rather than
+/// recomputing them on every call.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TreeShape {
+    /// The number of bytes merkleized into the tree.
+    num_bytes: usize,
+    /// The number of leaves in the full tree (including padding nodes), a power of two.
+    num_leaves: usize,
+    /// The number of levels in the tree, where a tree with a single node has `height == 1`.
+    height: usize,
+    /// The number of parents of the leaves that have at least one non-padding leaf.
+    initial_parents_with_values: usize,
+}
+
+impl TreeShape {
+    /// The shape of the tree for `num_bytes` bytes, padded out to at least `min_leaves` leaves.
+    pub fn new(num_bytes: usize, min_leaves: usize) -> Self {
+        Self::with_chunk_size(num_bytes, min_leaves, BYTES_PER_CHUNK)
+    }
+
+    /// As `new`, but for a tree of `chunk_size`-byte nodes.
+    fn with_chunk_size(num_bytes: usize, min_leaves: usize, chunk_size: usize) -> Self {
+        // The number of leaves that can be made directly from the bytes.
+        let leaves_with_values = (num_bytes + (chunk_size - 1)) / chunk_size;
+        let num_leaves = std::cmp::max(leaves_with_values, min_leaves).next_power_of_two();
+
+        Self {
+            num_bytes,
+            num_leaves,
+            height: num_leaves.trailing_zeros() as usize + 1,
+            initial_parents_with_values: std::cmp::max(1, next_even_number(leaves_with_values) / 2),
+        }
+    }
+
+    /// The number of leaves in the full tree (including padding nodes).
+    pub fn num_leaves(&self) -> usize {
+        self.num_leaves
+    }
+
+    /// The number of levels in the tree.
+    pub fn height(&self) -> usize {
+        self.height
+    }
+}
+
+/// Merkleize `bytes` in the same manner as `merkleize_padded`, into a tree of the precomputed
+/// `shape`.
+///
+/// `shape` must have been computed for `bytes.len()` bytes, which is only checked in debug builds.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_padded_fixed(bytes: &[u8], shape: &TreeShape) -> Result<Vec<u8>, Error> {
+    debug_assert_eq!(
+        bytes.len(),
+        shape.num_bytes,
+        "shape must be computed for the length of bytes"
+    );
+
+    let mut root = vec![0; BYTES_PER_CHUNK];
+    merkleize_padded_core_fixed(
+        bytes,
+        shape,
+        &TreeParams::sha256(),
+        PARALLEL_THRESHOLD,
+        &mut vec![],
+        &mut root,
+    )?;
+    Ok(root)
+}
+
+/// Implementation of `merkleize_padded_root`, hashing layers of at least `parallel_threshold`
+/// nodes in parallel (if the `rayon` feature is enabled) and storing them in `scratch`.
+fn merkleize_padded_with_threshold(
+    bytes: &[u8],
+    min_leaves: usize,
+    parallel_threshold: usize,
+    scratch: &mut Vec<u8>,
+) -> Result<Hash256, Error> {
+    let mut root = Hash256::zero();
+    merkleize_padded_core(
+        bytes,
+        min_leaves,
+        &TreeParams::sha256(),
+        parallel_threshold,
+        scratch,
+        root.as_bytes_mut(),
+    )?;
+    Ok(root)
+}
+
+/// The size of each chunk, the hash function and the padding nodes of a Merkle tree.
+///
+/// `merkleize_padded` always uses `TreeParams::sha256`; other parameters are only used to test
+/// the shape of the tree independently of SHA-256.
+struct TreeParams<'a, F> {
+    /// The number of bytes in each node of the tree.
+    chunk_size: usize,
+    /// Writes the hash of the `left` and `right` children to `parent`, each of which is
+    /// `chunk_size` bytes.
+    hash_concat: F,
+    /// The padding node at each height, where `zero_hashes[0]` is a zero-valued leaf.
+    ///
+    /// The length of this list determines the maximum depth of the tree.
+    zero_hashes: &'a [Vec<u8>],
+}
+
+impl TreeParams<'static, fn(&[u8], &[u8], &mut [u8])> {
+    /// Parameters for a tree of `BYTES_PER_CHUNK` chunks hashed with SHA-256.
+    fn sha256() -> Self {
+        Self {
+            chunk_size: BYTES_PER_CHUNK,
+            hash_concat: |left, right, parent| parent.copy_from_slice(&hash32_concat(left, right)),
+            zero_hashes: &ZERO_HASHES,
+        }
+    }
+}
+
+impl<'a, F> TreeParams<'a, F>
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    /// Returns the padding node at `height`.
+    ///
+    /// Returns `Error::MaxTreeDepthExceeded` if `height` is beyond `self.zero_hashes`.
+    fn zero_hash(&self, height: usize) -> Result<&'a [u8], Error> {
+        self.zero_hashes
+            .get(height)
+            .map(Vec::as_slice)
+            .ok_or_else(|| Error::MaxTreeDepthExceeded { height })
+    }
+
+    /// Returns `Err` if a tree with `height` levels would be deeper than `self.zero_hashes`
+    /// permits.
+    fn check_tree_depth(&self, height: usize) -> Result<(), Error> {
+        self.zero_hash(height - 1).map(|_| ())
+    }
+}
+
+/// Merkleize `bytes` in the same manner as `merkleize_padded_root`, using the chunk size and hash
+/// function of `params`, and write the root to `root` (which must be one chunk).
+///
+/// Layers of at least `parallel_threshold` nodes are hashed in parallel (if the `rayon` feature is
+/// enabled) and stored in `scratch`.
+fn merkleize_padded_core<F>(
+    bytes: &[u8],
+    min_leaves: usize,
+    params: &TreeParams<F>,
+    parallel_threshold: usize,
+    scratch: &mut Vec<u8>,
+    root: &mut [u8],
+) -> Result<(), Error>
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    let shape = TreeShape::with_chunk_size(bytes.len(), min_leaves, params.chunk_size);
+
+    merkleize_padded_core_fixed(bytes, &shape, params, parallel_threshold, scratch, root)
+}
+
+/// Implementation of `merkleize_padded_core` for a tree of the given `shape`, which must have been
+/// computed for `bytes.len()` bytes and `params.chunk_size`.
+fn merkleize_padded_core_fixed<F>(
+    bytes: &[u8],
+    shape: &TreeShape,
+    params: &TreeParams<F>,
+    parallel_threshold: usize,
+    scratch: &mut Vec<u8>,
+    root: &mut [u8],
+) -> Result<(), Error>
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    let num_leaves = shape.num_leaves;
+
     // If the bytes are just one chunk or less, pad to one chunk and return without hashing.
-    if bytes.len() <= BYTES_PER_CHUNK && min_leaves <= 1 {
-        let mut o = bytes.to_vec();
-        o.resize(BYTES_PER_CHUNK, 0);
-        return o;
+    if num_leaves <= 1 {
+        let (value, padding) = root.split_at_mut(bytes.len());
+        value.copy_from_slice(bytes);
+        padding.iter_mut().for_each(|byte| *byte = 0);
+        return Ok(());
     }
 
     assert!(
-        bytes.len() > BYTES_PER_CHUNK || min_leaves > 1,
+        num_leaves > 1,
         "Merkle hashing only needs to happen if there is more than one chunk"
     );
 
-    // The number of leaves that can be made directly from `bytes`.
-    let leaves_with_values = (bytes.len() + (BYTES_PER_CHUNK - 1)) / BYTES_PER_CHUNK;
+    // If the bytes fit in two chunks, pad to two chunks and return their parent without the
+    // overhead of the general algorithm.
+    if num_leaves == 2 {
+        hash_initial_parent(bytes, 0, params, root);
+        return Ok(());
+    }
 
-    // The number of parents that have at least one non-padding leaf.
-    //
     // Since there is more than one node in this tree (see prior assertion), there should always be
     // one or more initial parent nodes.
-    let initial_parents_with_values = std::cmp::max(1, next_even_number(leaves_with_values) / 2);
-
-    // The number of leaves in the full tree (including padding nodes).
-    let num_leaves = std::cmp::max(leaves_with_values, min_leaves).next_power_of_two();
-
-    // The number of levels in the tree.
-    //
-    // A tree with a single node has `height == 1`.
-    let height = num_leaves.trailing_zeros() as usize + 1;
+    let initial_parents_with_values = shape.initial_parents_with_values;
+    let height = shape.height;
 
     assert!(height >= 2, "The tree should have two or more heights");
 
+    // Reject trees that are too deep before doing any hashing.
+    params.check_tree_depth(height)?;
+
     // A buffer/scratch-space used for storing each round of hashes at each height.
     //
     // This buffer is kept as small as possible; it will shrink so it never stores a padding node.
-    let mut chunks = ChunkStore::with_capacity(initial_parents_with_values);
+    let mut chunks = ChunkStore::from_scratch(
+        std::mem::take(scratch),
+        initial_parents_with_values,
+        params.chunk_size,
+    );
 
     // Create a parent in the `chunks` buffer for every two chunks in `bytes`.
     //
     // I.e., do the first round of hashing, hashing from the `bytes` slice and filling the `chunks`
     // struct.
-    for i in 0..initial_parents_with_values {
-        let start = i * BYTES_PER_CHUNK * 2;
-
-        // Hash two chunks, creating a parent chunk.
-        let hash = match bytes.get(start..start + BYTES_PER_CHUNK * 2) {
-            // All bytes are available, hash as usual.
-            Some(slice) => hash(slice),
-            // Unable to get all the bytes, get a small slice and pad it out.
-            None => {
-                let mut preimage = bytes
-                    .get(start..)
-                    .expect("`i` can only be larger than zero if there are bytes to read")
-                    .to_vec();
-                preimage.resize(BYTES_PER_CHUNK * 2, 0);
-                hash(&preimage)
-            }
-        };
+    hash_initial_parents(bytes, &mut chunks, params, parallel_threshold);
 
-        assert_eq!(
-            hash.len(),
-            BYTES_PER_CHUNK,
-            "Hashes should be exactly one chunk"
-        );
+    let result = merkleize_parents(&mut chunks, height, params, parallel_threshold)
+        .map(|parent| root.copy_from_slice(parent));
+    *scratch = chunks.bytes;
+    result
+}
 
-        // Store the parent node.
-        chunks
-            .set(i, &hash)
-            .expect("Buffer should always have capacity for parent nodes")
+/// Fill `chunks` with the parent of each pair of chunks in `bytes`, i.e., the first round of
+/// hashing.
+#[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+fn hash_initial_parents<F>(
+    bytes: &[u8],
+    chunks: &mut ChunkStore,
+    params: &TreeParams<F>,
+    parallel_threshold: usize,
+) where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    #[cfg(feature = "rayon")]
+    {
+        // Each parent has two children.
+        if chunks.len() * 2 >= parallel_threshold {
+            chunks
+                .bytes
+                .par_chunks_mut(params.chunk_size)
+                .enumerate()
+                .for_each(|(i, parent)| hash_initial_parent(bytes, i, params, parent));
+            return;
+        }
+    }
+
+    for (i, parent) in chunks.bytes.chunks_mut(params.chunk_size).enumerate() {
+        hash_initial_parent(bytes, i, params, parent)
+    }
+}
+
+/// Hash the `i`th pair of chunks in `bytes`, writing the parent chunk to `parent`.
+fn hash_initial_parent<F>(bytes: &[u8], i: usize, params: &TreeParams<F>, parent: &mut [u8])
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    let chunk_size = params.chunk_size;
+    let start = i * chunk_size * 2;
+
+    match bytes.get(start..start + chunk_size * 2) {
+        // All bytes are available, hash as usual.
+        Some(slice) => {
+            let (left, right) = slice.split_at(chunk_size);
+            (params.hash_concat)(left, right, parent)
+        }
+        // Unable to get all the bytes, get a small slice and pad it out.
+        None => {
+            let mut preimage = bytes
+                .get(start..)
+                .expect("`i` can only be larger than zero if there are bytes to read")
+                .to_vec();
+            preimage.resize(chunk_size * 2, 0);
+            let (left, right) = preimage.split_at(chunk_size);
+            (params.hash_concat)(left, right, parent)
+        }
     }
+}
+
+/// Merkleize `bytes` in the same manner as `merkleize_padded`, returning the root along with every
+/// layer of the tree, ordered from the leaves up to the root.
+///
+/// Only nodes that have at least one non-padding leaf beneath them are stored in each layer; the
+/// right-most siblings which are omitted are always padding nodes and can be obtained from
+/// `zero_hash` using the index of the layer. The final layer always contains exactly
+/// one node: the root.
+///
+/// ## Memory Performance
+///
+/// Unlike `merkleize_padded`, all intermediate nodes are retained, so the memory footprint is
+/// roughly `O(2V)`, where `V` is the number of leaf chunks with values.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+#[allow(clippy::type_complexity)]
+pub fn merkleize_padded_with_layers(
+    bytes: &[u8],
+    min_leaves: usize,
+) -> Result<(Vec<u8>, Vec<Vec<[u8; BYTES_PER_CHUNK]>>), Error> {
+    let leaves: Vec<[u8; BYTES_PER_CHUNK]> = bytes
+        .chunks(BYTES_PER_CHUNK)
+        .map(|chunk| {
+            let mut leaf = [0; BYTES_PER_CHUNK];
+            leaf[0..chunk.len()].copy_from_slice(chunk);
+            leaf
+        })
+        .collect();
+
+    // The number of leaves in the full tree (including padding nodes).
+    let num_leaves = std::cmp::max(leaves.len(), min_leaves).next_power_of_two();
+
+    // The number of levels in the tree.
+    //
+    // A tree with a single node has `height == 1`.
+    let height = num_leaves.trailing_zeros() as usize + 1;
+
+    check_tree_depth(height)?;
+
+    let mut layers = Vec::with_capacity(height);
+    layers.push(leaves);
+
+    for height in 1..height {
+        let children = &layers[height - 1];
+
+        let zero_hash = zero_hash(height - 1)?;
+        let parents = children
+            .chunks(2)
+            .map(|pair| {
+                let right = pair.get(1).map_or(zero_hash, |right| &right[..]);
+                hash32_concat(&pair[0], right)
+            })
+            .collect();
+
+        layers.push(parents);
+    }
+
+    // If there are no leaves with values the root is entirely padding.
+    let root_layer = layers
+        .last_mut()
+        .expect("There is always at least one layer");
+    if root_layer.is_empty() {
+        let mut root = [0; BYTES_PER_CHUNK];
+        root.copy_from_slice(zero_hash(height - 1)?);
+        root_layer.push(root);
+    }
+
+    assert_eq!(root_layer.len(), 1, "Only one chunk should remain");
+
+    Ok((root_layer[0].to_vec(), layers))
+}
+
+/// Generate a Merkle proof for the leaf at `leaf_index` in the tree given by `bytes` and
+/// `min_leaves` (as per `merkleize_padded`).
+///
+/// Returns the leaf chunk along with the sibling of each node on the path from that leaf to the
+/// root, ordered from the leaf upwards. Siblings which are entirely padding are taken from the
+/// `zero_hash` cache. Hashing the leaf with each sibling in turn (as the left or right child, per
+/// the corresponding bit of `leaf_index`) yields the root returned by `merkleize_padded`.
+///
+/// A tree with a single leaf has an empty proof; its root is the leaf.
+///
+/// ## Errors
+///
+/// - `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+/// - `Error::LeafIndexOutOfBounds` if `leaf_index` is not less than the number of leaves in the
+///   tree (including padding).
+pub fn merkle_proof(
+    bytes: &[u8],
+    min_leaves: usize,
+    leaf_index: usize,
+) -> Result<(Vec<u8>, Vec<Vec<u8>>), Error> {
+    let (_root, layers) = merkleize_padded_with_layers(bytes, min_leaves)?;
+
+    // The root is the last layer, it has no sibling.
+    let depth = layers.len() - 1;
+    let num_leaves = 1 << depth;
+
+    if leaf_index >= num_leaves {
+        return Err(Error::LeafIndexOutOfBounds {
+            index: leaf_index,
+            num_leaves,
+        });
+    }
+
+    // Returns the node at `index` in the layer at `height`, or the padding node if it's beyond the
+    // nodes with values.
+    let node = |height: usize, index: usize| -> Result<Vec<u8>, Error> {
+        match layers[height].get(index) {
+            Some(node) => Ok(node.to_vec()),
+            None => zero_hash(height).map(<[u8]>::to_vec),
+        }
+    };
+
+    let leaf = node(0, leaf_index)?;
+    let proof = (0..depth)
+        .map(|height| node(height, (leaf_index >> height) ^ 1))
+        .collect::<Result<_, _>>()?;
+
+    Ok((leaf, proof))
+}
+
+/// Merkleize `hashes`, treating each `Hash256` as a leaf chunk, and return the root, optionally
+/// padding the tree out to `min_leaves` number of leaves.
+///
+/// Equivalent to calling `merkleize_padded` on the concatenation of `hashes`, without first
+/// copying them into a flat byte array.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_hashes(hashes: &[Hash256], min_leaves: usize) -> Result<Hash256, Error> {
+    merkleize_from_chunks(hashes.iter().map(|hash| hash.to_fixed_bytes()), min_leaves)
+}
 
+/// Merkleize `roots` (e.g., the roots of the fields of a container), treating each as a leaf
+/// chunk, and return the root of the tree padded out to the next power of two.
+///
+/// Equivalent to `merkleize_hashes(roots, 0)`. A single root is returned without hashing.
+///
+/// ## Panics
+///
+/// If there are more than `2^MAX_TREE_DEPTH` roots, which could not be stored in memory.
+pub fn merkleize_roots(roots: &[Hash256]) -> Hash256 {
+    merkleize_hashes(roots, 0).expect("Tree should not exceed MAX_TREE_DEPTH")
+}
+
+/// Merkleize the leaf chunks yielded by `chunks` and return the root, optionally padding the tree
+/// out to `min_leaves` number of leaves.
+///
+/// Equivalent to calling `merkleize_padded` on the concatenation of `chunks`, except that the
+/// first round of hashing is done as the chunks are yielded, so neither the concatenation nor the
+/// leaves are ever stored.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`.
+pub fn merkleize_from_chunks<I>(chunks: I, min_leaves: usize) -> Result<Hash256, Error>
+where
+    I: Iterator<Item = [u8; BYTES_PER_CHUNK]>,
+{
+    // Reject trees which are too deep due to padding alone before doing any hashing.
+    check_tree_depth(min_leaves.next_power_of_two().trailing_zeros() as usize + 1)?;
+
+    let mut parents = ChunkStore::with_capacity(0, BYTES_PER_CHUNK);
+    parents
+        .bytes
+        .reserve(next_even_number(chunks.size_hint().0) / 2 * BYTES_PER_CHUNK);
+
+    // Hash each pair of leaves as soon as the right-hand leaf arrives.
+    let mut num_leaves_with_values = 0;
+    let mut left: Option<[u8; BYTES_PER_CHUNK]> = None;
+    for chunk in chunks {
+        num_leaves_with_values += 1;
+        match left.take() {
+            Some(left) => parents.push(&hash32_concat(&left, &chunk)),
+            None => left = Some(chunk),
+        }
+    }
+
+    // If there is just one leaf or less, return it (or a padding leaf) without hashing.
+    if num_leaves_with_values <= 1 && min_leaves <= 1 {
+        return Ok(Hash256::from(left.unwrap_or([0; BYTES_PER_CHUNK])));
+    }
+
+    // Pair a trailing leaf with a padding leaf, or use a single padding parent if there are no
+    // leaves.
+    if let Some(left) = left {
+        parents.push(&hash32_concat(&left, zero_hash(0)?));
+    } else if parents.len() == 0 {
+        parents.push(zero_hash(1)?);
+    }
+
+    // The number of leaves in the full tree (including padding nodes).
+    let num_leaves = std::cmp::max(num_leaves_with_values, min_leaves).next_power_of_two();
+
+    // The number of levels in the tree.
+    //
+    // A tree with a single node has `height == 1`.
+    let height = num_leaves.trailing_zeros() as usize + 1;
+
+    check_tree_depth(height)?;
+
+    merkleize_parents(
+        &mut parents,
+        height,
+        &TreeParams::sha256(),
+        PARALLEL_THRESHOLD,
+    )
+    .map(Hash256::from_slice)
+}
+
+/// Hash the first layer of parent nodes in `chunks` up to the root of a tree with `height` levels,
+/// padding with the zero hashes of `params` as required, and return the root.
+///
+/// `chunks` should contain each parent (at height `1`) which has at least one non-padding leaf.
+fn merkleize_parents<'c, F>(
+    chunks: &'c mut ChunkStore,
+    height: usize,
+    params: &TreeParams<F>,
+    parallel_threshold: usize,
+) -> Result<&'c [u8], Error>
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
     // Iterate through all heights above the leaf nodes and either (a) hash two children or, (b)
     // hash a left child and a right padding node.
     //
     // Skip the 0'th height because the leaves have already been processed. Skip the highest-height
     // in the tree as it is the root does not require hashing.
     //
-    // The padding nodes for each height are cached via `lazy static` to simulate non-adjacent
-    // padding nodes (i.e., avoid doing unnecessary hashing).
+    // The padding nodes for each height are cached in `params` to simulate non-adjacent padding
+    // nodes (i.e., avoid doing unnecessary hashing).
     for height in 1..height - 1 {
-        let child_nodes = chunks.len();
-        let parent_nodes = next_even_number(child_nodes) / 2;
-
-        // For each pair of nodes stored in `chunks`:
-        //
-        // - If two nodes are available, hash them to form a parent.
-        // - If one node is available, hash it and a cached padding node to form a parent.
-        for i in 0..parent_nodes {
-            let (left, right) = match (chunks.get(i * 2), chunks.get(i * 2 + 1)) {
-                (Ok(left), Ok(right)) => (left, right),
-                (Ok(left), Err(_)) => (left, get_zero_hash(height)),
-                // Deriving `parent_nodes` from `chunks.len()` has ensured that we never encounter the
-                // scenario where we expect two nodes but there are none.
-                (Err(_), Err(_)) => unreachable!("Parent must have one child"),
-                // `chunks` is a contiguous array so it is impossible for an index to be missing
-                // when a higher index is present.
-                (Err(_), Ok(_)) => unreachable!("Parent must have a left child"),
-            };
-
-            assert!(
-                left.len() == right.len() && right.len() == BYTES_PER_CHUNK,
-                "Both children should be `BYTES_PER_CHUNK` bytes."
-            );
+        hash_layer(chunks, height, params, parallel_threshold)?;
+    }
 
-            let hash = hash32_concat(left, right);
+    // There should be a single chunk left in the buffer and it is the Merkle root.
+    assert_eq!(chunks.len(), 1, "Only one chunk should remain");
 
-            // Store a parent node.
-            chunks
-                .set(i, &hash)
-                .expect("Buf is adequate size for parent");
-        }
+    Ok(chunks.get(0).expect("Buffer contains one chunk"))
+}
+
+/// Replace the nodes at `height` in `chunks` with their parents.
+///
+/// For each pair of nodes stored in `chunks`:
+///
+/// - If two nodes are available, hash them to form a parent.
+/// - If one node is available, hash it and a cached padding node to form a parent.
+#[cfg_attr(not(feature = "rayon"), allow(unused_variables))]
+fn hash_layer<F>(
+    chunks: &mut ChunkStore,
+    height: usize,
+    params: &TreeParams<F>,
+    parallel_threshold: usize,
+) -> Result<(), Error>
+where
+    F: Fn(&[u8], &[u8], &mut [u8]) + Sync,
+{
+    let chunk_size = params.chunk_size;
+    let child_nodes = chunks.len();
+    let parent_nodes = next_even_number(child_nodes) / 2;
+
+    #[cfg(feature = "rayon")]
+    {
+        if child_nodes >= parallel_threshold {
+            let padding = params.zero_hash(height)?;
+            let mut parents = ChunkStore::with_capacity(parent_nodes, chunk_size);
+
+            parents
+                .bytes
+                .par_chunks_mut(chunk_size)
+                .zip(chunks.bytes.par_chunks(chunk_size * 2))
+                .for_each(|(parent, children)| {
+                    let (left, right) = children.split_at(chunk_size);
+                    let right = if right.is_empty() { padding } else { right };
+                    (params.hash_concat)(left, right, parent);
+                });
 
-        // Shrink the buffer so it neatly fits the number of new nodes created in this round.
-        //
-        // The number of `parent_nodes` is either decreasing or stable. It never increases.
-        chunks.truncate(parent_nodes);
+            // Copy the parents back, so that `chunks` retains its buffer.
+            chunks.truncate(parent_nodes);
+            chunks.bytes.copy_from_slice(&parents.bytes);
+            return Ok(());
+        }
     }
 
-    // There should be a single chunk left in the buffer and it is the Merkle root.
-    let root = chunks.into_vec();
+    let mut hash = vec![0; chunk_size];
+    for i in 0..parent_nodes {
+        let (left, right) = match (chunks.get(i * 2), chunks.get(i * 2 + 1)) {
+            (Ok(left), Ok(right)) => (left, right),
+            (Ok(left), Err(_)) => (left, params.zero_hash(height)?),
+            // Deriving `parent_nodes` from `chunks.len()` has ensured that we never encounter the
+            // scenario where we expect two nodes but there are none.
+            (Err(_), Err(_)) => unreachable!("Parent must have one child"),
+            // `chunks` is a contiguous array so it is impossible for an index to be missing
+            // when a higher index is present.
+            (Err(_), Ok(_)) => unreachable!("Parent must have a left child"),
+        };
+
+        assert!(
+            left.len() == right.len() && right.len() == chunk_size,
+            "Both children should be `chunk_size` bytes."
+        );
 
-    assert_eq!(root.len(), BYTES_PER_CHUNK, "Only one chunk should remain");
+        (params.hash_concat)(left, right, &mut hash);
 
-    root
+        // Store a parent node.
+        chunks
+            .set(i, &hash)
+            .expect("Buf is adequate size for parent");
+    }
+
+    // Shrink the buffer so it neatly fits the number of new nodes created in this round.
+    //
+    // The number of `parent_nodes` is either decreasing or stable. It never increases.
+    chunks.truncate(parent_nodes);
+
+    Ok(())
 }
 
-/// A helper struct for storing words of `BYTES_PER_CHUNK` size in a flat byte array.
+/// A helper struct for storing words of `chunk_size` bytes in a flat byte array.
 #[derive(Debug)]
-struct ChunkStore(Vec<u8>);
+struct ChunkStore {
+    bytes: Vec<u8>,
+    chunk_size: usize,
+}
 
 impl ChunkStore {
     /// Creates a new instance with `chunks` padding nodes.
-    fn with_capacity(chunks: usize) -> Self {
-        Self(vec![0; chunks * BYTES_PER_CHUNK])
+    fn with_capacity(chunks: usize, chunk_size: usize) -> Self {
+        Self {
+            bytes: vec![0; chunks * chunk_size],
+            chunk_size,
+        }
+    }
+
+    /// Creates a new instance with `chunks` padding nodes, reusing the allocation of `scratch`.
+    fn from_scratch(mut scratch: Vec<u8>, chunks: usize, chunk_size: usize) -> Self {
+        scratch.clear();
+        scratch.resize(chunks * chunk_size, 0);
+        Self {
+            bytes: scratch,
+            chunk_size,
+        }
     }
 
     /// Set the `i`th chunk to `value`.
     ///
-    /// Returns `Err` if `value.len() != BYTES_PER_CHUNK` or `i` is out-of-bounds.
+    /// Returns `Err` if `value.len() != self.chunk_size` or `i` is out-of-bounds.
     fn set(&mut self, i: usize, value: &[u8]) -> Result<(), ()> {
-        if i < self.len() && value.len() == BYTES_PER_CHUNK {
-            let slice = &mut self.0[i * BYTES_PER_CHUNK..i * BYTES_PER_CHUNK + BYTES_PER_CHUNK];
+        if i < self.len() && value.len() == self.chunk_size {
+            let slice = &mut self.bytes[i * self.chunk_size..(i + 1) * self.chunk_size];
             slice.copy_from_slice(value);
             Ok(())
         } else {
@@ -188,39 +742,55 @@ impl ChunkStore {
     /// Returns `Err` if `i` is out-of-bounds.
     fn get(&self, i: usize) -> Result<&[u8], ()> {
         if i < self.len() {
-            Ok(&self.0[i * BYTES_PER_CHUNK..i * BYTES_PER_CHUNK + BYTES_PER_CHUNK])
+            Ok(&self.bytes[i * self.chunk_size..(i + 1) * self.chunk_size])
         } else {
             Err(())
         }
     }
 
+    /// Appends `value` as the last chunk.
+    fn push(&mut self, value: &[u8]) {
+        assert_eq!(value.len(), self.chunk_size, "Value should be one chunk");
+        self.bytes.extend_from_slice(value)
+    }
+
     /// Returns the number of chunks presently stored in `self`.
     fn len(&self) -> usize {
-        self.0.len() / BYTES_PER_CHUNK
+        self.bytes.len() / self.chunk_size
     }
 
     /// Truncates 'self' to `num_chunks` chunks.
     ///
     /// Functionally identical to `Vec::truncate`.
     fn truncate(&mut self, num_chunks: usize) {
-        self.0.truncate(num_chunks * BYTES_PER_CHUNK)
-    }
-
-    /// Consumes `self`, returning the underlying byte array.
-    fn into_vec(self) -> Vec<u8> {
-        self.0
+        self.bytes.truncate(num_chunks * self.chunk_size)
     }
 }
 
-/// Returns a cached padding node for a given height.
-fn get_zero_hash(height: usize) -> &'static [u8] {
+/// Returns the root of a tree of `2^height` zero-valued leaves (i.e., the padding node at
+/// `height`), from the table cached in `eth2_hashing::ZERO_HASHES`.
+///
+/// `zero_hash(0)` is a single zero chunk and `zero_hash(h + 1)` is the hash of two `zero_hash(h)`
+/// nodes.
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if `height > MAX_TREE_DEPTH`.
+pub fn zero_hash(height: usize) -> Result<&'static [u8], Error> {
     if height <= MAX_TREE_DEPTH {
-        &ZERO_HASHES[height]
+        Ok(&ZERO_HASHES[height])
     } else {
-        panic!("Tree exceeds MAX_TREE_DEPTH of {}", MAX_TREE_DEPTH)
+        Err(Error::MaxTreeDepthExceeded { height })
     }
 }
 
+/// Returns `Err` if a tree with `height` levels has a root above `MAX_TREE_DEPTH`.
+///
+/// A tree with a single node has `height == 1` and a depth of `0`.
+fn check_tree_depth(height: usize) -> Result<(), Error> {
+    zero_hash(height - 1).map(|_| ())
+}
+
 /// Returns the next even number following `n`. If `n` is even, `n` is returned.
 fn next_even_number(n: usize) -> usize {
     n + n % 2
@@ -231,7 +801,7 @@ mod test {
     use super::*;
 
     pub fn reference_root(bytes: &[u8]) -> Vec<u8> {
-        crate::merkleize_standard(&bytes)[0..32].to_vec()
+        crate::merkleize_standard(&bytes).unwrap()[0..32].to_vec()
     }
 
     macro_rules! common_tests {
@@ -290,8 +860,28 @@ mod test {
                 let input = vec![0; 10 * BYTES_PER_CHUNK];
                 let min_nodes = 2usize.pow(MAX_TREE_DEPTH as u32);
                 assert_eq!(
-                    merkleize_padded(&input, min_nodes),
-                    get_zero_hash(MAX_TREE_DEPTH)
+                    merkleize_padded(&input, min_nodes).unwrap(),
+                    zero_hash(MAX_TREE_DEPTH).unwrap()
+                );
+            }
+
+            #[test]
+            fn exceeds_max_tree_depth() {
+                let input = $get_bytes(10 * BYTES_PER_CHUNK);
+                let min_nodes = 2usize.pow(MAX_TREE_DEPTH as u32 + 1);
+                let err = Err(Error::MaxTreeDepthExceeded {
+                    height: MAX_TREE_DEPTH + 1,
+                });
+
+                assert_eq!(merkleize_padded(&input, min_nodes), err);
+                assert_eq!(
+                    merkleize_padded_with_layers(&input, min_nodes).map(|(root, _)| root),
+                    err
+                );
+                assert_eq!(
+                    merkleize_hashes(&[Hash256::zero()], min_nodes)
+                        .map(|root| root.as_bytes().to_vec()),
+                    err
                 );
             }
         };
@@ -320,6 +910,476 @@ mod test {
         common_tests!(random_bytes);
     }
 
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel_matches_serial() {
+        use rand::RngCore;
+
+        let mut scratch = vec![];
+        for &num_chunks in &[2, 3, 9, 1023, 1024, 1025, 4099] {
+            // Include inputs which end part-way through a chunk.
+            for &len in &[
+                num_chunks * BYTES_PER_CHUNK,
+                num_chunks * BYTES_PER_CHUNK - 1,
+            ] {
+                let mut bytes = vec![0; len];
+                rand::thread_rng().fill_bytes(&mut bytes);
+
+                for &min_leaves in &[0, 8192] {
+                    let serial = merkleize_padded_with_threshold(
+                        &bytes,
+                        min_leaves,
+                        usize::max_value(),
+                        &mut vec![],
+                    );
+
+                    for &threshold in &[0, 2, PARALLEL_THRESHOLD] {
+                        assert_eq!(
+                            merkleize_padded_with_threshold(
+                                &bytes,
+                                min_leaves,
+                                threshold,
+                                &mut scratch
+                            ),
+                            serial,
+                            "len: {:?}, min_leaves: {:?}, threshold: {:?}",
+                            len,
+                            min_leaves,
+                            threshold
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    /// Hash `leaf` up the tree with each of `proof`, returning the root.
+    fn root_from_proof(leaf: &[u8], proof: &[Vec<u8>], leaf_index: usize) -> Vec<u8> {
+        proof
+            .iter()
+            .enumerate()
+            .fold(leaf.to_vec(), |node, (height, sibling)| {
+                if (leaf_index >> height) & 1 == 0 {
+                    hash32_concat(&node, sibling).to_vec()
+                } else {
+                    hash32_concat(sibling, &node).to_vec()
+                }
+            })
+    }
+
+    #[test]
+    fn merkle_proof_nine_chunks() {
+        let input: Vec<u8> = (0..9 * BYTES_PER_CHUNK).map(|byte| byte as u8).collect();
+        let root = merkleize_padded(&input, 0).unwrap();
+
+        for leaf_index in 0..16 {
+            let (leaf, proof) = merkle_proof(&input, 0, leaf_index).unwrap();
+
+            assert_eq!(proof.len(), 4);
+            assert_eq!(
+                leaf,
+                input
+                    .chunks(BYTES_PER_CHUNK)
+                    .nth(leaf_index)
+                    .unwrap_or_else(|| zero_hash(0).unwrap())
+            );
+            assert_eq!(
+                root_from_proof(&leaf, &proof, leaf_index),
+                root,
+                "leaf_index: {:?}",
+                leaf_index
+            );
+        }
+
+        assert_eq!(
+            merkle_proof(&input, 0, 16),
+            Err(Error::LeafIndexOutOfBounds {
+                index: 16,
+                num_leaves: 16
+            })
+        );
+    }
+
+    #[test]
+    fn merkle_proof_padded() {
+        // Partial chunks are padded with zeros.
+        let input: Vec<u8> = (0..3 * BYTES_PER_CHUNK - 7)
+            .map(|byte| byte as u8 + 1)
+            .collect();
+
+        for &min_leaves in &[0, 1, 4, 5, 64] {
+            let root = merkleize_padded(&input, min_leaves).unwrap();
+
+            for leaf_index in 0..std::cmp::max(4, min_leaves.next_power_of_two()) {
+                let (leaf, proof) = merkle_proof(&input, min_leaves, leaf_index).unwrap();
+
+                assert_eq!(
+                    root_from_proof(&leaf, &proof, leaf_index),
+                    root,
+                    "min_leaves: {:?}, leaf_index: {:?}",
+                    min_leaves,
+                    leaf_index
+                );
+            }
+        }
+
+        // A single leaf is its own root.
+        let (leaf, proof) = merkle_proof(&[42; 3], 0, 0).unwrap();
+        assert_eq!(proof, Vec::<Vec<u8>>::new());
+        assert_eq!(leaf, merkleize_padded(&[42; 3], 0).unwrap());
+    }
+
+    #[test]
+    fn merkleize_padded_root_matches_merkleize_padded() {
+        for len in 0..9 * BYTES_PER_CHUNK {
+            let input: Vec<u8> = (0..len).map(|byte| byte as u8).collect();
+
+            for min_leaves in 0..17 {
+                assert_eq!(
+                    merkleize_padded_root(&input, min_leaves)
+                        .unwrap()
+                        .as_bytes(),
+                    &merkleize_padded(&input, min_leaves).unwrap()[..],
+                    "len: {:?}, min_leaves: {:?}",
+                    len,
+                    min_leaves
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn fixed_matches_merkleize_padded() {
+        for &num_leaves in &[1, 2, 4, 8, 16, 64] {
+            for len in 0..=num_leaves * BYTES_PER_CHUNK {
+                let input: Vec<u8> = (0..len).map(|byte| byte as u8).collect();
+
+                let shape = TreeShape::new(len, num_leaves);
+                assert_eq!(shape.num_leaves(), num_leaves);
+
+                assert_eq!(
+                    merkleize_padded_fixed(&input, &shape).unwrap(),
+                    merkleize_padded(&input, num_leaves).unwrap(),
+                    "len: {:?}, num_leaves: {:?}",
+                    len,
+                    num_leaves
+                );
+            }
+        }
+    }
+
+    #[test]
+    #[cfg(debug_assertions)]
+    #[should_panic]
+    fn fixed_rejects_mismatched_shape() {
+        let shape = TreeShape::new(BYTES_PER_CHUNK * 2, 0);
+        let _ = merkleize_padded_fixed(&[0; BYTES_PER_CHUNK * 3], &shape);
+    }
+
+    #[test]
+    fn scratch_matches_merkleize_padded_root() {
+        let mut scratch = vec![];
+
+        // Shrink and then grow the tree, so that the scratch is both reused and reallocated.
+        let lens = (0..9 * BYTES_PER_CHUNK)
+            .rev()
+            .chain(0..17 * BYTES_PER_CHUNK);
+        for len in lens {
+            let input: Vec<u8> = (0..len).map(|byte| byte as u8).collect();
+
+            for &min_leaves in &[0, 1, 5, 32] {
+                assert_eq!(
+                    merkleize_padded_with_scratch(&input, min_leaves, &mut scratch),
+                    merkleize_padded_root(&input, min_leaves),
+                    "len: {:?}, min_leaves: {:?}",
+                    len,
+                    min_leaves
+                );
+            }
+        }
+        assert!(scratch.capacity() >= 8 * BYTES_PER_CHUNK);
+    }
+
+    #[test]
+    fn zero_hash_heights() {
+        assert_eq!(zero_hash(0), Ok(&[0; BYTES_PER_CHUNK][..]));
+        assert_eq!(
+            zero_hash(1).unwrap(),
+            hash32_concat(zero_hash(0).unwrap(), zero_hash(0).unwrap())
+        );
+        assert!(zero_hash(MAX_TREE_DEPTH).is_ok());
+        assert_eq!(
+            zero_hash(MAX_TREE_DEPTH + 1),
+            Err(Error::MaxTreeDepthExceeded {
+                height: MAX_TREE_DEPTH + 1
+            })
+        );
+    }
+
+    /// A trivial, order-dependent hash of two 4-byte chunks.
+    fn tiny_hash_concat(left: &[u8], right: &[u8], parent: &mut [u8]) {
+        for i in 0..4 {
+            parent[i] = left[i].wrapping_mul(3) ^ right[i].wrapping_add(i as u8 + 1);
+        }
+    }
+
+    /// Merkleize 4-byte `leaves` with `tiny_hash_concat`, naively padding the tree out to
+    /// `num_leaves` leaves.
+    fn tiny_reference_root(leaves: &[[u8; 4]], num_leaves: usize) -> [u8; 4] {
+        if num_leaves == 1 {
+            return leaves.get(0).copied().unwrap_or([0; 4]);
+        }
+
+        let half = num_leaves / 2;
+        let (left, right) = leaves.split_at(std::cmp::min(half, leaves.len()));
+        let mut parent = [0; 4];
+        tiny_hash_concat(
+            &tiny_reference_root(left, half),
+            &tiny_reference_root(right, half),
+            &mut parent,
+        );
+        parent
+    }
+
+    #[test]
+    fn core_with_tiny_chunks() {
+        let mut zero_hashes = vec![vec![0; 4]];
+        for height in 0..8 {
+            let mut parent = vec![0; 4];
+            tiny_hash_concat(&zero_hashes[height], &zero_hashes[height], &mut parent);
+            zero_hashes.push(parent);
+        }
+        let params = TreeParams {
+            chunk_size: 4,
+            hash_concat: tiny_hash_concat,
+            zero_hashes: &zero_hashes,
+        };
+
+        let mut scratch = vec![];
+        for len in 0..=4 * 20 + 3 {
+            let bytes = (0..len).map(|i| i as u8 + 1).collect::<Vec<_>>();
+            let leaves = bytes
+                .chunks(4)
+                .map(|chunk| {
+                    let mut leaf = [0; 4];
+                    leaf[0..chunk.len()].copy_from_slice(chunk);
+                    leaf
+                })
+                .collect::<Vec<_>>();
+
+            for min_leaves in 0..=33 {
+                let num_leaves = std::cmp::max(leaves.len(), min_leaves).next_power_of_two();
+                let expected = tiny_reference_root(&leaves, num_leaves);
+
+                for &threshold in &[0, PARALLEL_THRESHOLD] {
+                    let mut root = [0xff; 4];
+                    merkleize_padded_core(
+                        &bytes,
+                        min_leaves,
+                        &params,
+                        threshold,
+                        &mut scratch,
+                        &mut root,
+                    )
+                    .unwrap();
+                    assert_eq!(
+                        root, expected,
+                        "len: {}, min_leaves: {}, threshold: {}",
+                        len, min_leaves, threshold
+                    );
+                }
+            }
+        }
+
+        // The tree may be no deeper than the zero hashes allow.
+        assert_eq!(
+            merkleize_padded_core(&[1], 1 << 9, &params, 0, &mut scratch, &mut [0; 4]),
+            Err(Error::MaxTreeDepthExceeded { height: 9 })
+        );
+    }
+
+    #[test]
+    fn two_chunk_fast_path() {
+        for len in BYTES_PER_CHUNK + 1..=BYTES_PER_CHUNK * 2 {
+            let input: Vec<u8> = (0..len).map(|byte| byte as u8 + 1).collect();
+
+            for min_leaves in 0..=2 {
+                test_against_reference(&input, min_leaves);
+                assert_eq!(
+                    merkleize_padded(&input, min_leaves).unwrap(),
+                    merkleize_padded_with_layers(&input, min_leaves).unwrap().0,
+                    "len: {:?}, min_leaves: {:?}",
+                    len,
+                    min_leaves
+                );
+            }
+        }
+
+        // A single chunk padded out to two chunks also takes the fast path.
+        test_against_reference(&[1; BYTES_PER_CHUNK], 2);
+    }
+
+    #[test]
+    fn merkleize_hashes_matches_flattened() {
+        let hashes: Vec<Hash256> = (0..9).map(|i| Hash256::repeat_byte(i as u8 + 1)).collect();
+
+        for len in 0..=hashes.len() {
+            let hashes = &hashes[0..len];
+            let bytes: Vec<u8> = hashes.iter().flat_map(|h| h.as_bytes().to_vec()).collect();
+
+            for min_leaves in 0..=16 {
+                assert_eq!(
+                    merkleize_hashes(hashes, min_leaves).unwrap().as_bytes(),
+                    &merkleize_padded(&bytes, min_leaves).unwrap()[..],
+                    "len: {:?}, min_leaves: {:?}",
+                    len,
+                    min_leaves
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn merkleize_roots_matches_flattened() {
+        for &num_roots in &[1, 3, 8] {
+            let roots: Vec<Hash256> = (0..num_roots)
+                .map(|i| Hash256::from_low_u64_be(i as u64 + 1))
+                .collect();
+            let flattened: Vec<u8> = roots
+                .iter()
+                .flat_map(|root| root.to_fixed_bytes())
+                .collect();
+
+            assert_eq!(
+                merkleize_roots(&roots).as_bytes(),
+                &merkleize_padded(&flattened, 0).unwrap()[..],
+                "num_roots: {}",
+                num_roots
+            );
+        }
+
+        // A single root is its own tree hash root.
+        let root = Hash256::repeat_byte(42);
+        assert_eq!(merkleize_roots(&[root]), root);
+    }
+
+    #[test]
+    fn merkleize_from_chunks_matches_flattened() {
+        for &len in &[0, 1, 2, 3, 4, 5, 8, 9, 31, 32, 33, 1025] {
+            let chunks: Vec<[u8; BYTES_PER_CHUNK]> = (0..len)
+                .map(|i| {
+                    let mut chunk = [0; BYTES_PER_CHUNK];
+                    chunk[0..8].copy_from_slice(&(i as u64 + 1).to_le_bytes());
+                    chunk
+                })
+                .collect();
+            let bytes: Vec<u8> = chunks.iter().flat_map(|chunk| chunk.to_vec()).collect();
+
+            for &min_leaves in &[0, 1, 2, 3, 16, 64, 2048] {
+                assert_eq!(
+                    merkleize_from_chunks(chunks.iter().copied(), min_leaves)
+                        .unwrap()
+                        .as_bytes(),
+                    &merkleize_padded(&bytes, min_leaves).unwrap()[..],
+                    "len: {:?}, min_leaves: {:?}",
+                    len,
+                    min_leaves
+                );
+            }
+        }
+
+        // The chunks need not come from an iterator with an accurate size hint.
+        let filtered = (0..5_u8)
+            .map(|i| [i; BYTES_PER_CHUNK])
+            .filter(|chunk| chunk[0] != 2);
+        let bytes: Vec<u8> = [0, 1, 3, 4]
+            .iter()
+            .flat_map(|&i| vec![i; BYTES_PER_CHUNK])
+            .collect();
+        assert_eq!(
+            merkleize_from_chunks(filtered, 0).unwrap().as_bytes(),
+            &merkleize_padded(&bytes, 0).unwrap()[..]
+        );
+
+        assert_eq!(
+            merkleize_from_chunks(
+                std::iter::once([0; BYTES_PER_CHUNK]),
+                1 << (MAX_TREE_DEPTH + 1)
+            ),
+            Err(Error::MaxTreeDepthExceeded {
+                height: MAX_TREE_DEPTH + 1
+            })
+        );
+    }
+
+    #[test]
+    fn empty_input_with_min_leaves() {
+        for i in 0..8 {
+            assert_eq!(
+                merkleize_padded(&[], 1 << i).unwrap(),
+                zero_hash(i).unwrap(),
+                "min_leaves: {:?}",
+                1 << i
+            );
+        }
+
+        assert_eq!(merkleize_padded(&[], 8).unwrap(), zero_hash(3).unwrap());
+    }
+
+    #[test]
+    fn with_layers_matches_merkleize_padded() {
+        for i in 0..9 * BYTES_PER_CHUNK {
+            let input: Vec<u8> = (0..i).map(|byte| byte as u8).collect();
+
+            for min_leaves in 0..17 {
+                let (root, layers) = merkleize_padded_with_layers(&input, min_leaves).unwrap();
+
+                assert_eq!(
+                    root,
+                    merkleize_padded(&input, min_leaves).unwrap(),
+                    "input.len(): {:?}, min_leaves: {:?}",
+                    i,
+                    min_leaves
+                );
+                assert_eq!(
+                    layers.last(),
+                    Some(&vec![{
+                        let mut node = [0; BYTES_PER_CHUNK];
+                        node.copy_from_slice(&root);
+                        node
+                    }])
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn with_layers_reconstruct_root() {
+        let input: Vec<u8> = (0..5 * BYTES_PER_CHUNK).map(|byte| byte as u8).collect();
+        let (root, layers) = merkleize_padded_with_layers(&input, 8).unwrap();
+
+        assert_eq!(layers.len(), 4);
+        assert_eq!(
+            layers.iter().map(Vec::len).collect::<Vec<_>>(),
+            vec![5, 3, 2, 1]
+        );
+
+        for (height, pair) in layers.windows(2).enumerate() {
+            let (children, parents) = (&pair[0], &pair[1]);
+
+            for (i, parent) in parents.iter().enumerate() {
+                let left = &children[i * 2];
+                let right = children
+                    .get(i * 2 + 1)
+                    .map_or(zero_hash(height).unwrap(), |right| &right[..]);
+
+                assert_eq!(&hash32_concat(left, right), parent);
+            }
+        }
+
+        assert_eq!(&layers[3][0][..], &root[..]);
+    }
+
     fn test_against_reference(input: &[u8], min_nodes: usize) {
         let mut reference_input = input.to_vec();
         reference_input.resize(
@@ -332,7 +1392,7 @@ mod test {
 
         assert_eq!(
             reference_root(&reference_input),
-            merkleize_padded(&input, min_nodes),
+            merkleize_padded(&input, min_nodes).unwrap(),
             "input.len(): {:?}",
             input.len()
         );
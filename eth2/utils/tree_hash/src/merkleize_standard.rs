@@ -1,12 +1,15 @@
 use super::*;
 use eth2_hashing::hash;
 
-/// Merkleizes bytes and returns the root, using a simple algorithm that does not optimize to avoid
-/// processing or storing padding bytes.
+/// Merkleizes bytes using the simple, full-tree reference algorithm, returning every node of the
+/// tree with the root first (i.e., the root is the first `HASHSIZE` bytes).
 ///
 /// The input `bytes` will be padded to ensure that the number of leaves is a power-of-two.
 ///
-/// It is likely a better choice to use [merkleize_padded](fn.merkleize_padded.html) instead.
+/// This is intended as a reference for testing other implementations (including third-party
+/// ones) against, since it is short enough to be checked by inspection. It is likely a better
+/// choice to use [merkleize_padded](fn.merkleize_padded.html) otherwise, which uses far less
+/// memory.
 ///
 /// ## CPU Performance
 ///
@@ -17,15 +20,25 @@ use eth2_hashing::hash;
 ///  - Duplicates the input `bytes`.
 ///  - Stores all internal nodes, even if they are padding.
 ///  - Does not free up unused memory during operation.
-pub fn merkleize_standard(bytes: &[u8]) -> Vec<u8> {
+///
+/// ## Errors
+///
+/// Returns `Error::MaxTreeDepthExceeded` if the tree would be deeper than `MAX_TREE_DEPTH`,
+/// before allocating it.
+pub fn merkleize_standard(bytes: &[u8]) -> Result<Vec<u8>, Error> {
     // If the bytes are just one chunk (or less than one chunk) just return them.
     if bytes.len() <= HASHSIZE {
         let mut o = bytes.to_vec();
         o.resize(HASHSIZE, 0);
-        return o;
+        return Ok(o);
     }
 
     let leaves = num_sanitized_leaves(bytes.len());
+    let depth = leaves.trailing_zeros() as usize;
+    if depth > MAX_TREE_DEPTH {
+        return Err(Error::MaxTreeDepthExceeded { height: depth });
+    }
+
     let nodes = num_nodes(leaves);
     let internal_nodes = nodes - leaves;
 
@@ -67,7 +80,7 @@ pub fn merkleize_standard(bytes: &[u8]) -> Vec<u8> {
         o[j..j + HASHSIZE].copy_from_slice(&hash);
     }
 
-    o
+    Ok(o)
 }
 
 fn num_sanitized_leaves(num_bytes: usize) -> usize {